@@ -209,6 +209,8 @@ impl TaskCompileArgs {
             when,
             output: self.output.as_deref().map(PathPattern::new),
             transform: transforms,
+            on_success: vec![],
+            on_failure: vec![],
         };
 
         let config = match output_format {
@@ -4,6 +4,7 @@ mod args;
 mod compiler;
 mod entry;
 mod model;
+mod schema;
 
 #[cfg(feature = "lsp")]
 mod lock;
@@ -18,6 +19,7 @@ pub use args::*;
 pub use compiler::*;
 pub use entry::*;
 pub use model::*;
+pub use schema::*;
 
 #[cfg(feature = "lsp")]
 pub use lock::*;
@@ -1,18 +1,21 @@
 #![allow(missing_docs)]
 
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::{path::Path, sync::Arc};
 
 use ecow::{eco_vec, EcoVec};
+use sha2::{Digest, Sha256};
 use tinymist_std::error::prelude::*;
 use tinymist_std::path::unix_slash;
-use tinymist_std::{bail, ImmutPath};
+use tinymist_std::{bail, ErrKind, ImmutPath};
+use tinymist_world::WorldDeps;
 use typst::diag::EcoString;
 use typst::World;
 
 use crate::model::{ApplyProjectTask, Id, ProjectInput, ProjectRoute, ResourcePath};
-use crate::{LockFile, LockFileCompat, LspWorld, ProjectPathMaterial, LOCK_VERSION};
+use crate::{LockFile, LockFileCompat, LspWorld, PackagePin, ProjectPathMaterial, LOCK_VERSION};
 
 pub const LOCK_FILENAME: &str = "tinymist.lock";
 
@@ -52,10 +55,44 @@ impl LockFile {
         self.route.push(route);
     }
 
+    pub fn replace_package(&mut self, pin: PackagePin) {
+        let index = self.package.iter().position(|i| i.spec == pin.spec);
+        if let Some(index) = index {
+            self.package[index] = pin;
+        } else {
+            self.package.push(pin);
+        }
+    }
+
+    /// Checks that the packages used by a compilation match the pinned ones.
+    ///
+    /// Returns the specifiers of the used packages that are not pinned yet.
+    pub fn check_package_pins(&self, pins: &[PackagePin]) -> Result<Vec<EcoString>> {
+        let mut unpinned = vec![];
+        for pin in pins {
+            let Some(pinned) = self.package.iter().find(|i| i.spec == pin.spec) else {
+                unpinned.push(pin.spec.clone());
+                continue;
+            };
+
+            if pinned.hash != pin.hash {
+                bail!(
+                    "package {} has changed since it was pinned, pinned hash {}, current hash {}",
+                    pin.spec,
+                    pinned.hash,
+                    pin.hash
+                );
+            }
+        }
+
+        Ok(unpinned)
+    }
+
     pub fn sort(&mut self) {
         self.document.sort_by(|a, b| a.id.cmp(&b.id));
         self.task
             .sort_by(|a, b| a.doc_id().cmp(b.doc_id()).then_with(|| a.id().cmp(b.id())));
+        self.package.sort_by(|a, b| a.spec.cmp(&b.spec));
         // the route's order is important, so we don't sort them.
     }
 
@@ -95,6 +132,15 @@ impl LockFile {
             }
         }
 
+        let package = content.get("package");
+        if let Some(package) = package {
+            for package in package.as_array().unwrap() {
+                out.push('\n');
+                out.push_str("[[package]]\n");
+                out.push_str(&package.as_table().unwrap().to_string());
+            }
+        }
+
         let task = content.get("task");
         if let Some(task) = task {
             for task in task.as_array().unwrap() {
@@ -149,27 +195,10 @@ impl LockFile {
                 document: vec![],
                 task: vec![],
                 route: eco_vec![],
+                package: vec![],
             }
         } else {
-            let old_state = toml::from_str::<LockFileCompat>(old_data)
-                .context_ut("tinymist.lock file is not a valid TOML file")?;
-
-            let version = old_state.version()?;
-            match Version(version).partial_cmp(&Version(LOCK_VERSION)) {
-                Some(Ordering::Equal | Ordering::Less) => {}
-                Some(Ordering::Greater) => {
-                    bail!(
-                    "trying to update lock file having a future version, current tinymist-cli supports {LOCK_VERSION}, the lock file is {version}",
-                );
-                }
-                None => {
-                    bail!(
-                    "cannot compare version, are version strings in right format? current tinymist-cli supports {LOCK_VERSION}, the lock file is {version}",
-                );
-                }
-            }
-
-            old_state.migrate()?
+            Self::parse(&cwd.join(LOCK_FILENAME), old_data)?
         };
 
         f(&mut state)?;
@@ -210,13 +239,125 @@ impl LockFile {
 
         let data = std::str::from_utf8(&data).context("tinymist.lock file is not valid utf-8")?;
 
+        Self::parse(&dir.join(LOCK_FILENAME), data)
+    }
+
+    /// Migrates the lock file in the directory to the current version in
+    /// place.
+    ///
+    /// Returns the version migrated from, or `None` if the lock file is
+    /// already in the current version.
+    pub fn migrate(dir: &Path) -> Result<Option<String>> {
+        let path = dir.join(LOCK_FILENAME);
+        let data = std::fs::read_to_string(&path).context(LOCK_FILENAME)?;
+        let state = toml::from_str::<LockFileCompat>(&data)
+            .map_err(|err| lock_file_error(&path, &data, err))?;
+        if !state.is_outdated() {
+            return Ok(None);
+        }
+
+        let version = state.version()?.to_owned();
+        Self::update(dir, |_| Ok(()))?;
+
+        Ok(Some(version))
+    }
+
+    /// Parses the content of a lock file, migrating it to the current version
+    /// if it is in an older version.
+    pub fn parse(path: &Path, data: &str) -> Result<Self> {
         let state = toml::from_str::<LockFileCompat>(data)
-            .context_ut("tinymist.lock file is not a valid TOML file")?;
+            .map_err(|err| lock_file_error(path, data, err))?;
+
+        let version = state.version()?;
+        match Version(version).partial_cmp(&Version(LOCK_VERSION)) {
+            Some(Ordering::Equal | Ordering::Less) => {}
+            Some(Ordering::Greater) => {
+                bail!(
+                    "trying to use lock file having a future version, current tinymist-cli supports {LOCK_VERSION}, the lock file is {version}",
+                );
+            }
+            None => {
+                bail!(
+                    "cannot compare version, are version strings in right format? current tinymist-cli supports {LOCK_VERSION}, the lock file is {version}",
+                );
+            }
+        }
+
+        if Version(version) == Version(LOCK_VERSION) && matches!(state, LockFileCompat::Other(..)) {
+            // The lock file is in the current version, but either spells the version
+            // differently, e.g. `1.0.0`, or doesn't match the schema. Parses it again,
+            // which also locates the error.
+            return toml::from_str::<LockFile>(data)
+                .map_err(|err| lock_file_error(path, data, err));
+        }
 
         state.migrate()
     }
 }
 
+/// Creates an error pointing to the location of a TOML error in a lock file.
+fn lock_file_error(path: &Path, data: &str, err: toml::de::Error) -> tinymist_std::Error {
+    let loc = match err.span() {
+        Some(span) => {
+            let prefix = &data[..span.start.min(data.len())];
+            let line = prefix.matches('\n').count() + 1;
+            let column = prefix
+                .rsplit('\n')
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .count()
+                + 1;
+            format!("{}:{line}:{column}", path.display())
+        }
+        None => path.display().to_string(),
+    };
+
+    let msg = format!("{loc}: invalid lock file: {}", err.message().trim_end());
+    tinymist_std::Error::new(LOCK_FILENAME, ErrKind::Msg(msg.into()), None)
+}
+
+impl PackagePin {
+    /// Computes the pins of the packages used by a compilation.
+    ///
+    /// The hash of a package is the SHA-256 digest of its used files listed in
+    /// the format of `sha256sum`, i.e. a line of `<file digest>  <path>` per
+    /// file sorted by the path, where the path is relative to the package root.
+    pub fn from_world(world: &LspWorld) -> Vec<Self> {
+        let mut files: BTreeMap<EcoString, Vec<(EcoString, String)>> = BTreeMap::new();
+        world.iter_dependencies(&mut |id| {
+            let Some(spec) = id.package() else {
+                return;
+            };
+            let Ok(content) = world.file(id) else {
+                return;
+            };
+
+            let path = unix_slash(id.vpath().as_rootless_path()).into();
+            let digest = format!("{:x}", Sha256::digest(content.as_slice()));
+            files
+                .entry(spec.to_string().into())
+                .or_default()
+                .push((path, digest));
+        });
+
+        files
+            .into_iter()
+            .map(|(spec, mut files)| {
+                files.sort();
+                let mut hasher = Sha256::new();
+                for (path, digest) in files {
+                    hasher.update(format!("{digest}  {path}\n"));
+                }
+                PackagePin {
+                    spec,
+                    hash: format!("{:x}", hasher.finalize()),
+                }
+            })
+            .collect()
+    }
+}
+
 /// Make a new project lock updater.
 pub fn update_lock(root: ImmutPath) -> LockFileUpdate {
     LockFileUpdate {
@@ -353,19 +494,122 @@ impl LockFileUpdate {
 
 struct Version<'a>(&'a str);
 
-impl PartialEq for Version<'_> {
-    fn eq(&self, other: &Self) -> bool {
+impl Version<'_> {
+    /// Parses the version, where the patch version can be omitted, e.g. `1.0`.
+    fn parse(&self) -> Option<semver::Version> {
         semver::Version::parse(self.0)
+            .or_else(|_| semver::Version::parse(&format!("{}.0", self.0)))
             .ok()
-            .and_then(|a| semver::Version::parse(other.0).ok().map(|b| a == b))
-            .unwrap_or(false)
+    }
+}
+
+impl PartialEq for Version<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.parse().zip(other.parse()).is_some_and(|(a, b)| a == b)
     }
 }
 
 impl PartialOrd for Version<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        let lhs = semver::Version::parse(self.0).ok()?;
-        let rhs = semver::Version::parse(other.0).ok()?;
-        Some(lhs.cmp(&rhs))
+        Some(self.parse()?.cmp(&other.parse()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BETA0_LOCK: &str = r#"
+version = "0.1.0-beta0"
+
+[[document]]
+id = "file:main.typ"
+inputs = []
+main = "file:main.typ"
+root = "file:."
+
+[[task]]
+document = "file:main.typ"
+id = "file:main.typ"
+type = "export-pdf"
+when = "never"
+"#;
+
+    #[test]
+    fn test_version_order() {
+        assert!(Version("0.1.0-beta0") < Version("1.0"));
+        assert!(Version("1.0") == Version("1.0.0"));
+        assert!(Version("1.0") < Version(LOCK_VERSION));
+        assert!(Version("1.2") > Version(LOCK_VERSION));
+        assert_eq!(Version("invalid").partial_cmp(&Version("1.0")), None);
+    }
+
+    #[test]
+    fn test_migrate_beta0() {
+        let lock = LockFile::parse(Path::new(LOCK_FILENAME), BETA0_LOCK).unwrap();
+        assert_eq!(lock.document.len(), 1);
+        assert_eq!(lock.task.len(), 1);
+
+        let migrated = lock.serialize_resolve();
+        assert!(migrated.contains(&format!("version = {LOCK_VERSION:?}")));
+        assert_eq!(
+            LockFile::parse(Path::new(LOCK_FILENAME), &migrated).unwrap(),
+            lock
+        );
+    }
+
+    #[test]
+    fn test_migrate_package_pins() {
+        let data = BETA0_LOCK.replace("0.1.0-beta0", "1.0")
+            + r#"
+[[package]]
+spec = "@preview/example:0.1.0"
+hash = "0123456789abcdef0123456789abcdef"
+"#;
+        let lock = LockFile::parse(Path::new(LOCK_FILENAME), &data).unwrap();
+        assert_eq!(lock.task.len(), 1);
+        assert!(lock.package.is_empty());
+    }
+
+    #[test]
+    fn test_current_version_spelling() {
+        let data = BETA0_LOCK.replace("0.1.0-beta0", "1.1.0");
+        let lock = LockFile::parse(Path::new(LOCK_FILENAME), &data).unwrap();
+        assert_eq!(lock.task.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_lock_location() {
+        let data = BETA0_LOCK
+            .replace("0.1.0-beta0", LOCK_VERSION)
+            .replace(r#"when = "never""#, r#"when = "sometimes""#);
+        let err = LockFile::parse(Path::new(LOCK_FILENAME), &data).unwrap_err();
+        assert!(err.to_string().contains("tinymist.lock:"), "{err}");
+    }
+
+    #[test]
+    fn test_future_version() {
+        let data = BETA0_LOCK.replace("0.1.0-beta0", "2.0");
+        assert!(LockFile::parse(Path::new(LOCK_FILENAME), &data).is_err());
+    }
+
+    #[test]
+    fn test_check_package_pins() {
+        let pin = |spec: &str, hash: &str| PackagePin {
+            spec: spec.into(),
+            hash: hash.into(),
+        };
+        let lock = LockFile {
+            package: vec![pin("@preview/a:0.1.0", "1")],
+            ..LockFile::default()
+        };
+
+        let unpinned = lock
+            .check_package_pins(&[pin("@preview/a:0.1.0", "1"), pin("@preview/b:0.1.0", "2")])
+            .unwrap();
+        assert_eq!(unpinned, vec![EcoString::from("@preview/b:0.1.0")]);
+        assert!(lock
+            .check_package_pins(&[pin("@preview/a:0.1.0", "2")])
+            .is_err());
     }
 }
@@ -10,15 +10,46 @@ pub use task::*;
 pub use tinymist_task as task;
 
 /// The currently using lock file version.
-pub const LOCK_VERSION: &str = "0.1.0-beta0";
+pub const LOCK_VERSION: &str = "1.1";
+
+/// A migration step of the lock file schema.
+struct LockMigration {
+    /// The version to migrate from.
+    from: &'static str,
+    /// The version to migrate to.
+    to: &'static str,
+    /// Rewrites the lock file data, excepting the `version` field, in place.
+    migrate: fn(&mut serde_json::Map<String, serde_json::Value>) -> Result<()>,
+}
+
+/// The migration chain of the lock file schema. The chain must end at
+/// [`LOCK_VERSION`].
+const LOCK_MIGRATIONS: &[LockMigration] = &[
+    LockMigration {
+        from: "0.1.0-beta0",
+        to: "1.0",
+        // The 1.0 schema stabilizes the 0.1.0-beta0 schema without changes.
+        migrate: |_| Ok(()),
+    },
+    LockMigration {
+        from: "1.0",
+        to: "1.1",
+        // The 1.1 schema hashes the pinned packages by SHA-256, so the pins in
+        // the older hash are dropped, which are pinned again by `--save-lock`.
+        migrate: |data| {
+            data.remove("package");
+            Ok(())
+        },
+    },
+];
 
 /// A lock file compatibility wrapper.
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "kebab-case", tag = "version")]
 pub enum LockFileCompat {
-    /// The lock file schema with version 0.1.0-beta0.
-    #[serde(rename = "0.1.0-beta0")]
-    Version010Beta0(LockFile),
+    /// The lock file schema with version 1.1.
+    #[serde(rename = "1.1")]
+    Version110(LockFile),
     /// Other lock file schema.
     #[serde(untagged)]
     Other(serde_json::Value),
@@ -28,7 +59,7 @@ impl LockFileCompat {
     /// Returns the lock file version.
     pub fn version(&self) -> Result<&str> {
         match self {
-            LockFileCompat::Version010Beta0(..) => Ok(LOCK_VERSION),
+            LockFileCompat::Version110(..) => Ok(LOCK_VERSION),
             LockFileCompat::Other(v) => v
                 .get("version")
                 .and_then(|v| v.as_str())
@@ -36,17 +67,42 @@ impl LockFileCompat {
         }
     }
 
+    /// Checks whether the lock file is of an older version that needs a
+    /// migration.
+    pub fn is_outdated(&self) -> bool {
+        self.version()
+            .is_ok_and(|version| LOCK_MIGRATIONS.iter().any(|m| m.from == version))
+    }
+
     /// Migrates the lock file to the current version.
     pub fn migrate(self) -> Result<LockFile> {
-        match self {
-            LockFileCompat::Version010Beta0(v) => Ok(v),
-            this @ LockFileCompat::Other(..) => {
-                bail!(
-                    "cannot migrate from version: {}",
-                    this.version().unwrap_or("unknown version")
-                )
-            }
+        let mut data = match self {
+            LockFileCompat::Version110(v) => return Ok(v),
+            LockFileCompat::Other(serde_json::Value::Object(data)) => data,
+            LockFileCompat::Other(..) => bail!("lock file is not a table"),
+        };
+
+        let mut version = data
+            .remove("version")
+            .and_then(|v| v.as_str().map(ToOwned::to_owned))
+            .context("missing version field")?;
+
+        while version != LOCK_VERSION {
+            let Some(step) = LOCK_MIGRATIONS.iter().find(|m| m.from == version) else {
+                bail!("cannot migrate from version: {version}");
+            };
+
+            (step.migrate)(&mut data).with_context("migrate lock file", || {
+                Some(Box::new([
+                    ("from", step.from.to_owned()),
+                    ("to", step.to.to_owned()),
+                ]))
+            })?;
+            version = step.to.to_owned();
         }
+
+        serde_json::from_value(serde_json::Value::Object(data))
+            .context("lock file does not match the schema")
     }
 }
 
@@ -64,6 +120,9 @@ pub struct LockFile {
     /// The project's task route.
     #[serde(skip_serializing_if = "EcoVec::is_empty", default)]
     pub route: EcoVec<ProjectRoute>,
+    /// The project's pinned packages, recorded by reproducible builds.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub package: Vec<PackagePin>,
 }
 
 /// A project input specifier.
@@ -132,6 +191,16 @@ impl ProjectPathMaterial {
     }
 }
 
+/// A pinned package specifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackagePin {
+    /// The package specifier, e.g. `@preview/example:0.1.0`.
+    pub spec: EcoString,
+    /// The hash of the package files used by the compilation.
+    pub hash: String,
+}
+
 /// A project route specifier.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -0,0 +1,452 @@
+//! The JSON schema of the lock file.
+
+use serde_json::{json, Value};
+
+use crate::LOCK_VERSION;
+
+/// The task types that export a document, i.e. having [`crate::ExportTask`]
+/// arguments.
+const EXPORT_TASK_TYPES: &[&str] = &[
+    "export-pdf",
+    "export-png",
+    "export-svg",
+    "export-html",
+    "export-svg-html",
+    "export-md",
+    "export-te-x",
+    "export-text",
+    "export-odt",
+    "query",
+];
+
+/// Generates the JSON schema (draft-07) of the lock file in the current
+/// version, which can be used by editors to validate `tinymist.lock`.
+pub fn lock_file_schema() -> Value {
+    let resource_path = json!({
+        "type": "string",
+        "description": "A resource path in the form of `scheme:path`, e.g. `file:main.typ`.",
+        "pattern": "^[^:]+:[^:]*$"
+    });
+
+    let document = json!({
+        "type": "object",
+        "description": "A project input specifier.",
+        "required": ["id", "main", "inputs"],
+        "properties": {
+            "id": { "type": "string", "description": "The project's ID." },
+            "root": resource_path.clone(),
+            "main": resource_path.clone(),
+            "inputs": {
+                "type": "array",
+                "description": "The key-value pairs visible through `sys.inputs`.",
+                "items": {
+                    "type": "array",
+                    "items": [{ "type": "string" }, { "type": "string" }],
+                    "minItems": 2,
+                    "maxItems": 2
+                }
+            },
+            "font-paths": { "type": "array", "items": resource_path.clone() },
+            "system-fonts": { "type": "boolean", "description": "Whether to use system fonts." },
+            "package-path": resource_path.clone(),
+            "package-cache-path": resource_path,
+        }
+    });
+
+    let route = json!({
+        "type": "object",
+        "description": "A project route specifier.",
+        "required": ["id", "priority"],
+        "properties": {
+            "id": { "type": "string", "description": "A project." },
+            "priority": {
+                "type": "integer",
+                "minimum": 0,
+                "description": "The priority of the project (lower numbers are higher priority)."
+            }
+        }
+    });
+
+    let package = json!({
+        "type": "object",
+        "description": "A pinned package specifier.",
+        "required": ["spec", "hash"],
+        "properties": {
+            "spec": { "type": "string", "description": "The package specifier." },
+            "hash": {
+                "type": "string",
+                "description": "The hash of the package files used by the compilation."
+            }
+        }
+    });
+
+    let pages = json!({
+        "type": "string",
+        "description": "A page range, e.g. `1-3`, `4`, `5-`, or `-2`.",
+        "pattern": "^\\s*([1-9][0-9]*)?\\s*(-\\s*([1-9][0-9]*)?\\s*)?$"
+    });
+
+    let transform = json!({
+        "type": "object",
+        "description": "A project export transform specifier.",
+        "minProperties": 1,
+        "maxProperties": 1,
+        "properties": {
+            "pages": {
+                "type": "object",
+                "required": ["ranges"],
+                "properties": { "ranges": { "type": "array", "items": pages.clone() } }
+            },
+            "merge": {
+                "type": "object",
+                "properties": { "gap": { "type": "string" } }
+            },
+            "script": {
+                "type": "object",
+                "properties": { "script": { "type": "string" } }
+            },
+            "pretty": {
+                "type": "object",
+                "properties": { "script": { "type": "string" } }
+            },
+            "metadata": {
+                "type": "object",
+                "properties": {
+                    "title": { "type": "string" },
+                    "author": { "type": "array", "items": { "type": "string" } },
+                    "description": { "type": "string" },
+                    "keywords": { "type": "array", "items": { "type": "string" } }
+                }
+            },
+            "attach": {
+                "type": "object",
+                "required": ["files"],
+                "properties": { "files": { "type": "array", "items": { "type": "string" } } }
+            },
+            "watermark": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string" },
+                    "image": { "type": "string" },
+                    "pages": { "type": "array", "items": pages },
+                    "fill": { "type": "string" },
+                    "size": { "type": "string" },
+                    "angle": { "type": "number" }
+                }
+            },
+            "impose": {
+                "type": "object",
+                "required": ["layout"],
+                "properties": { "layout": { "enum": ["two-up", "booklet"] } }
+            }
+        }
+    });
+
+    let hook = json!({
+        "type": "object",
+        "description": "A hook to run after an export task completes.",
+        "required": ["type"],
+        "properties": {
+            "type": { "enum": ["command", "webhook"] },
+            "command": { "type": "string" },
+            "args": { "type": "array", "items": { "type": "string" } },
+            "url": { "type": "string" },
+            "timeout": { "type": "integer", "minimum": 0 }
+        },
+        "allOf": [
+            {
+                "if": { "properties": { "type": { "const": "command" } } },
+                "then": { "required": ["command"] }
+            },
+            {
+                "if": { "properties": { "type": { "const": "webhook" } } },
+                "then": { "required": ["url"] }
+            }
+        ]
+    });
+
+    let mut task_types = vec!["preview"];
+    task_types.extend(EXPORT_TASK_TYPES);
+
+    let task = json!({
+        "type": "object",
+        "description": "A project task application specifier.",
+        "required": ["id", "document", "type", "when"],
+        "properties": {
+            "id": { "type": "string", "description": "The task's ID." },
+            "document": { "type": "string", "description": "The document's ID." },
+            "type": { "enum": task_types },
+            "when": {
+                "enum": ["never", "onSave", "onType", "onDocumentHasTitle", "script"],
+                "description": "When to run the task."
+            },
+            "output": { "type": "string", "description": "The output path pattern." },
+            "transform": { "type": "array", "items": transform },
+            "on-success": { "type": "array", "items": hook.clone() },
+            "on-failure": { "type": "array", "items": hook },
+            "pdf-standards": { "type": "array", "items": { "enum": ["1.7", "a-2b", "a-3b"] } },
+            "creation-timestamp": { "type": "integer" },
+            "ppi": { "type": "number", "exclusiveMinimum": 0 },
+            "fill": { "type": "string" },
+            "processor": { "type": "string" },
+            "assets-path": { "type": "string" },
+            "format": { "type": "string" },
+            "output-extension": { "type": "string" },
+            "selector": { "type": "string" },
+            "field": { "type": "string" },
+            "one": { "type": "boolean" }
+        },
+        "allOf": [
+            {
+                "if": { "properties": { "type": { "const": "export-png" } } },
+                "then": { "required": ["ppi"] }
+            },
+            {
+                "if": { "properties": { "type": { "const": "query" } } },
+                "then": { "required": ["format", "selector", "one"] }
+            }
+        ]
+    });
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "$id": "https://github.com/Myriad-Dreamin/tinymist/tinymist.lock.schema.json",
+        "title": "tinymist.lock",
+        "description": "A lock file storing project information of tinymist.",
+        "type": "object",
+        "required": ["version"],
+        "properties": {
+            "version": { "const": LOCK_VERSION },
+            "document": { "type": "array", "items": document },
+            "task": { "type": "array", "items": task },
+            "route": { "type": "array", "items": route },
+            "package": { "type": "array", "items": package }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ApplyProjectTask, ExportHtmlTask, ExportMarkdownTask, ExportOdtTask, ExportPdfTask,
+        ExportPngTask, ExportSvgTask, ExportTask, ExportTeXTask, ExportTextTask, ExportTransform,
+        Id, Imposition, LockFile, PackagePin, Pages, PathPattern, PdfMetadata, PdfStandard,
+        PreviewTask, ProjectInput, ProjectRoute, ProjectTask, QueryTask, ResourcePath, TaskHook,
+        TaskWhen, Watermark,
+    };
+
+    #[test]
+    fn test_schema_task_type() {
+        let task = ApplyProjectTask {
+            id: Id::new("task".to_owned()),
+            document: Id::new("doc".to_owned()),
+            task: ProjectTask::ExportPdf(ExportPdfTask {
+                export: ExportTask::new(TaskWhen::OnSave),
+                ..Default::default()
+            }),
+        };
+        let task = serde_json::to_value(task).unwrap();
+
+        let schema = lock_file_schema();
+        let task_schema = &schema["properties"]["task"]["items"]["properties"];
+        let types = task_schema["type"]["enum"].as_array().unwrap();
+        assert!(types.contains(&task["type"]));
+        let whens = task_schema["when"]["enum"].as_array().unwrap();
+        assert!(whens.contains(&task["when"]));
+    }
+
+    /// Creates a task of each type with all of its fields set.
+    fn tasks() -> Vec<ProjectTask> {
+        let sh = |script: &str| TaskHook::Command {
+            command: "sh".to_owned(),
+            args: vec!["-c".to_owned(), script.to_owned()],
+            timeout: Some(5),
+        };
+        let pages = || vec!["1-3".parse::<Pages>().unwrap()];
+        let export = ExportTask {
+            when: TaskWhen::OnSave,
+            output: Some(PathPattern::new("out/$name")),
+            transform: vec![
+                ExportTransform::Pages { ranges: pages() },
+                ExportTransform::Merge {
+                    gap: Some("1pt".to_owned()),
+                },
+                ExportTransform::Script {
+                    script: Some("it".to_owned()),
+                },
+                ExportTransform::Pretty {
+                    script: Some("it".to_owned()),
+                },
+                ExportTransform::Metadata(PdfMetadata {
+                    title: Some("Title".to_owned()),
+                    author: Some(vec!["Author".to_owned()]),
+                    description: Some("Description".to_owned()),
+                    keywords: Some(vec!["keyword".to_owned()]),
+                }),
+                ExportTransform::Attach {
+                    files: vec!["data.csv".into()],
+                },
+                ExportTransform::Watermark(Watermark {
+                    text: Some("DRAFT".to_owned()),
+                    image: Some("logo.png".into()),
+                    pages: Some(pages()),
+                    fill: Some("luma(200)".to_owned()),
+                    size: Some("64pt".to_owned()),
+                    angle: Some(45f32.try_into().unwrap()),
+                }),
+                ExportTransform::Impose {
+                    layout: Imposition::Booklet,
+                },
+            ],
+            on_success: vec![
+                sh("true"),
+                TaskHook::Webhook {
+                    url: "http://localhost:8080".to_owned(),
+                    timeout: Some(5),
+                },
+            ],
+            on_failure: vec![sh("false")],
+        };
+
+        vec![
+            ProjectTask::Preview(PreviewTask {
+                when: TaskWhen::OnSave,
+            }),
+            ProjectTask::ExportPdf(ExportPdfTask {
+                export: export.clone(),
+                pdf_standards: vec![PdfStandard::A_2b],
+                creation_timestamp: Some(0),
+            }),
+            ProjectTask::ExportPng(ExportPngTask {
+                export: export.clone(),
+                ppi: 144f32.try_into().unwrap(),
+                fill: Some("white".to_owned()),
+            }),
+            ProjectTask::ExportSvg(ExportSvgTask {
+                export: export.clone(),
+            }),
+            ProjectTask::ExportHtml(ExportHtmlTask {
+                export: export.clone(),
+            }),
+            ProjectTask::ExportSvgHtml(ExportHtmlTask {
+                export: export.clone(),
+            }),
+            ProjectTask::ExportMd(ExportMarkdownTask {
+                processor: Some("/processor.typ".to_owned()),
+                assets_path: Some("assets".into()),
+                export: export.clone(),
+            }),
+            ProjectTask::ExportTeX(ExportTeXTask {
+                processor: Some("/processor.typ".to_owned()),
+                assets_path: Some("assets".into()),
+                export: export.clone(),
+            }),
+            ProjectTask::ExportText(ExportTextTask {
+                export: export.clone(),
+            }),
+            ProjectTask::ExportOdt(ExportOdtTask {
+                processor: Some("/processor.typ".to_owned()),
+                export: export.clone(),
+            }),
+            ProjectTask::Query(QueryTask {
+                export,
+                format: "json".to_owned(),
+                output_extension: Some("json".to_owned()),
+                selector: "heading".to_owned(),
+                field: Some("body".to_owned()),
+                one: false,
+            }),
+        ]
+    }
+
+    /// Asserts that every field of a value is described by the schema.
+    fn assert_described(value: &Value, schema: &Value, path: &str) {
+        match value {
+            Value::Object(fields) => {
+                for (key, field) in fields {
+                    let path = format!("{path}.{key}");
+                    let field_schema = &schema["properties"][key];
+                    assert!(!field_schema.is_null(), "{path} is missing in the schema");
+                    assert_described(field, field_schema, &path);
+                }
+            }
+            Value::Array(items) if schema["items"].is_object() => {
+                for item in items {
+                    assert_described(item, &schema["items"], path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_schema_task_types() {
+        let tasks = tasks();
+
+        let schema = lock_file_schema();
+        let types = schema["properties"]["task"]["items"]["properties"]["type"]["enum"]
+            .as_array()
+            .unwrap();
+        assert_eq!(types.len(), tasks.len());
+        for task in tasks {
+            // Adding a variant breaks the match, which reminds to cover it here.
+            match task {
+                ProjectTask::Preview(..)
+                | ProjectTask::ExportPdf(..)
+                | ProjectTask::ExportPng(..)
+                | ProjectTask::ExportSvg(..)
+                | ProjectTask::ExportHtml(..)
+                | ProjectTask::ExportSvgHtml(..)
+                | ProjectTask::ExportMd(..)
+                | ProjectTask::ExportTeX(..)
+                | ProjectTask::ExportText(..)
+                | ProjectTask::ExportOdt(..)
+                | ProjectTask::Query(..) => {}
+            }
+            let task = serde_json::to_value(task).unwrap();
+            assert!(types.contains(&task["type"]), "{} is missing", task["type"]);
+        }
+    }
+
+    #[test]
+    fn test_schema_fields() {
+        let root: ResourcePath = "file:.".parse().unwrap();
+        let doc_id = Id::new("file:main.typ".to_owned());
+        let lock = LockFile {
+            document: vec![ProjectInput {
+                id: doc_id.clone(),
+                root: Some(root.clone()),
+                main: "file:main.typ".parse().unwrap(),
+                inputs: vec![("key".to_owned(), "value".to_owned())],
+                font_paths: vec![root.clone()],
+                system_fonts: true,
+                package_path: Some(root.clone()),
+                package_cache_path: Some(root),
+            }],
+            task: tasks()
+                .into_iter()
+                .enumerate()
+                .map(|(idx, task)| ApplyProjectTask {
+                    id: Id::new(format!("task{idx}")),
+                    document: doc_id.clone(),
+                    task,
+                })
+                .collect(),
+            route: [ProjectRoute {
+                id: doc_id,
+                priority: 0,
+            }]
+            .into_iter()
+            .collect(),
+            package: vec![PackagePin {
+                spec: "@preview/example:0.1.0".into(),
+                hash: "0".repeat(64),
+            }],
+        };
+
+        let mut lock = serde_json::to_value(lock).unwrap();
+        lock["version"] = LOCK_VERSION.into();
+        assert_described(&lock, &lock_file_schema(), "lock");
+    }
+}
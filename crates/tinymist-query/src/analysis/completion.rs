@@ -360,6 +360,10 @@ impl<'a> CompletionCursor<'a> {
             label: item.label.clone(),
             kind: item.kind,
             detail: item.detail.clone(),
+            documentation: item.documentation.as_ref().map(|value| MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: value.to_string(),
+            }),
             sort_text: item.sort_text.clone(),
             filter_text: item.filter_text.clone(),
             label_details: item.label_details.clone().map(From::from),
@@ -4,6 +4,12 @@
 use typst::foundations::Symbol;
 
 use super::*;
+use crate::analysis::ThumbnailTarget;
+
+/// The maximum number of label completions documented with the thumbnails of
+/// the labelled elements.
+const MAX_LABEL_THUMBNAILS: usize = 16;
+
 impl CompletionPair<'_, '_, '_> {
     /// Add completions for all font families.
     pub fn font_completions(&mut self) {
@@ -95,6 +101,8 @@ impl CompletionPair<'_, '_, '_> {
             return;
         };
         let (labels, split) = analyze_labels(document);
+        let periscope = self.worker.ctx.analysis.periscope.clone();
+        let mut thumbnails = 0;
 
         let head = &self.cursor.text[..self.cursor.from];
         let at = head.ends_with('@');
@@ -120,6 +128,21 @@ impl CompletionPair<'_, '_, '_> {
             if !self.worker.seen_casts.insert(hash128(&label)) {
                 continue;
             }
+            // Renders the thumbnails of the labelled elements, e.g. figures, for the
+            // first labels only, since rendering is expensive.
+            let documentation = match &periscope {
+                Some(periscope) if bib_title.is_none() && thumbnails < MAX_LABEL_THUMBNAILS => {
+                    let elem = document.introspector().query_label(label).ok();
+                    let thumbnail = elem.and_then(|elem| elem.location()).and_then(|loc| {
+                        let target = ThumbnailTarget::Element(loc);
+                        periscope.thumbnail(self.worker.ctx, document, target)
+                    });
+                    thumbnails += usize::from(thumbnail.is_some());
+                    thumbnail.map(EcoString::from)
+                }
+                _ => None,
+            };
+
             let label: EcoString = label.resolve().as_str().into();
             let completion = Completion {
                 kind: CompletionKind::Reference,
@@ -133,6 +156,7 @@ impl CompletionPair<'_, '_, '_> {
                 label_details: label_desc.clone(),
                 filter_text: Some(label.clone()),
                 detail: detail.clone(),
+                documentation,
                 ..Completion::default()
             };
 
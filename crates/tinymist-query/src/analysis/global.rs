@@ -24,7 +24,7 @@ use typst::diag::{
     eco_format, At, FileError, FileResult, SourceDiagnostic, SourceResult, StrResult,
 };
 use typst::foundations::{Bytes, IntoValue, Module, StyleChain, Styles};
-use typst::introspection::Introspector;
+use typst::introspection::{Introspector, Location};
 use typst::layout::Position;
 use typst::model::BibliographyElem;
 use typst::syntax::package::{PackageManifest, PackageSpec};
@@ -233,6 +233,27 @@ pub trait PeriscopeProvider {
     ) -> Option<String> {
         None
     }
+
+    /// Resolve a thumbnail image of the target, e.g. a figure or an equation,
+    /// in markdown format.
+    fn thumbnail(
+        &self,
+        _ctx: &mut LocalContext,
+        _doc: &TypstDocument,
+        _target: ThumbnailTarget,
+    ) -> Option<String> {
+        None
+    }
+}
+
+/// The target of a thumbnail resolved by the [`PeriscopeProvider`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ThumbnailTarget {
+    /// The element at the location, e.g. a labelled figure, equation or table.
+    Element(Location),
+    /// The content laid out from a range of the source file, e.g. the body of
+    /// a `#let` binding.
+    Range(TypstFileId, Range<usize>),
 }
 
 /// The local context guard that performs gc once dropped.
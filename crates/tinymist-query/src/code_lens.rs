@@ -1,6 +1,6 @@
 use lsp_types::Command;
 
-use crate::{prelude::*, SemanticRequest};
+use crate::{find_snippets, prelude::*, ReviewComments, SemanticRequest, SnippetKind};
 
 /// The [`textDocument/codeLens`] request is sent from the client to the server
 /// to compute code lenses for a given text document.
@@ -59,6 +59,70 @@ impl SemanticRequest for CodeLensRequest {
             vec!["more".into()],
         ));
 
+        let uri = path_to_url(&self.path).ok()?;
+        // Only the example blocks get a lens. The functions are previewed on demand, as
+        // most of them are not meant to be called without arguments.
+        for snippet in find_snippets(&source) {
+            if !matches!(snippet.kind, SnippetKind::Example(..)) {
+                continue;
+            }
+
+            let start = snippet.range.start;
+            let range = ctx.to_lsp_range(start..start, &source);
+            let args = vec![
+                "preview-snippet".into(),
+                uri.as_str().into(),
+                serde_json::to_value(range).ok()?,
+            ];
+            res.push(CodeLens {
+                range,
+                command: Some(Command {
+                    title: tinymist_l10n::t!(
+                        "tinymist-query.code-action.previewExample",
+                        "Preview Example"
+                    )
+                    .to_string(),
+                    command: "tinymist.runCodeLens".to_string(),
+                    arguments: Some(args),
+                }),
+                data: None,
+            });
+        }
+
+        for comment in ReviewComments::load(&self.path).comments {
+            let offset = comment
+                .locate(&source)
+                .unwrap_or_else(|| comment.last_offset(&source));
+            let range = ctx.to_lsp_range(offset..offset, &source);
+            let title = match &comment.author {
+                Some(author) => format!("💬 {author}: {}", comment.text),
+                None => format!("💬 {}", comment.text),
+            };
+            // The comment is shown as a label, which has no command to run.
+            res.push(CodeLens {
+                range,
+                command: Some(Command {
+                    title,
+                    command: String::new(),
+                    arguments: None,
+                }),
+                data: None,
+            });
+            res.push(CodeLens {
+                range,
+                command: Some(Command {
+                    title: tinymist_l10n::t!(
+                        "tinymist-query.code-action.resolveReviewComment",
+                        "Resolve"
+                    )
+                    .to_string(),
+                    command: "tinymist.resolveReviewComment".to_string(),
+                    arguments: Some(vec![uri.as_str().into(), comment.id.into()]),
+                }),
+                data: None,
+            });
+        }
+
         Some(res)
     }
 }
@@ -1,5 +1,5 @@
 use ecow::EcoString;
-use lsp_types::InsertTextFormat;
+use lsp_types::{InsertTextFormat, MarkupContent};
 use serde::{Deserialize, Serialize};
 
 use crate::StrRef;
@@ -106,6 +106,8 @@ pub struct Completion {
     pub apply: Option<EcoString>,
     /// An optional short description, at most one sentence.
     pub detail: Option<EcoString>,
+    /// An optional documentation in markdown format.
+    pub documentation: Option<EcoString>,
     /// An optional array of additional text edits that are applied when
     /// selecting this completion. Edits must not overlap with the main edit
     /// nor with themselves.
@@ -202,6 +204,10 @@ pub struct CompletionItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detail: Option<EcoString>,
 
+    /// A human-readable string that represents a doc-comment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<MarkupContent>,
+
     /// A string that should be used when comparing this item
     /// with other items. When `falsy` the label is used
     /// as the sort text for this item.
@@ -4,7 +4,7 @@ use tinymist_std::typst::TypstDocument;
 use typst::foundations::repr::separated_list;
 use typst_shim::syntax::LinkedNodeExt;
 
-use crate::analysis::get_link_exprs_in;
+use crate::analysis::{get_link_exprs_in, ThumbnailTarget};
 use crate::bib::{render_citation_string, RenderedBibCitation};
 use crate::jump_from_cursor;
 use crate::prelude::*;
@@ -129,8 +129,23 @@ impl HoverWorker<'_> {
                     self.def.push(format!("Ref: `{}`\n", def.name()));
                     self.def
                         .push(format!("```typc\n{}\n```", truncated_repr(&val)));
+                    if let Some(loc) = match &val {
+                        Value::Content(content) => content.location(),
+                        _ => None,
+                    } {
+                        self.thumbnail(ThumbnailTarget::Element(loc));
+                    }
                 } else {
                     self.def.push(format!("Label: `{}`\n", def.name()));
+
+                    let label = typst::foundations::Label::construct(def.name().as_ref().into());
+                    let loc = self.doc.as_ref().and_then(|doc| {
+                        let elem = doc.introspector().query_label(label).ok()?;
+                        elem.location()
+                    });
+                    if let Some(loc) = loc {
+                        self.thumbnail(ThumbnailTarget::Element(loc));
+                    }
                 }
             }
             BibEntry(..) => {
@@ -174,6 +189,10 @@ impl HoverWorker<'_> {
                     self.def.push(format!("```typc\n{type_doc};\n```"));
                 }
 
+                if let Some((fid, range)) = self.let_content_range(&def) {
+                    self.thumbnail(ThumbnailTarget::Range(fid, range));
+                }
+
                 if let Some(doc) = sym_docs {
                     let hover_docs = doc.hover_docs();
 
@@ -257,6 +276,12 @@ impl HoverWorker<'_> {
     }
 
     fn preview(&mut self) -> Option<()> {
+        // The thumbnail of the referenced content is preferred to the periscope
+        // at the cursor.
+        if !self.preview.is_empty() {
+            return None;
+        }
+
         // Preview results
         let provider = self.ctx.analysis.periscope.clone()?;
         let doc = self.doc.as_ref()?;
@@ -293,6 +318,37 @@ impl HoverWorker<'_> {
         self.preview.push(preview_content);
         Some(())
     }
+
+    /// Thumbnail of the content referenced by the hovered syntax.
+    fn thumbnail(&mut self, target: ThumbnailTarget) -> Option<()> {
+        let provider = self.ctx.analysis.periscope.clone()?;
+        let doc = self.doc.as_ref()?;
+        let thumbnail = provider.thumbnail(self.ctx, doc, target)?;
+        self.preview.push(thumbnail);
+        Some(())
+    }
+
+    /// Gets the range of the content bound by a `#let` binding, e.g. `#let
+    /// diagram = figure(..)`, which is laid out wherever the variable is used.
+    fn let_content_range(&mut self, def: &Definition) -> Option<(TypstFileId, Range<usize>)> {
+        if !matches!(def.decl.as_ref(), Decl::Var(..)) {
+            return None;
+        }
+
+        let span = def.decl.span();
+        let fid = span.id()?;
+        let source = self.ctx.source_by_id(fid).ok()?;
+        let node = LinkedNode::new(source.root()).find(span)?;
+        let init = node.parent()?.cast::<ast::LetBinding>()?.init()?;
+        if !matches!(
+            init,
+            ast::Expr::Content(..) | ast::Expr::Equation(..) | ast::Expr::FuncCall(..)
+        ) {
+            return None;
+        }
+
+        Some((fid, source.range(init.span())?))
+    }
 }
 
 fn try_get_bib_details(
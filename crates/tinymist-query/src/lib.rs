@@ -34,10 +34,12 @@ pub use on_enter::*;
 pub use prepare_rename::*;
 pub use references::*;
 pub use rename::*;
+pub use review::*;
 pub use selection_range::*;
 pub use semantic_tokens_delta::*;
 pub use semantic_tokens_full::*;
 pub use signature_help::*;
+pub use snippet::*;
 pub use symbol::*;
 pub use will_rename_files::*;
 pub use workspace_label::*;
@@ -79,10 +81,12 @@ mod on_enter;
 mod prepare_rename;
 mod references;
 mod rename;
+mod review;
 mod selection_range;
 mod semantic_tokens_delta;
 mod semantic_tokens_full;
 mod signature_help;
+mod snippet;
 mod symbol;
 mod will_rename_files;
 mod workspace_label;
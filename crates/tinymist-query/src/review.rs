@@ -0,0 +1,218 @@
+//! Review comments pinned to the locations of a source file, which are stored in
+//! a sidecar file next to the source.
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// The maximum length of the anchor text of a comment in bytes.
+const ANCHOR_LEN: usize = 48;
+
+/// The review comments of a source file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReviewComments {
+    /// The comments, ordered by their creation.
+    pub comments: Vec<ReviewComment>,
+}
+
+/// A review comment pinned to a location of the source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewComment {
+    /// The id of the comment, which is unique in the sidecar file.
+    pub id: u64,
+    /// The name of the reviewer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// The content of the comment.
+    pub text: String,
+    /// The 0-based line of the anchor.
+    pub line: usize,
+    /// The 0-based column of the anchor in characters.
+    pub column: usize,
+    /// The source text at the anchor, which is searched to re-anchor the comment
+    /// after the source is edited.
+    pub anchor: String,
+}
+
+/// Gets the path of the sidecar file storing the review comments of the
+/// source, e.g. `chapter1.review.json` for `chapter1.typ`.
+pub fn review_sidecar_path(path: &Path) -> PathBuf {
+    path.with_extension("review.json")
+}
+
+impl ReviewComments {
+    /// Loads the review comments of the source at the path. A missing or
+    /// malformed sidecar file is taken as no comments.
+    pub fn load(path: &Path) -> Self {
+        let sidecar = review_sidecar_path(path);
+        let Ok(data) = std::fs::read(&sidecar) else {
+            return Self::default();
+        };
+
+        serde_json::from_slice(&data).unwrap_or_else(|err| {
+            log::warn!("failed to parse review comments in {sidecar:?}: {err}");
+            Self::default()
+        })
+    }
+
+    /// Saves the review comments of the source at the path. The sidecar file is
+    /// removed if there is no comment.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let sidecar = review_sidecar_path(path);
+        if self.comments.is_empty() {
+            return match std::fs::remove_file(sidecar) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            };
+        }
+
+        let data = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(sidecar, data + "\n")
+    }
+
+    /// Adds a comment at the offset of the source. The existing comments are
+    /// re-anchored to the source at the same time.
+    pub fn add(
+        &mut self,
+        source: &Source,
+        offset: usize,
+        text: String,
+        author: Option<String>,
+    ) -> &ReviewComment {
+        self.reanchor(source);
+
+        let offset = offset.min(source.len_bytes());
+        let line = source.byte_to_line(offset).unwrap_or_default();
+        let column = source.byte_to_column(offset).unwrap_or_default();
+        let id = self.comments.iter().map(|c| c.id + 1).max().unwrap_or(1);
+        self.comments.push(ReviewComment {
+            id,
+            author,
+            text,
+            line,
+            column,
+            anchor: anchor_at(source.text(), offset),
+        });
+        self.comments.last().unwrap()
+    }
+
+    /// Removes the comment with the id, returning whether it is found.
+    pub fn remove(&mut self, id: u64) -> bool {
+        let len = self.comments.len();
+        self.comments.retain(|comment| comment.id != id);
+        self.comments.len() != len
+    }
+
+    /// Moves the comments to their anchors in the edited source. The comments
+    /// whose anchors are removed are kept at their lines.
+    pub fn reanchor(&mut self, source: &Source) {
+        for comment in &mut self.comments {
+            let Some(offset) = comment.locate(source) else {
+                continue;
+            };
+            if let Some((line, column)) = source
+                .byte_to_line(offset)
+                .zip(source.byte_to_column(offset))
+            {
+                comment.line = line;
+                comment.column = column;
+            }
+        }
+    }
+}
+
+impl ReviewComment {
+    /// Locates the anchor of the comment in the source, which is the occurrence
+    /// of the anchor text nearest to the last known location.
+    pub fn locate(&self, source: &Source) -> Option<usize> {
+        let last = self.last_offset(source);
+        if self.anchor.is_empty() {
+            return Some(last);
+        }
+
+        source
+            .text()
+            .match_indices(&self.anchor)
+            .map(|(offset, _)| offset)
+            .min_by_key(|offset| offset.abs_diff(last))
+    }
+
+    /// Gets the offset of the last known location, which is clamped to the
+    /// source.
+    pub fn last_offset(&self, source: &Source) -> usize {
+        let last_line = source.len_lines().saturating_sub(1);
+        let line = self.line.min(last_line);
+        source
+            .line_column_to_byte(line, self.column)
+            .or_else(|| source.line_to_byte(line))
+            .unwrap_or_else(|| source.len_bytes())
+    }
+}
+
+/// Gets the anchor text at the offset, which is the rest of the line, limited
+/// to [`ANCHOR_LEN`] bytes.
+fn anchor_at(text: &str, offset: usize) -> String {
+    let rest = &text[offset..];
+    let mut end = rest
+        .find(['\n', '\r'])
+        .unwrap_or(rest.len())
+        .min(ANCHOR_LEN);
+    while !rest.is_char_boundary(end) {
+        end -= 1;
+    }
+    rest[..end].trim_end().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review_sidecar_path() {
+        assert_eq!(
+            review_sidecar_path(Path::new("thesis/chapter1.typ")),
+            PathBuf::from("thesis/chapter1.review.json")
+        );
+    }
+
+    #[test]
+    fn test_reanchor() {
+        let text = "= Introduction\nWe prove the theorem.\nIt is trivial.\n";
+        let mut comments = ReviewComments::default();
+        let offset = text.find("the theorem").unwrap();
+        let comment = comments.add(&Source::detached(text), offset, "Which one?".into(), None);
+        assert_eq!((comment.line, comment.column), (1, 9));
+        assert_eq!(comment.anchor, "the theorem.");
+
+        // Inserting a paragraph before the comment moves it down.
+        let edited = Source::detached(format!("= Abstract\nShort.\n\n{text}"));
+        comments.reanchor(&edited);
+        let comment = &comments.comments[0];
+        assert_eq!((comment.line, comment.column), (4, 9));
+
+        // Removing the anchor keeps the comment at its line.
+        let removed = Source::detached("= Abstract\nShort.\n\n= Introduction\nDone.\n");
+        assert_eq!(comment.locate(&removed), None);
+        comments.reanchor(&removed);
+        assert_eq!(comments.comments[0].line, 4);
+
+        let id = comments.comments[0].id;
+        assert!(comments.remove(id));
+        assert!(comments.comments.is_empty());
+    }
+
+    #[test]
+    fn test_locate_nearest() {
+        let text = "a note\nb note\nc note\n";
+        let comment = ReviewComment {
+            id: 1,
+            author: None,
+            text: String::new(),
+            line: 2,
+            column: 2,
+            anchor: "note".into(),
+        };
+        let offset = comment.locate(&Source::detached(text)).unwrap();
+        assert_eq!(offset, text.rfind("note").unwrap());
+    }
+}
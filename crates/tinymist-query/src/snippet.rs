@@ -0,0 +1,285 @@
+//! Snippets of a source file that can be previewed in isolation.
+
+use crate::prelude::*;
+
+/// A snippet of a source file, which is previewed by wrapping it in a
+/// temporary entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    /// The range of the snippet in the source.
+    pub range: Range<usize>,
+    /// The kind of the snippet.
+    pub kind: SnippetKind,
+}
+
+/// The kind of a [`Snippet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnippetKind {
+    /// A selected range of the source.
+    Selection,
+    /// An example block in doc comments, i.e. `/// ```example`, with the
+    /// comment markers stripped.
+    Example(String),
+    /// A function that can be called without arguments.
+    Function(EcoString),
+}
+
+impl Snippet {
+    /// The markup to preview the snippet.
+    pub fn body(&self, source: &Source) -> String {
+        match &self.kind {
+            SnippetKind::Selection => source.text()[self.range.clone()].to_owned(),
+            SnippetKind::Example(code) => code.clone(),
+            SnippetKind::Function(name) => format!("#{name}()"),
+        }
+    }
+}
+
+/// Finds the example blocks in doc comments and the top-level functions whose
+/// parameters all have default values.
+pub fn find_snippets(source: &Source) -> Vec<Snippet> {
+    let mut snippets = find_examples(source.text());
+
+    let root = source.root().cast::<ast::Markup>();
+    for expr in root.iter().flat_map(|markup| markup.exprs()) {
+        let ast::Expr::Let(binding) = expr else {
+            continue;
+        };
+        let ast::LetBindingKind::Closure(name) = binding.kind() else {
+            continue;
+        };
+        let Some(ast::Expr::Closure(closure)) = binding.init() else {
+            continue;
+        };
+        let params = closure.params().children();
+        if !params
+            .into_iter()
+            .all(|p| matches!(p, ast::Param::Named(..)))
+        {
+            continue;
+        }
+        let Some(range) = source.range(binding.span()) else {
+            continue;
+        };
+
+        snippets.push(Snippet {
+            range,
+            kind: SnippetKind::Function(name.get().clone()),
+        });
+    }
+
+    snippets.sort_by_key(|snippet| snippet.range.start);
+    snippets
+}
+
+/// Finds the snippet to preview at the range. A non-empty range is previewed
+/// as is, otherwise the example block or the function at the range is
+/// previewed.
+pub fn snippet_at(source: &Source, range: Range<usize>) -> Option<Snippet> {
+    if !range.is_empty() {
+        source.text().get(range.clone())?;
+        return Some(Snippet {
+            range,
+            kind: SnippetKind::Selection,
+        });
+    }
+
+    let cursor = range.start;
+    find_snippets(source)
+        .into_iter()
+        .find(|snippet| snippet.range.start <= cursor && cursor <= snippet.range.end)
+}
+
+/// Makes the temporary entry to preview the snippet. The entry inherits the
+/// top-level imports and the definitions of the source, which is imported by
+/// the `file_name`. Hence, the entry must be placed in the same directory as
+/// the source.
+pub fn snippet_entry(source: &Source, file_name: &str, body: &str) -> String {
+    let mut entry = String::new();
+
+    let root = source.root().cast::<ast::Markup>();
+    for expr in root.iter().flat_map(|markup| markup.exprs()) {
+        if let ast::Expr::Import(import) = expr {
+            if let Some(range) = source.range(import.span()) {
+                entry.push('#');
+                entry.push_str(&source.text()[range]);
+                entry.push('\n');
+            }
+        }
+    }
+
+    let file_name = file_name.replace('\\', "\\\\").replace('"', "\\\"");
+    entry.push_str(&format!("#import \"{file_name}\": *\n\n"));
+    entry.push_str(body);
+    entry
+}
+
+/// Makes the temporary entry to convert a selection of the source. Besides
+/// the imports and the definitions inherited by [`snippet_entry`], the entry
+/// keeps the top-level set and show rules before the selection, except the
+/// show rules without selectors, e.g. `#show: template`, which usually add
+/// the title pages of the whole document.
+pub fn selection_entry(source: &Source, file_name: &str, range: Range<usize>) -> String {
+    let mut body = String::new();
+
+    let root = source.root().cast::<ast::Markup>();
+    for expr in root.iter().flat_map(|markup| markup.exprs()) {
+        let is_rule = match expr {
+            ast::Expr::Set(..) => true,
+            ast::Expr::Show(rule) => rule.selector().is_some(),
+            _ => false,
+        };
+        let Some(rule_range) = source.range(expr.span()).filter(|_| is_rule) else {
+            continue;
+        };
+        if rule_range.end > range.start {
+            break;
+        }
+        body.push('#');
+        body.push_str(&source.text()[rule_range]);
+        body.push('\n');
+    }
+
+    body.push_str(&source.text()[range]);
+    snippet_entry(source, file_name, &body)
+}
+
+/// Finds the example blocks in the doc comments of the text.
+fn find_examples(text: &str) -> Vec<Snippet> {
+    // The start of the block and the indent of its opening fence.
+    let mut block: Option<(usize, usize)> = None;
+    let mut code = String::new();
+    let mut snippets = vec![];
+
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+
+        let Some(comment) = line.trim_start().strip_prefix("///") else {
+            block = None;
+            continue;
+        };
+        let comment = comment.trim_end_matches(['\n', '\r']);
+        let content = comment.trim_start();
+
+        match block {
+            None if content.starts_with("```example") => {
+                block = Some((start, comment.len() - content.len()));
+                code.clear();
+            }
+            None => {}
+            Some((block_start, _)) if content.starts_with("```") => {
+                let end = start + line.trim_end_matches(['\n', '\r']).len();
+                snippets.push(Snippet {
+                    range: block_start..end,
+                    kind: SnippetKind::Example(std::mem::take(&mut code)),
+                });
+                block = None;
+            }
+            Some((_, indent)) => {
+                code.push_str(strip_indent(comment, indent));
+                code.push('\n');
+            }
+        }
+    }
+
+    snippets
+}
+
+/// Strips at most `indent` leading whitespaces of the line.
+fn strip_indent(line: &str, indent: usize) -> &str {
+    let stripped = line.len() - line.trim_start().len();
+    &line[stripped.min(indent)..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"#import "@preview/example:0.1.0": util
+#import "colors.typ": accent
+
+/// Draws a badge.
+///
+/// ```example
+/// #badge[New]
+///   #badge(fill: red)[Old]
+/// ```
+#let badge(fill: accent, body) = box(fill: fill, body)
+
+#let banner(title: "Hello") = heading(title)
+"#;
+
+    fn source() -> Source {
+        Source::detached(SOURCE)
+    }
+
+    #[test]
+    fn test_find_snippets() {
+        let source = source();
+        let snippets = find_snippets(&source);
+        let bodies = snippets.iter().map(|s| s.body(&source)).collect::<Vec<_>>();
+        assert_eq!(
+            bodies,
+            vec!["#badge[New]\n  #badge(fill: red)[Old]\n", "#banner()"]
+        );
+
+        let example = &SOURCE[snippets[0].range.clone()];
+        assert!(example.starts_with("/// ```example"));
+        assert!(example.ends_with("/// ```"));
+    }
+
+    #[test]
+    fn test_snippet_at() {
+        let source = source();
+        let cursor = SOURCE.find("#badge[New]").unwrap();
+        let snippet = snippet_at(&source, cursor..cursor).unwrap();
+        assert!(matches!(snippet.kind, SnippetKind::Example(..)));
+
+        let cursor = SOURCE.find("heading").unwrap();
+        let snippet = snippet_at(&source, cursor..cursor).unwrap();
+        assert_eq!(snippet.kind, SnippetKind::Function("banner".into()));
+
+        // The badge function requires the body argument.
+        let cursor = SOURCE.find("box").unwrap();
+        assert_eq!(snippet_at(&source, cursor..cursor), None);
+
+        let selection = SOURCE.find("heading(title)").unwrap();
+        let snippet = snippet_at(&source, selection..selection + 7).unwrap();
+        assert_eq!(snippet.body(&source), "heading");
+    }
+
+    #[test]
+    fn test_selection_entry() {
+        let text = "#import \"colors.typ\": accent\n\
+                    #set text(fill: accent)\n\
+                    #show: rest => rest\n\
+                    #show heading: emph\n\
+                    = Title\n\
+                    #set par(justify: true)\n";
+        let source = Source::detached(text);
+        let start = text.find("= Title").unwrap();
+        let entry = selection_entry(&source, "notes.typ", start..start + 7);
+        assert_eq!(
+            entry,
+            "#import \"colors.typ\": accent\n\
+             #import \"notes.typ\": *\n\n\
+             #set text(fill: accent)\n\
+             #show heading: emph\n\
+             = Title"
+        );
+    }
+
+    #[test]
+    fn test_snippet_entry() {
+        let entry = snippet_entry(&source(), "badge.typ", "#banner()");
+        assert_eq!(
+            entry,
+            "#import \"@preview/example:0.1.0\": util\n\
+             #import \"colors.typ\": accent\n\
+             #import \"badge.typ\": *\n\n\
+             #banner()"
+        );
+    }
+}
@@ -5,12 +5,19 @@
 //!
 //! This crate provides rendering features for tinymist server.
 
+mod thumbnail;
+pub use thumbnail::*;
+
 use core::fmt;
+use std::sync::{Arc, Mutex};
 
 use base64::Engine;
+use reflexo_vec2svg::ir::{Module, Page};
 use reflexo_vec2svg::{ExportFeature, SvgExporter, SvgText};
+use tinymist_query::analysis::ThumbnailTarget;
 use tinymist_query::{FramePosition, LocalContext};
-use tinymist_std::typst::TypstDocument;
+use tinymist_std::typst::{TypstDocument, TypstPagedDocument};
+use typst::layout::{Frame, Point, Size};
 
 struct PeriscopeExportFeature {}
 
@@ -26,6 +33,34 @@ impl ExportFeature for PeriscopeExportFeature {
     const AWARE_HTML_ENTITY: bool = false;
 }
 
+type UsingExporter = SvgExporter<PeriscopeExportFeature>;
+
+/// The SVG module of a revision of the document, which is converted once and
+/// shared by the periscope images and the thumbnails.
+pub(crate) struct SvgModule {
+    module: Module,
+    pages: Vec<Page>,
+}
+
+impl SvgModule {
+    fn new(paged_doc: &TypstPagedDocument) -> Self {
+        let mut doc = UsingExporter::svg_doc(paged_doc);
+        doc.module.prepare_glyphs();
+        Self {
+            module: doc.module,
+            pages: doc.pages,
+        }
+    }
+}
+
+impl fmt::Debug for SvgModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SvgModule")
+            .field("pages", &self.pages.len())
+            .finish_non_exhaustive()
+    }
+}
+
 /// The arguments for periscope renderer.
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -38,6 +73,9 @@ pub struct PeriscopeArgs {
     pub scale: f32,
     /// Whether to invert the color. (will become smarter in the future)
     pub invert_color: String,
+    /// The format of the thumbnails of figures, equations and other content.
+    #[serde(default)]
+    pub thumbnail_format: ThumbnailFormat,
 }
 
 impl Default for PeriscopeArgs {
@@ -47,6 +85,7 @@ impl Default for PeriscopeArgs {
             y_below: 55.,
             scale: 1.5,
             invert_color: "never".to_owned(),
+            thumbnail_format: ThumbnailFormat::default(),
         }
     }
 }
@@ -56,6 +95,8 @@ impl Default for PeriscopeArgs {
 pub struct PeriscopeRenderer {
     /// The arguments for periscope renderer.
     p: PeriscopeArgs,
+    /// The thumbnails and the SVG module of the last document.
+    thumbnails: Arc<Mutex<ThumbnailCache>>,
 }
 
 impl Default for PeriscopeRenderer {
@@ -67,7 +108,10 @@ impl Default for PeriscopeRenderer {
 impl PeriscopeRenderer {
     /// Create a new periscope renderer.
     pub fn new(args: PeriscopeArgs) -> Self {
-        Self { p: args }
+        Self {
+            p: args,
+            thumbnails: Arc::default(),
+        }
     }
 
     /// Render the periscope image for the given document into markdown format.
@@ -100,36 +144,113 @@ impl PeriscopeRenderer {
     ) -> Option<(String, f32, f32)> {
         match doc {
             TypstDocument::Paged(paged_doc) => {
-                // todo: svg viewer compatibility
-                type UsingExporter = SvgExporter<PeriscopeExportFeature>;
-                let mut doc = UsingExporter::svg_doc(paged_doc);
-                doc.module.prepare_glyphs();
-                let page0 = doc.pages.get(pos.page.get() - 1)?.clone();
-                let mut svg_text = UsingExporter::render(&doc.module, &[page0.clone()], None);
-
-                // todo: let typst.ts expose it
-                let svg_header = svg_text.get_mut(0)?;
+                let page = paged_doc.pages.get(pos.page.get() - 1)?;
 
                 let y_center = pos.point.y.to_pt() as f32;
                 let y_lo = y_center - self.p.y_above;
                 let y_hi = y_center + self.p.y_below;
 
-                let width = page0.size.x.0;
+                let width = page.frame.width().to_pt() as f32;
                 let height = y_hi - y_lo;
 
-                *svg_header = SvgText::Plain(header_inner(
-                    page0.size.x.0,
-                    y_lo,
-                    y_hi,
-                    self.p.scale,
-                    self.p.invert_color == "always",
-                ));
-
-                Some((SvgText::join(svg_text), width, height))
+                let module = self.svg_module(doc, paged_doc)?;
+                let svg = self.render_svg(&module, pos.page.get() - 1, 0., y_lo, width, height)?;
+                Some((svg, width, height))
             }
             _ => None,
         }
     }
+
+    /// Render the thumbnail of the target into markdown format. The thumbnails
+    /// are cached until the document is changed.
+    pub fn render_thumbnail(
+        &self,
+        ctx: &mut LocalContext,
+        doc: &TypstDocument,
+        target: ThumbnailTarget,
+    ) -> Option<String> {
+        let TypstDocument::Paged(paged_doc) = doc else {
+            return None;
+        };
+
+        ThumbnailCache::get_or_render(&self.thumbnails, doc, target.clone(), || {
+            let region = target_region(ctx, paged_doc, &target)?;
+            let x_lo = region.min.x.to_pt() as f32;
+            let y_lo = region.min.y.to_pt() as f32;
+            let w = region.width().to_pt() as f32;
+            let h = region.height().to_pt() as f32;
+            let (sw, sh) = (w * self.p.scale, h * self.p.scale);
+
+            let image = match self.p.thumbnail_format {
+                ThumbnailFormat::Svg => {
+                    let module = self.svg_module(doc, paged_doc)?;
+                    let svg = self.render_svg(&module, region.page, x_lo, y_lo, w, h)?;
+                    let base64 = base64::engine::general_purpose::STANDARD.encode(svg);
+                    format!("data:image/svg+xml;base64,{base64}")
+                }
+                ThumbnailFormat::Png => {
+                    let png = self.render_png(paged_doc, &region)?;
+                    let base64 = base64::engine::general_purpose::STANDARD.encode(png);
+                    format!("data:image/png;base64,{base64}")
+                }
+            };
+
+            log::debug!("thumbnail image: {sw}x{sh}, {target:?}");
+            Some(enlarge_image(format_args!(
+                "![Thumbnail]({image}|width={sw}|height={sh})"
+            )))
+        })
+    }
+
+    /// Gets the SVG module of the document, which is converted once per
+    /// revision of the document.
+    fn svg_module(
+        &self,
+        doc: &TypstDocument,
+        paged_doc: &TypstPagedDocument,
+    ) -> Option<Arc<SvgModule>> {
+        ThumbnailCache::svg_module(&self.thumbnails, doc, || SvgModule::new(paged_doc))
+    }
+
+    /// Render the region of the page into SVG.
+    fn render_svg(
+        &self,
+        module: &SvgModule,
+        page: usize,
+        x_lo: f32,
+        y_lo: f32,
+        w: f32,
+        h: f32,
+    ) -> Option<String> {
+        // todo: svg viewer compatibility
+        let page = module.pages.get(page)?.clone();
+        let mut svg_text = UsingExporter::render(&module.module, &[page], None);
+
+        // todo: let typst.ts expose it
+        let svg_header = svg_text.get_mut(0)?;
+        *svg_header = SvgText::Plain(header_inner(
+            x_lo,
+            y_lo,
+            w,
+            h,
+            self.p.scale,
+            self.p.invert_color == "always",
+        ));
+
+        Some(SvgText::join(svg_text))
+    }
+
+    /// Render the region of the page into PNG, at twice the scale for sharp
+    /// images on high density displays.
+    fn render_png(&self, paged_doc: &TypstPagedDocument, region: &PageRegion) -> Option<Vec<u8>> {
+        let mut page = paged_doc.pages.get(region.page)?.clone();
+        let mut frame = Frame::hard(Size::new(region.width(), region.height()));
+        frame.push_frame(Point::new(-region.min.x, -region.min.y), page.frame);
+        page.frame = frame;
+
+        let pixmap = typst_render::render(&page, self.p.scale * 2.);
+        pixmap.encode_png().ok()
+    }
 }
 
 fn enlarge_image(md: fmt::Arguments) -> String {
@@ -139,8 +260,7 @@ fn enlarge_image(md: fmt::Arguments) -> String {
 /// Render the header of SVG.
 /// <svg> .. </svg>
 /// ^^^^^
-fn header_inner(w: f32, y_lo: f32, y_hi: f32, scale: f32, invert_color: bool) -> String {
-    let h = y_hi - y_lo;
+fn header_inner(x_lo: f32, y_lo: f32, w: f32, h: f32, scale: f32, invert_color: bool) -> String {
     let sw = w * scale;
     let sh = h * scale;
 
@@ -151,6 +271,6 @@ fn header_inner(w: f32, y_lo: f32, y_hi: f32, scale: f32, invert_color: bool) ->
     };
 
     format!(
-        r#"<svg style="{invert_style}" class="typst-doc" width="{sw:.3}px" height="{sh:.3}px" data-width="{w:.3}" data-height="{h:.3}" viewBox="0 {y_lo:.3} {w:.3} {h:.3}" xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:h5="http://www.w3.org/1999/xhtml">"#,
+        r#"<svg style="{invert_style}" class="typst-doc" width="{sw:.3}px" height="{sh:.3}px" data-width="{w:.3}" data-height="{h:.3}" viewBox="{x_lo:.3} {y_lo:.3} {w:.3} {h:.3}" xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:h5="http://www.w3.org/1999/xhtml">"#,
     )
 }
@@ -0,0 +1,348 @@
+//! Thumbnails of the elements in a document, which are shown in the hover and
+//! the completion documentation.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use tinymist_query::analysis::ThumbnailTarget;
+use tinymist_query::LocalContext;
+use tinymist_std::typst::{TypstDocument, TypstPagedDocument};
+use typst::introspection::{Location, Tag};
+use typst::layout::{Abs, Frame, FrameItem, Point, Transform};
+use typst::syntax::{Source, Span};
+
+use crate::SvgModule;
+
+/// The padding around the region of a thumbnail in points.
+const THUMBNAIL_PADDING: f64 = 4.;
+
+/// The format of the thumbnails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ThumbnailFormat {
+    /// The SVG format, which is rendered by the periscope exporter.
+    #[default]
+    Svg,
+    /// The PNG format, which is accepted by more editors.
+    Png,
+}
+
+/// A rectangular region on a page of the document.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRegion {
+    /// The 0-based index of the page.
+    pub page: usize,
+    /// The top-left corner of the region in points.
+    pub min: Point,
+    /// The bottom-right corner of the region in points.
+    pub max: Point,
+}
+
+impl PageRegion {
+    /// Gets the width of the region.
+    pub fn width(&self) -> Abs {
+        self.max.x - self.min.x
+    }
+
+    /// Gets the height of the region.
+    pub fn height(&self) -> Abs {
+        self.max.y - self.min.y
+    }
+}
+
+/// Finds the region of the element at the location, which covers the items
+/// laid out between the start and the end tags of the element on the first
+/// page containing it.
+pub fn element_region(doc: &TypstPagedDocument, loc: Location) -> Option<PageRegion> {
+    let page = doc.introspector.position(loc).page.get() - 1;
+    let frame = &doc.pages.get(page)?.frame;
+
+    let mut collector = RegionCollector::new(Matcher::Element(loc));
+    collector.walk(frame, Transform::identity());
+    collector.finish(page, frame)
+}
+
+/// Finds the region of the content laid out from the range of the source,
+/// which covers the items on the first page containing any of them.
+pub fn range_region(
+    doc: &TypstPagedDocument,
+    source: &Source,
+    range: Range<usize>,
+) -> Option<PageRegion> {
+    let in_range = |span: Span| {
+        span.id() == Some(source.id())
+            && source
+                .range(span)
+                .is_some_and(|rng| range.start <= rng.start && rng.end <= range.end)
+    };
+
+    doc.pages.iter().enumerate().find_map(|(page, p)| {
+        let mut collector = RegionCollector::new(Matcher::Span(&in_range));
+        collector.walk(&p.frame, Transform::identity());
+        collector.finish(page, &p.frame)
+    })
+}
+
+/// The items to collect into a region.
+enum Matcher<'a> {
+    /// The items inside an element.
+    Element(Location),
+    /// The items created by the spans.
+    Span(&'a dyn Fn(Span) -> bool),
+}
+
+/// Collects the bounding box of the matched items in a page.
+struct RegionCollector<'a> {
+    matcher: Matcher<'a>,
+    inside: bool,
+    bbox: Option<(Point, Point)>,
+}
+
+impl<'a> RegionCollector<'a> {
+    fn new(matcher: Matcher<'a>) -> Self {
+        Self {
+            matcher,
+            inside: false,
+            bbox: None,
+        }
+    }
+
+    fn walk(&mut self, frame: &Frame, ts: Transform) {
+        for (pos, item) in frame.items() {
+            let ts = ts.pre_concat(Transform::translate(pos.x, pos.y));
+            match item {
+                FrameItem::Group(group) => {
+                    self.walk(&group.frame, ts.pre_concat(group.transform));
+                }
+                FrameItem::Tag(Tag::Start(elem)) => {
+                    if matches!(self.matcher, Matcher::Element(loc) if elem.location() == Some(loc))
+                    {
+                        self.inside = true;
+                    }
+                }
+                FrameItem::Tag(Tag::End(end, _)) => {
+                    if matches!(self.matcher, Matcher::Element(loc) if *end == loc) {
+                        self.inside = false;
+                    }
+                }
+                FrameItem::Text(text) => {
+                    if self.matches(|| text.glyphs.iter().map(|glyph| glyph.span.0)) {
+                        let metrics = text.font.metrics();
+                        let min = Point::new(Abs::zero(), -metrics.ascender.at(text.size));
+                        let max = Point::new(text.width(), -metrics.descender.at(text.size));
+                        self.add(min, max, ts);
+                    }
+                }
+                FrameItem::Shape(shape, span) => {
+                    if self.matches(|| std::iter::once(*span)) {
+                        let size = shape.geometry.bbox_size();
+                        self.add(Point::zero(), size.to_point(), ts);
+                    }
+                }
+                FrameItem::Image(_, size, span) => {
+                    if self.matches(|| std::iter::once(*span)) {
+                        self.add(Point::zero(), size.to_point(), ts);
+                    }
+                }
+                FrameItem::Link(..) => {}
+            }
+        }
+    }
+
+    /// Whether an item with the spans is collected.
+    fn matches<I: Iterator<Item = Span>>(&self, spans: impl FnOnce() -> I) -> bool {
+        match self.matcher {
+            Matcher::Element(..) => self.inside,
+            Matcher::Span(in_range) => spans().any(in_range),
+        }
+    }
+
+    /// Adds the transformed box to the region.
+    fn add(&mut self, min: Point, max: Point, ts: Transform) {
+        let corners = [min, Point::new(max.x, min.y), Point::new(min.x, max.y), max]
+            .map(|corner| corner.transform(ts));
+
+        for corner in corners {
+            let (lo, hi) = self.bbox.get_or_insert((corner, corner));
+            *lo = Point::new(lo.x.min(corner.x), lo.y.min(corner.y));
+            *hi = Point::new(hi.x.max(corner.x), hi.y.max(corner.y));
+        }
+    }
+
+    /// Pads the region and clamps it to the page.
+    fn finish(self, page: usize, frame: &Frame) -> Option<PageRegion> {
+        let (lo, hi) = self.bbox?;
+        let pad = Abs::pt(THUMBNAIL_PADDING);
+        let size = frame.size();
+        let min = Point::new((lo.x - pad).max(Abs::zero()), (lo.y - pad).max(Abs::zero()));
+        let max = Point::new((hi.x + pad).min(size.x), (hi.y + pad).min(size.y));
+
+        (min.x < max.x && min.y < max.y).then_some(PageRegion { page, min, max })
+    }
+}
+
+/// The thumbnails rendered for a revision of the document.
+#[derive(Debug, Default)]
+pub(crate) struct ThumbnailCache {
+    /// The document that the thumbnails are rendered from.
+    doc: Option<TypstDocument>,
+    /// The SVG module converted from the document.
+    svg: Option<Arc<SvgModule>>,
+    /// The rendered thumbnails, where `None` means nothing to render.
+    entries: HashMap<ThumbnailTarget, Option<String>>,
+}
+
+impl ThumbnailCache {
+    /// Resets the cache if the document is changed.
+    fn sync(&mut self, doc: &TypstDocument) {
+        if !self
+            .doc
+            .as_ref()
+            .is_some_and(|cached| same_doc(cached, doc))
+        {
+            self.doc = Some(doc.clone());
+            self.svg = None;
+            self.entries.clear();
+        }
+    }
+
+    /// Gets the SVG module of the document, which is converted by `f` if it is
+    /// not converted for the document yet. The lock is held during the
+    /// conversion so that it runs once for each revision.
+    pub(crate) fn svg_module(
+        cache: &Mutex<Self>,
+        doc: &TypstDocument,
+        f: impl FnOnce() -> SvgModule,
+    ) -> Option<Arc<SvgModule>> {
+        let mut cache = cache.lock().ok()?;
+        cache.sync(doc);
+        Some(cache.svg.get_or_insert_with(|| Arc::new(f())).clone())
+    }
+
+    /// Gets the thumbnail of the target, which is rendered by `f` if it is not
+    /// rendered for the document yet.
+    pub(crate) fn get_or_render(
+        cache: &Mutex<Self>,
+        doc: &TypstDocument,
+        target: ThumbnailTarget,
+        f: impl FnOnce() -> Option<String>,
+    ) -> Option<String> {
+        {
+            let mut cache = cache.lock().ok()?;
+            cache.sync(doc);
+            if let Some(thumbnail) = cache.entries.get(&target) {
+                return thumbnail.clone();
+            }
+        }
+
+        // Renders without holding the lock.
+        let thumbnail = f();
+        let mut cache = cache.lock().ok()?;
+        if cache
+            .doc
+            .as_ref()
+            .is_some_and(|cached| same_doc(cached, doc))
+        {
+            cache.entries.insert(target, thumbnail.clone());
+        }
+        thumbnail
+    }
+}
+
+/// Whether the documents are the same revision.
+fn same_doc(lhs: &TypstDocument, rhs: &TypstDocument) -> bool {
+    match (lhs, rhs) {
+        (TypstDocument::Paged(lhs), TypstDocument::Paged(rhs)) => Arc::ptr_eq(lhs, rhs),
+        (TypstDocument::Html(lhs), TypstDocument::Html(rhs)) => Arc::ptr_eq(lhs, rhs),
+        _ => false,
+    }
+}
+
+/// Finds the region of the target in the document.
+pub(crate) fn target_region(
+    ctx: &mut LocalContext,
+    doc: &TypstPagedDocument,
+    target: &ThumbnailTarget,
+) -> Option<PageRegion> {
+    match target {
+        ThumbnailTarget::Element(loc) => element_region(doc, *loc),
+        ThumbnailTarget::Range(fid, range) => {
+            let source = ctx.source_by_id(*fid).ok()?;
+            range_region(doc, &source, range.clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tinymist_tests::run_with_sources;
+    use typst::foundations::Label;
+    use typst::utils::PicoStr;
+    use typst::World;
+
+    const PAGE: &str = "#set page(width: 200pt, height: 200pt, margin: 10pt)\n";
+
+    fn compile(source: &str) -> (TypstPagedDocument, Source) {
+        run_with_sources(source, |verse, _| {
+            let world = verse.snapshot();
+            let doc = typst::compile::<TypstPagedDocument>(&world).output.unwrap();
+            (doc, world.source(world.main()).unwrap())
+        })
+    }
+
+    fn label_loc(doc: &TypstPagedDocument, name: &str) -> Location {
+        let label = Label::new(PicoStr::intern(name));
+        doc.introspector
+            .query_label(label)
+            .unwrap()
+            .location()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_element_region() {
+        let (doc, _) = compile(&format!(
+            "{PAGE}#v(50pt)\n#figure(rect(width: 60pt, height: 40pt)) <fig>"
+        ));
+        let region = element_region(&doc, label_loc(&doc, "fig")).unwrap();
+        assert_eq!(region.page, 0);
+        assert!(region.min.y > Abs::pt(50.), "{region:?}");
+        assert!(region.width() >= Abs::pt(60.), "{region:?}");
+        assert!(region.height() >= Abs::pt(40.), "{region:?}");
+        assert!(region.height() < Abs::pt(100.), "{region:?}");
+    }
+
+    #[test]
+    fn test_element_region_next_page() {
+        let (doc, _) = compile(&format!("{PAGE}First\n#pagebreak()\n$ x + y $ <eq>"));
+        let region = element_region(&doc, label_loc(&doc, "eq")).unwrap();
+        assert_eq!(region.page, 1);
+        assert!(region.height() < Abs::pt(50.), "{region:?}");
+    }
+
+    #[test]
+    fn test_range_region() {
+        let (doc, source) = compile(&format!(
+            "{PAGE}Some text\n\n#rect(width: 30pt, height: 20pt)\n\nMore text"
+        ));
+        let start = source.text().find("#rect").unwrap();
+        let end = source.text()[start..].find('\n').unwrap() + start;
+        let region = range_region(&doc, &source, start..end).unwrap();
+        assert_eq!(region.page, 0);
+        assert!(region.width() >= Abs::pt(30.), "{region:?}");
+        assert!(region.width() < Abs::pt(60.), "{region:?}");
+        assert!(region.height() >= Abs::pt(20.), "{region:?}");
+        assert!(region.height() < Abs::pt(40.), "{region:?}");
+    }
+
+    #[test]
+    fn test_range_region_nothing_laid_out() {
+        let (doc, source) = compile(&format!("{PAGE}#let x = 1\nText"));
+        let start = source.text().find("#let").unwrap();
+        let region = range_region(&doc, &source, start..start + "#let x = 1".len());
+        assert_eq!(region, None);
+    }
+}
@@ -7,6 +7,9 @@ use typst_pdf::{PdfOptions, PdfStandards, Timestamp};
 use super::*;
 use crate::model::ExportPdfTask;
 
+mod transform;
+pub use transform::*;
+
 pub struct PdfExport;
 
 impl<F: CompilerFeat> ExportComputation<F, TypstPagedDocument> for PdfExport {
@@ -14,10 +17,12 @@ impl<F: CompilerFeat> ExportComputation<F, TypstPagedDocument> for PdfExport {
     type Config = ExportPdfTask;
 
     fn run(
-        _graph: &Arc<WorldComputeGraph<F>>,
+        graph: &Arc<WorldComputeGraph<F>>,
         doc: &Arc<TypstPagedDocument>,
         config: &ExportPdfTask,
     ) -> Result<Bytes> {
+        let doc = &apply_pdf_transforms(graph, doc, &config.export.transform)?;
+
         let creation_timestamp = config
             .creation_timestamp
             .map(|ts| ts.to_utc_datetime().context("timestamp is out of range"))
@@ -0,0 +1,502 @@
+//! Native transforms applied to paged documents before PDF export.
+
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+use tinymist_std::path::unix_slash;
+use tinymist_world::{with_main, CompilerWorld, ShadowApi};
+use typst::foundations::{Repr, Smart};
+use typst::introspection::{Introspector, Location, Tag};
+use typst::layout::{Frame, FrameItem, GroupItem, Point, Size};
+use typst::syntax::FileId;
+
+use super::*;
+use crate::model::{Imposition, PdfMetadata, Watermark};
+
+/// The default fill of a text watermark.
+const DEFAULT_WATERMARK_FILL: &str = "rgb(50%, 50%, 50%, 30%)";
+/// The default size of a text watermark.
+const DEFAULT_WATERMARK_SIZE: &str = "72pt";
+/// The default rotation of a watermark in degrees.
+const DEFAULT_WATERMARK_ANGLE: f32 = 45.;
+
+/// Applies the PDF transforms of a task to the document.
+///
+/// Transforms are applied in order. The document is returned as is if none of
+/// the transforms affect it.
+pub fn apply_pdf_transforms<F: CompilerFeat>(
+    graph: &Arc<WorldComputeGraph<F>>,
+    doc: &Arc<TypstPagedDocument>,
+    transforms: &[ExportTransform],
+) -> Result<Arc<TypstPagedDocument>> {
+    let is_pdf_transform = |t: &ExportTransform| {
+        matches!(
+            t,
+            ExportTransform::Metadata(..)
+                | ExportTransform::Attach { .. }
+                | ExportTransform::Watermark(..)
+                | ExportTransform::Impose { .. }
+        )
+    };
+    if !transforms.iter().any(is_pdf_transform) {
+        return Ok(doc.clone());
+    }
+
+    let mut doc = doc.as_ref().clone();
+    let mut overlay = OverlayCompiler::new(&graph.snap.world, &doc);
+    for transform in transforms {
+        match transform {
+            ExportTransform::Metadata(meta) => apply_metadata(&mut doc, meta),
+            ExportTransform::Attach { files } => attach(&mut doc, &mut overlay, files)?,
+            ExportTransform::Watermark(mark) => watermark(&mut doc, &mut overlay, mark)?,
+            ExportTransform::Impose { layout } => impose(&mut doc, *layout),
+            ExportTransform::Pages { .. }
+            | ExportTransform::Merge { .. }
+            | ExportTransform::Script { .. }
+            | ExportTransform::Pretty { .. } => {}
+        }
+    }
+
+    // Attachments and impositions change the elements and their positions, so
+    // the introspector must be rebuilt from the new pages.
+    doc.introspector = Introspector::paged(&doc.pages);
+
+    Ok(Arc::new(doc))
+}
+
+/// Overrides the document information.
+fn apply_metadata(doc: &mut TypstPagedDocument, meta: &PdfMetadata) {
+    let info = &mut doc.info;
+    if let Some(title) = &meta.title {
+        info.title = Some(title.as_str().into());
+    }
+    if let Some(author) = &meta.author {
+        info.author = author.iter().map(|a| a.as_str().into()).collect();
+    }
+    if let Some(description) = &meta.description {
+        info.description = Some(description.as_str().into());
+    }
+    if let Some(keywords) = &meta.keywords {
+        info.keywords = keywords.iter().map(|k| k.as_str().into()).collect();
+    }
+}
+
+/// Embeds files by placing `pdf.embed` elements on the first page.
+fn attach<F: CompilerFeat>(
+    doc: &mut TypstPagedDocument,
+    overlay: &mut OverlayCompiler<F>,
+    files: &[PathBuf],
+) -> Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let Some(first_page) = doc.pages.first_mut() else {
+        bail!("cannot attach files to a document without pages");
+    };
+
+    let mut source = String::new();
+    for file in files {
+        source.push_str(&format!("#pdf.embed({})\n", rooted_path(file)));
+    }
+
+    let frame = overlay.compile(source)?;
+    first_page.frame.push_frame(Point::zero(), frame);
+    Ok(())
+}
+
+/// Stamps a watermark on the selected pages.
+fn watermark<F: CompilerFeat>(
+    doc: &mut TypstPagedDocument,
+    overlay: &mut OverlayCompiler<F>,
+    mark: &Watermark,
+) -> Result<()> {
+    let body = match (&mark.text, &mark.image) {
+        (Some(text), None) => format!(
+            "text(size: {}, fill: {}, {})",
+            mark.size.as_deref().unwrap_or(DEFAULT_WATERMARK_SIZE),
+            mark.fill.as_deref().unwrap_or(DEFAULT_WATERMARK_FILL),
+            Value::Str(text.as_str().into()).repr(),
+        ),
+        (None, Some(image)) => format!("image({}, width: 50%)", rooted_path(image)),
+        _ => bail!("a watermark must specify exactly one of text and image"),
+    };
+    let angle = mark.angle.map_or(DEFAULT_WATERMARK_ANGLE, |a| a.to_f32());
+
+    for (idx, page) in doc.pages.iter_mut().enumerate() {
+        let number = NonZeroUsize::new(idx + 1).expect("page number is positive");
+        let selected = mark
+            .pages
+            .as_ref()
+            .is_none_or(|ranges| ranges.iter().any(|r| r.contains(number)));
+        if !selected {
+            continue;
+        }
+
+        let size = page.frame.size();
+        let source = format!(
+            "#set page(width: {}pt, height: {}pt, margin: 0pt, fill: none)\n\
+             #place(center + horizon, rotate(-{angle}deg, reflow: true, {body}))\n",
+            size.x.to_pt(),
+            size.y.to_pt(),
+        );
+
+        let frame = overlay.compile(source)?;
+        page.frame.push_frame(Point::zero(), frame);
+    }
+
+    Ok(())
+}
+
+/// Imposes pages onto sheets holding two pages side by side.
+fn impose(doc: &mut TypstPagedDocument, layout: Imposition) {
+    let Some(template) = doc.pages.first().cloned() else {
+        return;
+    };
+    let blank = template.frame.size();
+
+    let pages = std::mem::take(&mut doc.pages);
+    let order = match layout {
+        Imposition::TwoUp => two_up_order(pages.len()),
+        Imposition::Booklet => booklet_order(pages.len()),
+    };
+
+    doc.pages = order
+        .chunks(2)
+        .enumerate()
+        .map(|(idx, slots)| {
+            let frames = slots
+                .iter()
+                .map(|slot| match slot {
+                    Some(i) => {
+                        let page = &pages[*i];
+                        let mut frame = page.frame.clone();
+                        if let Smart::Custom(Some(fill)) = &page.fill {
+                            frame.fill(fill.clone());
+                        }
+                        frame
+                    }
+                    None => Frame::hard(blank),
+                })
+                .collect::<Vec<_>>();
+
+            let width = frames.iter().map(Frame::width).sum();
+            let height = frames.iter().fold(Abs::zero(), |h, f| h.max(f.height()));
+
+            let mut sheet = Frame::hard(Size::new(width, height));
+            let mut x = Abs::zero();
+            for frame in frames {
+                let w = frame.width();
+                sheet.push_frame(Point::with_x(x), frame);
+                x += w;
+            }
+
+            typst::layout::Page {
+                frame: sheet,
+                number: idx + 1,
+                ..template.clone()
+            }
+        })
+        .collect();
+}
+
+/// Computes the page slots of a 2-up imposition, padded to an even count.
+fn two_up_order(count: usize) -> Vec<Option<usize>> {
+    let total = count.div_ceil(2) * 2;
+    (0..total).map(|i| (i < count).then_some(i)).collect()
+}
+
+/// Computes the page slots of a saddle-stitch booklet, padded to a multiple of
+/// four. Each consecutive pair of slots is one side of a sheet.
+fn booklet_order(count: usize) -> Vec<Option<usize>> {
+    let total = count.div_ceil(4) * 4;
+    let slot = |i: usize| (i < count).then_some(i);
+
+    let mut order = Vec::with_capacity(total);
+    for sheet in 0..total / 4 {
+        // Front side: the last and the first remaining pages.
+        order.push(slot(total - 1 - 2 * sheet));
+        order.push(slot(2 * sheet));
+        // Back side: the second and the second-to-last remaining pages.
+        order.push(slot(2 * sheet + 1));
+        order.push(slot(total - 2 - 2 * sheet));
+    }
+
+    order
+}
+
+/// Converts a path relative to the project root to a rooted typst string.
+fn rooted_path(path: &Path) -> EcoString {
+    let path = unix_slash(path);
+    let path = if path.starts_with('/') {
+        path
+    } else {
+        format!("/{path}")
+    };
+    Value::Str(path.as_str().into()).repr()
+}
+
+/// Compiles typst snippets in a virtual file of the world, whose first page is
+/// used as an overlay.
+struct OverlayCompiler<F: CompilerFeat> {
+    world: CompilerWorld<F>,
+    id: FileId,
+    cache: HashMap<String, Frame>,
+    /// The locations of the elements in the document and the placed overlays.
+    locations: HashSet<Location>,
+}
+
+impl<F: CompilerFeat> OverlayCompiler<F> {
+    fn new(world: &CompilerWorld<F>, doc: &TypstPagedDocument) -> Self {
+        let world = world.paged_task().into_owned();
+        let id = world.main().join("/__tinymist_overlay__.typ");
+        let mut locations = HashSet::new();
+        for page in &doc.pages {
+            collect_locations(&page.frame, &mut locations);
+        }
+        Self {
+            world,
+            id,
+            cache: HashMap::new(),
+            locations,
+        }
+    }
+
+    /// Compiles an overlay to place. The locations of its elements, e.g. of
+    /// `pdf.embed`, are assigned by a separate compilation, so they are
+    /// relocated to not clash with the elements of the document, or of the
+    /// same overlay placed on other pages.
+    fn compile(&mut self, source: String) -> Result<Frame> {
+        let frame = self.compile_frame(source)?;
+        Ok(self.relocate(&frame, &mut HashMap::new()))
+    }
+
+    fn compile_frame(&mut self, source: String) -> Result<Frame> {
+        if let Some(frame) = self.cache.get(&source) {
+            return Ok(frame.clone());
+        }
+
+        self.world
+            .map_shadow_by_id(self.id, Bytes::from_string(source.clone()))
+            .map_err(|e| anyhow::anyhow!("failed to prepare overlay: {e}"))?;
+
+        let res = typst::compile::<TypstPagedDocument>(&with_main(&self.world, self.id));
+        let doc = match res.output {
+            Ok(doc) => doc,
+            Err(e) if e.is_empty() => bail!("failed to compile overlay: internal error"),
+            Err(e) => bail!("failed to compile overlay: {}", e[0].message),
+        };
+        let frame = doc
+            .pages
+            .into_iter()
+            .next()
+            .map(|page| page.frame)
+            .unwrap_or_else(|| Frame::hard(Size::zero()));
+
+        self.cache.insert(source, frame.clone());
+        Ok(frame)
+    }
+
+    /// Assigns unused locations to the elements in the frame.
+    fn relocate(&mut self, frame: &Frame, relocated: &mut HashMap<Location, Location>) -> Frame {
+        let mut out = frame.clone();
+        out.clear();
+        for (pos, item) in frame.items() {
+            let item = match item {
+                FrameItem::Group(group) => FrameItem::Group(GroupItem {
+                    frame: self.relocate(&group.frame, relocated),
+                    ..group.clone()
+                }),
+                FrameItem::Tag(Tag::Start(elem)) => {
+                    let mut elem = elem.clone();
+                    if let Some(loc) = elem.location() {
+                        let mut fresh = loc;
+                        let mut variant = 1;
+                        while !self.locations.insert(fresh) {
+                            fresh = loc.variant(variant);
+                            variant += 1;
+                        }
+                        relocated.insert(loc, fresh);
+                        elem.set_location(fresh);
+                    }
+                    FrameItem::Tag(Tag::Start(elem))
+                }
+                FrameItem::Tag(Tag::End(loc, key)) => {
+                    let loc = relocated.get(loc).copied().unwrap_or(*loc);
+                    FrameItem::Tag(Tag::End(loc, *key))
+                }
+                item => item.clone(),
+            };
+            out.push(*pos, item);
+        }
+        out
+    }
+}
+
+/// Collects the locations of the elements in the frame.
+fn collect_locations(frame: &Frame, locations: &mut HashSet<Location>) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => collect_locations(&group.frame, locations),
+            FrameItem::Tag(Tag::Start(elem)) => locations.extend(elem.location()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tinymist_world::args::CompileFontArgs;
+    use tinymist_world::system::SystemUniverseBuilder;
+    use tinymist_world::{EntryManager, EntryState, TypstSystemWorld};
+    use typst::foundations::NativeElement;
+    use typst::pdf::EmbedElem;
+    use typst::syntax::VirtualPath;
+
+    use super::*;
+    use crate::Pages;
+
+    /// Compiles the main file with the other files in the root.
+    fn compile(main: &str, files: &[(&str, &str)]) -> (TypstSystemWorld, TypstPagedDocument) {
+        let root = if cfg!(windows) {
+            PathBuf::from("C:\\dummy-root")
+        } else {
+            PathBuf::from("/dummy-root")
+        };
+        let fonts = SystemUniverseBuilder::resolve_fonts(CompileFontArgs {
+            ignore_system_fonts: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let mut verse = SystemUniverseBuilder::build(
+            EntryState::new_rooted(root.as_path().into(), None),
+            Default::default(),
+            Arc::new(fonts),
+            SystemUniverseBuilder::resolve_package(None, None),
+        );
+        for (path, content) in files.iter().chain([&("main.typ", main)]) {
+            let content = Bytes::from_string(content.to_string());
+            verse.map_shadow(&root.join(path), content).unwrap();
+        }
+        verse
+            .mutate_entry(EntryState::new_rooted(
+                root.as_path().into(),
+                Some(VirtualPath::new("main.typ")),
+            ))
+            .unwrap();
+
+        let world = verse.snapshot();
+        let doc = typst::compile::<TypstPagedDocument>(&world).output.unwrap();
+        (world, doc)
+    }
+
+    fn has_text(frame: &Frame, text: &str) -> bool {
+        frame.items().any(|(_, item)| match item {
+            FrameItem::Group(group) => has_text(&group.frame, text),
+            FrameItem::Text(item) => item.text == text,
+            _ => false,
+        })
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut doc = TypstPagedDocument::default();
+        doc.info.keywords = vec!["kept".into()];
+        let meta = PdfMetadata {
+            title: Some("Title".into()),
+            author: Some(vec!["A".into(), "B".into()]),
+            ..Default::default()
+        };
+        apply_metadata(&mut doc, &meta);
+
+        assert_eq!(doc.info.title.as_deref(), Some("Title"));
+        assert_eq!(doc.info.author, ["A", "B"]);
+        assert_eq!(doc.info.description, None);
+        assert_eq!(doc.info.keywords, ["kept"]);
+    }
+
+    #[test]
+    fn test_watermark() {
+        let (world, mut doc) = compile("One #pagebreak() Two #pagebreak() Three", &[]);
+        let mut overlay = OverlayCompiler::new(&world, &doc);
+        let second = NonZeroUsize::new(2);
+        let mark = Watermark {
+            text: Some("DRAFT".into()),
+            pages: Some(vec![Pages(second..=None)]),
+            ..Default::default()
+        };
+        watermark(&mut doc, &mut overlay, &mark).unwrap();
+
+        let stamped = doc.pages.iter().map(|page| has_text(&page.frame, "DRAFT"));
+        assert_eq!(stamped.collect::<Vec<_>>(), [false, true, true]);
+
+        let mark = Watermark::default();
+        assert!(watermark(&mut doc, &mut overlay, &mark).is_err());
+    }
+
+    #[test]
+    fn test_attach() {
+        let main = "#pdf.embed(\"data.txt\")\nBody";
+        let (world, doc) = compile(main, &[("data.txt", "data")]);
+        let graph = WorldComputeGraph::from_world(world);
+        let files = vec![PathBuf::from("data.txt")];
+        let transforms = [
+            ExportTransform::Attach {
+                files: files.clone(),
+            },
+            ExportTransform::Attach { files },
+        ];
+        let doc = apply_pdf_transforms(&graph, &Arc::new(doc), &transforms).unwrap();
+
+        // The embeds of the document and the overlays, where the same overlay
+        // is placed twice, are all kept with their own locations.
+        let embeds = doc.introspector.query(&EmbedElem::elem().select());
+        let locations = embeds.iter().flat_map(|embed| embed.location());
+        assert_eq!(embeds.len(), 3);
+        assert_eq!(locations.collect::<HashSet<_>>().len(), 3);
+    }
+
+    #[test]
+    fn test_relocate() {
+        let (world, doc) = compile("#metadata(1) <a> #metadata(2) <b>", &[]);
+        let mut overlay = OverlayCompiler::new(&world, &doc);
+        let mut original = HashSet::new();
+        collect_locations(&doc.pages[0].frame, &mut original);
+        assert!(!original.is_empty());
+
+        let frame = overlay.relocate(&doc.pages[0].frame, &mut HashMap::new());
+        let mut relocated = HashSet::new();
+        collect_locations(&frame, &mut relocated);
+        assert_eq!(relocated.len(), original.len());
+        assert!(relocated.is_disjoint(&original));
+    }
+
+    #[test]
+    fn test_two_up_order() {
+        assert!(two_up_order(0).is_empty());
+        assert_eq!(two_up_order(3), vec![Some(0), Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn test_booklet_order() {
+        assert_eq!(
+            booklet_order(8),
+            [7, 0, 1, 6, 5, 2, 3, 4].map(Some).to_vec()
+        );
+        assert_eq!(
+            booklet_order(5),
+            vec![
+                None,
+                Some(0),
+                Some(1),
+                None,
+                None,
+                Some(2),
+                Some(3),
+                Some(4)
+            ]
+        );
+    }
+}
@@ -76,6 +76,8 @@ pub enum ProjectTask {
     ExportTeX(ExportTeXTask),
     /// An export Text task.
     ExportText(ExportTextTask),
+    /// An export ODT task.
+    ExportOdt(ExportOdtTask),
     /// An query task.
     Query(QueryTask),
     // todo: compatibility
@@ -96,6 +98,7 @@ impl ProjectTask {
             | Self::ExportMd(..)
             | Self::ExportTeX(..)
             | Self::ExportText(..)
+            | Self::ExportOdt(..)
             | Self::Query(..) => &self.as_export()?.when,
         })
     }
@@ -112,10 +115,28 @@ impl ProjectTask {
             Self::ExportTeX(task) => &task.export,
             Self::ExportMd(task) => &task.export,
             Self::ExportText(task) => &task.export,
+            Self::ExportOdt(task) => &task.export,
             Self::Query(task) => &task.export,
         })
     }
 
+    /// Returns the mutable export configuration of a task.
+    pub fn as_export_mut(&mut self) -> Option<&mut ExportTask> {
+        Some(match self {
+            Self::Preview(..) => return None,
+            Self::ExportPdf(task) => &mut task.export,
+            Self::ExportPng(task) => &mut task.export,
+            Self::ExportSvg(task) => &mut task.export,
+            Self::ExportHtml(task) => &mut task.export,
+            Self::ExportSvgHtml(task) => &mut task.export,
+            Self::ExportTeX(task) => &mut task.export,
+            Self::ExportMd(task) => &mut task.export,
+            Self::ExportText(task) => &mut task.export,
+            Self::ExportOdt(task) => &mut task.export,
+            Self::Query(task) => &mut task.export,
+        })
+    }
+
     /// Returns extension of the artifact.
     pub fn extension(&self) -> &str {
         match self {
@@ -124,6 +145,7 @@ impl ProjectTask {
             Self::ExportMd { .. } => "md",
             Self::ExportTeX { .. } => "tex",
             Self::ExportText { .. } => "txt",
+            Self::ExportOdt { .. } => "odt",
             Self::ExportSvg { .. } => "svg",
             Self::ExportPng { .. } => "png",
             Self::Query(QueryTask {
@@ -155,6 +177,12 @@ pub struct ExportTask {
     /// The task's transforms.
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub transform: Vec<ExportTransform>,
+    /// The hooks to run after the task succeeds.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub on_success: Vec<TaskHook>,
+    /// The hooks to run after the task fails.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub on_failure: Vec<TaskHook>,
 }
 
 impl ExportTask {
@@ -164,6 +192,17 @@ impl ExportTask {
             when,
             output: None,
             transform: Vec::new(),
+            on_success: Vec::new(),
+            on_failure: Vec::new(),
+        }
+    }
+
+    /// Gets the hooks to run after the task completes.
+    pub fn hooks(&self, success: bool) -> &[TaskHook] {
+        if success {
+            &self.on_success
+        } else {
+            &self.on_failure
         }
     }
 
@@ -174,6 +213,50 @@ impl ExportTask {
     }
 }
 
+/// A hook to run after an export task completes.
+///
+/// A command hook is run with the following environment variables:
+/// - `TINYMIST_TASK_ID`: the ID of the task.
+/// - `TINYMIST_ARTIFACT`: the path to the exported artifact, if any.
+/// - `TINYMIST_DIAGNOSTICS`: the number of diagnostics of the compilation.
+/// - `TINYMIST_STATUS`: either `success` or `failure`.
+///
+/// A webhook receives the same information as a JSON payload.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "type")]
+pub enum TaskHook {
+    /// Runs a local command.
+    Command {
+        /// The program to run.
+        command: String,
+        /// The arguments passed to the program.
+        #[serde(skip_serializing_if = "Vec::is_empty", default)]
+        args: Vec<String>,
+        /// The timeout in seconds.
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        timeout: Option<u64>,
+    },
+    /// POSTs a JSON payload to a URL.
+    Webhook {
+        /// The URL to post to.
+        url: String,
+        /// The timeout in seconds.
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        timeout: Option<u64>,
+    },
+}
+
+impl TaskHook {
+    /// The default timeout of a hook.
+    pub const DEFAULT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+    /// Gets the timeout of the hook.
+    pub fn timeout(&self) -> std::time::Duration {
+        let (TaskHook::Command { timeout, .. } | TaskHook::Webhook { timeout, .. }) = self;
+        timeout.map_or(Self::DEFAULT_TIMEOUT, std::time::Duration::from_secs)
+    }
+}
+
 /// The legacy page selection specifier.
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -217,6 +300,79 @@ pub enum ExportTransform {
         #[serde(skip_serializing_if = "Option::is_none", default)]
         script: Option<String>,
     },
+    /// Overrides the document information (and the XMP metadata derived from
+    /// it) of the exported PDF. Unset fields keep the values specified in the
+    /// document.
+    Metadata(PdfMetadata),
+    /// Embeds files into the exported PDF as attachments.
+    Attach {
+        /// The files to embed, relative to the project root.
+        files: Vec<PathBuf>,
+    },
+    /// Stamps a text or image watermark on the selected pages.
+    Watermark(Watermark),
+    /// Imposes pages onto sheets for printing.
+    Impose {
+        /// The imposition layout.
+        layout: Imposition,
+    },
+}
+
+/// The document information to override in a PDF export.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PdfMetadata {
+    /// The document's title.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub title: Option<String>,
+    /// The document's authors.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub author: Option<Vec<String>>,
+    /// The document's description.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub description: Option<String>,
+    /// The document's keywords.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub keywords: Option<Vec<String>>,
+}
+
+/// A watermark specifier.
+///
+/// Exactly one of [`Self::text`] and [`Self::image`] should be provided.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Watermark {
+    /// The text of the watermark.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub text: Option<String>,
+    /// The path to the image of the watermark, relative to the project root.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub image: Option<PathBuf>,
+    /// The pages to stamp. When unspecified, all pages are stamped.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub pages: Option<Vec<Pages>>,
+    /// The expression constructing the text color (in typst script), e.g.
+    /// `luma(200)` or `rgb("#ff000080")`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fill: Option<String>,
+    /// The text size (typst code expression, e.g. `64pt`).
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub size: Option<String>,
+    /// The rotation of the watermark in degrees, counter-clockwise.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub angle: Option<Scalar>,
+}
+
+/// A page imposition layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Imposition {
+    /// Places two consecutive pages side by side on each sheet.
+    TwoUp,
+    /// Places pages side by side in saddle-stitch order, so that the printed
+    /// sheets, folded and nested, read in the original order. The document is
+    /// padded with blank pages to a multiple of four.
+    Booklet,
 }
 
 /// An export pdf task specifier.
@@ -299,6 +455,17 @@ pub struct ExportTeXTask {
     pub export: ExportTask,
 }
 
+/// An export ODT task specifier.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExportOdtTask {
+    /// The processor to use for the ODT export.
+    pub processor: Option<String>,
+    /// The shared export arguments.
+    #[serde(flatten)]
+    pub export: ExportTask,
+}
+
 /// An export text task specifier.
 #[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -180,6 +180,13 @@ pub struct Pages(pub RangeInclusive<Option<NonZeroUsize>>);
 impl Pages {
     /// Selects the first page.
     pub const FIRST: Pages = Pages(NonZeroUsize::new(1)..=None);
+
+    /// Checks whether the (one-indexed) page number is in the range.
+    pub fn contains(&self, page: NonZeroUsize) -> bool {
+        let after_start = self.0.start().is_none_or(|start| page >= start);
+        let before_end = self.0.end().is_none_or(|end| page <= end);
+        after_start && before_end
+    }
 }
 
 impl FromStr for Pages {
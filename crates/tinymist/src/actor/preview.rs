@@ -9,7 +9,7 @@ use tinymist_std::error::IgnoreLogging;
 use tokio::sync::{mpsc, oneshot};
 
 use crate::project::ProjectPreviewState;
-use crate::tool::preview::{HttpServer, ProjectPreviewHandler};
+use crate::tool::preview::{HttpServer, ProjectPreviewHandler, SNIPPET_TASK_ID};
 
 pub struct PreviewTab {
     /// Task ID
@@ -102,6 +102,9 @@ impl PreviewActor {
                 .settle()
                 .log_error_with(|| format!("PreviewTask({}): failed to settle", tab.task_id));
         }
+        if task_id == SNIPPET_TASK_ID {
+            tab.compile_handler.dispose_snippet();
+        }
 
         let client = self.client.clone();
         self.client.handle.spawn(async move {
@@ -2,7 +2,10 @@ use std::path::Path;
 
 use sync_ls::transport::MirrorArgs;
 use tinymist::project::DocCommands;
-use tinymist::tool::project::{CompileArgs, GenerateScriptArgs, TaskCommands};
+use tinymist::tool::diff::DiffArgs;
+use tinymist::tool::project::{
+    CompileArgs, GenerateScriptArgs, ProjectCommands, TaskCommands, VerifyArgs,
+};
 use tinymist::tool::testing::TestArgs;
 use tinymist::{CompileFontArgs, CompileOnceArgs};
 use tinymist_core::LONG_VERSION;
@@ -41,6 +44,11 @@ pub enum Commands {
     Test(TestArgs),
     /// Runs compile command like `typst-cli compile`
     Compile(CompileArgs),
+    /// Verifies an artifact by rebuilding it reproducibly and comparing bytes
+    Verify(VerifyArgs),
+    /// Compares a document against a git revision and reports the changed
+    /// pages
+    Diff(DiffArgs),
     /// Generates build script for compilation
     #[clap(hide(true))] // still in development
     GenerateScript(GenerateScriptArgs),
@@ -56,6 +64,9 @@ pub enum Commands {
     #[clap(hide(true))] // still in development
     #[clap(subcommand)]
     Task(TaskCommands),
+    /// Manages the project lock file
+    #[clap(subcommand)]
+    Project(ProjectCommands),
 }
 
 impl Default for Commands {
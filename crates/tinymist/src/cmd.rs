@@ -2,24 +2,28 @@
 
 use std::ops::{Deref, Range};
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use lsp_types::TextDocumentIdentifier;
+use lsp_types::{TextDocumentIdentifier, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use sync_ls::RequestId;
 use task::TraceParams;
 use tinymist_assets::TYPST_PREVIEW_HTML;
 use tinymist_project::{
-    ExportHtmlTask, ExportPdfTask, ExportPngTask, ExportSvgTask, ExportTask, ExportTeXTask,
-    ExportTextTask, ExportTransform, PageSelection, Pages, ProjectTask, QueryTask,
+    ExportHtmlTask, ExportOdtTask, ExportPdfTask, ExportPngTask, ExportSvgTask, ExportTask,
+    ExportTeXTask, ExportTextTask, ExportTransform, PageSelection, Pages, ProjectTask, QueryTask,
 };
 use tinymist_query::package::PackageInfo;
 use tinymist_query::{LocalContextGuard, LspRange};
 use tinymist_std::error::prelude::*;
 use tinymist_task::ExportMarkdownTask;
 use typst::diag::{eco_format, EcoString, StrResult};
+use typst::foundations::Bytes;
 use typst::syntax::package::{PackageSpec, VersionlessPackageSpec};
 use typst::syntax::{LinkedNode, Source};
+use typst::World;
+use world::base::ShadowApi;
 use world::TaskInputs;
 
 use super::*;
@@ -76,6 +80,33 @@ struct ExportSyntaxRangeOpts {
     range: Option<LspRange>,
 }
 
+/// The options of importing a Markdown document.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportMarkdownOpts {
+    /// Renders the math out of the supported subset of LaTeX by `mitex`.
+    #[serde(default)]
+    mitex_fallback: bool,
+}
+
+/// The format of a selection copied by typlite.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum CopyFormat {
+    #[default]
+    Markdown,
+    Latex,
+    Text,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyAsOpts {
+    range: Option<LspRange>,
+    #[serde(default)]
+    format: CopyFormat,
+}
+
 /// Here are implemented the handlers for each command.
 impl ServerState {
     /// Export the current document as PDF file(s).
@@ -153,6 +184,21 @@ impl ServerState {
         )
     }
 
+    /// Export the current document as ODT file(s).
+    pub fn export_odt(&mut self, req_id: RequestId, mut args: Vec<JsonValue>) -> ScheduledResult {
+        let opts = get_arg_or_default!(args[1] as ExportTypliteOpts);
+        let export = self.config.export_task();
+        self.export(
+            req_id,
+            ProjectTask::ExportOdt(ExportOdtTask {
+                processor: opts.processor,
+                export,
+            }),
+            opts.open.unwrap_or_default(),
+            args,
+        )
+    }
+
     /// Export the current document as Text file(s).
     pub fn export_text(&mut self, req_id: RequestId, mut args: Vec<JsonValue>) -> ScheduledResult {
         let opts = get_arg_or_default!(args[1] as ExportOpts);
@@ -268,6 +314,18 @@ impl ServerState {
         just_ok(JsonValue::String(output))
     }
 
+    /// Converts a Markdown document into typst markup.
+    pub fn import_markdown(&mut self, mut args: Vec<JsonValue>) -> AnySchedulableResponse {
+        let markdown = get_arg!(args[0] as String);
+        let opts = get_arg_or_default!(args[1] as ImportMarkdownOpts);
+        let options = typlite::import::ImportOptions {
+            mitex_fallback: opts.mitex_fallback,
+        };
+        let output = typlite::import::markdown_to_typst(&markdown, &options);
+
+        just_ok(JsonValue::String(output.into()))
+    }
+
     /// Export a range of the current file's AST.
     pub fn export_ast(&mut self, mut args: Vec<JsonValue>) -> AnySchedulableResponse {
         let path = get_arg!(args[0] as PathBuf);
@@ -281,6 +339,58 @@ impl ServerState {
         just_ok(JsonValue::String(output))
     }
 
+    /// Converts a selection of the current file through typlite, and returns
+    /// the text for the client to put on the clipboard. The selection is
+    /// converted with the imports, set and show rules of the file before it.
+    pub fn copy_as(&mut self, mut args: Vec<JsonValue>) -> AnySchedulableResponse {
+        let path = get_arg!(args[0] as PathBuf);
+        let opts = get_arg_or_default!(args[1] as CopyAsOpts);
+
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let file_name = file_name.into_owned();
+        let entry = self.select_range(path.clone(), opts.range, |source, range| {
+            let range = range
+                .filter(|range| !range.is_empty())
+                .ok_or_else(|| invalid_params("no selection to copy"))?;
+            Ok(tinymist_query::selection_entry(&source, &file_name, range))
+        })?;
+
+        // The entry is placed beside the file to resolve the relative imports,
+        // but it is never written to the disk.
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let entry_path = path.with_file_name(format!(".{stem}.selection.typ"));
+        let entry_state = self.entry_resolver().resolve(Some(entry_path.into()));
+        let snap = self.snapshot().map_err(internal_error)?;
+
+        just_future(async move {
+            let mut world = snap.world().task(TaskInputs {
+                entry: Some(entry_state),
+                ..TaskInputs::default()
+            });
+            world
+                .map_shadow_by_id(world.main(), Bytes::from_string(entry))
+                .map_err(|e| error_once!("cannot map shadow", err: e))
+                .map_err(internal_error)?;
+
+            let format = match opts.format {
+                CopyFormat::Markdown => typlite::Format::Md,
+                CopyFormat::Latex => typlite::Format::LaTeX,
+                CopyFormat::Text => typlite::Format::Text,
+            };
+            // The equations are pasted as LaTeX instead of inline images.
+            let output = typlite::Typlite::new(Arc::new(world))
+                .with_format(format)
+                .with_feature(typlite::TypliteFeat {
+                    math_output: typlite::MathOutput::Latex,
+                    ..Default::default()
+                })
+                .convert()
+                .map_err(|e| internal_error(format!("failed to convert the selection: {e}")))?;
+
+            Ok(JsonValue::String(output.trim().into()))
+        })
+    }
+
     fn select_range<T>(
         &mut self,
         path: PathBuf,
@@ -415,6 +525,52 @@ impl ServerState {
         self.preview.scroll(task_id, req)
     }
 
+    /// Starts a preview of a snippet of a source file, which is a selected
+    /// range, an example block in doc comments, or a function that can be
+    /// called without arguments.
+    #[cfg(feature = "preview")]
+    pub fn snippet_preview(
+        &mut self,
+        mut args: Vec<JsonValue>,
+    ) -> SchedulableResponse<crate::tool::preview::StartPreviewResponse> {
+        let uri = get_arg!(args[0] as Url);
+        let range = get_arg!(args[1] as LspRange);
+        let cli_args = get_arg_or_default!(args[2] as Vec<String>);
+
+        let path = as_path_(uri);
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let file_name = file_name.into_owned();
+        let entry = self.select_range(path.clone(), Some(range), |source, range| {
+            let range = range.unwrap_or_default();
+            let snippet = tinymist_query::snippet_at(&source, range)
+                .ok_or_else(|| invalid_params("no snippet to preview at the range"))?;
+            let body = snippet.body(&source);
+            Ok(tinymist_query::snippet_entry(&source, &file_name, &body))
+        })?;
+
+        self.start_snippet_preview(path, entry, cli_args)
+    }
+
+    /// Resolves a review comment left in the preview, which removes it from the
+    /// review sidecar of the source.
+    #[cfg(feature = "preview")]
+    pub fn resolve_review_comment(&mut self, mut args: Vec<JsonValue>) -> AnySchedulableResponse {
+        let uri = get_arg!(args[0] as Url);
+        let id = get_arg!(args[1] as u64);
+
+        let path = as_path_(uri);
+        let mut comments = tinymist_query::ReviewComments::load(&path);
+        if !comments.remove(id) {
+            return Err(invalid_params(format!(
+                "no review comment {id} in {path:?}"
+            )));
+        }
+        comments.save(&path).map_err(internal_error)?;
+
+        self.refresh_code_lens();
+        just_ok(JsonValue::Null)
+    }
+
     /// Initialize a new template.
     pub fn init_template(&mut self, mut args: Vec<JsonValue>) -> AnySchedulableResponse {
         use crate::tool::package::{self, TemplateSource};
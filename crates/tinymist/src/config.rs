@@ -27,7 +27,7 @@ use typst_shim::utils::LazyHash;
 use super::*;
 use crate::project::{
     EntryResolver, ExportPdfTask, ExportTask, ImmutDict, PathPattern, ProjectResolutionKind,
-    ProjectTask, TaskWhen,
+    ProjectTask, TaskHook, TaskWhen,
 };
 use crate::world::font::FontResolverImpl;
 
@@ -39,6 +39,7 @@ const CONFIG_ITEMS: &[&str] = &[
     "lint",
     "completion",
     "development",
+    "exportHooks",
     "exportPdf",
     "exportTarget",
     "fontPaths",
@@ -122,6 +123,8 @@ pub struct Config {
     pub export_pdf: TaskWhen,
     /// The output directory for PDF export.
     pub output_path: PathPattern,
+    /// The hooks to run after export tasks complete.
+    pub export_hooks: ExportHooks,
 
     /// Dynamic configuration for the experimental formatter.
     pub formatter_mode: FormatterMode,
@@ -328,6 +331,7 @@ impl Config {
         assign_config!(completion.trigger_suggest_and_parameter_hints := "triggerSuggestAndParameterHints"?: bool);
         assign_config!(customized_show_document := "customizedShowDocument"?: bool);
         assign_config!(entry_resolver.project_resolution := "projectResolution"?: ProjectResolutionKind);
+        assign_config!(export_hooks := "exportHooks"?: ExportHooks);
         assign_config!(export_pdf := "exportPdf"?: TaskWhen);
         assign_config!(export_target := "exportTarget"?: ExportTarget);
         assign_config!(font_paths := "fontPaths"?: Vec<_>);
@@ -512,10 +516,12 @@ impl Config {
     /// Gets the preview configuration.
     pub fn preview(&self) -> PreviewConfig {
         PreviewConfig {
+            mode: Default::default(),
             enable_partial_rendering: self.preview.partial_rendering,
             refresh_style: self.preview.refresh.clone().unwrap_or(TaskWhen::OnType),
             invert_colors: serde_json::to_string(&self.preview.invert_colors)
                 .unwrap_or_else(|_| "never".to_string()),
+            notes_label: None,
         }
     }
 
@@ -525,6 +531,8 @@ impl Config {
             when: self.export_pdf.clone(),
             output: Some(self.output_path.clone()),
             transform: vec![],
+            on_success: self.export_hooks.on_success.clone(),
+            on_failure: self.export_hooks.on_failure.clone(),
         }
     }
 
@@ -737,6 +745,8 @@ pub struct ConstConfig {
     pub doc_line_folding_only: bool,
     /// Allow dynamic registration of document formatting.
     pub doc_fmt_dynamic_registration: bool,
+    /// Allow refreshing code lenses by `workspace/codeLens/refresh`.
+    pub code_lens_refresh: bool,
     /// The locale of the editor.
     pub locale: Option<String>,
 }
@@ -765,6 +775,7 @@ impl From<&InitializeParams> for ConstConfig {
 
         let workspace = params.capabilities.workspace.as_ref();
         let file_operations = try_(|| workspace?.file_operations.as_ref());
+        let code_lens = try_(|| workspace?.code_lens.as_ref());
         let doc = params.capabilities.text_document.as_ref();
         let sema = try_(|| doc?.semantic_tokens.as_ref());
         let fold = try_(|| doc?.folding_range.as_ref());
@@ -785,6 +796,7 @@ impl From<&InitializeParams> for ConstConfig {
             tokens_multiline_token_support: try_or(|| sema?.multiline_token_support, false),
             doc_line_folding_only: try_or(|| fold?.line_folding_only, true),
             doc_fmt_dynamic_registration: try_or(|| format?.dynamic_registration, false),
+            code_lens_refresh: try_or(|| code_lens?.refresh_support, false),
             locale: locale.map(ToOwned::to_owned),
         }
     }
@@ -878,6 +890,18 @@ pub struct PreviewFeat {
     pub invert_colors: PreviewInvertColors,
 }
 
+/// The hooks to run after export tasks complete.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportHooks {
+    /// The hooks to run after an export succeeds.
+    #[serde(default)]
+    pub on_success: Vec<TaskHook>,
+    /// The hooks to run after an export fails.
+    #[serde(default)]
+    pub on_failure: Vec<TaskHook>,
+}
+
 /// The lint features.
 #[derive(Debug, Default, Clone, Deserialize)]
 pub struct LintFeat {
@@ -1024,6 +1048,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_export_hooks() {
+        let mut config = Config::default();
+
+        let update = json!({
+            "exportPdf": "onSave",
+            "exportHooks": {
+                "onSuccess": [
+                    { "type": "command", "command": "cp", "args": ["-f", "out.pdf", "synced/"] },
+                    { "type": "webhook", "url": "http://localhost:8080/refresh", "timeout": 5 }
+                ]
+            }
+        });
+
+        good_config(&mut config, &update);
+
+        let task = config.export_task();
+        assert_eq!(task.hooks(true).len(), 2);
+        assert!(task.hooks(false).is_empty());
+        assert_eq!(
+            task.hooks(true)[1].timeout(),
+            std::time::Duration::from_secs(5)
+        );
+        assert_eq!(task.hooks(true)[0].timeout(), TaskHook::DEFAULT_TIMEOUT);
+    }
+
     #[test]
     fn test_namespaced_config() {
         let mut config = Config::default();
@@ -21,7 +21,10 @@ use sync_ls::{
     internal_error, DapBuilder, DapMessage, GetMessageKind, LsHook, LspBuilder, LspClientRoot,
     LspMessage, LspResult, Message, RequestId, TConnectionTx,
 };
-use tinymist::tool::project::{compile_main, generate_script_main, project_main, task_main};
+use tinymist::tool::diff::diff_main;
+use tinymist::tool::project::{
+    compile_main, generate_script_main, project_lock_main, project_main, task_main, verify_main,
+};
 use tinymist::tool::testing::{coverage_main, test_main};
 use tinymist::world::TaskInputs;
 use tinymist::{Config, DapRegularInit, RegularInit, ServerState, SuperInit, UserActionTask};
@@ -79,7 +82,10 @@ fn main() -> Result<()> {
 
     // Starts logging
     let _ = {
-        let is_transient_cmd = matches!(args.command, Some(Commands::Compile(..)));
+        let is_transient_cmd = matches!(
+            args.command,
+            Some(Commands::Compile(..) | Commands::Verify(..) | Commands::Diff(..))
+        );
         let is_test_no_verbose =
             matches!(&args.command, Some(Commands::Test(test)) if !test.verbose);
         use log::LevelFilter::*;
@@ -104,6 +110,8 @@ fn main() -> Result<()> {
         Commands::Cov(args) => coverage_main(args),
         Commands::Test(args) => RUNTIMES.tokio_runtime.block_on(test_main(args)),
         Commands::Compile(args) => RUNTIMES.tokio_runtime.block_on(compile_main(args)),
+        Commands::Verify(args) => RUNTIMES.tokio_runtime.block_on(verify_main(args)),
+        Commands::Diff(args) => RUNTIMES.tokio_runtime.block_on(diff_main(args)),
         Commands::GenerateScript(args) => generate_script_main(args),
         Commands::Query(query_cmds) => query_main(query_cmds),
         Commands::Lsp(args) => lsp_main(args),
@@ -118,6 +126,7 @@ fn main() -> Result<()> {
         }
         Commands::Doc(args) => project_main(args),
         Commands::Task(args) => task_main(args),
+        Commands::Project(args) => project_lock_main(args),
         Commands::Probe => Ok(()),
     }
 }
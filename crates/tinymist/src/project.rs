@@ -29,7 +29,7 @@ use parking_lot::Mutex;
 use reflexo::hash::FxHashMap;
 use sync_ls::{LspClient, TypedLspClient};
 use tinymist_project::vfs::{FileChangeSet, MemoryEvent};
-use tinymist_query::analysis::{Analysis, LspQuerySnapshot, PeriscopeProvider};
+use tinymist_query::analysis::{Analysis, LspQuerySnapshot, PeriscopeProvider, ThumbnailTarget};
 use tinymist_query::{
     CheckRequest, CompilerQueryRequest, DiagnosticsMap, LocalContext, SemanticRequest,
 };
@@ -360,6 +360,16 @@ impl PeriscopeProvider for TypstPeriscopeProvider {
     ) -> Option<String> {
         self.0.render_marked(ctx, doc, pos)
     }
+
+    /// Resolve thumbnail image of the target.
+    fn thumbnail(
+        &self,
+        ctx: &mut LocalContext,
+        doc: &TypstDocument,
+        target: ThumbnailTarget,
+    ) -> Option<String> {
+        self.0.render_thumbnail(ctx, doc, target)
+    }
 }
 
 #[derive(Default, Clone)]
@@ -654,7 +664,7 @@ impl CompileHandler<LspCompilerFeat, ProjectInsStateExt> for CompileHandlerImpl
         #[cfg(feature = "preview")]
         if let Some(inner) = self.preview.get(art.id()) {
             let art = art.clone();
-            inner.notify_compile(Arc::new(crate::tool::preview::PreviewCompileView { art }));
+            inner.notify_compile(Arc::new(crate::tool::preview::PreviewCompileView::new(art)));
         } else {
             log::debug!("Project: no preview for {:?}", art.id());
         }
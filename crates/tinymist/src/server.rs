@@ -211,6 +211,11 @@ impl ServerState {
             // User commands
             .with_command("tinymist.startDefaultPreview", State::default_preview)
             .with_command("tinymist.scrollPreview", State::scroll_preview)
+            .with_command("tinymist.startSnippetPreview", State::snippet_preview)
+            .with_command(
+                "tinymist.resolveReviewComment",
+                State::resolve_review_comment,
+            )
             // Internal commands
             .with_command("tinymist.doStartPreview", State::do_start_preview)
             .with_command("tinymist.doStartBrowsingPreview", State::browse_preview)
@@ -271,9 +276,12 @@ impl ServerState {
             .with_command_("tinymist.exportHtml", State::export_html)
             .with_command_("tinymist.exportMarkdown", State::export_markdown)
             .with_command_("tinymist.exportTeX", State::export_tex)
+            .with_command_("tinymist.exportOdt", State::export_odt)
             .with_command_("tinymist.exportQuery", State::export_query)
             .with_command("tinymist.exportAnsiHighlight", State::export_ansi_hl)
             .with_command("tinymist.exportAst", State::export_ast)
+            .with_command("tinymist.copyAs", State::copy_as)
+            .with_command("tinymist.importMarkdown", State::import_markdown)
             .with_command("tinymist.doClearCache", State::clear_cache)
             .with_command("tinymist.pinMain", State::pin_document)
             .with_command("tinymist.focusMain", State::focus_document)
@@ -361,6 +369,14 @@ impl ServerState {
             ServerEvent::UnpinPrimaryByPreview => {
                 ready.set_pin_by_preview(false, false);
             }
+            #[cfg(feature = "preview")]
+            ServerEvent::ReviewComment(info) => {
+                ready
+                    .add_review_comment(info)
+                    .log_error("failed to add review comment");
+            }
+            #[cfg(feature = "preview")]
+            ServerEvent::DisposeSnippetPreview => ready.dispose_snippet_preview(),
         }
 
         Ok(())
@@ -391,6 +407,12 @@ impl ServerState {
 pub enum ServerEvent {
     /// Updates the `pinning_by_preview` status to false.
     UnpinPrimaryByPreview,
+    /// Stores a review comment left in a preview.
+    #[cfg(feature = "preview")]
+    ReviewComment(tinymist_preview::ReviewCommentInfo),
+    /// Removes the entry of the killed snippet preview.
+    #[cfg(feature = "preview")]
+    DisposeSnippetPreview,
 }
 
 impl ServerState {
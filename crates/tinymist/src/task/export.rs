@@ -21,8 +21,9 @@ use typst::visualize::Color;
 use super::{FutureFolder, SyncTaskFactory};
 use crate::project::{
     ApplyProjectTask, CompiledArtifact, DevEvent, DevExportEvent, EntryReader, ExportHtmlTask,
-    ExportPdfTask, ExportPngTask, ExportSvgTask, ExportTask as ProjectExportTask, ExportTeXTask,
-    ExportTextTask, LspCompiledArtifact, ProjectClient, ProjectTask, QueryTask, TaskWhen,
+    ExportOdtTask, ExportPdfTask, ExportPngTask, ExportSvgTask, ExportTask as ProjectExportTask,
+    ExportTeXTask, ExportTextTask, Id, LspCompiledArtifact, ProjectClient, ProjectTask, QueryTask,
+    TaskHook, TaskWhen,
 };
 use crate::{actor::editor::EditorRequest, tool::word_count};
 
@@ -71,7 +72,7 @@ impl ExportTask {
         config: &Arc<ExportUserConfig>,
         client: &std::sync::Arc<(dyn ProjectClient + 'static)>,
     ) -> Option<()> {
-        let doc = artifact.doc.as_ref()?;
+        let doc = artifact.doc.as_ref();
         let s = artifact.snap.signal;
 
         let when = config.task.when().unwrap_or(&TaskWhen::Never);
@@ -80,8 +81,19 @@ impl ExportTask {
             TaskWhen::Script => s.by_entry_update,
             TaskWhen::OnType => s.by_mem_events,
             TaskWhen::OnSave => s.by_fs_events,
-            TaskWhen::OnDocumentHasTitle => s.by_fs_events && doc.info().title.is_some(),
+            TaskWhen::OnDocumentHasTitle => {
+                s.by_fs_events && doc.is_none_or(|doc| doc.info().title.is_some())
+            }
         };
+        // Nothing is exported if the compilation fails, but the failure hooks are
+        // still run. They are only run on saves since a document fails to compile
+        // frequently while it is being typed.
+        let run_failure_hooks = s.by_fs_events;
+        let need_export = need_export
+            && (doc.is_some()
+                || (run_failure_hooks
+                    && (config.task.as_export())
+                        .is_some_and(|task| !task.hooks(false).is_empty())));
 
         let export_hook = config.development.then_some({
             let client = client.clone();
@@ -116,7 +128,25 @@ impl ExportTask {
             let task = config.task.clone();
             let artifact = artifact.clone();
             Box::pin(async move {
-                log_err(Self::do_export(task, artifact, None).await);
+                // The task is recorded by the ID of its document in the lock file.
+                let task_id = Id::from_world(artifact.world()).map(|id| id.to_string());
+                let diagnostics = artifact.error_cnt() + artifact.warning_cnt();
+                let hooks = task.as_export().cloned();
+
+                let res = if artifact.doc.is_some() {
+                    Self::do_export(task, artifact, None).await
+                } else {
+                    Err(error_once!("cannot export with compilation errors"))
+                };
+                if let Some(hooks) = hooks {
+                    let payload = HookPayload::new(task_id.unwrap_or_default(), diagnostics, &res);
+                    let hooks = hooks.hooks(payload.success).to_vec();
+                    // The hooks are run out of the export, which shouldn't wait for them.
+                    if !hooks.is_empty() && (payload.success || run_failure_hooks) {
+                        tokio::spawn(async move { run_hooks(&hooks, &payload).await });
+                    }
+                }
+                log_err(res);
                 if let Some(f) = export_hook {
                     f()
                 }
@@ -341,6 +371,22 @@ impl ExportTask {
 
                     Bytes::from_string(conv)
                 }
+                ExportOdt(ExportOdtTask {
+                    processor,
+                    export: _,
+                }) => {
+                    log::info!("ExportTask({export_id}): exporting to ODT with processor {processor:?}");
+                    let conv = Typlite::new(Arc::new(graph.world().clone()))
+                        .with_format(Format::Odt)
+                        .with_feature(typlite::TypliteFeat {
+                            processor,
+                            ..Default::default()
+                        })
+                        .to_odt()
+                        .map_err(|e| anyhow::anyhow!("failed to convert to odt: {e}"))?;
+
+                    Bytes::new(conv)
+                }
                 ExportSvg(ExportSvgTask { export }) => {
                     let (is_first, merged_gap) = get_page_selection(&export)?;
 
@@ -390,6 +436,121 @@ impl ExportTask {
     }
 }
 
+/// The information passed to the hooks of an export task.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HookPayload {
+    /// The ID of the task, which is the ID of its document in the lock file.
+    task_id: String,
+    /// Whether the export succeeded.
+    success: bool,
+    /// The path to the exported artifact.
+    artifact: Option<PathBuf>,
+    /// The number of diagnostics of the compilation.
+    diagnostics: usize,
+    /// The error message if the export failed.
+    error: Option<String>,
+}
+
+impl HookPayload {
+    pub(crate) fn new(task_id: String, diagnostics: usize, res: &Result<Option<PathBuf>>) -> Self {
+        Self {
+            task_id,
+            success: res.is_ok(),
+            artifact: res.as_ref().ok().cloned().flatten(),
+            diagnostics,
+            error: res.as_ref().err().map(|e| e.to_string()),
+        }
+    }
+
+    fn envs(&self) -> [(&'static str, String); 4] {
+        let artifact = self.artifact.as_ref();
+        [
+            ("TINYMIST_TASK_ID", self.task_id.clone()),
+            (
+                "TINYMIST_ARTIFACT",
+                artifact
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default(),
+            ),
+            ("TINYMIST_DIAGNOSTICS", self.diagnostics.to_string()),
+            (
+                "TINYMIST_STATUS",
+                (if self.success { "success" } else { "failure" }).to_owned(),
+            ),
+        ]
+    }
+}
+
+/// Runs the hooks of an export task in order, logging their failures.
+pub(crate) async fn run_hooks(hooks: &[TaskHook], payload: &HookPayload) {
+    for hook in hooks {
+        log::info!("ExportTask({}): running hook {hook:?}", payload.task_id);
+        if let Err(err) = run_hook(hook, payload).await {
+            log::error!("ExportTask({}): hook failed: {err}", payload.task_id);
+        }
+    }
+}
+
+/// Runs a hook, which is killed if it doesn't complete in time.
+async fn run_hook(hook: &TaskHook, payload: &HookPayload) -> Result<()> {
+    let timeout = hook.timeout();
+    match tokio::time::timeout(timeout, run_hook_once(hook, payload)).await {
+        Ok(res) => res,
+        Err(..) => bail!("hook timed out after {timeout:?}"),
+    }
+}
+
+async fn run_hook_once(hook: &TaskHook, payload: &HookPayload) -> Result<()> {
+    match hook {
+        TaskHook::Command { command, args, .. } => {
+            let output = tokio::process::Command::new(command)
+                .args(args)
+                .envs(payload.envs())
+                .stdin(std::process::Stdio::null())
+                .kill_on_drop(true)
+                .output()
+                .await
+                .with_context("failed to run hook command", || {
+                    Some(Box::new([("command", command.clone())]))
+                })?;
+
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if !stdout.trim().is_empty() {
+                log::info!("ExportTask({}): hook stdout: {stdout}", payload.task_id);
+            }
+            if !stderr.trim().is_empty() {
+                log::info!("ExportTask({}): hook stderr: {stderr}", payload.task_id);
+            }
+            if !output.status.success() {
+                bail!("hook command {command:?} exited with {}", output.status);
+            }
+        }
+        TaskHook::Webhook { url, .. } => {
+            let body = serde_json::to_vec(payload).context("failed to serialize hook payload")?;
+            let response = reqwest::Client::new()
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body)
+                .send()
+                .await
+                .context_ut("failed to post webhook")?;
+
+            let status = response.status();
+            if !status.is_success() {
+                bail!("webhook {url:?} responded with {status}");
+            }
+            log::info!(
+                "ExportTask({}): webhook responded with {status}",
+                payload.task_id
+            );
+        }
+    }
+
+    Ok(())
+}
+
 /// User configuration for export.
 #[derive(Clone, PartialEq, Eq)]
 pub struct ExportUserConfig {
@@ -522,6 +683,38 @@ mod tests {
         assert!(parse_color("invalid".to_owned()).is_err());
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_hook() {
+        let payload = HookPayload {
+            task_id: "file:main.typ".into(),
+            success: true,
+            artifact: Some(PathBuf::from("/tmp/main.pdf")),
+            diagnostics: 2,
+            error: None,
+        };
+        let sh = |script: &str, timeout| TaskHook::Command {
+            command: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            timeout,
+        };
+
+        let check_envs = sh(
+            r#"test "$TINYMIST_TASK_ID" = file:main.typ &&
+            test "$TINYMIST_ARTIFACT" = /tmp/main.pdf &&
+            test "$TINYMIST_DIAGNOSTICS" = 2 &&
+            test "$TINYMIST_STATUS" = success"#,
+            None,
+        );
+        run_hook(&check_envs, &payload).await.unwrap();
+        assert!(run_hook(&sh("exit 1", None), &payload).await.is_err());
+
+        let err = run_hook(&sh("sleep 10", Some(1)), &payload)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"), "{err}");
+    }
+
     #[test]
     fn compilation_default_never() {
         let args = CompileOnceArgs::parse_from(["tinymist", "main.typ"]);
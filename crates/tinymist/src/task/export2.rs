@@ -172,6 +172,17 @@ impl WorldComputable<LspCompilerFeat> for ProjectExport {
 
                     Ok(TypliteTeXExport::run(graph)?.map(Bytes::from_string))
                 }
+                ExportOdt(..) => {
+                    let doc = graph.compute::<OptionDocumentTask<TypstPagedDocument>>()?;
+                    let doc = doc.as_ref();
+                    let n =
+                        ExportTimings::needs_run(&graph.snap, when, doc.as_deref()).unwrap_or(true);
+                    if !n {
+                        return Ok(None);
+                    }
+
+                    Ok(TypliteOdtExport::run(graph)?.map(Bytes::new))
+                }
                 ExportText(config) => Self::export_string::<_, TextExport>(graph, when, config),
                 Query(..) => todo!(),
             }
@@ -228,3 +239,24 @@ impl<const F: char> WorldComputable<LspCompilerFeat> for TypliteExport<F> {
 
 pub type TypliteMdExport = TypliteExport<'m'>;
 pub type TypliteTeXExport = TypliteExport<'x'>;
+
+pub struct TypliteOdtExport;
+
+impl TypliteOdtExport {
+    fn run(graph: &Arc<WorldComputeGraph<LspCompilerFeat>>) -> Result<Option<Vec<u8>>> {
+        let conv = Typlite::new(Arc::new(graph.snap.world.clone()))
+            .with_format(Format::Odt)
+            .to_odt()
+            .map_err(|e| anyhow::anyhow!("failed to convert to ODT: {e}"))?;
+
+        Ok(Some(conv))
+    }
+}
+
+impl WorldComputable<LspCompilerFeat> for TypliteOdtExport {
+    type Output = Option<Vec<u8>>;
+
+    fn compute(graph: &Arc<WorldComputeGraph<LspCompilerFeat>>) -> Result<Self::Output> {
+        Self::run(graph)
+    }
+}
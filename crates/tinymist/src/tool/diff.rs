@@ -0,0 +1,742 @@
+//! Visual diff of a document against a git revision.
+
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use reflexo::{path::unix_slash, ImmutPath};
+use reflexo_typst::WorldComputeGraph;
+use serde::Serialize;
+use tinymist_std::typst::{TypstDocument, TypstPagedDocument};
+use tinymist_std::{bail, error::prelude::*};
+use typst::diag::FileError;
+use typst::foundations::Bytes;
+use typst::layout::{Abs, Frame, FrameItem, Page, Point};
+use typst::syntax::{FileId, VirtualPath};
+use typst::visualize::Color;
+
+use crate::project::*;
+use crate::world::system::print_diagnostics;
+use crate::world::vfs::FileSnapshot;
+use crate::world::{EntryReader, EntryState};
+
+/// Arguments for comparing a document against a git revision.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct DiffArgs {
+    /// Argument to identify the document.
+    #[clap(flatten)]
+    pub declare: DocNewArgs,
+
+    /// The git revision to compare against, e.g. `HEAD~1` or a branch name.
+    #[clap(long, default_value = "HEAD")]
+    pub rev: String,
+
+    /// Path to the report, which is a PDF or PNG file according to the
+    /// extension. Defaults to `<name>.diff.pdf` next to the document.
+    #[clap(long, short = 'o', value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// The resolution of the rendered pages, in pixels per inch.
+    #[clap(long, default_value_t = 96.)]
+    pub ppi: f32,
+
+    /// Opens the report in the default viewer after writing it.
+    #[clap(long)]
+    pub open: bool,
+
+    /// Serves the report in the browser instead of writing it to a file. The
+    /// report is a snapshot, which is not updated when the files change.
+    #[cfg(feature = "preview")]
+    #[clap(long, conflicts_with = "output")]
+    pub serve: bool,
+
+    /// The address the report server binds to.
+    #[cfg(feature = "preview")]
+    #[clap(
+        long,
+        default_value = "127.0.0.1:23625",
+        value_name = "HOST",
+        requires = "serve"
+    )]
+    pub data_plane_host: String,
+}
+
+/// The template of the report, which reads the [`DiffReport`] from
+/// `/report.json` and the rendered pages from the paths in it.
+const REPORT_TEMPLATE: &str = r##"#let report = json("/report.json")
+#set page(width: auto, height: auto, margin: 24pt)
+#set text(size: 10pt)
+
+#let removed = rgb("#d73a49")
+#let inserted = rgb("#22863a")
+#let side(label, img) = block(width: if img == none { auto } else { img.width * 1pt })[
+  #strong(label) \
+  #if img == none [_No page_] else {
+    box(stroke: 0.5pt + gray, image(img.path, width: img.width * 1pt))
+  }
+]
+
+= Changes of #raw(report.main) since #raw(report.rev)
+
+#report.pages.len() changed and #report.unchanged unchanged pages.
+
+#for page in report.pages {
+  pagebreak(weak: true)
+  heading(level: 2, page.title)
+  grid(columns: 2, column-gutter: 16pt, side([Before], page.old), side([After], page.new))
+  for change in page.text {
+    let (color, sign) = if change.kind == "inserted" { (inserted, "+ ") } else { (removed, "- ") }
+    block(spacing: 4pt, text(fill: color, raw(sign + change.text)))
+  }
+}
+"##;
+
+/// The channel difference below which two pixels are considered the same,
+/// which tolerates anti-aliasing noise.
+const PIXEL_TOLERANCE: u8 = 16;
+
+/// Compares a document in the working tree against a git revision, and writes
+/// a report highlighting the changed pages, or serves it in the browser.
+pub async fn diff_main(args: DiffArgs) -> Result<()> {
+    let ppi = args.ppi;
+    if ppi <= 1e-6 {
+        bail!("invalid ppi: {ppi}");
+    }
+
+    let input = args.declare.to_input();
+    let lock_dir: ImmutPath = std::env::current_dir().context("lock directory")?.into();
+
+    // Compiles the working tree.
+    let mut new_universe = (input.clone(), lock_dir.clone()).resolve()?;
+    let (compiled, new_doc) = compile_paged(&new_universe, "the working tree")?;
+    let root = compiled
+        .world()
+        .entry_state()
+        .workspace_root()
+        .context("cannot determine the root of the document")?;
+
+    // Compiles the revision, by shadowing the files in the root with the blobs.
+    let revision = GitRevision::resolve(&root, &args.rev)?;
+    let files = revision.read_files(&root)?;
+    let mut old_universe = (input, lock_dir).resolve()?;
+    let tracked = files.iter().map(|(path, _)| path.as_path());
+    let tracked = tracked.collect::<HashSet<_>>();
+    old_universe
+        .increment_revision(|verse| {
+            let mut vfs = verse.vfs();
+            // Hides the files that are not tracked at the revision.
+            for path in compiled.world().depended_fs_paths().iter() {
+                if path.starts_with(&root) && !tracked.contains(&**path) {
+                    let not_found = FileError::NotFound(path.to_path_buf());
+                    vfs.map_shadow(path, FileSnapshot::from(Err(not_found)))?;
+                }
+            }
+            for (path, content) in &files {
+                vfs.map_shadow(path, FileSnapshot::from(Ok(content.clone())))?;
+            }
+
+            Ok::<_, FileError>(())
+        })
+        .context_ut("shadow files at the revision")?;
+    let (_, old_doc) = compile_paged(&old_universe, &format!("the revision {}", args.rev))?;
+
+    // Compares the pages.
+    let ppp = ppi / 72.;
+    let old_text = old_doc.pages.iter().map(|p| page_text(&p.frame));
+    let old_text = old_text.collect::<Vec<_>>();
+    let new_text = new_doc.pages.iter().map(|p| page_text(&p.frame));
+    let new_text = new_text.collect::<Vec<_>>();
+
+    let mut report = DiffReport {
+        main: args.declare.id.input.clone(),
+        rev: format!("{} ({})", args.rev, revision.short_commit()),
+        unchanged: 0,
+        pages: vec![],
+    };
+    let mut images = vec![];
+    for pair in align_pages(&old_text, &new_text) {
+        let old_page = pair.old.map(|idx| &old_doc.pages[idx]);
+        let new_page = pair.new.map(|idx| &new_doc.pages[idx]);
+        let mut old_pixmap = old_page.map(|page| typst_render::render(page, ppp));
+        let mut new_pixmap = new_page.map(|page| typst_render::render(page, ppp));
+
+        let text = diff_lines(
+            pair.old.map_or("", |idx| old_text[idx].as_str()),
+            pair.new.map_or("", |idx| new_text[idx].as_str()),
+        );
+        let pixels_changed = match (&mut old_pixmap, &mut new_pixmap) {
+            (Some(old), Some(new)) => {
+                let (old_w, old_h) = (old.width() as usize, old.height() as usize);
+                let (new_w, new_h) = (new.width() as usize, new.height() as usize);
+                highlight_pixels(
+                    Raster::new(old.data_mut(), old_w, old_h),
+                    Raster::new(new.data_mut(), new_w, new_h),
+                ) > 0
+            }
+            _ => true,
+        };
+        if !pixels_changed && text.is_empty() {
+            report.unchanged += 1;
+            continue;
+        }
+
+        let idx = report.pages.len();
+        let mut image = |side: &str, page: &Page, png: Vec<u8>| {
+            let path = format!("/{side}-{idx}.png");
+            images.push((path.clone(), Bytes::new(png)));
+            PageImage {
+                path,
+                width: page.frame.width().to_pt(),
+            }
+        };
+        let old = match old_page.zip(old_pixmap) {
+            Some((page, pixmap)) => {
+                let png = pixmap.encode_png().context_ut("failed to encode PNG")?;
+                Some(image("old", page, png))
+            }
+            None => None,
+        };
+        let new = match new_page.zip(new_pixmap) {
+            Some((page, pixmap)) => {
+                let png = pixmap.encode_png().context_ut("failed to encode PNG")?;
+                Some(image("new", page, png))
+            }
+            None => None,
+        };
+
+        report.pages.push(PageDiff {
+            title: page_title(pair),
+            old,
+            new,
+            text,
+        });
+    }
+
+    // The report is compiled in the universe of the working tree, whose entry is
+    // replaced by the report.
+    map_report(&mut new_universe, &report, images)?;
+    if report.pages.is_empty() {
+        eprintln!("no changes since {}", report.rev);
+    } else {
+        eprintln!("{} pages changed since {}", report.pages.len(), report.rev);
+    }
+
+    #[cfg(feature = "preview")]
+    if args.serve {
+        use clap::Parser;
+
+        use crate::tool::preview::{serve_preview, PreviewCliArgs};
+
+        // The report is served by the preview, which is static as the report
+        // only depends on the memory files. The control plane is not used, so it
+        // binds to a random port.
+        let preview = PreviewCliArgs::try_parse_from([
+            "preview",
+            "--data-plane-host",
+            &args.data_plane_host,
+            "--control-plane-host",
+            "127.0.0.1:0",
+        ])
+        .context_ut("preview arguments")?;
+        return serve_preview(preview, new_universe).await;
+    }
+
+    let report_doc = typst::compile::<TypstPagedDocument>(&new_universe.snapshot())
+        .output
+        .map_err(|err| anyhow::anyhow!("cannot compile the report: {err:?}"))?;
+
+    // Writes the report.
+    let output = args
+        .output
+        .unwrap_or_else(|| Path::new(&args.declare.id.input).with_extension("diff.pdf"));
+    let data = match output.extension().and_then(|ext| ext.to_str()) {
+        Some("png") => {
+            let pixmap =
+                typst_render::render_merged(&report_doc, ppp, Abs::pt(16.), Some(Color::WHITE));
+            pixmap.encode_png().context_ut("failed to encode PNG")?
+        }
+        Some("pdf") => typst_pdf::pdf(&report_doc, &typst_pdf::PdfOptions::default())?,
+        _ => bail!("the report must be a PDF or PNG file: {output:?}"),
+    };
+    std::fs::write(&output, data).context("write report")?;
+    eprintln!("report written to {output:?}");
+
+    if args.open {
+        open::that_detached(&output).context("open report")?;
+    }
+
+    Ok(())
+}
+
+/// Compiles the universe to a paged document.
+fn compile_paged(
+    universe: &LspUniverse,
+    what: &str,
+) -> Result<(LspCompiledArtifact, Arc<TypstPagedDocument>)> {
+    let graph = WorldComputeGraph::from_world(universe.snapshot());
+    let compiled = CompiledArtifact::from_graph(graph, false);
+
+    let diag = compiled.diagnostics();
+    print_diagnostics(compiled.world(), diag, DiagnosticFormat::Human)
+        .context_ut("print diagnostics")?;
+
+    match &compiled.doc {
+        Some(TypstDocument::Paged(doc)) if !compiled.has_errors() => {
+            let doc = doc.clone();
+            Ok((compiled, doc))
+        }
+        _ => bail!("failed to compile {what}"),
+    }
+}
+
+/// Replaces the entry of the universe by the report with the rendered pages.
+fn map_report(
+    universe: &mut LspUniverse,
+    report: &DiffReport,
+    images: Vec<(String, Bytes)>,
+) -> Result<()> {
+    let json = serde_json::to_vec(report).context("serialize report")?;
+    let files = images.into_iter().chain([
+        ("/report.json".to_owned(), Bytes::new(json)),
+        (
+            "/report.typ".to_owned(),
+            Bytes::from_string(REPORT_TEMPLATE),
+        ),
+    ]);
+
+    universe.increment_revision(|verse| {
+        let mut vfs = verse.vfs();
+        for (path, content) in files {
+            let id = FileId::new(None, VirtualPath::new(&path));
+            vfs.map_shadow_by_id(id, FileSnapshot::from(Ok(content)))
+                .context_ut("map report file")?;
+        }
+        drop(vfs);
+
+        let entry = EntryState::new_rootless(VirtualPath::new("/report.typ"));
+        verse
+            .mutate_entry(entry)
+            .map_err(|err| anyhow::anyhow!("cannot enter the report: {err:?}"))?;
+        Ok(())
+    })
+}
+
+/// The data of the report.
+#[derive(Debug, Serialize)]
+struct DiffReport {
+    /// The path to the document.
+    main: String,
+    /// The compared revision.
+    rev: String,
+    /// The number of unchanged pages.
+    unchanged: usize,
+    /// The changed pages.
+    pages: Vec<PageDiff>,
+}
+
+/// A changed page in the report.
+#[derive(Debug, Serialize)]
+struct PageDiff {
+    /// The title of the page.
+    title: String,
+    /// The page at the revision, with the changed pixels highlighted.
+    old: Option<PageImage>,
+    /// The page in the working tree, with the changed pixels highlighted.
+    new: Option<PageImage>,
+    /// The changed lines of text.
+    text: Vec<LineChange>,
+}
+
+/// A rendered page in the report.
+#[derive(Debug, Serialize)]
+struct PageImage {
+    /// The path to the image in the report.
+    path: String,
+    /// The width of the page in points.
+    width: f64,
+}
+
+/// A changed line of text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "text", rename_all = "camelCase")]
+enum LineChange {
+    Removed(String),
+    Inserted(String),
+}
+
+/// A git revision to read files from.
+struct GitRevision {
+    /// The top-level directory of the repository.
+    toplevel: PathBuf,
+    /// The hash of the commit.
+    commit: String,
+}
+
+impl GitRevision {
+    /// Resolves the revision in the repository containing `dir`.
+    fn resolve(dir: &Path, rev: &str) -> Result<Self> {
+        let toplevel = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let commit = git(
+            dir,
+            &["rev-parse", "--verify", &format!("{rev}^{{commit}}")],
+        )?;
+
+        Ok(Self {
+            toplevel: PathBuf::from(toplevel.trim()),
+            commit: commit.trim().to_owned(),
+        })
+    }
+
+    fn short_commit(&self) -> &str {
+        &self.commit[..self.commit.len().min(10)]
+    }
+
+    /// Reads the files under `dir` at the revision.
+    fn read_files(&self, dir: &Path) -> Result<Vec<(PathBuf, Bytes)>> {
+        let toplevel = self.toplevel.canonicalize().context("canonicalize")?;
+        let prefix = dir.canonicalize().context("canonicalize")?;
+        let prefix = prefix
+            .strip_prefix(&toplevel)
+            .context("the root of the document is not in the git repository")?;
+        let prefix = unix_slash(prefix);
+
+        // Lists the blobs, in the format of `<mode> <type> <object>\t<path>`.
+        let mut ls_tree = vec!["ls-tree", "-r", "-z", self.commit.as_str()];
+        if !prefix.is_empty() {
+            ls_tree.extend(["--", prefix.as_str()]);
+        }
+        let listed = git(&self.toplevel, &ls_tree)?;
+        let blobs = listed
+            .split('\0')
+            .filter_map(|entry| {
+                let (info, path) = entry.split_once('\t')?;
+                let mut info = info.split(' ');
+                let (_mode, kind, object) = (info.next()?, info.next()?, info.next()?);
+                (kind == "blob").then_some((object, path))
+            })
+            .collect::<Vec<_>>();
+
+        // Reads the blobs in a batch.
+        let mut child = Command::new("git")
+            .arg("-C")
+            .arg(&self.toplevel)
+            .args(["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .context("spawn git")?;
+        let mut stdin = child.stdin.take().context("git stdin")?;
+        let requests = blobs.iter().map(|(object, _)| format!("{object}\n"));
+        let requests = requests.collect::<String>();
+        let writer = std::thread::spawn(move || stdin.write_all(requests.as_bytes()));
+
+        let mut stdout = BufReader::new(child.stdout.take().context("git stdout")?);
+        let mut files = Vec::with_capacity(blobs.len());
+        for (object, path) in blobs {
+            // The header is `<object> <type> <size>`.
+            let mut header = String::new();
+            stdout.read_line(&mut header).context("read git object")?;
+            let Some(size) = header.trim_end().rsplit(' ').next() else {
+                bail!("unexpected git object header: {header:?}");
+            };
+            let Ok(size) = size.parse::<usize>() else {
+                bail!("cannot read git object {object}: {header:?}");
+            };
+
+            // The content is followed by a line feed.
+            let mut content = vec![0; size + 1];
+            stdout.read_exact(&mut content).context("read git object")?;
+            content.pop();
+
+            let path = Path::new(path)
+                .strip_prefix(&prefix)
+                .unwrap_or(Path::new(path));
+            files.push((dir.join(path), Bytes::new(content)));
+        }
+
+        let _ = writer.join();
+        child.wait().context("wait git")?;
+        Ok(files)
+    }
+}
+
+/// Runs a git command in `dir` and returns the stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("run git")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git {} failed: {}", args.join(" "), stderr.trim());
+    }
+
+    String::from_utf8(output.stdout).context("git output")
+}
+
+/// A pair of aligned pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PagePair {
+    old: Option<usize>,
+    new: Option<usize>,
+}
+
+fn page_title(pair: PagePair) -> String {
+    match (pair.old, pair.new) {
+        (Some(old), Some(new)) if old == new => format!("Page {}", new + 1),
+        (Some(old), Some(new)) => format!("Page {} (was page {})", new + 1, old + 1),
+        (None, Some(new)) => format!("Page {} (inserted)", new + 1),
+        (Some(old), None) => format!("Page {} (removed)", old + 1),
+        (None, None) => unreachable!(),
+    }
+}
+
+/// Aligns the pages by their text, so that an inserted or removed page does
+/// not shift the comparison of the following pages. The unmatched pages
+/// between two matched pages are compared one by one.
+fn align_pages(old: &[String], new: &[String]) -> Vec<PagePair> {
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    let matches = lcs(old, new).into_iter().map(Some).chain([None]);
+    for matched in matches {
+        let (x, y) = matched.unwrap_or((old.len(), new.len()));
+        while i < x && j < y {
+            pairs.push(PagePair {
+                old: Some(i),
+                new: Some(j),
+            });
+            (i, j) = (i + 1, j + 1);
+        }
+        pairs.extend((i..x).map(|i| PagePair {
+            old: Some(i),
+            new: None,
+        }));
+        pairs.extend((j..y).map(|j| PagePair {
+            old: None,
+            new: Some(j),
+        }));
+        if matched.is_some() {
+            pairs.push(PagePair {
+                old: Some(x),
+                new: Some(y),
+            });
+        }
+        (i, j) = (x + 1, y + 1);
+    }
+
+    pairs
+}
+
+/// Computes the changed lines between two texts.
+fn diff_lines(old: &str, new: &str) -> Vec<LineChange> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+
+    let mut changes = vec![];
+    let (mut i, mut j) = (0, 0);
+    let matches = lcs(&old, &new).into_iter().chain([(old.len(), new.len())]);
+    for (x, y) in matches {
+        let removed = old[i..x].iter().map(|l| LineChange::Removed(l.to_string()));
+        changes.extend(removed);
+        let inserted = new[j..y]
+            .iter()
+            .map(|l| LineChange::Inserted(l.to_string()));
+        changes.extend(inserted);
+        (i, j) = (x + 1, y + 1);
+    }
+
+    changes
+}
+
+/// Computes the longest common subsequence, as pairs of indices.
+fn lcs<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    let (n, m) = (old.len(), new.len());
+    let mut table = vec![0u32; (n + 1) * (m + 1)];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[at(i, j)] = if old[i] == new[j] {
+                table[at(i + 1, j + 1)] + 1
+            } else {
+                table[at(i + 1, j)].max(table[at(i, j + 1)])
+            };
+        }
+    }
+
+    let mut res = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            res.push((i, j));
+            (i, j) = (i + 1, j + 1);
+        } else if table[at(i + 1, j)] >= table[at(i, j + 1)] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    res
+}
+
+/// Extracts the text of a page, line by line.
+fn page_text(frame: &Frame) -> String {
+    fn collect(frame: &Frame, offset: Point, lines: &mut Vec<(Abs, String)>) {
+        for (pos, item) in frame.items() {
+            let pos = offset + *pos;
+            match item {
+                FrameItem::Group(group) => collect(&group.frame, pos, lines),
+                FrameItem::Text(text) => match lines.last_mut() {
+                    Some((y, line)) if (*y - pos.y).abs() < Abs::pt(1.) => {
+                        line.push_str(&text.text)
+                    }
+                    _ => lines.push((pos.y, text.text.to_string())),
+                },
+                FrameItem::Shape(..)
+                | FrameItem::Image(..)
+                | FrameItem::Link(..)
+                | FrameItem::Tag(..) => {}
+            }
+        }
+    }
+
+    let mut lines = vec![];
+    collect(frame, Point::zero(), &mut lines);
+    let lines = lines.into_iter().map(|(_, line)| line);
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+/// A mutable view of RGBA pixels.
+struct Raster<'a> {
+    data: &'a mut [u8],
+    width: usize,
+    height: usize,
+}
+
+impl<'a> Raster<'a> {
+    fn new(data: &'a mut [u8], width: usize, height: usize) -> Self {
+        Self {
+            data,
+            width,
+            height,
+        }
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> Option<&[u8]> {
+        (x < self.width && y < self.height).then(|| {
+            let idx = (y * self.width + x) * 4;
+            &self.data[idx..idx + 4]
+        })
+    }
+
+    fn tint(&mut self, x: usize, y: usize, color: [u8; 3]) {
+        if x < self.width && y < self.height {
+            let idx = (y * self.width + x) * 4;
+            let pixel = &mut self.data[idx..idx + 4];
+            for (channel, color) in pixel.iter_mut().zip(color) {
+                *channel = ((*channel as u16 + color as u16 * 2) / 3) as u8;
+            }
+            pixel[3] = 255;
+        }
+    }
+}
+
+/// Highlights the differing pixels of two rendered pages, and returns the
+/// number of them.
+fn highlight_pixels(mut old: Raster, mut new: Raster) -> usize {
+    const REMOVED: [u8; 3] = [0xd7, 0x3a, 0x49];
+    const INSERTED: [u8; 3] = [0x22, 0x86, 0x3a];
+
+    let mut changed = 0;
+    for y in 0..old.height.max(new.height) {
+        for x in 0..old.width.max(new.width) {
+            let same = match (old.pixel(x, y), new.pixel(x, y)) {
+                (Some(a), Some(b)) => a
+                    .iter()
+                    .zip(b)
+                    .all(|(a, b)| a.abs_diff(*b) <= PIXEL_TOLERANCE),
+                _ => false,
+            };
+            if !same {
+                changed += 1;
+                old.tint(x, y, REMOVED);
+                new.tint(x, y, INSERTED);
+            }
+        }
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_align_inserted_page() {
+        let old = pages(&["a", "b", "c"]);
+        let new = pages(&["a", "x", "b", "c"]);
+
+        let pairs = align_pages(&old, &new);
+        let pairs = pairs.iter().map(|p| (p.old, p.new)).collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![
+                (Some(0), Some(0)),
+                (None, Some(1)),
+                (Some(1), Some(2)),
+                (Some(2), Some(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_align_modified_page() {
+        let old = pages(&["a", "b", "c"]);
+        let new = pages(&["a", "B", "c", "d"]);
+
+        let pairs = align_pages(&old, &new);
+        let pairs = pairs.iter().map(|p| (p.old, p.new)).collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![
+                (Some(0), Some(0)),
+                (Some(1), Some(1)),
+                (Some(2), Some(2)),
+                (None, Some(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let changes = diff_lines("Title\nold line\nend", "Title\nnew line\nend\nmore");
+        assert_eq!(
+            changes,
+            vec![
+                LineChange::Removed("old line".into()),
+                LineChange::Inserted("new line".into()),
+                LineChange::Inserted("more".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_pixels() {
+        let mut old = [255u8; 2 * 2 * 4];
+        let mut new = [255u8; 2 * 2 * 4];
+        new[4..8].copy_from_slice(&[0, 0, 0, 255]);
+
+        let changed = highlight_pixels(Raster::new(&mut old, 2, 2), Raster::new(&mut new, 2, 2));
+        assert_eq!(changed, 1);
+        assert_ne!(old[4..8], [255; 4]);
+        assert_eq!(old[0..4], [255; 4]);
+    }
+}
@@ -1,6 +1,7 @@
 //! All the language tools provided by the `tinymist` crate.
 
 pub mod ast;
+pub mod diff;
 pub mod package;
 pub mod project;
 pub mod testing;
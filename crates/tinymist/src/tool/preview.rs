@@ -1,12 +1,17 @@
 //! Document preview tool for Typst
 
 pub use compile::{PreviewCompileView, ProjectPreviewHandler};
-pub use http::{make_http_server, HttpServer};
+pub use http::{make_http_server, HttpServer, ServerAccess};
+pub use terminal::GraphicsProtocol;
 
 mod compile;
 mod http;
+mod participants;
+mod review;
+mod terminal;
 
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::path::{Path, PathBuf};
+use std::{collections::HashMap, sync::Arc};
 
 use clap::{Parser, ValueEnum};
 use futures::{SinkExt, StreamExt, TryStreamExt};
@@ -24,10 +29,11 @@ use tinymist_preview::{
 };
 use tinymist_query::{LspPosition, LspRange};
 use tinymist_std::error::IgnoreLogging;
+use tinymist_std::ImmutPath;
 use tokio::sync::{mpsc, oneshot};
 
 use crate::actor::preview::{PreviewActor, PreviewRequest, PreviewTab};
-use crate::project::{ProjectInsId, ProjectPreviewState, WorldProvider};
+use crate::project::{LspUniverse, ProjectInsId, ProjectPreviewState, WorldProvider};
 use crate::tool::project::{start_project, ProjectOpts, StartProjectResult};
 use crate::*;
 
@@ -105,6 +111,12 @@ pub struct PreviewArgs {
     #[clap(long)]
     pub invert_colors: Option<String>,
 
+    /// The label of the metadata carrying speaker notes, which are shown in
+    /// the presenter console of the slide mode. By default, the `pdfpc`
+    /// metadata of Touying and Polylux is used.
+    #[clap(long, value_name = "LABEL")]
+    pub notes_label: Option<String>,
+
     /// Used by lsp for controlling the preview refresh style.
     #[clap(long, hide(true))]
     pub refresh_style: Option<RefreshStyle>,
@@ -114,6 +126,7 @@ impl PreviewArgs {
     /// Get the configuration for the preview.
     pub fn config(&self, config: &PreviewConfig) -> PreviewConfig {
         PreviewConfig {
+            mode: self.preview_mode,
             enable_partial_rendering: self
                 .enable_partial_rendering
                 .unwrap_or(config.enable_partial_rendering),
@@ -125,6 +138,10 @@ impl PreviewArgs {
                 Some(s) => s.clone(),
                 None => config.invert_colors.clone(),
             },
+            notes_label: self
+                .notes_label
+                .clone()
+                .or_else(|| config.notes_label.clone()),
         }
     }
 }
@@ -191,6 +208,49 @@ pub struct PreviewCliArgs {
     /// set as well, this flag will win.
     #[clap(long = "no-open")]
     pub no_open: bool,
+
+    /// Require a token to access the preview servers. A random token is
+    /// generated and printed in the preview URL unless `--token` is given.
+    #[clap(long)]
+    pub auth: bool,
+
+    /// The token required to access the preview servers. Implies `--auth`.
+    #[clap(long, value_name = "TOKEN")]
+    pub token: Option<String>,
+
+    /// Serve the preview over HTTPS with the certificate chain in the PEM
+    /// file.
+    #[clap(long, value_name = "PATH", requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// The private key of `--tls-cert` in the PEM file.
+    #[clap(long, value_name = "PATH", requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// An origin allowed to connect to the preview servers in addition to the
+    /// local ones, e.g. `https://devbox:23625`. Can be passed multiple times.
+    #[clap(long = "allowed-origin", value_name = "ORIGIN")]
+    pub allowed_origins: Vec<String>,
+
+    /// Allow several editors to connect to the control plane. The first editor
+    /// drives the preview until another one takes the lead, and the others
+    /// follow it. The cursors of all editors are shown in the preview.
+    #[clap(long)]
+    pub multi_client: bool,
+
+    /// Draw the pages in the terminal instead of starting a preview server.
+    /// The current page is redrawn on every compilation.
+    #[clap(long)]
+    pub terminal: bool,
+
+    /// The graphics protocol used to draw the pages in the terminal. By
+    /// default, it is detected from the environment variables.
+    #[clap(long, default_value = "auto", value_name = "PROTOCOL")]
+    pub graphics: GraphicsProtocol,
+
+    /// The resolution of the pages drawn in the terminal, in pixels per inch.
+    #[clap(long, default_value_t = 72., value_name = "PPI")]
+    pub terminal_ppi: f32,
 }
 
 impl PreviewCliArgs {
@@ -198,15 +258,42 @@ impl PreviewCliArgs {
     pub fn open_in_browser(&self, default: bool) -> bool {
         !self.no_open && (self.open || default)
     }
+
+    /// The access control of the preview servers.
+    pub fn access(&self) -> Result<ServerAccess> {
+        let token = match &self.token {
+            Some(token) => Some(token.clone()),
+            None if self.auth => Some(ServerAccess::random_token()?),
+            None => None,
+        };
+        let tls = match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => Some(ServerAccess::load_tls(cert, key)?),
+            _ => None,
+        };
+
+        Ok(ServerAccess {
+            token,
+            allowed_origins: self.allowed_origins.clone(),
+            tls,
+        })
+    }
 }
 
+/// The task id of the snippet preview, which is shared by all snippets.
+pub(crate) const SNIPPET_TASK_ID: &str = "snippet";
+
 /// Response for starting a preview.
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StartPreviewResponse {
     static_server_port: Option<u16>,
     static_server_addr: Option<String>,
+    /// The URL to open the preview, carrying the token if it is required.
+    static_server_url: Option<String>,
     data_plane_port: Option<u16>,
+    /// The URL to connect to the data plane, carrying the token if it is
+    /// required.
+    data_plane_url: Option<String>,
     is_primary: bool,
 }
 
@@ -245,6 +332,62 @@ impl ServerState {
         });
     }
 
+    /// Starts a preview of a snippet, or streams the snippet into the running
+    /// snippet preview. The entry of the snippet is kept in memory and placed
+    /// next to the source file, so that the relative paths still work.
+    pub fn start_snippet_preview(
+        &mut self,
+        path: PathBuf,
+        entry: String,
+        mut cli_args: Vec<String>,
+    ) -> SchedulableResponse<StartPreviewResponse> {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let entry_path: ImmutPath = path.with_file_name(format!(".{stem}.snippet.typ")).into();
+        self.create_source(entry_path.clone(), entry)
+            .map_err(internal_error)?;
+        // The entry of the last snippet is removed if the snippet is from another file.
+        let last = self.preview.snippet_entry.replace(entry_path.clone());
+        if let Some(last) = last.filter(|last| *last != entry_path) {
+            self.remove_source(last)
+                .log_error("failed to remove the snippet entry");
+        }
+
+        let id = ProjectInsId(SNIPPET_TASK_ID.into());
+        if self.project.preview.get(&id).is_some() {
+            self.restart_dedicate(SNIPPET_TASK_ID, Some(entry_path))
+                .map_err(internal_error)?;
+            return just_ok(StartPreviewResponse {
+                static_server_port: None,
+                static_server_addr: None,
+                static_server_url: None,
+                data_plane_port: None,
+                data_plane_url: None,
+                is_primary: false,
+            });
+        }
+
+        cli_args.extend([
+            "--not-primary".to_owned(),
+            format!("--task-id={SNIPPET_TASK_ID}"),
+            entry_path.to_string_lossy().into_owned(),
+        ]);
+        self.start_preview(cli_args, PreviewKind::Regular)
+    }
+
+    /// Removes the entry of the snippet preview from the memory files when the
+    /// preview is killed, unless another snippet preview has been started.
+    pub(crate) fn dispose_snippet_preview(&mut self) {
+        let id = ProjectInsId(SNIPPET_TASK_ID.into());
+        if self.project.preview.get(&id).is_some() {
+            return;
+        }
+
+        if let Some(entry) = self.preview.snippet_entry.take() {
+            self.remove_source(entry)
+                .log_error("failed to remove the snippet entry");
+        }
+    }
+
     /// Starts a preview instance.
     pub fn start_preview(
         &mut self,
@@ -257,6 +400,11 @@ impl ServerState {
             .chain(cli_args.iter().map(|e| e.as_str()));
         let cli_args =
             PreviewCliArgs::try_parse_from(cli_args).map_err(|e| invalid_params(e.to_string()))?;
+        if cli_args.terminal {
+            return Err(invalid_params(
+                "terminal preview is only available in the CLI",
+            ));
+        }
         // default configs
         let config = cli_args.preview.config(&self.config.preview());
 
@@ -341,6 +489,8 @@ pub struct PreviewState {
     pub(crate) watchers: ProjectPreviewState,
     /// Whether to send show document requests with customized notification.
     pub customized_show_document: bool,
+    /// The entry of the snippet preview, which is kept in the memory files.
+    pub(crate) snippet_entry: Option<ImmutPath>,
 }
 
 impl PreviewState {
@@ -367,6 +517,7 @@ impl PreviewState {
             preview_tx,
             watchers,
             customized_show_document: config.customized_show_document,
+            snippet_entry: None,
         }
     }
 
@@ -409,6 +560,7 @@ impl PreviewState {
         if !args.static_file_host.is_empty() && (args.static_file_host != args.data_plane_host) {
             return Err(internal_error("--static-file-host is removed"));
         }
+        let access = args.access().map_err(internal_error)?;
 
         let (lsp_tx, lsp_rx) = ControlPlaneTx::new(false);
         let ControlPlaneRx {
@@ -419,6 +571,7 @@ impl PreviewState {
 
         let (websocket_tx, websocket_rx) = mpsc::unbounded_channel();
 
+        let views = previewer.view_reader();
         let previewer = previewer.build(lsp_tx, compile_handler.clone());
 
         // Forward preview responses to lsp client
@@ -444,6 +597,7 @@ impl PreviewState {
                         }
                     }
                     Outline(s) => client.send_notification::<NotifDocumentOutline>(&s),
+                    ReviewComment(s) => client.send_event(ServerEvent::ReviewComment(s)),
                 }
             }
 
@@ -478,19 +632,29 @@ impl PreviewState {
             // Replace the data plane port in the html to self
             let frontend_html = frontend_html(TYPST_PREVIEW_HTML, args.preview.preview_mode, "/");
 
-            let srv = make_http_server(frontend_html, args.data_plane_host, websocket_tx).await;
+            let srv = make_http_server(
+                frontend_html,
+                args.data_plane_host,
+                websocket_tx,
+                access.clone(),
+                Some(views),
+            )
+            .await;
             let addr = srv.addr;
             log::info!("PreviewTask({task_id}): preview server listening on: {addr}");
 
+            let local_addr = format!("127.0.0.1:{}", addr.port());
             let resp = StartPreviewResponse {
                 static_server_port: Some(addr.port()),
                 static_server_addr: Some(addr.to_string()),
+                static_server_url: Some(access.url(&local_addr)),
                 data_plane_port: Some(addr.port()),
+                data_plane_url: Some(access.ws_url(&local_addr)),
                 is_primary,
             };
 
             if open_in_browser {
-                open::that_detached(format!("http://127.0.0.1:{}", addr.port()))
+                open::that_detached(access.url(&local_addr))
                     .log_error("failed to open browser for preview");
             }
 
@@ -549,6 +713,17 @@ impl PreviewState {
 /// Entry point of the preview tool.
 pub async fn preview_main(args: PreviewCliArgs) -> Result<()> {
     log::info!("Arguments: {args:#?}");
+    if args.terminal {
+        return terminal::terminal_preview_main(args).await;
+    }
+
+    let verse = args.compile.resolve()?;
+    serve_preview(args, verse).await
+}
+
+/// Serves the preview of a universe in the browser, which is recompiled when
+/// the depended files change.
+pub async fn serve_preview(args: PreviewCliArgs, verse: LspUniverse) -> Result<()> {
     let handle = tokio::runtime::Handle::current();
 
     let config = args.preview.config(&PreviewConfig::default());
@@ -560,9 +735,10 @@ pub async fn preview_main(args: PreviewCliArgs) -> Result<()> {
             None
         };
 
+    let access = args.access()?;
+
     exit_on_ctrl_c();
 
-    let verse = args.compile.resolve()?;
     let previewer = PreviewBuilder::new(config);
 
     let (service, handle) = {
@@ -599,14 +775,30 @@ pub async fn preview_main(args: PreviewCliArgs) -> Result<()> {
     };
 
     let (lsp_tx, mut lsp_rx) = ControlPlaneTx::new(true);
+    lsp_rx.resp_rx = review::forward_review_comments(lsp_rx.resp_rx);
 
+    let control_access = access.control_plane(&args.control_plane_host);
+    let multi_client = args.multi_client;
     let control_plane_server_handle = tokio::spawn(async move {
         let (control_sock_tx, mut control_sock_rx) = mpsc::unbounded_channel();
 
-        let srv =
-            make_http_server(String::default(), args.control_plane_host, control_sock_tx).await;
+        let srv = make_http_server(
+            String::default(),
+            args.control_plane_host,
+            control_sock_tx,
+            control_access,
+            None,
+        )
+        .await;
         log::info!("Control panel server listening on: {}", srv.addr);
 
+        if multi_client {
+            participants::serve_participants(control_sock_rx, lsp_rx).await;
+            let _ = srv.shutdown_tx.send(());
+            let _ = srv.join.await;
+            return;
+        }
+
         let control_websocket = control_sock_rx.recv().await.unwrap();
         let ws = control_websocket.await.unwrap();
 
@@ -661,6 +853,7 @@ pub async fn preview_main(args: PreviewCliArgs) -> Result<()> {
     });
 
     let (websocket_tx, websocket_rx) = mpsc::unbounded_channel();
+    let views = previewer.view_reader();
     let mut previewer = previewer.build(lsp_tx, handle.clone()).await;
     tokio::spawn(service.run());
 
@@ -671,20 +864,32 @@ pub async fn preview_main(args: PreviewCliArgs) -> Result<()> {
     let static_server = if let Some(static_file_host) = static_file_host {
         log::warn!("--static-file-host is deprecated, which will be removed in the future. Use --data-plane-host instead.");
         let html = frontend_html.clone();
-        Some(make_http_server(html, static_file_host, websocket_tx.clone()).await)
+        let tx = websocket_tx.clone();
+        let views = Some(views.clone());
+        Some(make_http_server(html, static_file_host, tx, access.clone(), views).await)
     } else {
         None
     };
 
-    let srv = make_http_server(frontend_html, args.data_plane_host, websocket_tx).await;
+    let srv = make_http_server(
+        frontend_html,
+        args.data_plane_host,
+        websocket_tx,
+        access.clone(),
+        Some(views),
+    )
+    .await;
     log::info!("Data plane server listening on: {}", srv.addr);
 
     let static_server_addr = static_server.as_ref().map(|s| s.addr).unwrap_or(srv.addr);
     log::info!("Static file server listening on: {static_server_addr}");
 
+    let url = access.url(static_server_addr);
+    if access.token.is_some() {
+        log::info!("Open the preview at: {url}");
+    }
     if open_in_browser {
-        open::that_detached(format!("http://{static_server_addr}"))
-            .log_error("failed to open browser for preview");
+        open::that_detached(url).log_error("failed to open browser for preview");
     }
 
     let _ = tokio::join!(previewer.join(), srv.join, control_plane_server_handle);
@@ -1,9 +1,10 @@
 //! Document preview tool for Typst
 
 use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
 use reflexo::debug_loc::SourceSpanOffset;
-use reflexo_typst::{error::prelude::*, Bytes, Error, TypstDocument};
+use reflexo_typst::{error::prelude::*, Bytes, Error, TypstDocument, TypstHtmlDocument};
 use tinymist_preview::{
     CompileStatus, DocToSrcJumpInfo, EditorServer, Location, MemoryFiles, MemoryFilesShort,
 };
@@ -45,6 +46,11 @@ impl ProjectPreviewHandler {
     pub fn unpin_primary(&self) {
         self.client.server_event(ServerEvent::UnpinPrimaryByPreview);
     }
+
+    /// Requests the server to remove the entry of the snippet preview.
+    pub fn dispose_snippet(&self) {
+        self.client.server_event(ServerEvent::DisposeSnippetPreview);
+    }
 }
 
 impl EditorServer for ProjectPreviewHandler {
@@ -89,6 +95,18 @@ impl EditorServer for ProjectPreviewHandler {
 pub struct PreviewCompileView {
     /// The compiled artifact.
     pub art: LspCompiledArtifact,
+    /// The HTML document compiled for the HTML preview.
+    html: OnceLock<Option<Arc<TypstHtmlDocument>>>,
+}
+
+impl PreviewCompileView {
+    /// Creates a view of the compiled artifact.
+    pub fn new(art: LspCompiledArtifact) -> Self {
+        Self {
+            art,
+            html: OnceLock::new(),
+        }
+    }
 }
 
 impl tinymist_preview::CompileView for PreviewCompileView {
@@ -96,6 +114,17 @@ impl tinymist_preview::CompileView for PreviewCompileView {
         self.art.doc.clone()
     }
 
+    fn html_doc(&self) -> Option<Arc<TypstHtmlDocument>> {
+        let html = self.html.get_or_init(|| match self.art.doc.as_ref()? {
+            TypstDocument::Html(doc) => Some(doc.clone()),
+            // The HTML preview compiles the HTML document on demand if the
+            // project is compiled to paged documents.
+            TypstDocument::Paged(..) => self.art.graph.compile_html().output.ok(),
+        });
+
+        html.clone()
+    }
+
     fn status(&self) -> CompileStatus {
         match self.art.doc {
             Some(_) => CompileStatus::CompileSuccess,
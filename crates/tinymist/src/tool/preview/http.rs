@@ -1,7 +1,9 @@
 //! Document preview tool for Typst
 
+use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::sync::LazyLock;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
 
 use hyper::header::HeaderValue;
 use hyper::service::service_fn;
@@ -9,8 +11,189 @@ use hyper_tungstenite::HyperWebsocket;
 use hyper_util::rt::TokioIo;
 use hyper_util::server::graceful::GracefulShutdown;
 use lsp_types::Url;
-use tinymist_std::error::IgnoreLogging;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use tinymist_preview::{document_outline, CompileViewReader};
+use tinymist_std::error::{prelude::*, IgnoreLogging};
+use tinymist_std::typst::TypstDocument;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::{mpsc, oneshot};
+use tokio_rustls::rustls;
+use tokio_rustls::TlsAcceptor;
+use typst::layout::Abs;
+
+/// The presenter console of slide previews, which embeds the current and next
+/// slides served at `/`.
+const PRESENTER_HTML: &str = include_str!("presenter.html");
+/// The default resolution of the pages exported by `/export/page/<n>.png`.
+const DEFAULT_EXPORT_PPI: f32 = 144.;
+/// The time allowed for a client to complete the TLS handshake.
+const TLS_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// The access control of a preview server. The default one serves plain HTTP
+/// to anyone passing the `Origin` check, which is only safe on localhost.
+#[derive(Clone, Default)]
+pub struct ServerAccess {
+    /// The token required by requests, which is accepted from the `token`
+    /// query parameter, a bearer `Authorization` header, or the cookie set on
+    /// the first authorized request.
+    pub token: Option<String>,
+    /// The origins allowed to connect in addition to the local ones, e.g.
+    /// `https://devbox:23625`.
+    pub allowed_origins: Vec<String>,
+    /// The TLS acceptor to serve HTTPS and secure websockets.
+    pub tls: Option<TlsAcceptor>,
+}
+
+impl ServerAccess {
+    /// Generates a random token.
+    pub fn random_token() -> Result<String> {
+        let mut bytes = [0u8; 16];
+        let provider = rustls::crypto::ring::default_provider();
+        provider
+            .secure_random
+            .fill(&mut bytes)
+            .ok()
+            .context("failed to generate a random token")?;
+
+        Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+    }
+
+    /// Loads the TLS certificate chain and private key from PEM files.
+    pub fn load_tls(cert: &Path, key: &Path) -> Result<TlsAcceptor> {
+        use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+
+        let certs = std::fs::read(cert).context("failed to read the TLS certificate")?;
+        let certs = CertificateDer::pem_slice_iter(&certs)
+            .collect::<Result<Vec<_>, _>>()
+            .context_ut("failed to parse the TLS certificate")?;
+        let key = std::fs::read(key).context("failed to read the TLS private key")?;
+        let key = PrivateKeyDer::from_pem_slice(&key)
+            .context_ut("failed to parse the TLS private key")?;
+
+        let config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .context_ut("invalid TLS certificate or private key")?;
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+
+    /// The scheme of the URLs served by the server.
+    pub fn scheme(&self) -> &'static str {
+        if self.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// The URL to open the preview served at the address.
+    pub fn url(&self, addr: impl std::fmt::Display) -> String {
+        self.url_with_scheme(self.scheme(), addr)
+    }
+
+    /// The URL to connect to the websocket of the preview served at the
+    /// address.
+    pub fn ws_url(&self, addr: impl std::fmt::Display) -> String {
+        let scheme = if self.tls.is_some() { "wss" } else { "ws" };
+        self.url_with_scheme(scheme, addr)
+    }
+
+    fn url_with_scheme(&self, scheme: &str, addr: impl std::fmt::Display) -> String {
+        match &self.token {
+            Some(token) => {
+                let token = utf8_percent_encode(token, NON_ALPHANUMERIC);
+                format!("{scheme}://{addr}/?token={token}")
+            }
+            None => format!("{scheme}://{addr}"),
+        }
+    }
+
+    /// The access control of the control plane server bound to the host. The
+    /// editors connecting to the control plane don't know the token, so it is
+    /// not required on the loopback addresses, which only local processes can
+    /// connect to. The `Origin` check still keeps the browsers out.
+    pub fn control_plane(&self, host: &str) -> Self {
+        let is_loopback = match host.parse::<SocketAddr>() {
+            Ok(addr) => addr.ip().is_loopback(),
+            Err(..) => host.split(':').next() == Some("localhost"),
+        };
+        if !is_loopback {
+            return self.clone();
+        }
+
+        Self {
+            token: None,
+            ..self.clone()
+        }
+    }
+
+    fn allows_origin(&self, origin: &HeaderValue, static_file_addr: &str, port: u16) -> bool {
+        let allowed = self.allowed_origins.iter();
+        if allowed
+            .map(|o| o.trim_end_matches('/'))
+            .any(|o| origin == o)
+        {
+            return true;
+        }
+
+        // The local origins are served over HTTPS as well when TLS is enabled.
+        let plain = (self.tls.as_ref())
+            .and_then(|_| origin.to_str().ok()?.strip_prefix("https://"))
+            .and_then(|rest| HeaderValue::from_str(&format!("http://{rest}")).ok());
+        is_valid_origin(plain.as_ref().unwrap_or(origin), static_file_addr, port)
+    }
+
+    /// Checks the token of the request. Returns the cookie to set if the
+    /// request is authorized by other means than the cookie.
+    fn authorize<B>(&self, req: &hyper::Request<B>, port: u16) -> Result<Option<String>, ()> {
+        let Some(token) = &self.token else {
+            return Ok(None);
+        };
+        let cookie_name = format!("tinymist-preview-token-{port}");
+
+        let cookies = req.headers().get_all(hyper::header::COOKIE).iter();
+        let cookies = cookies
+            .filter_map(|h| h.to_str().ok())
+            .flat_map(|h| h.split(';'));
+        let by_cookie = cookies
+            .filter_map(|c| c.trim().split_once('='))
+            .any(|(name, value)| name == cookie_name && token_eq(value, token));
+        if by_cookie {
+            return Ok(None);
+        }
+
+        let by_query = (req.uri().query().unwrap_or_default().split('&'))
+            .filter_map(|pair| pair.split_once('='))
+            .filter(|(name, _)| *name == "token")
+            .filter_map(|(_, value)| percent_decode_str(value).decode_utf8().ok())
+            .any(|value| token_eq(&value, token));
+        let by_header = (req.headers().get(hyper::header::AUTHORIZATION))
+            .and_then(|h| h.to_str().ok()?.strip_prefix("Bearer "))
+            .is_some_and(|value| token_eq(value.trim(), token));
+        if !(by_query || by_header) {
+            return Err(());
+        }
+
+        let secure = if self.tls.is_some() { "; Secure" } else { "" };
+        Ok(Some(format!(
+            "{cookie_name}={token}; Path=/; HttpOnly; SameSite=Strict{secure}"
+        )))
+    }
+}
+
+/// Compares tokens in constant time to not leak them by timing.
+fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// A connection to the server, which is either plain TCP or TLS.
+trait Io: AsyncRead + AsyncWrite + Send + Unpin + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin + 'static> Io for T {}
 
 /// created by `make_http_server`
 pub struct HttpServer {
@@ -27,6 +210,8 @@ pub async fn make_http_server(
     frontend_html: String,
     static_file_addr: String,
     websocket_tx: mpsc::UnboundedSender<HyperWebsocket>,
+    access: ServerAccess,
+    views: Option<CompileViewReader>,
 ) -> HttpServer {
     use http_body_util::Full;
     use hyper::body::{Bytes, Incoming};
@@ -36,17 +221,25 @@ pub async fn make_http_server(
         .await
         .unwrap();
     let addr = listener.local_addr().unwrap();
-    log::info!("preview server listening on http://{addr}");
+    log::info!("preview server listening on {}://{addr}", access.scheme());
 
+    let tls = access.tls.clone();
     let frontend_html = hyper::body::Bytes::from(frontend_html);
+    let export_cache = Arc::new(tokio::sync::Mutex::new(ExportCache::default()));
     let make_service = move || {
         let frontend_html = frontend_html.clone();
         let websocket_tx = websocket_tx.clone();
         let static_file_addr = static_file_addr.clone();
+        let access = access.clone();
+        let views = views.clone();
+        let export_cache = export_cache.clone();
         service_fn(move |mut req: hyper::Request<Incoming>| {
             let frontend_html = frontend_html.clone();
             let websocket_tx = websocket_tx.clone();
             let static_file_addr = static_file_addr.clone();
+            let access = access.clone();
+            let views = views.clone();
+            let export_cache = export_cache.clone();
             async move {
                 // When a user visits a website in a browser, that website can try to connect to
                 // our http / websocket server on `127.0.0.1` which may leak sensitive
@@ -61,16 +254,24 @@ pub async fn make_http_server(
                 // connect to our http / websocket servers (using a browser and
                 // otherwise). And additionally they can impersonate a tinymist
                 // http / websocket server towards a legitimate frontend/html client.
-                // This requires additional protection that may be added in the future.
+                // This requires the token and TLS of `ServerAccess`.
                 let origin_header = req.headers().get("Origin");
                 if origin_header
-                    .is_some_and(|h| !is_valid_origin(h, &static_file_addr, addr.port()))
+                    .is_some_and(|h| !access.allows_origin(h, &static_file_addr, addr.port()))
                 {
                     anyhow::bail!(
                         "Connection with unexpected `Origin` header. Closing connection."
                     );
                 }
 
+                let Ok(set_cookie) = access.authorize(&req, addr.port()) else {
+                    let res = hyper::Response::builder()
+                        .status(hyper::StatusCode::UNAUTHORIZED)
+                        .body(Full::<Bytes>::from("missing or invalid preview token"))
+                        .unwrap();
+                    return Ok(res);
+                };
+
                 // Check if the request is a websocket upgrade request.
                 if hyper_tungstenite::is_upgrade_request(&req) {
                     if origin_header.is_none() {
@@ -87,13 +288,65 @@ pub async fn make_http_server(
 
                     // Return the response so the spawned future can continue.
                     Ok(response)
-                } else if req.uri().path() == "/" {
+                } else if matches!(req.uri().path(), "/" | "/presenter") {
                     // log::debug!("Serve frontend: {mode:?}");
-                    let res = hyper::Response::builder()
-                        .header(hyper::header::CONTENT_TYPE, "text/html")
-                        .body(Full::<Bytes>::from(frontend_html))
-                        .unwrap();
-                    Ok(res)
+                    let html = if req.uri().path() == "/" {
+                        Full::<Bytes>::from(frontend_html)
+                    } else {
+                        Full::<Bytes>::from(PRESENTER_HTML)
+                    };
+                    let mut res =
+                        hyper::Response::builder().header(hyper::header::CONTENT_TYPE, "text/html");
+                    if let Some(cookie) = set_cookie {
+                        res = res.header(hyper::header::SET_COOKIE, cookie);
+                    }
+                    Ok(res.body(html).unwrap())
+                } else if let Some((views, route)) = views.zip(ExportRoute::parse(req.uri().path()))
+                {
+                    let ppi = match export_ppi(req.uri().query()) {
+                        Ok(ppi) => ppi,
+                        Err(err) => {
+                            let res = hyper::Response::builder()
+                                .status(hyper::StatusCode::BAD_REQUEST)
+                                .body(Full::<Bytes>::from(err))
+                                .unwrap();
+                            return Ok(res);
+                        }
+                    };
+                    let doc = views.doc();
+                    let key = (route, ppi.to_bits());
+
+                    // The cache is locked during the export, so that the requests don't export
+                    // the document in parallel.
+                    let mut cache = export_cache.lock().await;
+                    cache.retain_doc(doc.as_ref());
+                    let exported = match cache.artifacts.get(&key) {
+                        Some(exported) => Ok(exported.clone()),
+                        None => {
+                            // Exporting may take a while for large documents.
+                            let exported = tokio::task::spawn_blocking(move || {
+                                route.export(doc.as_ref(), ppi)
+                            })
+                            .await?
+                            .map(|(content_type, data)| (content_type, Bytes::from(data)));
+                            if let Ok(exported) = &exported {
+                                cache.artifacts.insert(key, exported.clone());
+                            }
+                            exported
+                        }
+                    };
+                    drop(cache);
+
+                    let res = match exported {
+                        Ok((content_type, data)) => hyper::Response::builder()
+                            .header(hyper::header::CONTENT_TYPE, content_type)
+                            .header(hyper::header::CACHE_CONTROL, "no-store")
+                            .body(Full::<Bytes>::from(data)),
+                        Err((status, err)) => hyper::Response::builder()
+                            .status(status)
+                            .body(Full::<Bytes>::from(err)),
+                    };
+                    Ok(res.unwrap())
                 } else {
                     // jump to /
                     let res = hyper::Response::builder()
@@ -113,15 +366,7 @@ pub async fn make_http_server(
     // the graceful watcher
     let graceful = hyper_util::server::graceful::GracefulShutdown::new();
 
-    let serve_conn = move |server: &Server, graceful: &GracefulShutdown, conn| {
-        let (stream, _peer_addr) = match conn {
-            Ok(conn) => conn,
-            Err(e) => {
-                log::error!("accept error: {e}");
-                return;
-            }
-        };
-
+    let serve_conn = move |server: &Server, graceful: &GracefulShutdown, stream: Box<dyn Io>| {
         let conn = server.serve_connection_with_upgrades(TokioIo::new(stream), make_service());
         let conn = graceful.watch(conn.into_owned());
         tokio::spawn(async move {
@@ -136,9 +381,41 @@ pub async fn make_http_server(
         let mut server = Server::new(hyper_util::rt::TokioExecutor::new());
         server.http1().keep_alive(true);
 
+        // TLS handshakes are done aside to not block accepting connections.
+        let (tls_tx, mut tls_rx) = mpsc::unbounded_channel::<Box<dyn Io>>();
+
         loop {
             tokio::select! {
-                conn = listener.accept() => serve_conn(&server, &graceful, conn),
+                conn = listener.accept() => {
+                    let stream = match conn {
+                        Ok((stream, _peer_addr)) => stream,
+                        Err(e) => {
+                            log::error!("accept error: {e}");
+                            continue;
+                        }
+                    };
+
+                    match &tls {
+                        Some(acceptor) => {
+                            // A client stalling the handshake would hold the connection
+                            // forever.
+                            let accept = acceptor.accept(stream);
+                            let accept = tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, accept);
+                            let tls_tx = tls_tx.clone();
+                            tokio::spawn(async move {
+                                let Ok(stream) = accept.await else {
+                                    log::error!("TLS handshake timed out");
+                                    return;
+                                };
+                                if let Some(stream) = stream.log_error("TLS handshake failed") {
+                                    let _ = tls_tx.send(Box::new(stream));
+                                }
+                            });
+                        }
+                        None => serve_conn(&server, &graceful, Box::new(stream)),
+                    }
+                }
+                Some(stream) = tls_rx.recv() => serve_conn(&server, &graceful, stream),
                 Ok(_) = &mut signal => {
                     log::info!("graceful shutdown signal received");
                     break;
@@ -232,6 +509,123 @@ fn is_valid_origin_impl(
         || gitpod_expected_origin.is_some_and(|o| o == *origin_header)
 }
 
+/// An artifact of the current snapshot of the preview served over HTTP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ExportRoute {
+    /// `/export.pdf`
+    Pdf,
+    /// `/export.svg`, with all pages merged.
+    Svg,
+    /// `/export/page/<n>.png`, with the 1-based page number.
+    Png(usize),
+    /// `/outline.json`
+    Outline,
+}
+
+impl ExportRoute {
+    fn parse(path: &str) -> Option<Self> {
+        match path {
+            "/export.pdf" => Some(Self::Pdf),
+            "/export.svg" => Some(Self::Svg),
+            "/outline.json" => Some(Self::Outline),
+            path => {
+                let page = path.strip_prefix("/export/page/")?.strip_suffix(".png")?;
+                Some(Self::Png(page.parse().ok().filter(|page| *page > 0)?))
+            }
+        }
+    }
+
+    /// Exports the artifact, returning the content type and the data, or the
+    /// status and the reason of failure.
+    fn export(
+        self,
+        doc: Option<&TypstDocument>,
+        ppi: f32,
+    ) -> Result<(&'static str, Vec<u8>), (hyper::StatusCode, String)> {
+        use hyper::StatusCode;
+
+        let internal = |err: String| (StatusCode::INTERNAL_SERVER_ERROR, err);
+        let Some(doc) = doc else {
+            let reason = "the document is not compiled yet".to_owned();
+            return Err((StatusCode::SERVICE_UNAVAILABLE, reason));
+        };
+        let paged = || match doc {
+            TypstDocument::Paged(doc) => Ok(doc),
+            TypstDocument::Html(..) => {
+                let reason = "the document is not compiled to pages".to_owned();
+                Err((StatusCode::NOT_FOUND, reason))
+            }
+        };
+
+        match self {
+            Self::Pdf => typst_pdf::pdf(paged()?, &typst_pdf::PdfOptions::default())
+                .map(|pdf| ("application/pdf", pdf))
+                .map_err(|err| internal(format!("failed to export PDF: {err:?}"))),
+            Self::Svg => {
+                let svg = typst_svg::svg_merged(paged()?, Abs::zero());
+                Ok(("image/svg+xml", svg.into_bytes()))
+            }
+            Self::Png(page) => {
+                if !(ppi > 0. && ppi <= 1200.) {
+                    return Err((StatusCode::BAD_REQUEST, format!("invalid ppi: {ppi}")));
+                }
+                let Some(frame) = paged()?.pages.get(page - 1) else {
+                    let reason = format!("page {page} is out of range");
+                    return Err((StatusCode::NOT_FOUND, reason));
+                };
+                typst_render::render(frame, ppi / 72.)
+                    .encode_png()
+                    .map(|png| ("image/png", png))
+                    .map_err(|err| internal(format!("failed to encode PNG: {err}")))
+            }
+            Self::Outline => serde_json::to_vec(&document_outline(doc))
+                .map(|outline| ("application/json", outline))
+                .map_err(|err| internal(err.to_string())),
+        }
+    }
+}
+
+/// The artifacts exported from a compiled document, which are reused until
+/// the document is compiled again.
+#[derive(Default)]
+struct ExportCache {
+    /// The document that the artifacts are exported from.
+    doc: Option<TypstDocument>,
+    /// The content type and the data of the artifacts, by the route and the
+    /// bits of the ppi.
+    artifacts: HashMap<(ExportRoute, u32), (&'static str, hyper::body::Bytes)>,
+}
+
+impl ExportCache {
+    /// Clears the artifacts if they are not exported from the document.
+    fn retain_doc(&mut self, doc: Option<&TypstDocument>) {
+        let same = match (&self.doc, doc) {
+            (Some(TypstDocument::Paged(a)), Some(TypstDocument::Paged(b))) => Arc::ptr_eq(a, b),
+            (Some(TypstDocument::Html(a)), Some(TypstDocument::Html(b))) => Arc::ptr_eq(a, b),
+            _ => false,
+        };
+        if !same {
+            self.doc = doc.cloned();
+            self.artifacts.clear();
+        }
+    }
+}
+
+/// Gets the resolution of the exported pages from the `ppi` query parameter.
+fn export_ppi(query: Option<&str>) -> Result<f32, String> {
+    match query_param(query, "ppi") {
+        Some(ppi) => ppi.parse().map_err(|_| format!("invalid ppi: {ppi}")),
+        None => Ok(DEFAULT_EXPORT_PPI),
+    }
+}
+
+/// Gets the value of a query parameter.
+fn query_param<'a>(query: Option<&'a str>, name: &str) -> Option<&'a str> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -240,6 +634,50 @@ mod tests {
         is_valid_origin(&HeaderValue::from_static(origin), static_file_addr, port)
     }
 
+    #[test]
+    fn test_export_route() {
+        assert_eq!(ExportRoute::parse("/export.pdf"), Some(ExportRoute::Pdf));
+        assert_eq!(ExportRoute::parse("/export.svg"), Some(ExportRoute::Svg));
+        assert_eq!(
+            ExportRoute::parse("/outline.json"),
+            Some(ExportRoute::Outline)
+        );
+        assert_eq!(
+            ExportRoute::parse("/export/page/3.png"),
+            Some(ExportRoute::Png(3))
+        );
+        assert_eq!(ExportRoute::parse("/export/page/0.png"), None);
+        assert_eq!(ExportRoute::parse("/export/page/x.png"), None);
+        assert_eq!(ExportRoute::parse("/export/page/3.jpg"), None);
+        assert_eq!(ExportRoute::parse("/export"), None);
+
+        assert_eq!(query_param(Some("token=a&ppi=300"), "ppi"), Some("300"));
+        assert_eq!(query_param(Some("ppix=300"), "ppi"), None);
+        assert_eq!(query_param(None, "ppi"), None);
+
+        assert_eq!(export_ppi(Some("ppi=300")), Ok(300.));
+        assert_eq!(export_ppi(Some("token=a")), Ok(DEFAULT_EXPORT_PPI));
+        assert!(export_ppi(Some("ppi=abc")).is_err());
+    }
+
+    #[test]
+    fn test_export_cache() {
+        let doc = || TypstDocument::Paged(Arc::new(Default::default()));
+        let exported = ("image/svg+xml", hyper::body::Bytes::new());
+
+        let mut cache = ExportCache::default();
+        let first = doc();
+        cache.retain_doc(Some(&first));
+        cache.artifacts.insert((ExportRoute::Svg, 0), exported);
+
+        cache.retain_doc(Some(&first.clone()));
+        assert_eq!(cache.artifacts.len(), 1);
+        cache.retain_doc(Some(&doc()));
+        assert!(cache.artifacts.is_empty());
+        cache.retain_doc(None);
+        assert!(cache.doc.is_none());
+    }
+
     #[test]
     fn test_valid_origin_localhost() {
         assert!(check_origin("http://127.0.0.1:42", "127.0.0.1:42", 42));
@@ -317,6 +755,94 @@ mod tests {
         assert!(!check_origin("https://huh2.io", "huh.io:42", 42));
     }
 
+    #[test]
+    fn test_allowed_origins() {
+        let access = ServerAccess {
+            allowed_origins: vec!["https://devbox:42/".to_owned()],
+            ..ServerAccess::default()
+        };
+        let check = |origin: &'static str| {
+            access.allows_origin(&HeaderValue::from_static(origin), "0.0.0.0:42", 42)
+        };
+
+        assert!(check("https://devbox:42"));
+        assert!(check("http://localhost:42"));
+        assert!(!check("http://devbox:42"));
+        assert!(!check("https://huh.io"));
+    }
+
+    #[test]
+    fn test_authorize() {
+        let access = ServerAccess {
+            token: Some("secret".to_owned()),
+            ..ServerAccess::default()
+        };
+        let request = |uri: &str, header: Option<(hyper::header::HeaderName, &str)>| {
+            let mut req = hyper::Request::builder().uri(uri);
+            if let Some((name, value)) = header {
+                req = req.header(name, value);
+            }
+            req.body(()).unwrap()
+        };
+
+        let cookie = "tinymist-preview-token-42=secret; Path=/; HttpOnly; SameSite=Strict";
+        let by_query = access.authorize(&request("/?role=x&token=secret", None), 42);
+        assert_eq!(by_query, Ok(Some(cookie.to_owned())));
+
+        let bearer = (hyper::header::AUTHORIZATION, "Bearer secret");
+        let by_header = access.authorize(&request("/", Some(bearer)), 42);
+        assert_eq!(by_header, Ok(Some(cookie.to_owned())));
+
+        let cookie = (
+            hyper::header::COOKIE,
+            "a=b; tinymist-preview-token-42=secret",
+        );
+        assert_eq!(access.authorize(&request("/", Some(cookie)), 42), Ok(None));
+
+        let cookie = (hyper::header::COOKIE, "tinymist-preview-token-43=secret");
+        assert!(access.authorize(&request("/", Some(cookie)), 42).is_err());
+        assert!(access
+            .authorize(&request("/?token=secreT", None), 42)
+            .is_err());
+        assert!(access.authorize(&request("/", None), 42).is_err());
+
+        let open = ServerAccess::default();
+        assert_eq!(open.authorize(&request("/", None), 42), Ok(None));
+    }
+
+    #[test]
+    fn test_authorize_encoded_token() {
+        let access = ServerAccess {
+            token: Some("a b+c/d".to_owned()),
+            ..ServerAccess::default()
+        };
+        let url = access.url("127.0.0.1:42");
+        assert_eq!(url, "http://127.0.0.1:42/?token=a%20b%2Bc%2Fd");
+        assert_eq!(
+            access.ws_url("127.0.0.1:42"),
+            "ws://127.0.0.1:42/?token=a%20b%2Bc%2Fd"
+        );
+
+        let req = hyper::Request::builder()
+            .uri(url.strip_prefix("http://127.0.0.1:42").unwrap())
+            .body(())
+            .unwrap();
+        assert!(access.authorize(&req, 42).is_ok());
+    }
+
+    #[test]
+    fn test_control_plane_access() {
+        let access = ServerAccess {
+            token: Some("secret".to_owned()),
+            ..ServerAccess::default()
+        };
+        assert!(access.control_plane("127.0.0.1:23626").token.is_none());
+        assert!(access.control_plane("[::1]:23626").token.is_none());
+        assert!(access.control_plane("localhost:23626").token.is_none());
+        assert!(access.control_plane("0.0.0.0:23626").token.is_some());
+        assert!(access.control_plane("devbox:23626").token.is_some());
+    }
+
     // https://github.com/Myriad-Dreamin/tinymist/issues/1350
     // the origin of code-server's proxy
     #[test]
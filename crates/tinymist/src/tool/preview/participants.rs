@@ -0,0 +1,323 @@
+//! The control plane shared by several editors, where a leader drives the
+//! preview and the others follow it.
+
+use futures::{SinkExt, StreamExt};
+use hyper_tungstenite::{tungstenite::Message, HyperWebsocket};
+use serde::{Deserialize, Serialize};
+use tinymist_preview::{
+    ChangeCursorPositionRequest, ControlPlaneMessage, ControlPlaneResponse, ControlPlaneRx,
+    ParticipantCursorRequest, ParticipantLeaveRequest,
+};
+use tokio::sync::mpsc;
+
+/// The messages sent by the participants in addition to the control plane
+/// messages.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+enum ParticipantMessage {
+    /// Sets the name shown to the other participants.
+    Join { name: String },
+    /// Becomes the leader driving the preview.
+    TakeLead,
+}
+
+/// The events sent to the participants in addition to the control plane
+/// responses.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+enum ParticipantResponse<'a> {
+    /// The participants of the preview, which is sent when a participant joins
+    /// or leaves, or the leader changes.
+    Participants {
+        /// The id of the receiving participant.
+        you: u64,
+        /// The id of the leader.
+        leader: Option<u64>,
+        /// All the participants.
+        participants: Vec<ParticipantInfo<'a>>,
+    },
+    /// The cursor of the leader, which is followed by the other participants.
+    FollowCursor(&'a ChangeCursorPositionRequest),
+}
+
+/// A participant shown to the other participants.
+#[derive(Debug, Serialize)]
+struct ParticipantInfo<'a> {
+    id: u64,
+    name: &'a str,
+}
+
+/// The events of the participant connections.
+enum ConnEvent {
+    Joined(u64, mpsc::UnboundedSender<String>),
+    Message(u64, String),
+    Left(u64),
+}
+
+struct Participant {
+    id: u64,
+    name: String,
+    tx: mpsc::UnboundedSender<String>,
+}
+
+/// The participants of a shared preview.
+#[derive(Default)]
+struct Participants {
+    list: Vec<Participant>,
+    leader: Option<u64>,
+}
+
+impl Participants {
+    fn get(&self, id: u64) -> Option<&Participant> {
+        self.list.iter().find(|p| p.id == id)
+    }
+
+    fn is_leader(&self, id: u64) -> bool {
+        self.leader == Some(id)
+    }
+
+    /// Sends the message to the participants except the one with the id.
+    fn broadcast(&self, except: Option<u64>, msg: &str) {
+        for p in self.list.iter().filter(|p| Some(p.id) != except) {
+            let _ = p.tx.send(msg.to_owned());
+        }
+    }
+
+    /// Makes the participant the leader, who is asked to sync its unsaved
+    /// changes to the preview.
+    fn lead(&mut self, id: Option<u64>) {
+        if self.leader == id {
+            return;
+        }
+
+        self.leader = id;
+        if let Some(leader) = id.and_then(|id| self.get(id)) {
+            let sync = ControlPlaneResponse::SyncEditorChanges(());
+            let _ = leader.tx.send(serde_json::to_string(&sync).unwrap());
+        }
+    }
+
+    /// Tells each participant the current participants and the leader.
+    fn announce(&self) {
+        let participants = || {
+            (self.list.iter())
+                .map(|p| ParticipantInfo {
+                    id: p.id,
+                    name: &p.name,
+                })
+                .collect()
+        };
+        for p in &self.list {
+            let resp = ParticipantResponse::Participants {
+                you: p.id,
+                leader: self.leader,
+                participants: participants(),
+            };
+            let _ = p.tx.send(serde_json::to_string(&resp).unwrap());
+        }
+    }
+}
+
+/// Serves the control plane to all the editors connecting to it. The first
+/// editor leads the preview until another one takes the lead by a `takeLead`
+/// message, and the leadership is passed to the earliest remaining editor when
+/// the leader leaves. The cursors of all editors are shown in the preview, but
+/// only the messages from the leader drive the preview.
+pub(crate) async fn serve_participants(
+    mut sock_rx: mpsc::UnboundedReceiver<HyperWebsocket>,
+    mut lsp_rx: ControlPlaneRx,
+) {
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+    let mut participants = Participants::default();
+    let mut next_id = 1;
+
+    loop {
+        tokio::select! {
+            Some(websocket) = sock_rx.recv() => {
+                tokio::spawn(serve_connection(next_id, websocket, event_tx.clone()));
+                next_id += 1;
+            }
+            // The responses are kept until an editor joins.
+            Some(resp) = lsp_rx.resp_rx.recv(), if !participants.list.is_empty() => {
+                participants.broadcast(None, &serde_json::to_string(&resp).unwrap());
+            }
+            Some(event) = event_rx.recv() => match event {
+                ConnEvent::Joined(id, tx) => {
+                    log::info!("participant {id} joined the preview");
+                    let name = format!("Editor {id}");
+                    participants.list.push(Participant { id, name, tx });
+                    // The first editor syncs its unsaved changes on the request
+                    // sent by the preview at startup.
+                    participants.leader.get_or_insert(id);
+                    participants.announce();
+                }
+                ConnEvent::Message(id, msg) => {
+                    handle_message(&mut participants, &lsp_rx, id, &msg);
+                }
+                ConnEvent::Left(id) => {
+                    log::info!("participant {id} left the preview");
+                    participants.list.retain(|p| p.id != id);
+                    let leave = ParticipantLeaveRequest { id };
+                    let _ = lsp_rx.ctl_tx.send(ControlPlaneMessage::ParticipantLeave(leave));
+                    if participants.list.is_empty() {
+                        break;
+                    }
+
+                    if participants.is_leader(id) {
+                        participants.lead(participants.list.first().map(|p| p.id));
+                    }
+                    participants.announce();
+                }
+            },
+        }
+    }
+}
+
+/// Handles a message from a participant.
+fn handle_message(participants: &mut Participants, lsp_rx: &ControlPlaneRx, id: u64, msg: &str) {
+    if let Ok(msg) = serde_json::from_str::<ParticipantMessage>(msg) {
+        match msg {
+            ParticipantMessage::Join { name } => {
+                if let Some(p) = participants.list.iter_mut().find(|p| p.id == id) {
+                    p.name = name;
+                }
+            }
+            ParticipantMessage::TakeLead => participants.lead(Some(id)),
+        }
+        participants.announce();
+        return;
+    }
+
+    let Ok(msg) = serde_json::from_str::<ControlPlaneMessage>(msg) else {
+        log::warn!("failed to parse control plane request from participant {id}: {msg:?}");
+        return;
+    };
+
+    let leader = participants.is_leader(id);
+    match msg {
+        ControlPlaneMessage::ChangeCursorPosition(cursor) => {
+            if leader {
+                let follow = ParticipantResponse::FollowCursor(&cursor);
+                let follow = serde_json::to_string(&follow).unwrap();
+                participants.broadcast(Some(id), &follow);
+            }
+
+            let name = participants.get(id).map(|p| p.name.clone());
+            let presence = ParticipantCursorRequest {
+                id,
+                name: name.unwrap_or_default(),
+                leader,
+                cursor: cursor.clone(),
+            };
+            let _ = (lsp_rx.ctl_tx).send(ControlPlaneMessage::ParticipantCursor(presence));
+            if leader {
+                let _ = (lsp_rx.ctl_tx).send(ControlPlaneMessage::ChangeCursorPosition(cursor));
+            }
+        }
+        ControlPlaneMessage::ParticipantCursor(..) | ControlPlaneMessage::ParticipantLeave(..) => {
+            log::warn!("ignored internal control plane request from participant {id}");
+        }
+        msg if leader => {
+            let _ = lsp_rx.ctl_tx.send(msg);
+        }
+        msg => {
+            log::debug!("ignored control plane request from follower {id}: {msg:?}");
+        }
+    }
+}
+
+/// Forwards the messages between a participant and the control plane.
+async fn serve_connection(
+    id: u64,
+    websocket: HyperWebsocket,
+    event_tx: mpsc::UnboundedSender<ConnEvent>,
+) {
+    let ws = match websocket.await {
+        Ok(ws) => ws,
+        Err(err) => {
+            log::error!("failed to accept participant {id}: {err}");
+            return;
+        }
+    };
+
+    let (resp_tx, mut resp_rx) = mpsc::unbounded_channel();
+    let _ = event_tx.send(ConnEvent::Joined(id, resp_tx));
+
+    let (mut sink, mut stream) = ws.split();
+    loop {
+        tokio::select! {
+            Some(resp) = resp_rx.recv() => {
+                if let Err(err) = sink.send(Message::Text(resp)).await {
+                    log::warn!("failed to send response to participant {id}: {err:?}");
+                    break;
+                }
+            }
+            msg = stream.next() => match msg {
+                Some(Ok(Message::Text(msg))) => {
+                    let _ = event_tx.send(ConnEvent::Message(id, msg));
+                }
+                Some(Ok(Message::Close(..))) | None => break,
+                Some(Ok(msg)) => log::debug!("ignored message from participant {id}: {msg:?}"),
+                Some(Err(err)) => {
+                    log::error!("failed to receive message from participant {id}: {err}");
+                    break;
+                }
+            },
+        }
+    }
+
+    let _ = event_tx.send(ConnEvent::Left(id));
+}
+
+#[cfg(test)]
+mod tests {
+    use tinymist_preview::ControlPlaneTx;
+
+    use super::*;
+
+    #[test]
+    fn test_follow_leader() {
+        let (mut ctl, lsp_rx) = ControlPlaneTx::new(false);
+        let mut participants = Participants::default();
+        let mut rxs = vec![];
+        for id in [1, 2] {
+            let (tx, rx) = mpsc::unbounded_channel();
+            let name = format!("Editor {id}");
+            participants.list.push(Participant { id, name, tx });
+            rxs.push(rx);
+        }
+        participants.leader = Some(1);
+
+        let cursor =
+            r#"{"event":"changeCursorPosition","filepath":"main.typ","line":1,"character":2}"#;
+        let scroll = r#"{"event":"panelSlideTo","page":2}"#;
+
+        // The cursor of a follower is only shown in the preview.
+        handle_message(&mut participants, &lsp_rx, 2, cursor);
+        handle_message(&mut participants, &lsp_rx, 2, scroll);
+        let msg = ctl.ctl_rx.try_recv().unwrap();
+        assert!(
+            matches!(msg, ControlPlaneMessage::ParticipantCursor(req) if req.id == 2 && !req.leader)
+        );
+        assert!(ctl.ctl_rx.try_recv().is_err());
+        assert!(rxs[0].try_recv().is_err());
+
+        // The cursor of the leader drives the preview and the followers.
+        handle_message(&mut participants, &lsp_rx, 1, cursor);
+        let msg = ctl.ctl_rx.try_recv().unwrap();
+        assert!(matches!(msg, ControlPlaneMessage::ParticipantCursor(req) if req.leader));
+        let msg = ctl.ctl_rx.try_recv().unwrap();
+        assert!(matches!(msg, ControlPlaneMessage::ChangeCursorPosition(..)));
+        let follow = rxs[1].try_recv().unwrap();
+        assert!(follow.starts_with(r#"{"event":"followCursor","filepath":"main.typ""#));
+
+        // Taking the lead asks the new leader to sync its changes.
+        handle_message(&mut participants, &lsp_rx, 2, r#"{"event":"takeLead"}"#);
+        assert!(participants.is_leader(2));
+        let sync = rxs[1].try_recv().unwrap();
+        assert_eq!(sync, r#"{"event":"syncEditorChanges"}"#);
+        handle_message(&mut participants, &lsp_rx, 2, scroll);
+        let msg = ctl.ctl_rx.try_recv().unwrap();
+        assert!(matches!(msg, ControlPlaneMessage::PanelSlideTo(..)));
+    }
+}
@@ -0,0 +1,92 @@
+//! Review comments left in the preview, which are stored in the review sidecar
+//! files of the sources and shown as code lenses.
+
+use std::path::Path;
+
+use tinymist_preview::{ControlPlaneResponse, ReviewCommentInfo};
+use tinymist_query::ReviewComments;
+use tinymist_std::error::{prelude::*, IgnoreLogging};
+use tokio::sync::mpsc;
+use typst::syntax::Source;
+
+use crate::ServerState;
+
+impl ServerState {
+    /// Stores the review comment left in a preview. The comment is anchored to
+    /// the source in memory if the source is opened in the editor.
+    pub fn add_review_comment(&mut self, info: ReviewCommentInfo) -> Result<()> {
+        let path = Path::new(&info.location.filepath);
+        let source = match self.query_source(path.into(), Ok) {
+            Ok(source) => source,
+            Err(..) => read_source(path)?,
+        };
+        save_review_comment(&source, &info)?;
+
+        self.refresh_code_lens();
+        Ok(())
+    }
+
+    /// Asks the client to request the code lenses again, which shows the
+    /// changed review comments.
+    pub fn refresh_code_lens(&self) {
+        use lsp_types::request::CodeLensRefresh;
+
+        if !self.const_config().code_lens_refresh {
+            return;
+        }
+        self.client
+            .send_lsp_request::<CodeLensRefresh>((), |_s, resp| {
+                if let Some(err) = resp.error {
+                    log::error!("failed to refresh code lens: {err:?}");
+                }
+            });
+    }
+}
+
+/// Stores the review comments passed through the responses of a standalone
+/// preview, and forwards all the responses to the editor.
+pub(crate) fn forward_review_comments(
+    mut resp_rx: mpsc::UnboundedReceiver<ControlPlaneResponse>,
+) -> mpsc::UnboundedReceiver<ControlPlaneResponse> {
+    let (resp_tx, forwarded_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(resp) = resp_rx.recv().await {
+            if let ControlPlaneResponse::ReviewComment(info) = &resp {
+                let path = Path::new(&info.location.filepath);
+                let saved = read_source(path).and_then(|source| save_review_comment(&source, info));
+                saved.log_error("failed to save review comment");
+            }
+            if resp_tx.send(resp).is_err() {
+                break;
+            }
+        }
+    });
+
+    forwarded_rx
+}
+
+/// Reads the source from the file system.
+fn read_source(path: &Path) -> Result<Source> {
+    let text = std::fs::read_to_string(path).context("failed to read source")?;
+    Ok(Source::detached(text))
+}
+
+/// Adds the review comment to the review sidecar of the source.
+fn save_review_comment(source: &Source, info: &ReviewCommentInfo) -> Result<()> {
+    let path = Path::new(&info.location.filepath);
+    let (line, column) = (info.location.start)
+        .or(info.location.end)
+        .context_ut("the commented point has no source position")?;
+    let offset = source
+        .line_column_to_byte(line, column)
+        .context_ut("the commented point is out of the source")?;
+
+    let mut comments = ReviewComments::load(path);
+    comments.add(source, offset, info.text.clone(), info.author.clone());
+    comments
+        .save(path)
+        .context("failed to save review comments")?;
+
+    log::info!("saved review comment at {path:?}:{}:{column}", line + 1);
+    Ok(())
+}
@@ -0,0 +1,474 @@
+//! Terminal preview, which draws the pages by the graphics protocols of
+//! terminals instead of serving them to a browser.
+
+use std::fmt::Write as _;
+use std::io::Write;
+use std::sync::Arc;
+
+use base64::Engine;
+use clap::ValueEnum;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use parking_lot::Mutex;
+use tinymist_std::error::prelude::*;
+use tinymist_std::typst::{TypstDocument, TypstPagedDocument};
+
+use crate::project::*;
+use crate::tool::project::{start_project, StartProjectResult};
+use crate::utils::exit_on_ctrl_c;
+use crate::world::system::print_diagnostics;
+
+use super::PreviewCliArgs;
+
+/// The size of the base64 chunks sent by the kitty graphics protocol.
+const KITTY_CHUNK_SIZE: usize = 4096;
+/// The levels of each channel in the sixel palette, which makes a color cube
+/// of `SIXEL_LEVELS^3` colors.
+const SIXEL_LEVELS: usize = 6;
+
+/// The graphics protocol used to draw pages in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum GraphicsProtocol {
+    /// Detects the protocol from the environment variables.
+    #[default]
+    Auto,
+    /// The kitty graphics protocol, also supported by WezTerm and Ghostty.
+    Kitty,
+    /// The inline images protocol of iTerm2, also supported by WezTerm.
+    Iterm,
+    /// Sixel graphics, supported by foot, xterm and tmux.
+    Sixel,
+}
+
+impl GraphicsProtocol {
+    /// Resolves [`GraphicsProtocol::Auto`] by the environment variables set by
+    /// the terminal.
+    fn resolve(self) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let term_program = var("TERM_PROGRAM");
+        if std::env::var_os("KITTY_WINDOW_ID").is_some()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || term_program == "ghostty"
+        {
+            Self::Kitty
+        } else if term_program == "iTerm.app" || term_program == "WezTerm" {
+            Self::Iterm
+        } else {
+            Self::Sixel
+        }
+    }
+}
+
+/// The state of a terminal preview.
+struct TerminalPreview {
+    protocol: GraphicsProtocol,
+    /// Whether the escape sequences must be passed through tmux.
+    tmux: bool,
+    /// The pixels per point of the rendered pages.
+    ppp: f32,
+    /// The last successfully compiled document.
+    doc: Option<Arc<TypstPagedDocument>>,
+    /// The 0-based index of the current page.
+    page: usize,
+    /// The number of errors of the last compilation.
+    errors: usize,
+}
+
+impl TerminalPreview {
+    /// Moves to the page at the index, which is clamped to the document.
+    fn goto(&mut self, page: usize) {
+        let last = self.doc.as_ref().map_or(0, |doc| doc.pages.len());
+        self.page = page.min(last.saturating_sub(1));
+    }
+
+    /// Redraws the current page and the status line.
+    fn draw(&self) {
+        let mut out = String::new();
+        // Clears the screen and then moves the cursor to the top left corner.
+        out.push_str("\x1B[2J\x1B[1;1H");
+
+        let pages = self.doc.as_ref().map_or(0, |doc| doc.pages.len());
+        if let Some(page) = self.doc.as_ref().and_then(|doc| doc.pages.get(self.page)) {
+            let pixmap = typst_render::render(page, self.ppp);
+            let image = match self.protocol {
+                GraphicsProtocol::Kitty | GraphicsProtocol::Iterm => match pixmap.encode_png() {
+                    Ok(png) => self.encode_png(&png),
+                    Err(err) => {
+                        log::error!("TerminalPreview: failed to encode PNG: {err}");
+                        String::new()
+                    }
+                },
+                GraphicsProtocol::Sixel | GraphicsProtocol::Auto => {
+                    let (width, height) = (pixmap.width() as usize, pixmap.height() as usize);
+                    encode_sixel(width, height, pixmap.data())
+                }
+            };
+            out.push_str(&image);
+            out.push_str("\r\n");
+        }
+
+        let _ = write!(out, "\x1B[1mPage {}/{pages}\x1B[0m", self.page + 1);
+        if self.errors > 0 {
+            let _ = write!(out, " \x1B[31m({} errors)\x1B[0m", self.errors);
+        }
+        out.push_str("  n/p: next/previous, <page> Enter: goto, r: recompile, q: quit\r\n");
+
+        let mut stdout = std::io::stdout().lock();
+        let _ = stdout.write_all(out.as_bytes());
+        let _ = stdout.flush();
+    }
+
+    /// Encodes a PNG image by the kitty or iTerm2 protocol.
+    fn encode_png(&self, png: &[u8]) -> String {
+        let data = base64::engine::general_purpose::STANDARD.encode(png);
+        match self.protocol {
+            GraphicsProtocol::Kitty => encode_kitty(&data)
+                .into_iter()
+                .map(|chunk| self.passthrough(chunk))
+                .collect(),
+            _ => self.passthrough(format!(
+                "\x1B]1337;File=inline=1;size={};preserveAspectRatio=1:{data}\x07",
+                png.len()
+            )),
+        }
+    }
+
+    /// Wraps an escape sequence so that tmux passes it to the outer terminal.
+    fn passthrough(&self, seq: String) -> String {
+        if self.tmux {
+            format!("\x1BPtmux;{}\x1B\\", seq.replace('\x1B', "\x1B\x1B"))
+        } else {
+            seq
+        }
+    }
+}
+
+/// Splits the base64 data of a PNG image into the escape sequences of the
+/// kitty graphics protocol. The terminal is asked to not respond, so that the
+/// responses don't mix into the keyboard input.
+fn encode_kitty(data: &str) -> Vec<String> {
+    let chunks = data.as_bytes().chunks(KITTY_CHUNK_SIZE);
+    let last = chunks.len().saturating_sub(1);
+    chunks
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let more = u8::from(idx != last);
+            // The base64 data is ASCII, so any split is at char boundaries.
+            let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+            if idx == 0 {
+                format!("\x1B_Ga=T,f=100,q=2,m={more};{chunk}\x1B\\")
+            } else {
+                format!("\x1B_Gm={more};{chunk}\x1B\\")
+            }
+        })
+        .collect()
+}
+
+/// Encodes premultiplied RGBA pixels as sixel graphics, with the colors
+/// quantized to a color cube and composed over white.
+fn encode_sixel(width: usize, height: usize, rgba: &[u8]) -> String {
+    let level = |c: u8, a: u8| (255 - a as usize + c as usize) * (SIXEL_LEVELS - 1) / 255;
+    let colors = rgba
+        .chunks_exact(4)
+        .map(|px| {
+            let [r, g, b, a] = [px[0], px[1], px[2], px[3]];
+            let (r, g, b) = (level(r, a), level(g, a), level(b, a));
+            (r * SIXEL_LEVELS + g) * SIXEL_LEVELS + b
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = format!("\x1BP0;1q\"1;1;{width};{height}");
+    let percent = |level: usize| level * 100 / (SIXEL_LEVELS - 1);
+    for color in 0..SIXEL_LEVELS.pow(3) {
+        let (r, g, b) = (
+            color / SIXEL_LEVELS / SIXEL_LEVELS,
+            color / SIXEL_LEVELS % SIXEL_LEVELS,
+            color % SIXEL_LEVELS,
+        );
+        let _ = write!(
+            out,
+            "#{color};2;{};{};{}",
+            percent(r),
+            percent(g),
+            percent(b)
+        );
+    }
+
+    for top in (0..height).step_by(6) {
+        let rows = (height - top).min(6);
+        let mut used = vec![false; SIXEL_LEVELS.pow(3)];
+        for &color in &colors[top * width..(top + rows) * width] {
+            used[color] = true;
+        }
+
+        let used = used.iter().enumerate().filter(|(_, used)| **used);
+        for (idx, (color, _)) in used.enumerate() {
+            if idx > 0 {
+                // Returns to the start of the band to draw the next color.
+                out.push('$');
+            }
+            let _ = write!(out, "#{color}");
+
+            let mut run: Option<(char, usize)> = None;
+            for x in 0..width {
+                let bits = (0..rows)
+                    .filter(|dy| colors[(top + dy) * width + x] == color)
+                    .fold(0u8, |bits, dy| bits | (1 << dy));
+                let ch = char::from(63 + bits);
+                match &mut run {
+                    Some((prev, count)) if *prev == ch => *count += 1,
+                    _ => {
+                        if let Some((prev, count)) = run {
+                            push_sixel_run(&mut out, prev, count);
+                        }
+                        run = Some((ch, 1));
+                    }
+                }
+            }
+            if let Some((prev, count)) = run {
+                push_sixel_run(&mut out, prev, count);
+            }
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1B\\");
+    out
+}
+
+fn push_sixel_run(out: &mut String, ch: char, count: usize) {
+    if count > 3 {
+        let _ = write!(out, "!{count}{ch}");
+    } else {
+        out.extend(std::iter::repeat_n(ch, count));
+    }
+}
+
+/// Previews the document in the terminal, redrawing the current page on every
+/// compilation.
+pub async fn terminal_preview_main(args: PreviewCliArgs) -> Result<()> {
+    let ppi = args.terminal_ppi;
+    if ppi <= 1e-6 {
+        bail!("invalid ppi: {ppi}");
+    }
+
+    exit_on_ctrl_c();
+
+    let verse = args.compile.resolve()?;
+    let state = Arc::new(Mutex::new(TerminalPreview {
+        protocol: args.graphics.resolve(),
+        tmux: std::env::var_os("TMUX").is_some(),
+        ppp: ppi / 72.,
+        doc: None,
+        page: 0,
+        errors: 0,
+    }));
+
+    let compile_state = state.clone();
+    let StartProjectResult {
+        service,
+        mut editor_rx,
+        intr_tx,
+    } = start_project(verse, None, move |c, mut i, next| {
+        if let Interrupt::Compiled(artifact) = &mut i {
+            let mut state = compile_state.lock();
+            state.errors = artifact.error_cnt();
+            if let Some(TypstDocument::Paged(doc)) = &artifact.doc {
+                state.doc = Some(doc.clone());
+                let page = state.page;
+                state.goto(page);
+            }
+            state.draw();
+
+            if state.errors > 0 {
+                // The diagnostics are printed in the cooked mode, which breaks
+                // the lines correctly.
+                let raw = terminal::is_raw_mode_enabled().unwrap_or(false);
+                if raw {
+                    let _ = terminal::disable_raw_mode();
+                }
+                let diag = artifact.diagnostics();
+                let _ = print_diagnostics(artifact.world(), diag, DiagnosticFormat::Human);
+                if raw {
+                    let _ = terminal::enable_raw_mode();
+                }
+            }
+        }
+
+        next(c, i)
+    });
+
+    // Reads the keys in the raw mode, so that a single key works without Enter.
+    let proj_id = service.compiler.primary.id.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(err) = terminal::enable_raw_mode() {
+            log::error!("TerminalPreview: failed to enable the raw mode: {err}");
+            return;
+        }
+        let res = read_keys(&state, || {
+            let _ = intr_tx.send(Interrupt::Compile(proj_id.clone()));
+        });
+        let _ = terminal::disable_raw_mode();
+
+        match res {
+            Ok(()) => std::process::exit(0),
+            Err(err) => log::error!("TerminalPreview: failed to read keys: {err}"),
+        }
+    });
+
+    // Consume editor_rx
+    tokio::spawn(async move { while editor_rx.recv().await.is_some() {} });
+
+    service.run().await;
+
+    Ok(())
+}
+
+/// An action triggered by the keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyAction {
+    /// Moves to the page at the 0-based index.
+    Goto(usize),
+    Next,
+    Previous,
+    Recompile,
+    Quit,
+}
+
+impl KeyAction {
+    /// Maps a key to an action. The digits are collected into `number` until
+    /// Enter is pressed.
+    fn from_key(key: KeyEvent, number: &mut String) -> Option<Self> {
+        if key.kind == KeyEventKind::Release {
+            return None;
+        }
+
+        let action = match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Self::Quit,
+            KeyCode::Char(ch @ '0'..='9') => {
+                number.push(ch);
+                return None;
+            }
+            KeyCode::Enter if !number.is_empty() => {
+                let page = number.parse::<usize>().unwrap_or(usize::MAX);
+                Self::Goto(page.saturating_sub(1))
+            }
+            KeyCode::Char('n' | ' ') | KeyCode::Enter | KeyCode::Right | KeyCode::PageDown => {
+                Self::Next
+            }
+            KeyCode::Char('p') | KeyCode::Backspace | KeyCode::Left | KeyCode::PageUp => {
+                Self::Previous
+            }
+            KeyCode::Char('g') | KeyCode::Home => Self::Goto(0),
+            KeyCode::Char('G') | KeyCode::End => Self::Goto(usize::MAX),
+            KeyCode::Char('r') => Self::Recompile,
+            KeyCode::Char('q') | KeyCode::Esc => Self::Quit,
+            _ => return None,
+        };
+        number.clear();
+        Some(action)
+    }
+}
+
+/// Reads the keys and moves between the pages until the user quits.
+fn read_keys(state: &Mutex<TerminalPreview>, recompile: impl Fn()) -> std::io::Result<()> {
+    let mut number = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        let Some(action) = KeyAction::from_key(key, &mut number) else {
+            continue;
+        };
+
+        let mut state = state.lock();
+        match action {
+            KeyAction::Goto(page) => state.goto(page),
+            KeyAction::Next => {
+                let page = state.page + 1;
+                state.goto(page);
+            }
+            KeyAction::Previous => {
+                let page = state.page.saturating_sub(1);
+                state.goto(page);
+            }
+            KeyAction::Recompile => {
+                recompile();
+                continue;
+            }
+            KeyAction::Quit => return Ok(()),
+        }
+        state.draw();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_kitty() {
+        let data = "A".repeat(KITTY_CHUNK_SIZE + 1);
+        let chunks = encode_kitty(&data);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].starts_with("\x1B_Ga=T,f=100,q=2,m=1;AAAA"));
+        assert_eq!(chunks[1], "\x1B_Gm=0;A\x1B\\");
+    }
+
+    #[test]
+    fn test_encode_sixel() {
+        // A 4x2 image with a black pixel at the top left corner, and the rest
+        // is transparent, which is composed over white.
+        let mut rgba = vec![0u8; 4 * 2 * 4];
+        rgba[3] = 255;
+        let sixel = encode_sixel(4, 2, &rgba);
+
+        let white = SIXEL_LEVELS.pow(3) - 1;
+        let body = sixel.split_once("\"1;1;4;2").unwrap().1;
+        let body = body
+            .rsplit_once(&format!("#{white};2;100;100;100"))
+            .unwrap()
+            .1;
+        assert_eq!(body, format!("#0@???$#{white}ABBB-\x1B\\"));
+    }
+
+    #[test]
+    fn test_key_action() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let mut number = String::new();
+
+        let action = |code, number: &mut String| KeyAction::from_key(key(code), number);
+        assert_eq!(
+            action(KeyCode::Char('n'), &mut number),
+            Some(KeyAction::Next)
+        );
+        assert_eq!(action(KeyCode::Enter, &mut number), Some(KeyAction::Next));
+        assert_eq!(
+            action(KeyCode::Left, &mut number),
+            Some(KeyAction::Previous)
+        );
+        assert_eq!(action(KeyCode::Char('1'), &mut number), None);
+        assert_eq!(action(KeyCode::Char('2'), &mut number), None);
+        assert_eq!(
+            action(KeyCode::Enter, &mut number),
+            Some(KeyAction::Goto(11))
+        );
+        assert!(number.is_empty());
+        assert_eq!(
+            action(KeyCode::Char('q'), &mut number),
+            Some(KeyAction::Quit)
+        );
+
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(
+            KeyAction::from_key(ctrl_c, &mut number),
+            Some(KeyAction::Quit)
+        );
+    }
+}
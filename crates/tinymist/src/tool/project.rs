@@ -14,8 +14,10 @@ use tinymist_query::analysis::Analysis;
 use tinymist_std::{bail, error::prelude::*};
 use tokio::sync::mpsc;
 
+use crate::project::*;
+use crate::task::{run_hooks, ExportTask, HookPayload};
+use crate::world::base::debug_loc::DataSource;
 use crate::{actor::editor::EditorRequest, world::system::print_diagnostics, Config};
-use crate::{project::*, task::ExportTask};
 
 /// Arguments for project compilation.
 #[derive(Debug, Clone, clap::Parser)]
@@ -32,6 +34,36 @@ pub struct CompileArgs {
     /// set, the lock file will be saved.
     #[clap(long)]
     pub lockfile: Option<PathBuf>,
+
+    /// Writes a Makefile-style depfile, listing the files that the output
+    /// depends on, to the path.
+    #[clap(long, value_name = "PATH")]
+    pub make_deps: Option<PathBuf>,
+
+    /// Writes a JSON file, listing the files that the output depends on, to
+    /// the path.
+    #[clap(long, value_name = "PATH")]
+    pub deps_json: Option<PathBuf>,
+
+    /// Compiles reproducibly. The creation timestamp is taken from the
+    /// `SOURCE_DATE_EPOCH` environment variable, system fonts are ignored
+    /// unless included via `--font-path`, and the used packages are checked
+    /// against (or, with `--save-lock`, pinned to) the lock file.
+    #[clap(long)]
+    pub reproducible: bool,
+}
+
+/// Arguments for verifying an artifact by a reproducible build.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct VerifyArgs {
+    /// Inherits the compile task arguments. The output path is the artifact
+    /// to verify.
+    #[clap(flatten)]
+    pub compile: TaskCompileArgs,
+
+    /// Specifies the path to the lock file pinning the packages.
+    #[clap(long)]
+    pub lockfile: Option<PathBuf>,
 }
 
 /// Arguments for generating a build script.
@@ -51,6 +83,34 @@ pub use super::preview::PreviewArgs;
 #[cfg(feature = "preview")]
 pub use tinymist_preview::PreviewMode;
 
+/// Project lock file commands.
+#[derive(Debug, Clone, clap::Subcommand)]
+#[clap(rename_all = "kebab-case")]
+pub enum ProjectCommands {
+    /// Migrates the lock file to the current version in place.
+    Migrate(LockFileArgs),
+    /// Generates the JSON schema of the lock file.
+    Schema(LockSchemaArgs),
+}
+
+/// Arguments to locate a lock file.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct LockFileArgs {
+    /// Specifies the path to the lock file. Defaults to the lock file in the
+    /// current directory.
+    #[clap(long)]
+    pub lockfile: Option<PathBuf>,
+}
+
+/// Arguments for generating the JSON schema of the lock file.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct LockSchemaArgs {
+    /// The path to the output schema. If not provided, the schema will be
+    /// printed to stdout.
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+}
+
 /// Project task commands.
 #[derive(Debug, Clone, clap::Subcommand)]
 #[clap(rename_all = "kebab-case")]
@@ -112,8 +172,16 @@ impl LockFileExt for LockFile {
 /// Runs project compilation(s)
 pub async fn compile_main(args: CompileArgs) -> Result<()> {
     // Identifies the input and output
-    let input = args.compile.declare.to_input();
-    let output = args.compile.to_task(input.id.clone())?;
+    let mut input = args.compile.declare.to_input();
+    let mut output = args.compile.to_task(input.id.clone())?;
+
+    let reproducible = args
+        .reproducible
+        .then(ReproducibleBuild::from_env)
+        .transpose()?;
+    if let Some(reproducible) = &reproducible {
+        reproducible.prepare(&mut input, &mut output.task);
+    }
 
     // Saves the lock file if the flags are set
     let save_lock = args.save_lock || args.lockfile.is_some();
@@ -124,6 +192,21 @@ pub async fn compile_main(args: CompileArgs) -> Result<()> {
         std::env::current_dir().context("lock directory")?.into()
     };
 
+    // Keeps the hooks of the task recorded in the lock file, which are run after the
+    // export and are not overwritten by saving the task.
+    if lock_dir.join(LOCK_FILENAME).exists() {
+        match LockFile::read(&lock_dir) {
+            Ok(lock) => {
+                let recorded = lock.get_task(&output.id).and_then(|t| t.task.as_export());
+                if let (Some(recorded), Some(export)) = (recorded, output.task.as_export_mut()) {
+                    export.on_success = recorded.on_success.clone();
+                    export.on_failure = recorded.on_failure.clone();
+                }
+            }
+            Err(err) => log::warn!("failed to read the hooks from the lock file: {err}"),
+        }
+    }
+
     if save_lock {
         LockFile::update(&lock_dir, |state| {
             state.replace_document(input.clone());
@@ -134,12 +217,117 @@ pub async fn compile_main(args: CompileArgs) -> Result<()> {
     }
 
     // Prepares for the compilation
-    let universe = (input, lock_dir.clone()).resolve()?;
-    let world = universe.snapshot();
-    let graph = WorldComputeGraph::from_world(world);
+    let mut universe = (input, lock_dir.clone()).resolve()?;
+    if let Some(reproducible) = &reproducible {
+        reproducible.prepare_universe(&mut universe);
+    }
 
     // Compiles the project
     let is_html = matches!(output.task, ProjectTask::ExportHtml(..));
+    let compiled = compile_once(&universe, is_html)?;
+
+    if let Some(reproducible) = &reproducible {
+        reproducible.check_packages(compiled.world(), &lock_dir, save_lock)?;
+    }
+
+    // Collects the dependencies before the artifact is consumed by the export
+    let deps = (args.make_deps.is_some() || args.deps_json.is_some())
+        .then(|| CompileDeps::from_world(compiled.world()));
+
+    // Exports the compiled project
+    let lock_dir = save_lock.then_some(lock_dir);
+    let hooks = output.task.as_export().cloned();
+    let diagnostics = compiled.error_cnt() + compiled.warning_cnt();
+    let res = ExportTask::do_export(output.task, compiled, lock_dir).await;
+    if let Some(hooks) = hooks {
+        let payload = HookPayload::new(output.id.to_string(), diagnostics, &res);
+        run_hooks(hooks.hooks(payload.success), &payload).await;
+    }
+    let written = res?;
+
+    if let Some(mut deps) = deps {
+        deps.output = written.map(|p| unix_slash(&p));
+        if let Some(path) = &args.make_deps {
+            std::fs::write(path, deps.to_make_deps()).context("write make deps")?;
+        }
+        if let Some(path) = &args.deps_json {
+            let json = serde_json::to_string_pretty(&deps).context("serialize deps")?;
+            std::fs::write(path, json).context("write deps json")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Verifies that an artifact is reproduced byte by byte by a reproducible
+/// build.
+pub async fn verify_main(args: VerifyArgs) -> Result<()> {
+    let artifact = PathBuf::from(
+        (args.compile.output.as_ref()).context("the artifact to verify must be provided")?,
+    );
+
+    let mut input = args.compile.declare.to_input();
+    let mut output = args.compile.to_task(input.id.clone())?;
+
+    let reproducible = ReproducibleBuild::from_env()?;
+    reproducible.prepare(&mut input, &mut output.task);
+
+    // Rebuilds to a temporary directory instead of overwriting the artifact. The
+    // directory is removed when the guard is dropped, even if the rebuild fails.
+    let rebuild_dir = tempfile::Builder::new()
+        .prefix("tinymist-verify-")
+        .tempdir()
+        .context("create rebuild directory")?;
+    let export = output
+        .task
+        .as_export_mut()
+        .context("cannot verify a task without artifact")?;
+    export.output = Some(PathPattern::new(&unix_slash(
+        &rebuild_dir.path().join("artifact"),
+    )));
+
+    let lock_dir: ImmutPath = if let Some(lockfile) = args.lockfile {
+        lockfile.parent().context("no parent")?.into()
+    } else {
+        std::env::current_dir().context("lock directory")?.into()
+    };
+
+    let mut universe = (input, lock_dir.clone()).resolve()?;
+    reproducible.prepare_universe(&mut universe);
+
+    let is_html = matches!(output.task, ProjectTask::ExportHtml(..));
+    let compiled = compile_once(&universe, is_html)?;
+    reproducible.check_packages(compiled.world(), &lock_dir, false)?;
+
+    let rebuilt = ExportTask::do_export(output.task, compiled, None)
+        .await?
+        .context("no artifact is rebuilt")?;
+
+    let expected = std::fs::read(&artifact).context("read artifact")?;
+    let actual = std::fs::read(&rebuilt).context("read rebuilt artifact")?;
+
+    if expected != actual {
+        let offset = expected
+            .iter()
+            .zip(actual.iter())
+            .position(|(a, b)| a != b)
+            .unwrap_or(expected.len().min(actual.len()));
+        bail!(
+            "{artifact:?} is not reproduced, first difference at byte {offset} (artifact has {} bytes, rebuilt one has {} bytes)",
+            expected.len(),
+            actual.len()
+        );
+    }
+
+    eprintln!("{artifact:?} is reproduced byte by byte");
+    Ok(())
+}
+
+/// Compiles the universe once, and exits if there are errors.
+fn compile_once(universe: &LspUniverse, is_html: bool) -> Result<LspCompiledArtifact> {
+    let world = universe.snapshot();
+    let graph = WorldComputeGraph::from_world(world);
+
     let compiled = CompiledArtifact::from_graph(graph, is_html);
 
     let diag = compiled.diagnostics();
@@ -151,11 +339,149 @@ pub async fn compile_main(args: CompileArgs) -> Result<()> {
         std::process::exit(1);
     }
 
-    // Exports the compiled project
-    let lock_dir = save_lock.then_some(lock_dir);
-    ExportTask::do_export(output.task, compiled, lock_dir).await?;
+    Ok(compiled)
+}
 
-    Ok(())
+/// The settings of a reproducible build.
+struct ReproducibleBuild {
+    /// The creation timestamp, from the `SOURCE_DATE_EPOCH` environment
+    /// variable.
+    creation_timestamp: i64,
+}
+
+impl ReproducibleBuild {
+    /// Reads the settings from the environment.
+    fn from_env() -> Result<Self> {
+        let Ok(epoch) = std::env::var("SOURCE_DATE_EPOCH") else {
+            bail!("reproducible builds require the SOURCE_DATE_EPOCH environment variable");
+        };
+        let creation_timestamp = parse_source_date_epoch(&epoch)
+            .map_err(|e| anyhow::anyhow!("invalid SOURCE_DATE_EPOCH: {e}"))?;
+
+        Ok(Self { creation_timestamp })
+    }
+
+    /// Prepares the input and the task.
+    fn prepare(&self, input: &mut ProjectInput, task: &mut ProjectTask) {
+        input.system_fonts = false;
+        if let ProjectTask::ExportPdf(task) = task {
+            task.creation_timestamp = Some(self.creation_timestamp);
+        }
+    }
+
+    /// Prepares the universe.
+    fn prepare_universe(&self, universe: &mut LspUniverse) {
+        universe.increment_revision(|verse| {
+            verse.set_creation_timestamp(Some(self.creation_timestamp));
+        });
+    }
+
+    /// Checks the used packages against the pins in the lock file, and pins
+    /// them if `save_lock` is set.
+    fn check_packages(&self, world: &LspWorld, lock_dir: &Path, save_lock: bool) -> Result<()> {
+        let pins = PackagePin::from_world(world);
+
+        let unpinned = if lock_dir.join(LOCK_FILENAME).exists() {
+            LockFile::read(lock_dir)?.check_package_pins(&pins)?
+        } else {
+            pins.iter().map(|pin| pin.spec.clone()).collect()
+        };
+        if !save_lock && !unpinned.is_empty() {
+            log::warn!(
+                "packages are not pinned in the lock file, run with `--save-lock` to pin them: {}",
+                unpinned.join(", ")
+            );
+        }
+
+        if save_lock {
+            LockFile::update(lock_dir, |state| {
+                for pin in pins {
+                    state.replace_package(pin);
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The files that a compiled output depends on.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct CompileDeps {
+    /// The path to the written output, if any.
+    pub output: Option<String>,
+    /// The files read by the compilation, including sources, assets and
+    /// package files.
+    pub inputs: Vec<String>,
+    /// The font files used by the compilation.
+    pub fonts: Vec<String>,
+}
+
+impl CompileDeps {
+    /// Collects the dependencies tracked by the world of a compilation.
+    pub fn from_world(world: &LspWorld) -> Self {
+        let mut inputs = world
+            .depended_fs_paths()
+            .iter()
+            .map(|p| unix_slash(p))
+            .collect::<Vec<_>>();
+        inputs.sort();
+        inputs.dedup();
+
+        let resolver = &world.font_resolver;
+        let mut fonts = resolver
+            .loaded_fonts()
+            .filter_map(
+                |(idx, _)| match resolver.describe_font_by_id(idx)?.as_ref() {
+                    DataSource::Fs(fs) => Some(fs.path.clone()),
+                    DataSource::Memory(..) => None,
+                },
+            )
+            .collect::<Vec<_>>();
+        fonts.sort();
+        fonts.dedup();
+
+        Self {
+            output: None,
+            inputs,
+            fonts,
+        }
+    }
+
+    /// Renders the dependencies as a Makefile rule, which is also understood
+    /// by Ninja.
+    pub fn to_make_deps(&self) -> String {
+        let target = self.output.as_deref().unwrap_or("-");
+
+        let mut rule = make_escape(target);
+        rule.push(':');
+        for dep in self.inputs.iter().chain(self.fonts.iter()) {
+            rule.push_str(" \\\n  ");
+            rule.push_str(&make_escape(dep));
+        }
+        rule.push('\n');
+
+        rule
+    }
+}
+
+/// Escapes a path for a Makefile rule.
+fn make_escape(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for ch in path.chars() {
+        match ch {
+            ' ' | '#' | ':' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            '$' => escaped.push_str("$$"),
+            _ => escaped.push(ch),
+        }
+    }
+
+    escaped
 }
 
 /// Generates a build script for compilation
@@ -306,7 +632,12 @@ fn shell_build_script(shell: Shell) -> Result<String> {
                     }
                 }
                 // todo: export me
-                ExportTransform::Merge { .. } | ExportTransform::Script { .. } => {}
+                ExportTransform::Merge { .. }
+                | ExportTransform::Script { .. }
+                | ExportTransform::Metadata(..)
+                | ExportTransform::Attach { .. }
+                | ExportTransform::Watermark(..)
+                | ExportTransform::Impose { .. } => {}
             }
         }
 
@@ -344,6 +675,9 @@ fn shell_build_script(shell: Shell) -> Result<String> {
             ProjectTask::ExportText(..) => {
                 cmd.push("--format=txt");
             }
+            ProjectTask::ExportOdt(..) => {
+                cmd.push("--format=odt");
+            }
             ProjectTask::ExportHtml(..) => {
                 cmd.push("--format=html");
             }
@@ -384,6 +718,32 @@ pub fn project_main(args: DocCommands) -> Result<()> {
     })
 }
 
+/// Project lock file commands' main
+pub fn project_lock_main(args: ProjectCommands) -> Result<()> {
+    match args {
+        ProjectCommands::Migrate(args) => {
+            let lock_dir = match &args.lockfile {
+                Some(lockfile) => lockfile.parent().context("no parent")?.to_owned(),
+                None => std::env::current_dir().context("lock directory")?,
+            };
+
+            match LockFile::migrate(&lock_dir)? {
+                Some(from) => eprintln!("migrated lock file from {from} to {LOCK_VERSION}"),
+                None => eprintln!("lock file is already in version {LOCK_VERSION}"),
+            }
+        }
+        ProjectCommands::Schema(args) => {
+            let schema = serde_json::to_string_pretty(&lock_file_schema()).context("schema")?;
+            match &args.output {
+                Some(output) => std::fs::write(output, schema).context("write schema")?,
+                None => println!("{schema}"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Project task commands' main
 pub fn task_main(args: TaskCommands) -> Result<()> {
     LockFile::update(Path::new("."), |state| {
@@ -520,3 +880,43 @@ where
         log::info!("Project compiler exited");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_make_escape() {
+        assert_eq!(make_escape("out/main.pdf"), "out/main.pdf");
+        assert_eq!(make_escape("my doc.typ"), "my\\ doc.typ");
+        assert_eq!(make_escape("$HOME/#1.typ"), "$$HOME/\\#1.typ");
+        assert_eq!(
+            make_escape("C:\\Users\\me\\main.typ"),
+            "C\\:\\Users\\me\\main.typ"
+        );
+    }
+
+    #[test]
+    fn test_to_make_deps() {
+        let deps = CompileDeps {
+            output: Some("out dir/main.pdf".into()),
+            inputs: vec!["/root/main.typ".into(), "/root/$cost.csv".into()],
+            fonts: vec!["/fonts/a#b.ttf".into()],
+        };
+        assert_eq!(
+            deps.to_make_deps(),
+            "out\\ dir/main.pdf: \\\n  /root/main.typ \\\n  /root/$$cost.csv \\\n  /fonts/a\\#b.ttf\n"
+        );
+    }
+
+    #[test]
+    fn test_to_make_deps_without_output() {
+        // The target is `-` when nothing is written, e.g. to stdout.
+        let deps = CompileDeps {
+            inputs: vec!["main.typ".into()],
+            ..Default::default()
+        };
+        assert_eq!(deps.to_make_deps(), "-: \\\n  main.typ\n");
+        assert_eq!(CompileDeps::default().to_make_deps(), "-:\n");
+    }
+}
@@ -32,6 +32,13 @@ pub mod md_attr {
         value -> value
         caption -> caption
         class -> class
+        key -> key
+        target -> target
+        font -> font
+        size -> size
+        justify -> justify
+        leading -> leading
+        indent -> indent
     }
 }
 
@@ -74,6 +81,25 @@ pub struct ListItemAttr {
     pub value: Option<u32>,
 }
 
+#[derive(TypliteAttr, Default)]
+pub struct CiteAttr {
+    pub key: EcoString,
+}
+
+#[derive(TypliteAttr, Default)]
+pub struct RefAttr {
+    pub target: EcoString,
+}
+
+#[derive(TypliteAttr, Default)]
+pub struct ParAttr {
+    pub font: Option<EcoString>,
+    pub size: Option<f64>,
+    pub justify: bool,
+    pub leading: Option<f64>,
+    pub indent: Option<f64>,
+}
+
 #[derive(TypliteAttr, Default)]
 pub struct AlertsAttr {
     pub class: EcoString,
@@ -107,6 +133,14 @@ impl TypliteAttrParser for u32 {
     }
 }
 
+impl TypliteAttrParser for f64 {
+    fn parse_attr(content: &EcoString) -> Result<Self> {
+        Ok(content
+            .parse::<f64>()
+            .map_err(|_| format!("cannot parse {} as f64", content))?)
+    }
+}
+
 impl TypliteAttrParser for bool {
     fn parse_attr(content: &EcoString) -> Result<Self> {
         Ok(content
@@ -0,0 +1,187 @@
+//! Bibliography rendering for the formats that cannot cite by keys
+
+use std::sync::LazyLock;
+
+use ecow::EcoString;
+use hayagriva::citationberg::Locale;
+use hayagriva::{
+    BibliographyDriver, BibliographyRequest, BufWriteFormat, CitationItem, CitationRequest,
+    ElemChildren, Entry,
+};
+use tinymist_project::LspWorld;
+use typst::foundations::{Bytes, IntoValue, Packed, Smart, StyleChain, Value};
+use typst::model::{BibliographyElem, CslSource, CslStyle};
+use typst::World;
+
+/// The CSL locales providing the terms of the styles, e.g. `and`.
+static LOCALES: LazyLock<Vec<Locale>> = LazyLock::new(hayagriva::archive::locales);
+
+/// The entries loaded from the sources of a bibliography element.
+pub(crate) struct BibSources {
+    /// The CSL style of the bibliography.
+    pub style: CslStyle,
+    /// The BibTeX style equivalent to the CSL style.
+    pub bibtex_style: &'static str,
+    /// The entries in the order of the sources.
+    pub entries: Vec<Entry>,
+    /// The file names and the contents of the BibLaTeX sources.
+    pub biblatex_files: Vec<(EcoString, Bytes)>,
+}
+
+impl BibSources {
+    /// Loads the sources of the bibliography element. Unreadable sources are
+    /// skipped, since typst has already reported them.
+    pub fn load(world: &LspWorld, elem: &Packed<BibliographyElem>) -> Option<Self> {
+        // The fields set by rules are materialized into the realized element,
+        // e.g. the style of `set bibliography(style: "apa")`, which are read
+        // without the styles around the element.
+        let style = elem.style(StyleChain::default());
+        let bibtex_style = bibtex_style(&style.source);
+        let style = style.derived;
+        let Value::Array(paths) = elem.sources.clone().into_value() else {
+            return None;
+        };
+        let elem_fid = elem.span().id()?;
+
+        let mut entries = Vec::new();
+        let mut biblatex_files = Vec::new();
+        for path in paths
+            .into_iter()
+            .flat_map(|path| path.cast::<EcoString>().ok())
+        {
+            let fid = elem_fid.join(&path);
+            let Ok(content) = world.file(fid) else {
+                continue;
+            };
+            let Ok(text) = std::str::from_utf8(&content) else {
+                continue;
+            };
+            let path = fid.vpath().as_rootless_path();
+            let ext = path.extension().and_then(|ext| ext.to_str());
+            let library = match ext.map(str::to_lowercase).as_deref() {
+                Some("yml" | "yaml") => hayagriva::io::from_yaml_str(text).ok(),
+                Some("bib") => {
+                    if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                        biblatex_files.push((name.into(), content.clone()));
+                    }
+                    hayagriva::io::from_biblatex_str(text).ok()
+                }
+                _ => None,
+            };
+            if let Some(library) = library {
+                entries.extend(library.iter().cloned());
+            }
+        }
+
+        Some(Self {
+            style,
+            bibtex_style,
+            entries,
+            biblatex_files,
+        })
+    }
+
+    /// Renders the references of the cited keys in plain text, in the order
+    /// of the citations. All the entries are rendered if `full` is set.
+    pub fn render(&self, cited: &[EcoString], full: bool) -> Vec<String> {
+        let style = self.style.get();
+        let locales = LOCALES.as_slice();
+        let mut driver = BibliographyDriver::new();
+
+        let mut entries: Vec<&Entry> = cited
+            .iter()
+            .filter_map(|key| {
+                self.entries
+                    .iter()
+                    .find(|entry| entry.key() == key.as_str())
+            })
+            .collect();
+        if full {
+            entries.extend(
+                self.entries
+                    .iter()
+                    .filter(|entry| !cited.iter().any(|key| key.as_str() == entry.key())),
+            );
+        }
+        for entry in entries {
+            driver.citation(CitationRequest::from_items(
+                vec![CitationItem::with_entry(entry)],
+                style,
+                locales,
+            ));
+        }
+
+        let result = driver.finish(BibliographyRequest {
+            style,
+            locale: None,
+            locale_files: locales,
+        });
+        let Some(bibliography) = result.bibliography else {
+            return Vec::new();
+        };
+
+        let plain = |elem: &ElemChildren| {
+            let mut buf = String::new();
+            elem.write_buf(&mut buf, BufWriteFormat::Plain).ok()?;
+            Some(buf)
+        };
+        bibliography
+            .items
+            .iter()
+            .flat_map(|item| {
+                let content = plain(&item.content)?;
+                let prefix = item.first_field.as_ref().and_then(|field| {
+                    let mut buf = String::new();
+                    field.write_buf(&mut buf, BufWriteFormat::Plain).ok()?;
+                    Some(buf)
+                });
+                Some(match prefix {
+                    Some(prefix) => format!("{prefix} {content}"),
+                    None => content,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Gets the BibTeX style equivalent to a CSL style, which falls back to
+/// `plain` for the custom styles and the styles without equivalents in the
+/// standard BibTeX distribution.
+fn bibtex_style(source: &CslSource) -> &'static str {
+    let CslSource::Named(style) = source else {
+        return "plain";
+    };
+    let bibtex = style.names().iter().find_map(|name| {
+        Some(match *name {
+            "ieee" => "IEEEtran",
+            "apa"
+            | "american-psychological-association"
+            | "chicago-author-date"
+            | "harvard-cite-them-right"
+            | "elsevier-harvard"
+            | "springer-basic-author-date"
+            | "mla"
+            | "modern-language-association" => "apalike",
+            "alphanumeric" => "alpha",
+            "american-mathematical-society" => "amsplain",
+            "association-for-computing-machinery" => "acm",
+            "nature"
+            | "vancouver"
+            | "elsevier-vancouver"
+            | "american-medical-association"
+            | "american-chemical-society"
+            | "american-physics-society"
+            | "springer-basic" => "unsrt",
+            _ => return None,
+        })
+    });
+    bibtex.unwrap_or("plain")
+}
+
+/// Gets the title of the bibliography, or `None` if it has no title.
+pub(crate) fn bib_title(elem: &Packed<BibliographyElem>) -> Option<EcoString> {
+    match elem.title(StyleChain::default()) {
+        Smart::Auto => Some("Bibliography".into()),
+        Smart::Custom(title) => title.map(|title| title.plain_text()),
+    }
+}
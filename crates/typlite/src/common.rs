@@ -12,8 +12,11 @@ use cmark_writer::WriteResult;
 use cmark_writer::WriterOptions;
 use ecow::eco_format;
 use ecow::EcoString;
+use std::ops::Range;
 use std::path::PathBuf;
+use typst::introspection::Location;
 
+use crate::math::{to_latex, MathExpr};
 use crate::Result;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -29,8 +32,15 @@ pub enum Format {
     Md,
     LaTeX,
     Text,
+    /// The JSON AST of pandoc, which can be converted to other formats by
+    /// `pandoc -f json`.
+    PandocJson,
     #[cfg(feature = "docx")]
     Docx,
+    /// The OpenDocument text, which is read by LibreOffice and other office
+    /// suites.
+    #[cfg(feature = "odt")]
+    Odt,
 }
 
 /// Figure node implementation for all formats
@@ -41,6 +51,8 @@ pub struct FigureNode {
     pub body: Box<Node>,
     /// The caption text for the figure
     pub caption: String,
+    /// The label of the figure, which is kept as a `\label` in LaTeX
+    pub label: Option<EcoString>,
 }
 
 impl FigureNode {
@@ -228,6 +240,202 @@ impl VerbatimNode {
     }
 }
 
+/// Math equation node written in native math markups
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = false)]
+pub struct MathNode {
+    /// The content of the equation
+    pub expr: MathExpr,
+    /// Whether the equation is displayed in its own block
+    pub block: bool,
+    /// The label of the equation, which is kept as a `\label` in LaTeX
+    pub label: Option<EcoString>,
+}
+
+impl MathNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        let latex = to_latex(&self.expr, self.block);
+        if self.block {
+            writer.write_str(&format!("$$\n{latex}\n$$"))?;
+        } else {
+            writer.write_str(&format!("${latex}$"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Footnote node referring to the footnote by its number
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = false)]
+pub struct FootnoteNode {
+    /// The 1-based number of the footnote
+    pub index: usize,
+    /// The content of the footnote
+    pub content: Vec<Node>,
+}
+
+impl FootnoteNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        writer.write_str(&format!("[^{}]", self.index))?;
+        Ok(())
+    }
+}
+
+/// Footnote definition node, which is placed at the end of the document
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = true, html_impl = false)]
+pub struct FootnoteDefinitionNode {
+    /// The 1-based number of the footnote
+    pub index: usize,
+    /// The content of the footnote
+    pub content: Vec<Node>,
+}
+
+impl FootnoteDefinitionNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        let mut temp_writer = CommonMarkWriter::with_options(writer.options.clone());
+        temp_writer.write(&Node::Paragraph(self.content.clone()))?;
+        let content = temp_writer.into_string();
+        writer.write_str(&format!("[^{}]: {}", self.index, content.trim()))?;
+        Ok(())
+    }
+}
+
+/// Citation node with the citation text rendered by typst
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = false)]
+pub struct CitationNode {
+    /// The key of the cited entry
+    pub key: EcoString,
+    /// The rendered citation, e.g. `[1]`
+    pub content: Vec<Node>,
+}
+
+impl CitationNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        for node in &self.content {
+            writer.write(node)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reference node to a label, which is kept only when the references are
+/// resolved into links, e.g. between the chapters of a documentation site
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = false)]
+pub struct RefNode {
+    /// The name of the referenced label
+    pub target: EcoString,
+    /// The rendered reference, e.g. `Section 2`
+    pub content: Vec<Node>,
+}
+
+impl RefNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        for node in &self.content {
+            writer.write(node)?;
+        }
+        Ok(())
+    }
+}
+
+/// Anchor node marking the start of a heading, a figure or a block equation
+/// by the location of its element, which is kept only when the references are
+/// resolved
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = false)]
+pub struct AnchorNode {
+    /// The location of the element
+    pub location: Location,
+}
+
+impl AnchorNode {
+    fn write_custom(&self, _writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        Ok(())
+    }
+}
+
+/// Label node at the end of a heading, which is kept only in LaTeX
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = false)]
+pub struct LabelNode {
+    /// The name of the label
+    pub name: EcoString,
+}
+
+impl LabelNode {
+    fn write_custom(&self, _writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        Ok(())
+    }
+}
+
+/// Paragraph style node marking the settings of `text` and `par` of the
+/// paragraph containing it, which is kept only in DOCX
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = false, html_impl = false)]
+pub struct ParStyleNode {
+    /// The first font family
+    pub font: Option<EcoString>,
+    /// The font size in points
+    pub size: Option<f64>,
+    /// Whether the paragraph is justified
+    pub justify: bool,
+    /// The spacing between the lines in points
+    pub leading: Option<f64>,
+    /// The indent of the first line in points
+    pub indent: Option<f64>,
+}
+
+impl ParStyleNode {
+    fn write_custom(&self, _writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        Ok(())
+    }
+}
+
+/// Outline node, which is written as a table of contents field in DOCX
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = true, html_impl = false)]
+pub struct OutlineNode {
+    /// The title of the outline
+    pub title: Vec<Node>,
+    /// The deepest level of the headings in the outline, where `1` is the
+    /// level of the top-level headings in typst
+    pub depth: usize,
+}
+
+impl OutlineNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        for node in &self.title {
+            writer.write(node)?;
+        }
+        Ok(())
+    }
+}
+
+/// Bibliography node listing the references cited in the document
+#[derive(Debug, PartialEq, Clone)]
+#[custom_node(block = true, html_impl = false)]
+pub struct BibliographyNode {
+    /// The title and the rendered references
+    pub content: Vec<Node>,
+    /// The names of the BibLaTeX files without extensions, which are used
+    /// by LaTeX instead of the rendered references
+    pub bib_names: Vec<EcoString>,
+    /// The BibTeX style equivalent to the CSL style of the bibliography
+    pub bibtex_style: EcoString,
+}
+
+impl BibliographyNode {
+    fn write_custom(&self, writer: &mut CommonMarkWriter) -> WriteResult<()> {
+        let mut temp_writer = CommonMarkWriter::with_options(writer.options.clone());
+        temp_writer.write(&Node::Document(self.content.clone()))?;
+        let content = temp_writer.into_string();
+        writer.write_str(content.trim_end())?;
+        Ok(())
+    }
+}
+
 /// Alert node for alert messages
 #[derive(Debug, PartialEq, Clone)]
 #[custom_node(block = true, html_impl = false)]
@@ -269,4 +477,26 @@ pub trait FormatWriter {
 
     /// Write AST document to vector
     fn write_vec(&mut self, document: &Node) -> Result<Vec<u8>>;
+
+    /// Write the top-level blocks of the AST document one by one, and
+    /// returns the byte ranges of the blocks in the output.
+    fn write_blocks(
+        &mut self,
+        document: &Node,
+        output: &mut EcoString,
+    ) -> Result<Vec<Range<usize>>> {
+        let Node::Document(blocks) = document else {
+            let start = output.len();
+            self.write_eco(document, output)?;
+            let end = output.len();
+            return Ok(vec![Range { start, end }]);
+        };
+        let mut ranges = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let start = output.len();
+            self.write_eco(&Node::Document(vec![block.clone()]), output)?;
+            ranges.push(start..output.len());
+        }
+        Ok(ranges)
+    }
 }
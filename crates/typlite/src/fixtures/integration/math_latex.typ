@@ -0,0 +1,11 @@
+// math_output: latex
+Inline $integral x dif x$ and $a^2 + b^2 = c^2$.
+
+$ sum_(i=1)^n 1/i $
+
+$
+  x & = y \
+    & = z
+$
+
+$ mat(a, b; c, d) $
@@ -0,0 +1,2 @@
+// math_output: latex
+$alpha x in RR$, $sqrt(x + 1)$ and $hat(x) + sin x$
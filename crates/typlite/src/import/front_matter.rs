@@ -0,0 +1,155 @@
+//! Converts the YAML front matter of Markdown documents
+
+use serde_yaml::{Mapping, Value};
+
+use super::typst_str;
+
+/// Converts the front matter into a `#set document(...)` rule for the keys
+/// known by typst, and a metadata with the label `<front-matter>` for the
+/// rest.
+pub(super) fn convert_front_matter(yaml: &str) -> String {
+    let Ok(Value::Mapping(mut fields)) = serde_yaml::from_str::<Value>(yaml) else {
+        if yaml.trim().is_empty() {
+            return String::new();
+        }
+        return format!("#metadata({}) <front-matter>\n", typst_str(yaml));
+    };
+
+    let mut args = Vec::new();
+    if let Some(title) = take_str(&mut fields, "title") {
+        args.push(format!("title: {}", typst_str(&title)));
+    }
+    if let Some(authors) = take_strs(&mut fields, &["author", "authors"]) {
+        args.push(format!("author: {}", typst_array(&authors)));
+    }
+    if let Some(description) = take_str(&mut fields, "description") {
+        args.push(format!("description: {}", typst_str(&description)));
+    }
+    if let Some(keywords) = take_strs(&mut fields, &["keywords", "tags"]) {
+        args.push(format!("keywords: {}", typst_array(&keywords)));
+    }
+    if let Some(date) = take_date(&mut fields) {
+        args.push(format!("date: {date}"));
+    }
+
+    let mut out = String::new();
+    if !args.is_empty() {
+        out.push_str(&format!("#set document({})\n", args.join(", ")));
+    }
+    if !fields.is_empty() {
+        let rest = to_typst_value(&Value::Mapping(fields));
+        out.push_str(&format!("#metadata({rest}) <front-matter>\n"));
+    }
+    out
+}
+
+fn take_str(fields: &mut Mapping, key: &str) -> Option<String> {
+    match fields.get(key)? {
+        Value::String(value) => {
+            let value = value.clone();
+            fields.shift_remove(key);
+            Some(value)
+        }
+        _ => None,
+    }
+}
+
+/// Takes a string or an array of strings by the first present key.
+fn take_strs(fields: &mut Mapping, keys: &[&str]) -> Option<Vec<String>> {
+    let key = keys.iter().find(|key| fields.contains_key(**key))?;
+    let values = match fields.get(*key)? {
+        Value::String(value) => vec![value.clone()],
+        Value::Sequence(values) => values
+            .iter()
+            .map(|value| value.as_str().map(ToOwned::to_owned))
+            .collect::<Option<Vec<_>>>()?,
+        _ => return None,
+    };
+    fields.shift_remove(*key);
+    Some(values)
+}
+
+/// Takes a date in the form of `YYYY-MM-DD`.
+fn take_date(fields: &mut Mapping) -> Option<String> {
+    let date = fields.get("date")?.as_str()?;
+    let mut parts = date.get(..10)?.split('-').map(str::parse::<u32>);
+    let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day)), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+
+    fields.shift_remove("date");
+    Some(format!(
+        "datetime(year: {year}, month: {month}, day: {day})"
+    ))
+}
+
+fn typst_array(values: &[String]) -> String {
+    match values {
+        [value] => typst_str(value),
+        _ => {
+            let values: Vec<_> = values.iter().map(|value| typst_str(value)).collect();
+            format!("({})", values.join(", "))
+        }
+    }
+}
+
+/// Converts a YAML value into a typst value in code.
+fn to_typst_value(value: &Value) -> String {
+    match value {
+        Value::Null => "none".to_owned(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::String(value) => typst_str(value),
+        Value::Sequence(values) => {
+            let values: Vec<_> = values.iter().map(to_typst_value).collect();
+            match values.len() {
+                1 => format!("({},)", values[0]),
+                _ => format!("({})", values.join(", ")),
+            }
+        }
+        Value::Mapping(fields) => {
+            if fields.is_empty() {
+                return "(:)".to_owned();
+            }
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Value::String(key) if is_ident(key) => key.clone(),
+                        Value::String(key) => typst_str(key),
+                        key => typst_str(&to_typst_value(key)),
+                    };
+                    format!("{key}: {}", to_typst_value(value))
+                })
+                .collect();
+            format!("({})", fields.join(", "))
+        }
+        Value::Tagged(tagged) => to_typst_value(&tagged.value),
+    }
+}
+
+fn is_ident(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter() {
+        let yaml =
+            "title: Guide\nauthors: [Alice, Bob]\ndate: 2024-05-01\nlayout: post\nweight: 3\n";
+
+        assert_eq!(
+            convert_front_matter(yaml),
+            "#set document(title: \"Guide\", author: (\"Alice\", \"Bob\"), \
+             date: datetime(year: 2024, month: 5, day: 1))\n\
+             #metadata((layout: \"post\", weight: 3)) <front-matter>\n"
+        );
+    }
+}
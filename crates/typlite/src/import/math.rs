@@ -0,0 +1,767 @@
+//! Translates math in LaTeX into typst math
+//!
+//! This is the inverse of the LaTeX writer of equations for the common subset
+//! of LaTeX, e.g. fractions, roots, scripts, accents, fonts, delimiters,
+//! matrices and cases. Other constructs are left to the caller.
+
+/// Translates math in LaTeX into typst math, or returns `None` if it uses a
+/// construct out of the supported subset.
+pub(super) fn latex_to_typst(latex: &str) -> Option<String> {
+    let mut parser = MathParser {
+        chars: latex.chars().collect(),
+        pos: 0,
+        args: 0,
+    };
+    let atoms = parser.row(&[])?;
+    parser.skip_space();
+    (parser.pos == parser.chars.len()).then(|| atoms.join(" "))
+}
+
+/// The operators that are written as is in typst math.
+const OPERATORS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "csc", "deg", "det", "dim",
+    "exp", "gcd", "hom", "inf", "ker", "lg", "lim", "liminf", "limsup", "ln", "log", "max", "min",
+    "Pr", "sec", "sin", "sinh", "sup", "tan", "tanh",
+];
+
+/// The tokens ending a row of atoms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stop {
+    /// `}`, which closes a group.
+    Brace,
+    /// `]`, which closes an optional argument.
+    Bracket,
+    /// `&`, which separates the cells of a matrix.
+    Cell,
+    /// `\\`, which separates the rows of a matrix or cases.
+    Row,
+}
+
+struct MathParser {
+    chars: Vec<char>,
+    pos: usize,
+    /// The depth of the arguments of typst functions being written, in which
+    /// commas and semicolons are escaped.
+    args: usize,
+}
+
+impl MathParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        let matched = self.peek() == Some(ch);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Peeks the name of the command at the cursor, without the backslash.
+    fn peek_command(&self) -> Option<String> {
+        if self.peek() != Some('\\') {
+            return None;
+        }
+        let rest = &self.chars[self.pos + 1..];
+        let letters = rest.iter().take_while(|c| c.is_ascii_alphabetic()).count();
+        match letters {
+            0 => rest.first().map(|c| c.to_string()),
+            _ => Some(rest[..letters].iter().collect()),
+        }
+    }
+
+    /// Reads the name of the command at the cursor, without the backslash.
+    fn command(&mut self) -> Option<String> {
+        let name = self.peek_command()?;
+        self.pos += 1 + name.chars().count();
+        Some(name)
+    }
+
+    /// Parses atoms until the end of the input, `\right`, `\end` or one of the
+    /// `stops`, which is left unconsumed.
+    fn row(&mut self, stops: &[Stop]) -> Option<Vec<String>> {
+        let mut atoms = Vec::new();
+        loop {
+            self.skip_space();
+            let stop = match self.peek() {
+                None => true,
+                Some('}') => {
+                    if !stops.contains(&Stop::Brace) {
+                        return None;
+                    }
+                    true
+                }
+                Some(']') => stops.contains(&Stop::Bracket),
+                Some('&') => stops.contains(&Stop::Cell),
+                Some('\\') => match self.peek_command()?.as_str() {
+                    "right" | "end" => true,
+                    "\\" => stops.contains(&Stop::Row),
+                    _ => false,
+                },
+                _ => false,
+            };
+            if stop {
+                return Some(atoms);
+            }
+            if let Some(atom) = self.atom()? {
+                atoms.push(atom);
+            }
+        }
+    }
+
+    /// Parses an atom with its scripts, or returns `Some(None)` if nothing is
+    /// written, e.g. for `\displaystyle`.
+    fn atom(&mut self) -> Option<Option<String>> {
+        let Some(mut base) = self.base()? else {
+            return Some(None);
+        };
+        loop {
+            self.skip_space();
+            match self.peek() {
+                Some('\'') => {
+                    self.pos += 1;
+                    base.push('\'');
+                }
+                Some(script @ ('^' | '_')) => {
+                    self.pos += 1;
+                    let atoms = self.arg()?;
+                    base.push(script);
+                    base.push_str(&script_text(atoms)?);
+                }
+                Some('\\') if matches!(self.peek_command()?.as_str(), "limits" | "nolimits") => {
+                    self.command();
+                }
+                _ => return Some(Some(base)),
+            }
+        }
+    }
+
+    /// Parses an atom without scripts.
+    fn base(&mut self) -> Option<Option<String>> {
+        let ch = self.peek()?;
+        match ch {
+            '\\' => self.command_atom(),
+            '{' => {
+                let atoms = self.group()?;
+                // A group is invisible in LaTeX, which can't be written in
+                // typst unless it is a single atom.
+                match atoms.len() {
+                    0 => Some(None),
+                    1 => Some(atoms.into_iter().next()),
+                    _ => None,
+                }
+            }
+            '0'..='9' => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    let decimal = c == '.'
+                        && self
+                            .chars
+                            .get(self.pos + 1)
+                            .is_some_and(char::is_ascii_digit);
+                    if !c.is_ascii_digit() && !decimal {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                Some(Some(self.chars[start..self.pos].iter().collect()))
+            }
+            // Scripts and primes without bases, comments and macro parameters.
+            '^' | '_' | '\'' | '%' | '#' | '$' | '}' => None,
+            '~' => {
+                self.pos += 1;
+                Some(Some("space.nobreak".into()))
+            }
+            _ => {
+                self.pos += 1;
+                Some(Some(self.escape_char(ch)))
+            }
+        }
+    }
+
+    /// Escapes a character in typst math.
+    fn escape_char(&self, ch: char) -> String {
+        match ch {
+            ',' | ';' if self.args > 0 => format!("\\{ch}"),
+            '\\' | '/' | '_' | '^' | '&' | '"' | '@' | '#' | '$' | '{' | '}' | '\'' => {
+                format!("\\{ch}")
+            }
+            _ => ch.to_string(),
+        }
+    }
+
+    /// Parses a group in braces.
+    fn group(&mut self) -> Option<Vec<String>> {
+        self.skip_space();
+        if !self.eat('{') {
+            return None;
+        }
+        let atoms = self.row(&[Stop::Brace])?;
+        self.eat('}').then_some(atoms)
+    }
+
+    /// Parses the argument of a command or a script, which is a group or a
+    /// single token.
+    fn arg(&mut self) -> Option<Vec<String>> {
+        self.skip_space();
+        match self.peek()? {
+            '{' => self.group(),
+            '0'..='9' => {
+                // `x^12` only takes the first digit.
+                self.pos += 1;
+                Some(vec![self.chars[self.pos - 1].to_string()])
+            }
+            _ => Some(self.base()?.into_iter().collect()),
+        }
+    }
+
+    /// Parses the argument of a command written as the argument of a typst
+    /// function.
+    fn fn_arg(&mut self) -> Option<String> {
+        self.args += 1;
+        let atoms = self.arg();
+        self.args -= 1;
+        Some(atoms?.join(" "))
+    }
+
+    /// Reads the text in braces, e.g. of `\text`.
+    fn text_arg(&mut self) -> Option<String> {
+        self.skip_space();
+        if !self.eat('{') {
+            return None;
+        }
+        let start = self.pos;
+        let mut depth = 0usize;
+        loop {
+            match self.peek()? {
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                '\\' | '$' | '%' | '#' => return None,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos]
+            .iter()
+            .filter(|c| !matches!(c, '{' | '}'))
+            .collect();
+        self.pos += 1;
+        Some(text)
+    }
+
+    /// Parses an atom starting with a command.
+    fn command_atom(&mut self) -> Option<Option<String>> {
+        let name = self.command()?;
+        let atom = match name.as_str() {
+            // Spacing.
+            "," | "thinspace" => "thin".into(),
+            ":" | ">" | "medspace" => "med".into(),
+            ";" | "thickspace" => "thick".into(),
+            " " => "space".into(),
+            "quad" => "quad".into(),
+            "qquad" => "wide".into(),
+            "!" | "displaystyle" | "textstyle" | "scriptstyle" | "nonumber" | "notag" => {
+                return Some(None)
+            }
+            "\\" => "\\".into(),
+            // Fractions and roots.
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let num = self.fn_arg()?;
+                let denom = self.fn_arg()?;
+                format!("frac({num}, {denom})")
+            }
+            "binom" | "dbinom" | "tbinom" => {
+                let n = self.fn_arg()?;
+                let k = self.fn_arg()?;
+                format!("binom({n}, {k})")
+            }
+            "sqrt" => {
+                self.skip_space();
+                if self.eat('[') {
+                    self.args += 1;
+                    let index = self.row(&[Stop::Bracket]);
+                    self.args -= 1;
+                    let index = index?.join(" ");
+                    if !self.eat(']') {
+                        return None;
+                    }
+                    let radicand = self.fn_arg()?;
+                    format!("root({index}, {radicand})")
+                } else {
+                    format!("sqrt({})", self.fn_arg()?)
+                }
+            }
+            // Texts and operators.
+            "text" | "textrm" | "textnormal" | "mbox" | "mathrm" if self.is_text_arg() => {
+                escape_str(&self.text_arg()?)
+            }
+            "operatorname" => {
+                let limits = self.eat('*');
+                let name = escape_str(&self.text_arg()?);
+                match limits {
+                    true => format!("op({name}, limits: #true)"),
+                    false => format!("op({name})"),
+                }
+            }
+            "mathbf" => format!("upright(bold({}))", self.fn_arg()?),
+            "left" => return self.left().map(Some),
+            "begin" => return self.env().map(Some),
+            name if OPERATORS.contains(&name) => name.into(),
+            name => {
+                if let Some(func) = function(name) {
+                    format!("{func}({})", self.fn_arg()?)
+                } else {
+                    let ch = symbol(name)?;
+                    self.escape_char(ch)
+                }
+            }
+        };
+        Some(Some(atom))
+    }
+
+    /// Checks whether the argument of `\mathrm` is a plain text, which is
+    /// written as a string instead of `upright`.
+    fn is_text_arg(&self) -> bool {
+        let mut rest = self.chars[self.pos..]
+            .iter()
+            .skip_while(|c| c.is_whitespace());
+        rest.next() == Some(&'{') && rest.find(|c| matches!(c, '}' | '\\')) == Some(&'}')
+    }
+
+    /// Parses a delimiter after `\left` or `\right`, or returns `Some(None)`
+    /// for the invisible `.`.
+    fn delimiter(&mut self) -> Option<Option<String>> {
+        self.skip_space();
+        match self.peek()? {
+            '.' => {
+                self.pos += 1;
+                Some(None)
+            }
+            ch @ ('(' | ')' | '[' | ']' | '|' | '/') => {
+                self.pos += 1;
+                Some(Some(self.escape_char(ch)))
+            }
+            '\\' => {
+                let name = self.command()?;
+                let ch = symbol(&name)?;
+                matches!(
+                    ch,
+                    '{' | '}' | '⟨' | '⟩' | '⌊' | '⌋' | '⌈' | '⌉' | '|' | '‖'
+                )
+                .then(|| Some(self.escape_char(ch)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses `\left ... \right`, which is written as `lr`.
+    fn left(&mut self) -> Option<String> {
+        self.args += 1;
+        let parts = (|| {
+            let open = self.delimiter()?;
+            let body = self.row(&[])?;
+            if self.command()? != "right" {
+                return None;
+            }
+            let close = self.delimiter()?;
+            Some((open, body, close))
+        })();
+        self.args -= 1;
+        let (open, body, close) = parts?;
+        let atoms = open.into_iter().chain(body).chain(close);
+        Some(format!("lr({})", atoms.collect::<Vec<_>>().join(" ")))
+    }
+
+    /// Parses an environment after `\begin`.
+    fn env(&mut self) -> Option<String> {
+        let name = self.text_arg()?;
+        let body = match name.as_str() {
+            "matrix" | "pmatrix" | "bmatrix" | "Bmatrix" | "vmatrix" | "Vmatrix" => {
+                let delim = match name.as_str() {
+                    "matrix" => Some("#none"),
+                    "bmatrix" => Some("\"[\""),
+                    "Bmatrix" => Some("\"{\""),
+                    "vmatrix" => Some("\"|\""),
+                    "Vmatrix" => Some("\"||\""),
+                    _ => None,
+                };
+                let rows = self.rows()?;
+                let rows: Vec<_> = rows.iter().map(|cells| cells.join(", ")).collect();
+                let delim = delim.map(|delim| format!("delim: {delim}, "));
+                format!("mat({}{})", delim.unwrap_or_default(), rows.join("; "))
+            }
+            "cases" | "rcases" => {
+                let rows = self.rows()?;
+                let rows: Vec<_> = rows.iter().map(|cells| cells.join(" & ")).collect();
+                let reverse = (name == "rcases").then_some("reverse: #true, ");
+                format!("cases({}{})", reverse.unwrap_or_default(), rows.join(", "))
+            }
+            "aligned" | "align" | "align*" | "gathered" | "gather" | "gather*" | "split"
+            | "equation" | "equation*" => {
+                let mut atoms = Vec::new();
+                loop {
+                    atoms.extend(self.row(&[Stop::Cell])?);
+                    if !self.eat('&') {
+                        break;
+                    }
+                    atoms.push("&".into());
+                }
+                atoms.join(" ")
+            }
+            _ => return None,
+        };
+        if self.command()? != "end" || self.text_arg()? != name {
+            return None;
+        }
+        Some(body)
+    }
+
+    /// Parses the rows of a matrix or cases, whose cells are separated by `&`.
+    fn rows(&mut self) -> Option<Vec<Vec<String>>> {
+        self.args += 1;
+        let rows = (|| {
+            let mut rows = Vec::new();
+            let mut row = Vec::new();
+            loop {
+                row.push(self.row(&[Stop::Cell, Stop::Row])?.join(" "));
+                if self.eat('&') {
+                    continue;
+                }
+                if self.peek_command().as_deref() == Some("\\") {
+                    self.command();
+                    rows.push(std::mem::take(&mut row));
+                    continue;
+                }
+                break;
+            }
+            // The trailing `\\` is ignored.
+            if !(row.len() == 1 && row[0].is_empty()) {
+                rows.push(row);
+            }
+            Some(rows)
+        })();
+        self.args -= 1;
+        rows
+    }
+}
+
+/// Gets the typst function of a command taking a single argument.
+fn function(name: &str) -> Option<&'static str> {
+    Some(match name {
+        // Accents.
+        "hat" | "widehat" => "hat",
+        "tilde" | "widetilde" => "tilde",
+        "bar" => "macron",
+        "vec" | "overrightarrow" => "arrow",
+        "overleftarrow" => "arrow.l",
+        "overleftrightarrow" => "arrow.l.r",
+        "dot" => "dot",
+        "ddot" => "dot.double",
+        "dddot" => "dot.triple",
+        "acute" => "acute",
+        "grave" => "grave",
+        "breve" => "breve",
+        "check" => "caron",
+        "mathring" => "circle",
+        // Lines and braces.
+        "overline" => "overline",
+        "underline" => "underline",
+        "overbrace" => "overbrace",
+        "underbrace" => "underbrace",
+        "overbracket" => "overbracket",
+        "underbracket" => "underbracket",
+        "cancel" => "cancel",
+        // Fonts.
+        "mathrm" => "upright",
+        "boldsymbol" | "bm" => "bold",
+        "mathit" => "italic",
+        "mathbb" => "bb",
+        "mathcal" => "cal",
+        "mathscr" => "scr",
+        "mathfrak" => "frak",
+        "mathsf" => "sans",
+        "mathtt" => "mono",
+        _ => return None,
+    })
+}
+
+/// Gets the symbol of a command, which is the inverse of the symbols of the
+/// LaTeX writer with a few aliases.
+fn symbol(name: &str) -> Option<char> {
+    Some(match name {
+        // Aliases.
+        "le" => '≤',
+        "ge" => '≥',
+        "ne" => '≠',
+        "rightarrow" => '→',
+        "gets" => '←',
+        "implies" => '⟹',
+        "iff" => '⟺',
+        "dots" | "dotsc" | "dotsb" => '…',
+        "lbrace" => '{',
+        "rbrace" => '}',
+        "vert" | "lvert" | "rvert" => '|',
+        "Vert" | "lVert" | "rVert" => '‖',
+        "land" => '∧',
+        "lor" => '∨',
+        "lnot" => '¬',
+        "owns" => '∋',
+        "varnothing" => '∅',
+        "prime" => '′',
+        "colon" => ':',
+        // Greek letters.
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "varepsilon" => 'ε',
+        "epsilon" => 'ϵ',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "vartheta" => 'ϑ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "pi" => 'π',
+        "varpi" => 'ϖ',
+        "rho" => 'ρ',
+        "varrho" => 'ϱ',
+        "sigma" => 'σ',
+        "varsigma" => 'ς',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "varphi" => 'φ',
+        "phi" => 'ϕ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        // Letter-like symbols.
+        "ell" => 'ℓ',
+        "hbar" => 'ℏ',
+        "aleph" => 'ℵ',
+        "partial" => '∂',
+        "nabla" => '∇',
+        "infty" => '∞',
+        "emptyset" => '∅',
+        // Big operators.
+        "sum" => '∑',
+        "prod" => '∏',
+        "coprod" => '∐',
+        "int" => '∫',
+        "iint" => '∬',
+        "iiint" => '∭',
+        "oint" => '∮',
+        "bigcup" => '⋃',
+        "bigcap" => '⋂',
+        "bigoplus" => '⨁',
+        "bigotimes" => '⨂',
+        // Binary operators.
+        "pm" => '±',
+        "mp" => '∓',
+        "times" => '×',
+        "div" => '÷',
+        "cdot" => '⋅',
+        "circ" => '∘',
+        "ast" => '∗',
+        "oplus" => '⊕',
+        "ominus" => '⊖',
+        "otimes" => '⊗',
+        "odot" => '⊙',
+        "cup" => '∪',
+        "cap" => '∩',
+        "setminus" => '∖',
+        "wedge" => '∧',
+        "vee" => '∨',
+        // Relations.
+        "leq" => '≤',
+        "geq" => '≥',
+        "neq" => '≠',
+        "approx" => '≈',
+        "equiv" => '≡',
+        "sim" => '∼',
+        "simeq" => '≃',
+        "cong" => '≅',
+        "propto" => '∝',
+        "ll" => '≪',
+        "gg" => '≫',
+        "prec" => '≺',
+        "succ" => '≻',
+        "coloneqq" => '≔',
+        "in" => '∈',
+        "notin" => '∉',
+        "ni" => '∋',
+        "subset" => '⊂',
+        "supset" => '⊃',
+        "subseteq" => '⊆',
+        "supseteq" => '⊇',
+        "subsetneq" => '⊊',
+        "perp" => '⊥',
+        "parallel" => '∥',
+        "mid" => '∣',
+        "vdash" => '⊢',
+        "models" => '⊨',
+        // Logic.
+        "forall" => '∀',
+        "exists" => '∃',
+        "nexists" => '∄',
+        "neg" => '¬',
+        // Arrows.
+        "to" => '→',
+        "leftarrow" => '←',
+        "leftrightarrow" => '↔',
+        "Rightarrow" => '⇒',
+        "Leftarrow" => '⇐',
+        "Leftrightarrow" => '⇔',
+        "longrightarrow" => '⟶',
+        "Longrightarrow" => '⟹',
+        "Longleftrightarrow" => '⟺',
+        "mapsto" => '↦',
+        "uparrow" => '↑',
+        "downarrow" => '↓',
+        "hookrightarrow" => '↪',
+        // Dots.
+        "ldots" => '…',
+        "cdots" => '⋯',
+        "vdots" => '⋮',
+        "ddots" => '⋱',
+        // Delimiters.
+        "langle" => '⟨',
+        "rangle" => '⟩',
+        "lfloor" => '⌊',
+        "rfloor" => '⌋',
+        "lceil" => '⌈',
+        "rceil" => '⌉',
+        // Miscellaneous.
+        "angle" => '∠',
+        "triangle" => '△',
+        "square" => '□',
+        "diamond" => '◇',
+        "star" => '★',
+        "dagger" => '†',
+        "backslash" => '\\',
+        // Escaped characters.
+        "{" => '{',
+        "}" => '}',
+        "|" => '‖',
+        "#" => '#',
+        "%" => '%',
+        "&" => '&',
+        "$" => '$',
+        "_" => '_',
+        _ => return None,
+    })
+}
+
+/// Writes the text of a script, which is parenthesized unless it is a single
+/// token.
+fn script_text(atoms: Vec<String>) -> Option<String> {
+    let [atom] = atoms.as_slice() else {
+        return (!atoms.is_empty()).then(|| format!("({})", atoms.join(" ")));
+    };
+    let single = atom.chars().count() == 1
+        || atom.chars().all(|c| c.is_ascii_digit())
+        || atom.starts_with('\\') && atom.chars().count() == 2;
+    Some(match single {
+        true => atom.clone(),
+        false => format!("({atom})"),
+    })
+}
+
+/// Writes a string in typst math.
+fn escape_str(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        if matches!(c, '"' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conv(latex: &str) -> String {
+        latex_to_typst(latex).unwrap_or_else(|| panic!("{latex} is not translated"))
+    }
+
+    #[test]
+    fn test_scripts_and_symbols() {
+        assert_eq!(conv("e^{i\\pi}"), "e^(i π)");
+        assert_eq!(conv("x_1^2 + y'"), "x_1^2 + y'");
+        assert_eq!(conv("x^{12} \\leq 3.14"), "x^12 ≤ 3.14");
+        assert_eq!(conv("\\sum\\limits_{i=1}^n a_i"), "∑_(i = 1)^n a_i");
+        assert_eq!(conv("a/b, c"), "a \\/ b , c");
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(conv("\\frac{a+b}{2}"), "frac(a + b, 2)");
+        assert_eq!(conv("\\sqrt[3]{x} \\sqrt y"), "root(3, x) sqrt(y)");
+        assert_eq!(conv("\\frac{f(x, y)}{2}"), "frac(f ( x \\, y ), 2)");
+        assert_eq!(
+            conv("\\hat{x} \\vec v \\mathbb{R}"),
+            "hat(x) arrow(v) bb(R)"
+        );
+        assert_eq!(conv("\\sin x \\operatorname{sgn} x"), "sin x op(\"sgn\") x");
+        assert_eq!(conv("x \\text{if } y"), "x \"if \" y");
+        assert_eq!(conv("\\left( x \\right]"), "lr(( x ])");
+        assert_eq!(conv("\\left\\{ x \\right."), "lr(\\{ x)");
+    }
+
+    #[test]
+    fn test_environments() {
+        assert_eq!(
+            conv("\\begin{pmatrix} a & b \\\\ c & d \\end{pmatrix}"),
+            "mat(a, b; c, d)"
+        );
+        assert_eq!(
+            conv("\\begin{bmatrix} 1 & 0 \\\\ 0 & 1 \\\\ \\end{bmatrix}"),
+            "mat(delim: \"[\", 1, 0; 0, 1)"
+        );
+        assert_eq!(
+            conv("|x| = \\begin{cases} x & x \\geq 0 \\\\ -x & \\text{else} \\end{cases}"),
+            "| x | = cases(x & x ≥ 0, - x & \"else\")"
+        );
+        assert_eq!(
+            conv("\\begin{aligned} a &= b \\\\ &= c \\end{aligned}"),
+            "a & = b \\ & = c"
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(latex_to_typst("\\newcommand{\\x}{1}"), None);
+        assert_eq!(latex_to_typst("{a+b}^2"), None);
+        assert_eq!(latex_to_typst("\\begin{array}{cc} a \\end{array}"), None);
+        assert_eq!(latex_to_typst("\\frac{1}{2"), None);
+        assert_eq!(latex_to_typst("x}"), None);
+    }
+}
@@ -0,0 +1,719 @@
+//! Converts Markdown documents into typst markup
+//!
+//! CommonMark with the GFM extensions (tables, strikethrough, task lists and
+//! footnotes), math in `$...$` and the YAML front matter are supported. The
+//! common subset of math in LaTeX is translated into typst math, and the rest
+//! is rendered by the `mitex` package if [`ImportOptions::mitex_fallback`] is
+//! set.
+
+mod front_matter;
+mod math;
+
+use std::collections::HashMap;
+
+use ecow::EcoString;
+use pulldown_cmark::{
+    Alignment, BlockQuoteKind, CodeBlockKind, Event, LinkType, MetadataBlockKind, Options, Parser,
+    Tag, TagEnd,
+};
+
+use self::front_matter::convert_front_matter;
+use self::math::latex_to_typst;
+
+/// The import of the package rendering the math in LaTeX.
+const MITEX_IMPORT: &str = "#import \"@preview/mitex:0.2.5\": mi, mitex\n";
+
+/// The options of importing Markdown documents.
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Renders the math out of the supported subset of LaTeX by the `mitex`
+    /// package, instead of keeping it as raw LaTeX.
+    pub mitex_fallback: bool,
+}
+
+/// Converts a Markdown document into typst markup.
+pub fn markdown_to_typst(markdown: &str, options: &ImportOptions) -> EcoString {
+    let parser_options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_MATH
+        | Options::ENABLE_GFM
+        | Options::ENABLE_HEADING_ATTRIBUTES
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+    let events: Vec<Event> = Parser::new_ext(markdown, parser_options).collect();
+
+    // Footnotes are defined after their references, so they are rendered
+    // before the body.
+    let (body, definitions) = split_footnotes(events);
+    let mut footnotes = HashMap::new();
+    let mut uses_mitex = false;
+    for (name, events) in definitions {
+        let mut importer = Importer::new(HashMap::new(), options);
+        importer.run(&events);
+        uses_mitex |= importer.uses_mitex;
+        footnotes.insert(name, importer.finish_body());
+    }
+
+    let mut importer = Importer::new(footnotes, options);
+    for event in &body {
+        if let Event::FootnoteReference(name) = event {
+            *importer.footnote_refs.entry(name.to_string()).or_default() += 1;
+        }
+    }
+    importer.run(&body);
+
+    let mut header = String::new();
+    if uses_mitex || importer.uses_mitex {
+        header.push_str(MITEX_IMPORT);
+    }
+    if let Some(front_matter) = &importer.front_matter {
+        header.push_str(&convert_front_matter(front_matter));
+    }
+
+    let body = importer.finish_body();
+    let mut out = header;
+    if !out.is_empty() && !body.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&body);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.into()
+}
+
+/// Splits the footnote definitions from the body.
+fn split_footnotes(events: Vec<Event>) -> (Vec<Event>, Vec<(String, Vec<Event>)>) {
+    let mut body = Vec::new();
+    let mut definitions = Vec::new();
+    let mut current: Option<(String, Vec<Event>)> = None;
+    for event in events {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(name)) => {
+                current = Some((name.to_string(), Vec::new()));
+            }
+            Event::End(TagEnd::FootnoteDefinition) => definitions.extend(current.take()),
+            event => match &mut current {
+                Some((_, events)) => events.push(event),
+                None => body.push(event),
+            },
+        }
+    }
+    (body, definitions)
+}
+
+/// The state of a table being converted.
+#[derive(Default)]
+struct TableState {
+    aligns: Vec<Alignment>,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+}
+
+/// Converts the events of a Markdown document.
+struct Importer {
+    /// The output of the nested elements, the last of which is being written.
+    bufs: Vec<String>,
+    /// The next numbers of the items in the lists, or `None` for bullet lists.
+    lists: Vec<Option<u64>>,
+    table: Option<TableState>,
+    /// The types and destinations of the links and images being written.
+    links: Vec<(LinkType, String)>,
+    /// The label of the heading being written.
+    heading_id: Option<String>,
+    /// The language of the code block being written.
+    code_lang: Option<String>,
+    /// Whether the text is written as is, e.g. in code blocks.
+    verbatim: bool,
+    /// The number of the images being written, whose alternative text is
+    /// written in plain text.
+    images: usize,
+    /// The rendered footnotes by their names.
+    footnotes: HashMap<String, String>,
+    /// The numbers of references to each footnote, which is reset to zero
+    /// once the footnote is labelled.
+    footnote_refs: HashMap<String, usize>,
+    front_matter: Option<String>,
+    /// Whether the math out of the supported subset of LaTeX is rendered by
+    /// the `mitex` package.
+    mitex_fallback: bool,
+    uses_mitex: bool,
+}
+
+impl Importer {
+    fn new(footnotes: HashMap<String, String>, options: &ImportOptions) -> Self {
+        Self {
+            bufs: vec![String::new()],
+            lists: Vec::new(),
+            table: None,
+            links: Vec::new(),
+            heading_id: None,
+            code_lang: None,
+            verbatim: false,
+            images: 0,
+            footnotes,
+            footnote_refs: HashMap::new(),
+            front_matter: None,
+            mitex_fallback: options.mitex_fallback,
+            uses_mitex: false,
+        }
+    }
+
+    fn buf(&mut self) -> &mut String {
+        self.bufs
+            .last_mut()
+            .expect("the root buffer is never popped")
+    }
+
+    fn push_buf(&mut self) {
+        self.bufs.push(String::new());
+    }
+
+    fn pop_buf(&mut self) -> String {
+        self.bufs.pop().unwrap_or_default()
+    }
+
+    fn finish_body(mut self) -> String {
+        self.pop_buf().trim().to_owned()
+    }
+
+    /// Starts a block on a new line.
+    fn start_block(&mut self) {
+        let buf = self.buf();
+        if !buf.is_empty() && !buf.ends_with('\n') {
+            buf.push('\n');
+        }
+    }
+
+    /// Ends a block with an empty line.
+    fn end_block(&mut self) {
+        let buf = self.buf();
+        let len = buf.trim_end_matches('\n').len();
+        buf.truncate(len);
+        buf.push_str("\n\n");
+    }
+
+    fn run(&mut self, events: &[Event]) {
+        for (idx, event) in events.iter().enumerate() {
+            self.event(event, events.get(idx + 1));
+        }
+    }
+
+    fn event(&mut self, event: &Event, next: Option<&Event>) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag, next),
+            Event::Text(text) if self.verbatim || self.images > 0 => self.buf().push_str(text),
+            Event::Text(text) => {
+                let at_line_start = self.buf().is_empty() || self.buf().ends_with('\n');
+                let escaped = escape_markup(text, at_line_start);
+                self.buf().push_str(&escaped);
+            }
+            Event::Code(code) => {
+                let raw = if code.contains('`') {
+                    format!("#raw({})", typst_str(code))
+                } else {
+                    format!("`{code}`")
+                };
+                self.buf().push_str(&raw);
+            }
+            Event::InlineMath(math) => {
+                let math = self.math(math, false);
+                self.buf().push_str(&math);
+            }
+            Event::DisplayMath(math) => {
+                let math = self.math(math, true);
+                self.buf().push_str(&math);
+            }
+            Event::Html(html) if self.verbatim => self.buf().push_str(html),
+            Event::Html(..) => {}
+            Event::InlineHtml(html) => {
+                let tag = html.trim_start_matches('<').trim_end_matches('>');
+                let tag = tag.trim_end_matches('/').trim();
+                if tag.eq_ignore_ascii_case("br") {
+                    self.buf().push_str("\\\n");
+                }
+            }
+            Event::FootnoteReference(name) => self.footnote_ref(name),
+            Event::SoftBreak => self.buf().push('\n'),
+            Event::HardBreak => self.buf().push_str("\\\n"),
+            Event::Rule => {
+                self.start_block();
+                self.buf().push_str("#line(length: 100%)");
+                self.end_block();
+            }
+            Event::TaskListMarker(checked) => {
+                self.buf().push_str(if *checked { "☒ " } else { "☐ " });
+            }
+        }
+    }
+
+    fn start(&mut self, tag: &Tag) {
+        match tag {
+            Tag::Paragraph => self.start_block(),
+            Tag::Heading { id, .. } => {
+                self.start_block();
+                self.heading_id = id.as_ref().map(|id| id.to_string());
+                self.push_buf();
+            }
+            Tag::BlockQuote(..) => {
+                self.start_block();
+                self.push_buf();
+            }
+            Tag::Item
+            | Tag::TableCell
+            | Tag::Emphasis
+            | Tag::Strong
+            | Tag::Strikethrough
+            | Tag::Superscript
+            | Tag::Subscript => self.push_buf(),
+            Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            } => {
+                self.links.push((*link_type, dest_url.to_string()));
+                self.push_buf();
+            }
+            Tag::Image { dest_url, .. } => {
+                self.links.push((LinkType::Inline, dest_url.to_string()));
+                self.images += 1;
+                self.push_buf();
+            }
+            Tag::CodeBlock(kind) => {
+                self.start_block();
+                self.code_lang = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().map(Into::into),
+                    CodeBlockKind::Indented => None,
+                };
+                self.verbatim = true;
+                self.push_buf();
+            }
+            Tag::HtmlBlock | Tag::MetadataBlock(..) => {
+                self.start_block();
+                self.verbatim = true;
+                self.push_buf();
+            }
+            Tag::List(start) => {
+                self.start_block();
+                self.lists.push(*start);
+            }
+            Tag::Table(aligns) => {
+                self.start_block();
+                self.table = Some(TableState {
+                    aligns: aligns.clone(),
+                    ..TableState::default()
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: &TagEnd, next: Option<&Event>) {
+        match tag {
+            TagEnd::Paragraph => self.end_block(),
+            TagEnd::Heading(level) => {
+                let content = self.pop_buf();
+                let mut heading = format!("{} {}", "=".repeat(*level as usize), content.trim());
+                if let Some(id) = self.heading_id.take() {
+                    heading.push_str(&format!(" <{}>", label_name(&id)));
+                }
+                self.buf().push_str(&heading);
+                self.end_block();
+            }
+            TagEnd::BlockQuote(kind) => {
+                let content = self.pop_buf();
+                let title = kind.map(|kind| match kind {
+                    BlockQuoteKind::Note => "Note",
+                    BlockQuoteKind::Tip => "Tip",
+                    BlockQuoteKind::Important => "Important",
+                    BlockQuoteKind::Warning => "Warning",
+                    BlockQuoteKind::Caution => "Caution",
+                });
+                let content = match title {
+                    Some(title) => format!("*{title}*\n\n{}", content.trim()),
+                    None => content.trim().to_owned(),
+                };
+                let quote = format!("#quote(block: true)[\n{content}\n]");
+                self.buf().push_str(&quote);
+                self.end_block();
+            }
+            TagEnd::CodeBlock => {
+                self.verbatim = false;
+                let content = self.pop_buf();
+                let lang = self.code_lang.take().unwrap_or_default();
+                // The fence is longer than the backticks in the code.
+                let longest = content
+                    .split(|c| c != '`')
+                    .map(str::len)
+                    .max()
+                    .unwrap_or_default();
+                let fence = "`".repeat((longest + 1).max(3));
+                let content = content.trim_end_matches('\n');
+                let raw = format!("{fence}{lang}\n{content}\n{fence}");
+                self.buf().push_str(&raw);
+                self.end_block();
+            }
+            TagEnd::HtmlBlock => {
+                self.verbatim = false;
+                let html = self.pop_buf();
+                let html = html.trim().replace("*/", "* /");
+                if !html.is_empty() {
+                    let comment = format!("/* {html} */");
+                    self.buf().push_str(&comment);
+                    self.end_block();
+                }
+            }
+            TagEnd::MetadataBlock(kind) => {
+                self.verbatim = false;
+                let content = self.pop_buf();
+                if matches!(kind, MetadataBlockKind::YamlStyle) {
+                    self.front_matter = Some(content);
+                }
+            }
+            TagEnd::List(..) => {
+                self.lists.pop();
+                self.end_block();
+            }
+            TagEnd::Item => {
+                let content = self.pop_buf();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        let marker = if *number == 1 {
+                            "+ ".to_owned()
+                        } else {
+                            format!("{number}. ")
+                        };
+                        // The following items are numbered after the first one.
+                        *number = 1;
+                        marker
+                    }
+                    _ => "- ".to_owned(),
+                };
+                let item = indent_item(&marker, content.trim());
+                self.start_block();
+                self.buf().push_str(&item);
+                self.buf().push('\n');
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    let table = render_table(table);
+                    self.buf().push_str(&table);
+                    self.end_block();
+                }
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header = std::mem::take(&mut table.row);
+                }
+            }
+            TagEnd::TableRow => {
+                if let Some(table) = &mut self.table {
+                    let row = std::mem::take(&mut table.row);
+                    table.rows.push(row);
+                }
+            }
+            TagEnd::TableCell => {
+                let content = self.pop_buf();
+                if let Some(table) = &mut self.table {
+                    table.row.push(content.trim().to_owned());
+                }
+            }
+            TagEnd::Emphasis | TagEnd::Strong => {
+                let content = self.pop_buf();
+                let (delim, func) = match tag {
+                    TagEnd::Strong => ('*', "strong"),
+                    _ => ('_', "emph"),
+                };
+                // The delimiters only work at the boundaries of words.
+                let prev = self.buf().chars().last();
+                let next = match next {
+                    Some(Event::Text(text)) => text.chars().next(),
+                    _ => None,
+                };
+                let in_word = prev.is_some_and(char::is_alphanumeric)
+                    || next.is_some_and(char::is_alphanumeric);
+                let trimmed = content.trim() == content && !content.is_empty();
+                let styled = if in_word || !trimmed {
+                    format!("#{func}[{content}]")
+                } else {
+                    format!("{delim}{content}{delim}")
+                };
+                self.buf().push_str(&styled);
+            }
+            TagEnd::Strikethrough | TagEnd::Superscript | TagEnd::Subscript => {
+                let content = self.pop_buf();
+                let func = match tag {
+                    TagEnd::Strikethrough => "strike",
+                    TagEnd::Superscript => "super",
+                    _ => "sub",
+                };
+                let styled = format!("#{func}[{content}]");
+                self.buf().push_str(&styled);
+            }
+            TagEnd::Link => {
+                let content = self.pop_buf();
+                let (link_type, dest) = self
+                    .links
+                    .pop()
+                    .unwrap_or((LinkType::Inline, String::new()));
+                let link = match link_type {
+                    LinkType::Email => {
+                        format!("#link({})[{content}]", typst_str(&format!("mailto:{dest}")))
+                    }
+                    LinkType::Autolink => format!("#link({})", typst_str(&dest)),
+                    _ if content.is_empty() => format!("#link({})", typst_str(&dest)),
+                    _ => format!("#link({})[{content}]", typst_str(&dest)),
+                };
+                self.buf().push_str(&link);
+            }
+            TagEnd::Image => {
+                let alt = self.pop_buf();
+                self.images -= 1;
+                let (_, src) = self
+                    .links
+                    .pop()
+                    .unwrap_or((LinkType::Inline, String::new()));
+                // Typst cannot load remote images, which are linked instead.
+                let image = if src.starts_with("http://") || src.starts_with("https://") {
+                    let alt = if alt.is_empty() { &src } else { &alt };
+                    format!("#link({})[{}]", typst_str(&src), escape_markup(alt, false))
+                } else if alt.is_empty() {
+                    format!("#image({})", typst_str(&src))
+                } else {
+                    format!("#image({}, alt: {})", typst_str(&src), typst_str(&alt))
+                };
+                self.buf().push_str(&image);
+            }
+            _ => {}
+        }
+    }
+
+    /// Writes math in LaTeX, which is translated into typst math if possible.
+    fn math(&mut self, latex: &str, block: bool) -> String {
+        match latex_to_typst(latex) {
+            Some(math) if block => format!("$ {math} $"),
+            Some(math) => format!("${math}$"),
+            None if self.mitex_fallback => {
+                self.uses_mitex = true;
+                let func = if block { "mitex" } else { "mi" };
+                format!("#{func}({})", typst_str(latex))
+            }
+            None => {
+                let block = if block { "block: true, " } else { "" };
+                format!("#raw({block}lang: \"latex\", {})", typst_str(latex))
+            }
+        }
+    }
+
+    fn footnote_ref(&mut self, name: &str) {
+        let label = label_name(name);
+        let footnote = match self.footnotes.get(name) {
+            // The footnote referred to for several times is labelled at
+            // the first reference.
+            Some(content) => match self.footnote_refs.get_mut(name) {
+                Some(count) if *count == 0 => format!("#footnote(<fn-{label}>)"),
+                Some(count) if *count > 1 => {
+                    *count = 0;
+                    format!("#footnote[{content}] <fn-{label}>")
+                }
+                _ => format!("#footnote[{content}]"),
+            },
+            None => escape_markup(&format!("[^{name}]"), false),
+        };
+        self.buf().push_str(&footnote);
+    }
+}
+
+/// Indents the content of a list item after the marker.
+fn indent_item(marker: &str, content: &str) -> String {
+    let mut item = String::from(marker);
+    for (idx, line) in content.lines().enumerate() {
+        if idx > 0 {
+            item.push('\n');
+            if !line.is_empty() {
+                item.push_str("  ");
+            }
+        }
+        item.push_str(line);
+    }
+    item
+}
+
+fn render_table(table: TableState) -> String {
+    let columns = table
+        .aligns
+        .len()
+        .max(table.header.len())
+        .max(table.rows.iter().map(Vec::len).max().unwrap_or_default());
+
+    let mut out = String::from("#table(\n");
+    out.push_str(&format!("  columns: {columns},\n"));
+    if table.aligns.iter().any(|align| *align != Alignment::None) {
+        let aligns: Vec<_> = table
+            .aligns
+            .iter()
+            .map(|align| match align {
+                Alignment::None => "auto",
+                Alignment::Left => "left",
+                Alignment::Center => "center",
+                Alignment::Right => "right",
+            })
+            .collect();
+        out.push_str(&format!("  align: ({}),\n", aligns.join(", ")));
+    }
+
+    let cells = |row: &[String]| {
+        row.iter()
+            .map(|cell| format!("[{cell}]"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if !table.header.is_empty() {
+        out.push_str(&format!("  table.header({}),\n", cells(&table.header)));
+    }
+    for row in &table.rows {
+        out.push_str(&format!("  {},\n", cells(row)));
+    }
+    out.push(')');
+    out
+}
+
+/// Escapes the characters with special meanings in typst markup.
+fn escape_markup(text: &str, at_line_start: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut line_start = at_line_start;
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        let escape = match c {
+            '\\' | '*' | '_' | '`' | '$' | '#' | '@' | '<' | '[' | ']' | '~' => true,
+            // Comments
+            '/' => matches!(next, Some('/' | '*')),
+            // Headings and lists
+            '=' | '-' | '+' => line_start,
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+
+        // Numbered lists, e.g. `1. `
+        if line_start && c.is_ascii_digit() {
+            let digits: String =
+                std::iter::from_fn(|| chars.next_if(char::is_ascii_digit)).collect();
+            out.push_str(&digits);
+            if chars.peek() == Some(&'.') {
+                chars.next();
+                out.push_str("\\.");
+            }
+        }
+        line_start = c == '\n';
+    }
+    out
+}
+
+/// Creates a string literal in typst code.
+fn typst_str(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Converts a name into a valid label name.
+fn label_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks() {
+        let markdown =
+            "# Title {#intro}\n\nSome *text* and **bold**, see [docs](https://typst.app).\n\n\
+                        - one\n- two\n  1. nested\n\n```rust\nfn main() {}\n```\n\n> quoted\n";
+
+        assert_eq!(
+            markdown_to_typst(markdown, &ImportOptions::default()),
+            "= Title <intro>\n\n\
+             Some _text_ and *bold*, see #link(\"https://typst.app\")[docs].\n\n\
+             - one\n- two\n  + nested\n\n\
+             ```rust\nfn main() {}\n```\n\n\
+             #quote(block: true)[\nquoted\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let markdown = "| a | b |\n|:--|--:|\n| 1 | 2 |\n";
+
+        assert_eq!(
+            markdown_to_typst(markdown, &ImportOptions::default()),
+            "#table(\n  columns: 2,\n  align: (left, right),\n  \
+             table.header([a], [b]),\n  [1], [2],\n)\n"
+        );
+    }
+
+    #[test]
+    fn test_footnotes_and_math() {
+        let markdown = "Euler $e^{i\\pi}$[^1] again[^1].\n\n[^1]: A note.\n";
+
+        assert_eq!(
+            markdown_to_typst(markdown, &ImportOptions::default()),
+            "Euler $e^(i π)$#footnote[A note.] <fn-1> again#footnote(<fn-1>).\n"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_math() {
+        let markdown = "$\\mathbf{x}$ and $\\def\\x{1}$\n\n$$\\begin{array}{c} a \\end{array}$$\n";
+
+        assert_eq!(
+            markdown_to_typst(markdown, &ImportOptions::default()),
+            "$upright(bold(x))$ and #raw(lang: \"latex\", \"\\\\def\\\\x{1}\")\n\n\
+             #raw(block: true, lang: \"latex\", \"\\\\begin{array}{c} a \\\\end{array}\")\n"
+        );
+
+        let options = ImportOptions {
+            mitex_fallback: true,
+        };
+        assert_eq!(
+            markdown_to_typst(markdown, &options),
+            "#import \"@preview/mitex:0.2.5\": mi, mitex\n\n\
+             $upright(bold(x))$ and #mi(\"\\\\def\\\\x{1}\")\n\n\
+             #mitex(\"\\\\begin{array}{c} a \\\\end{array}\")\n"
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape_markup("1. #tag @user a*b // c", true),
+            "1\\. \\#tag \\@user a\\*b \\// c"
+        );
+    }
+}
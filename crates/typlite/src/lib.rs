@@ -1,9 +1,14 @@
 //! # Typlite
 
 pub mod attributes;
+mod bib;
 pub mod common;
 mod error;
+pub mod import;
+pub mod math;
 pub mod parser;
+pub mod site;
+pub mod source_map;
 pub mod tags;
 pub mod writer;
 
@@ -14,17 +19,23 @@ use std::sync::Arc;
 pub use error::*;
 
 use cmark_writer::ast::Node;
+use comemo::Track;
 use tinymist_project::base::ShadowApi;
 use tinymist_project::vfs::WorkspaceResolver;
 use tinymist_project::{EntryReader, LspWorld, TaskInputs};
 use tinymist_std::error::prelude::*;
 use typst::foundations::Bytes;
 use typst::html::HtmlDocument;
+use typst::model::BibliographyElem;
 use typst::World;
 use typst_syntax::VirtualPath;
 
+use crate::bib::BibSources;
 pub use crate::common::Format;
+use crate::common::FormatWriter;
 use crate::parser::HtmlToAstParser;
+use crate::site::{Site, SiteKind};
+use crate::source_map::{SourceMap, WrappedMain};
 use crate::writer::WriterFactory;
 use typst_syntax::FileId;
 
@@ -38,12 +49,22 @@ pub use cmark_writer::ast;
 pub use tinymist_project::CompileOnceArgs;
 pub use tinymist_std;
 
+/// The file wrapping the main file with the show rules converting the
+/// document.
+const WRAP_MAIN: &str = "__wrap_md_main.typ";
+/// The content of the wrapping file before the content of the main file.
+const WRAP_MAIN_PREFIX: &str =
+    "#import \"@local/_markdown:0.1.0\": md-doc, example; #show: md-doc\n";
+
 #[derive(Clone)]
 pub struct MarkdownDocument {
     pub base: HtmlDocument,
     world: Arc<LspWorld>,
     feat: TypliteFeat,
     ast: Option<Node>,
+    /// The main file wrapped by [`WRAP_MAIN`], which is recorded when the
+    /// world is prepared.
+    wrapped: Option<WrappedMain>,
 }
 
 impl MarkdownDocument {
@@ -54,6 +75,7 @@ impl MarkdownDocument {
             world,
             feat,
             ast: None,
+            wrapped: None,
         }
     }
 
@@ -69,6 +91,7 @@ impl MarkdownDocument {
             world,
             feat,
             ast: Some(ast),
+            wrapped: None,
         }
     }
 
@@ -81,6 +104,17 @@ impl MarkdownDocument {
         parser.parse(&self.base.root).context_ut("failed to parse")
     }
 
+    /// Gets the file names and the contents of the BibLaTeX sources of the
+    /// bibliography, which are cited by keys in LaTeX.
+    pub fn bib_files(&self) -> Vec<(ecow::EcoString, Bytes)> {
+        let Ok(elem) = BibliographyElem::find(self.base.introspector.track()) else {
+            return Vec::new();
+        };
+        BibSources::load(&self.world, &elem)
+            .map(|sources| sources.biblatex_files)
+            .unwrap_or_default()
+    }
+
     /// Convert content to markdown string
     pub fn to_md_string(&self) -> tinymist_std::Result<ecow::EcoString> {
         let mut output = ecow::EcoString::new();
@@ -107,12 +141,37 @@ impl MarkdownDocument {
         Ok(output)
     }
 
-    /// Convert the content to a LaTeX string.
+    /// Convert the content to a LaTeX string. The references to the labels
+    /// are written as `\ref`.
     pub fn to_tex_string(&self) -> tinymist_std::Result<ecow::EcoString> {
+        let mut output = ecow::EcoString::new();
+        let ast = self.parse_linked()?;
+
+        self.latex_writer()?
+            .write_eco(&ast, &mut output)
+            .context_ut("failed to write")?;
+
+        Ok(output)
+    }
+
+    fn latex_writer(&self) -> tinymist_std::Result<writer::LaTeXWriter> {
+        let mut writer = writer::LaTeXWriter::new();
+        if let Some(path) = &self.feat.tex_template {
+            let template = std::fs::read_to_string(path)
+                .with_context("failed to read the template", || {
+                    Some(Box::new([("path", path.display().to_string())]))
+                })?;
+            writer = writer.with_template(template, &self.base.info);
+        }
+        Ok(writer)
+    }
+
+    /// Convert the content to the JSON AST of pandoc.
+    pub fn to_pandoc_json_string(&self) -> tinymist_std::Result<ecow::EcoString> {
         let mut output = ecow::EcoString::new();
         let ast = self.parse()?;
 
-        let mut writer = WriterFactory::create(Format::LaTeX);
+        let mut writer = WriterFactory::create(Format::PandocJson);
         writer
             .write_eco(&ast, &mut output)
             .context_ut("failed to write")?;
@@ -120,13 +179,86 @@ impl MarkdownDocument {
         Ok(output)
     }
 
+    /// Converts the content to Markdown, LaTeX or plain text with a source map
+    /// from the top-level blocks in the output back to the typst sources.
+    pub fn to_string_with_source_map(
+        &self,
+        format: Format,
+    ) -> tinymist_std::Result<(ecow::EcoString, SourceMap)> {
+        let mut feat = self.feat.clone();
+        let mut writer: Box<dyn FormatWriter> = match format {
+            Format::Md | Format::Text => WriterFactory::create(format),
+            Format::LaTeX => {
+                feat.link_refs = true;
+                Box::new(self.latex_writer()?)
+            }
+            _ => bail!("source maps are only supported in Markdown, LaTeX and text"),
+        };
+        let (ast, block_spans) = HtmlToAstParser::new(feat, &self.world)
+            .parse_with_spans(&self.base.root)
+            .context_ut("failed to parse")?;
+
+        let mut output = ecow::EcoString::new();
+        let ranges = writer
+            .write_blocks(&ast, &mut output)
+            .context_ut("failed to write")?;
+        let map = SourceMap::new(&self.world, self.wrapped, &output, &ranges, &block_spans);
+        Ok((output, map))
+    }
+
+    /// Splits the content into the chapters of a documentation site at the
+    /// headings whose levels are not greater than `split_level`. The
+    /// references between the chapters are resolved into links.
+    pub fn to_site(&self, kind: SiteKind, split_level: usize) -> tinymist_std::Result<Site> {
+        let ast = self.parse_linked()?;
+        let labels = site::label_headings(&self.base.introspector);
+        site::build_site(
+            ast,
+            &labels,
+            &self.world,
+            self.base.info.title.clone(),
+            kind,
+            split_level,
+        )
+    }
+
     /// Convert the content to a DOCX document
     #[cfg(feature = "docx")]
     pub fn to_docx(&self) -> tinymist_std::Result<Vec<u8>> {
+        let ast = self.parse_linked()?;
+
+        let mut writer =
+            writer::DocxWriter::new().with_labels(site::label_locations(&self.base.introspector));
+        if let Some(path) = &self.feat.reference_doc {
+            let template = std::fs::read(path)
+                .with_context("failed to read the reference document", || {
+                    Some(Box::new([("path", path.display().to_string())]))
+                })?;
+            writer = writer.with_reference(template);
+        }
+        writer.write_vec(&ast).context_ut("failed to write")
+    }
+
+    /// Convert the content to an ODT document.
+    #[cfg(feature = "odt")]
+    pub fn to_odt(&self) -> tinymist_std::Result<Vec<u8>> {
         let ast = self.parse()?;
+        writer::OdtWriter::new()
+            .write_vec(&ast)
+            .context_ut("failed to write")
+    }
 
-        let mut writer = WriterFactory::create(Format::Docx);
-        writer.write_vec(&ast).context_ut("failed to write")
+    /// Parses the document with the references kept as
+    /// [`common::RefNode`].
+    fn parse_linked(&self) -> tinymist_std::Result<Node> {
+        if let Some(ast) = &self.ast {
+            return Ok(ast.clone());
+        }
+        let mut feat = self.feat.clone();
+        feat.link_refs = true;
+        HtmlToAstParser::new(feat, &self.world)
+            .parse(&self.base.root)
+            .context_ut("failed to parse")
     }
 }
 
@@ -138,6 +270,18 @@ pub enum ColorTheme {
     Dark,
 }
 
+/// The output of math equations.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum MathOutput {
+    /// Renders the equations into SVG images.
+    #[default]
+    Svg,
+    /// Writes the equations in LaTeX, e.g. `$x^2$`, which can be rendered by
+    /// KaTeX or MathJax in Markdown.
+    Latex,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct TypliteFeat {
     /// The preferred color theme.
@@ -154,6 +298,19 @@ pub struct TypliteFeat {
     pub remove_html: bool,
     /// The target to convert
     pub target: Format,
+    /// The output of math equations in Markdown and LaTeX. The equations are
+    /// always written in OMML in DOCX.
+    pub math_output: MathOutput,
+    /// Keeps the targets of the references and the links to labels as
+    /// [`common::RefNode`], which are resolved by [`MarkdownDocument::to_site`]
+    /// and written as `\ref` in LaTeX.
+    pub link_refs: bool,
+    /// The reference DOCX document, whose styles, page setup, headers, footers
+    /// and numbering are reused in DOCX.
+    pub reference_doc: Option<PathBuf>,
+    /// The LaTeX template wrapping the body, whose `{{title}}`, `{{authors}}`
+    /// and `{{body}}` placeholders are filled by the document.
+    pub tex_template: Option<PathBuf>,
     /// Import context for code examples (e.g., "#import \"/path/to/file.typ\":
     /// *")
     pub import_context: Option<String>,
@@ -178,11 +335,38 @@ pub struct TypliteFeat {
 }
 
 impl TypliteFeat {
+    /// Whether the equations are written as native math markups instead of
+    /// being rendered into frames.
+    pub fn native_math(&self, format: Format) -> bool {
+        match format {
+            _ if self.remove_html => false,
+            Format::Md | Format::LaTeX => self.math_output == MathOutput::Latex,
+            Format::Text => false,
+            Format::PandocJson => true,
+            #[cfg(feature = "docx")]
+            Format::Docx => true,
+            // Equations are embedded as pictures since there is no converter
+            // to MathML yet.
+            #[cfg(feature = "odt")]
+            Format::Odt => false,
+        }
+    }
+
     pub fn prepare_world(
         &self,
         world: &LspWorld,
         format: Format,
     ) -> tinymist_std::Result<LspWorld> {
+        Ok(self.wrap_world(world, format)?.0)
+    }
+
+    /// Prepares the world to convert, and records the files wrapping the
+    /// main file.
+    fn wrap_world(
+        &self,
+        world: &LspWorld,
+        format: Format,
+    ) -> tinymist_std::Result<(LspWorld, WrappedMain)> {
         let entry = world.entry_state();
         let main = entry.main();
         let current = main.context("no main file in workspace")?;
@@ -191,7 +375,7 @@ impl TypliteFeat {
             bail!("package file is not supported");
         }
 
-        let wrap_main_id = current.join("__wrap_md_main.typ");
+        let wrap_main_id = current.join(WRAP_MAIN);
 
         let (main_id, main_content) = match self.processor.as_ref() {
             None => (wrap_main_id, None),
@@ -199,7 +383,7 @@ impl TypliteFeat {
                 let main_id = current.join("__md_main.typ");
                 let content = format!(
                     r#"#import {processor:?}: article
-#article(include "__wrap_md_main.typ")"#
+#article(include {WRAP_MAIN:?})"#
                 );
 
                 (main_id, Some(Bytes::from_string(content)))
@@ -211,6 +395,13 @@ impl TypliteFeat {
         if format == Format::Text || self.remove_html {
             dict.insert("x-remove-html".into(), Str("true".into()));
         }
+        if self.native_math(format) {
+            dict.insert("x-math-output".into(), Str("native".into()));
+        }
+        #[cfg(feature = "docx")]
+        if format == Format::Docx {
+            dict.insert("x-docx-styles".into(), Str("true".into()));
+        }
 
         let task_inputs = TaskInputs {
             entry: Some(entry.select_in_workspace(main_id.vpath().as_rooted_path())),
@@ -240,12 +431,12 @@ impl TypliteFeat {
             )
             .context_ut("cannot map markdown.typ")?;
 
+        let prefix = WRAP_MAIN_PREFIX;
         world
             .map_shadow_by_id(
                 wrap_main_id,
                 Bytes::from_string(format!(
-                    r#"#import "@local/_markdown:0.1.0": md-doc, example; #show: md-doc
-{}"#,
+                    "{prefix}{}",
                     world
                         .source(current)
                         .context_ut("failed to get main file content")?
@@ -260,7 +451,13 @@ impl TypliteFeat {
                 .context_ut("cannot map source for main file")?;
         }
 
-        Ok(world)
+        let wrapped = WrappedMain {
+            main: current,
+            entry: main_id,
+            wrapper: wrap_main_id,
+            prefix_len: prefix.len(),
+        };
+        Ok((world, wrapped))
     }
 }
 
@@ -301,8 +498,13 @@ impl Typlite {
             Format::Md => self.convert_doc(Format::Md)?.to_md_string(),
             Format::LaTeX => self.convert_doc(Format::LaTeX)?.to_tex_string(),
             Format::Text => self.convert_doc(Format::Text)?.to_text_string(),
+            Format::PandocJson => self
+                .convert_doc(Format::PandocJson)?
+                .to_pandoc_json_string(),
             #[cfg(feature = "docx")]
             Format::Docx => bail!("docx format is not supported"),
+            #[cfg(feature = "odt")]
+            Format::Odt => bail!("odt format is not supported"),
         }
     }
 
@@ -315,11 +517,22 @@ impl Typlite {
         self.convert_doc(Format::Docx)?.to_docx()
     }
 
+    /// Convert the content to an ODT document
+    #[cfg(feature = "odt")]
+    pub fn to_odt(self) -> tinymist_std::Result<Vec<u8>> {
+        if self.format != Format::Odt {
+            bail!("format is not ODT");
+        }
+        self.convert_doc(Format::Odt)?.to_odt()
+    }
+
     /// Convert the content to a markdown document.
     pub fn convert_doc(self, format: Format) -> tinymist_std::Result<MarkdownDocument> {
-        let world = Arc::new(self.feat.prepare_world(&self.world, format)?);
+        let (world, wrapped) = self.feat.wrap_world(&self.world, format)?;
         let feat = self.feat.clone();
-        Self::convert_doc_prepared(feat, format, world)
+        let mut doc = Self::convert_doc_prepared(feat, format, Arc::new(world))?;
+        doc.wrapped = Some(wrapped);
+        Ok(doc)
     }
 
     /// Convert the content to a markdown document.
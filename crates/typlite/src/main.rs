@@ -7,12 +7,13 @@ use std::{
     sync::Arc,
 };
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use tinymist_project::{
     base::print_diagnostics, DiagnosticFormat, LspWorld, SourceWorld, WorldProvider,
 };
 use tinymist_std::{error::prelude::*, Result};
-use typlite::{common::Format, TypliteFeat};
+use typlite::site::{SiteKind, ASSETS_DIR};
+use typlite::{common::Format, MathOutput, TypliteFeat};
 use typlite::{CompileOnceArgs, Typlite};
 use typst::foundations::Bytes;
 
@@ -47,9 +48,111 @@ pub struct CompileArgs {
     /// ```
     #[clap(long = "processor", default_value = None, value_name = "PACKAGE_SPEC")]
     pub processor: Option<String>,
+
+    /// Configures how math equations are written in Markdown and LaTeX. They
+    /// are rendered into SVG images by default, or written in LaTeX with
+    /// `latex`, which can be rendered by KaTeX or MathJax in Markdown. The
+    /// equations are always written in Office Math in DOCX, and embedded as
+    /// pictures in ODT.
+    #[clap(long, value_enum, default_value = "svg", value_name = "OUTPUT")]
+    pub math_output: MathOutput,
+
+    /// Specifies the format of the output. It is inferred from the extension
+    /// of the output file by default, e.g. `.tex` for LaTeX, and falls back
+    /// to Markdown. `pandoc-json` writes the JSON AST of pandoc, which can be
+    /// converted by `pandoc -f json` to the formats supported by pandoc.
+    #[clap(long, value_enum, value_name = "FORMAT")]
+    pub format: Option<OutputFormat>,
+
+    /// Reuses the styles, page setup, headers, footers and numbering of the
+    /// reference document in DOCX, e.g. the Word template of a publisher.
+    #[clap(long, default_value = None, value_name = "REFERENCE_DOCX")]
+    pub reference_doc: Option<PathBuf>,
+
+    /// Wraps the body in a LaTeX template, whose `{{title}}`, `{{authors}}`
+    /// and `{{body}}` placeholders are filled by the document.
+    #[clap(long, default_value = None, value_name = "TEMPLATE_TEX")]
+    pub template: Option<PathBuf>,
+
+    /// Writes a source map next to the output, e.g. `main.md.map`, which maps
+    /// the blocks in the Markdown, LaTeX or text output back to the ranges of
+    /// the typst sources.
+    #[clap(long)]
+    pub source_map: bool,
+
+    /// Splits the document into the Markdown files of a documentation site,
+    /// with a `SUMMARY.md` for mdBook or a `sidebars.json` for Docusaurus.
+    /// The output is a directory, which defaults to the input path without
+    /// the extension.
+    #[clap(long, value_enum, value_name = "SITE")]
+    pub site: Option<SiteKind>,
+
+    /// The deepest level of the headings starting the chapters of the site,
+    /// e.g. `2` splits the document at `=` and `==` headings.
+    #[clap(long, default_value_t = 1, value_name = "LEVEL")]
+    pub split_level: usize,
+}
+
+/// The formats of the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Md,
+    Latex,
+    Text,
+    PandocJson,
+    Docx,
+    Odt,
+}
+
+impl OutputFormat {
+    fn format(self) -> Format {
+        match self {
+            Self::Md => Format::Md,
+            Self::Latex => Format::LaTeX,
+            Self::Text => Format::Text,
+            Self::PandocJson => Format::PandocJson,
+            Self::Docx => Format::Docx,
+            Self::Odt => Format::Odt,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Md => "md",
+            Self::Latex => "tex",
+            Self::Text => "txt",
+            Self::PandocJson => "json",
+            Self::Docx => "docx",
+            Self::Odt => "odt",
+        }
+    }
+}
+
+/// Arguments of `typlite import`, which converts a Markdown document into
+/// typst markup.
+#[derive(Debug, Clone, Parser, Default)]
+#[clap(name = "typlite import")]
+pub struct ImportArgs {
+    /// Path to the Markdown document, or `-` to read from stdin
+    #[clap(value_name = "INPUT")]
+    pub input: String,
+
+    /// Path to output file, or `-` to write to stdout. Defaults to the input
+    /// path with the `.typ` extension
+    #[clap(value_name = "OUTPUT", default_value = None)]
+    pub output: Option<String>,
+
+    /// Renders the math out of the supported subset of LaTeX by the `mitex`
+    /// package, instead of keeping it as raw LaTeX
+    #[clap(long)]
+    pub mitex: bool,
 }
 
 fn main() -> Result<()> {
+    if std::env::args().nth(1).as_deref() == Some("import") {
+        return import(ImportArgs::parse_from(std::env::args().skip(1)));
+    }
+
     // Parse command line arguments
     let args = CompileArgs::parse();
 
@@ -63,20 +166,30 @@ fn run(args: CompileArgs, world: Arc<LspWorld>) -> Result<()> {
     let input = args
         .compile
         .input
+        .clone()
         .context("Missing required argument: INPUT")?;
 
+    if let Some(kind) = args.site {
+        return run_site(&args, kind, &input, world);
+    }
+
     let is_stdout = args.output.as_deref() == Some("-");
-    let output_path = args
-        .output
-        .map(PathBuf::from)
-        .unwrap_or_else(|| Path::new(&input).with_extension("md"));
-
-    let output_format = match output_path.extension().and_then(std::ffi::OsStr::to_str) {
-        Some("tex") => Format::LaTeX,
-        Some("txt") => Format::Text,
-        #[cfg(feature = "docx")]
-        Some("docx") => Format::Docx,
-        _ => Format::Md,
+    let output_path = args.output.map(PathBuf::from).unwrap_or_else(|| {
+        let ext = args.format.map_or("md", OutputFormat::extension);
+        Path::new(&input).with_extension(ext)
+    });
+
+    let output_format = match args.format {
+        Some(format) => format.format(),
+        None => match output_path.extension().and_then(std::ffi::OsStr::to_str) {
+            Some("tex") => Format::LaTeX,
+            Some("txt") => Format::Text,
+            #[cfg(feature = "docx")]
+            Some("docx") => Format::Docx,
+            #[cfg(feature = "odt")]
+            Some("odt") => Format::Odt,
+            _ => Format::Md,
+        },
     };
 
     if let Some(assets_path) = args.assets_path.as_ref() {
@@ -89,16 +202,31 @@ fn run(args: CompileArgs, world: Arc<LspWorld>) -> Result<()> {
         .with_feature(TypliteFeat {
             assets_path: args.assets_path,
             processor: args.processor,
+            math_output: args.math_output,
+            reference_doc: args.reference_doc,
+            tex_template: args.template,
             ..Default::default()
         })
         .convert_doc(output_format)?;
 
+    let mut source_map = None;
     let result = match output_format {
+        _ if args.source_map => {
+            if is_stdout {
+                bail!("source maps cannot be written with the output to stdout");
+            }
+            let (output, map) = doc.to_string_with_source_map(output_format)?;
+            source_map = Some(map);
+            Bytes::from_string(output)
+        }
         Format::Md => Bytes::from_string(doc.to_md_string()?),
         Format::LaTeX => Bytes::from_string(doc.to_tex_string()?),
         Format::Text => Bytes::from_string(doc.to_text_string()?),
+        Format::PandocJson => Bytes::from_string(doc.to_pandoc_json_string()?),
         #[cfg(feature = "docx")]
         Format::Docx => Bytes::new(doc.to_docx()?),
+        #[cfg(feature = "odt")]
+        Format::Odt => Bytes::new(doc.to_odt()?),
     };
 
     if is_stdout {
@@ -109,6 +237,97 @@ fn run(args: CompileArgs, world: Arc<LspWorld>) -> Result<()> {
         bail!("failed to write file {output_path:?}: {err}");
     }
 
+    if let Some(map) = source_map {
+        let mut map_path = output_path.clone().into_os_string();
+        map_path.push(".map");
+        if let Err(err) = std::fs::write(&map_path, map.to_json()) {
+            bail!("failed to write file {map_path:?}: {err}");
+        }
+    }
+
+    // The citations in LaTeX refer to the copies of the BibLaTeX sources.
+    if output_format == Format::LaTeX && !is_stdout {
+        let dir = output_path.parent().unwrap_or(Path::new("."));
+        for (name, content) in doc.bib_files() {
+            let bib_path = dir.join(name.as_str());
+            if let Err(err) = std::fs::write(&bib_path, content.as_slice()) {
+                bail!("failed to write file {bib_path:?}: {err}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_site(args: &CompileArgs, kind: SiteKind, input: &str, world: Arc<LspWorld>) -> Result<()> {
+    let dir = match &args.output {
+        Some(output) => PathBuf::from(output),
+        None => Path::new(input).with_extension(""),
+    };
+    let assets_path = dir.join(ASSETS_DIR);
+    std::fs::create_dir_all(&assets_path).context("failed to create assets directory")?;
+
+    // The frames are written into the assets of the site as well.
+    let site = Typlite::new(world)
+        .with_feature(TypliteFeat {
+            assets_path: Some(assets_path.clone()),
+            processor: args.processor.clone(),
+            math_output: args.math_output,
+            ..Default::default()
+        })
+        .convert_doc(Format::Md)?
+        .to_site(kind, args.split_level)?;
+
+    let (toc_path, toc) = &site.toc;
+    let files = site
+        .chapters
+        .iter()
+        .map(|chapter| (dir.join(chapter.path.as_str()), chapter.content.as_bytes()))
+        .chain([(dir.join(toc_path.as_str()), toc.as_bytes())])
+        .chain(
+            site.assets
+                .iter()
+                .map(|(name, content)| (assets_path.join(name.as_str()), content.as_slice())),
+        );
+    for (path, content) in files {
+        if let Err(err) = std::fs::write(&path, content) {
+            bail!("failed to write file {path:?}: {err}");
+        }
+    }
+
+    Ok(())
+}
+
+fn import(args: ImportArgs) -> Result<()> {
+    let markdown = if args.input == "-" {
+        std::io::read_to_string(std::io::stdin()).context("failed to read from stdin")?
+    } else {
+        match std::fs::read_to_string(&args.input) {
+            Ok(markdown) => markdown,
+            Err(err) => bail!("failed to read file {:?}: {err}", args.input),
+        }
+    };
+
+    let options = typlite::import::ImportOptions {
+        mitex_fallback: args.mitex,
+    };
+    let result = typlite::import::markdown_to_typst(&markdown, &options);
+
+    let output = args.output.unwrap_or_else(|| match args.input.as_str() {
+        "-" => "-".to_owned(),
+        input => Path::new(input)
+            .with_extension("typ")
+            .to_string_lossy()
+            .into_owned(),
+    });
+    if output == "-" {
+        std::io::stdout()
+            .write_all(result.as_bytes())
+            .context("failed to write to stdout")?;
+    } else if let Err(err) = std::fs::write(&output, result.as_bytes()) {
+        bail!("failed to write file {output:?}: {err}");
+    }
+
     Ok(())
 }
 
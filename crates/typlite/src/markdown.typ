@@ -47,10 +47,11 @@
   attrs: (dest: dest),
   body,
 )
-#let md-ref(body) = html.elem(
+#let md-ref(target: none, body) = html.elem(
   "span",
   html.elem(
     "m1ref",
+    attrs: (target: target),
     body,
   ),
 )
@@ -122,6 +123,41 @@
   body,
 )
 
+#let md-footnote(body) = html.elem("span", html.elem("m1footnote", body))
+#let md-cite(key: "", body) = html.elem(
+  "span",
+  html.elem(
+    "m1cite",
+    attrs: (key: key),
+    body,
+  ),
+)
+// The references are rendered by typlite, in the order of citations.
+#let md-bibliography = html.elem("m1bib", "")
+// The settings of `text` and `par` differing from the defaults, which are
+// converted to paragraph styles in DOCX.
+#let md-par(it) = context {
+  let attrs = (:)
+  let font = text.font
+  if type(font) == array { font = font.first() }
+  if type(font) == dictionary { font = font.name }
+  if lower(font) != "libertinus serif" { attrs.insert("font", font) }
+  if text.size != 11pt { attrs.insert("size", str(text.size.pt())) }
+  if par.justify { attrs.insert("justify", "true") }
+  let leading = par.leading.to-absolute()
+  if leading != 0.65em.to-absolute() { attrs.insert("leading", str(leading.pt())) }
+  let indent = par.first-line-indent
+  if type(indent) == dictionary { indent = indent.amount }
+  indent = indent.to-absolute()
+  if indent != 0pt { attrs.insert("indent", str(indent.pt())) }
+
+  if attrs.len() == 0 {
+    it
+  } else {
+    html.elem("m1par", attrs: attrs, it)
+  }
+}
+
 #let if-not-paged(it, act) = {
   if target() == "html" {
     act
@@ -191,23 +227,47 @@
   show image: it => if-not-paged(it, md-image(src: it.source, alt: it.alt))
 
   show raw: it => if-not-paged(it, md-raw(lang: it.lang, block: it.block, it.text))
-  show link: it => if-not-paged(it, md-link(dest: it.dest, it.body))
-  show ref: it => if-not-paged(it, md-ref(it))
+  // Links to locations, e.g. from citations to the references, are not kept.
+  show link: it => if-not-paged(
+    it,
+    if type(it.dest) == str {
+      md-link(dest: it.dest, it.body)
+    } else if type(it.dest) == label {
+      md-ref(target: str(it.dest), it.body)
+    } else {
+      it.body
+    },
+  )
+  show ref: it => if-not-paged(it, md-ref(target: str(it.target), it))
+  // A footnote referring to another footnote by label is kept as is.
+  show footnote: it => if-not-paged(it, if type(it.body) == content { md-footnote(it.body) } else { it })
+  show cite: it => if-not-paged(it, md-cite(key: str(it.key), it))
+  show bibliography: it => if-not-paged(it, md-bibliography)
 
   show heading: it => if-not-paged(it, md-heading(level: it.level, it.body))
+  let docx-styles = sys.inputs.at("x-docx-styles", default: none) == "true"
+  show par: it => if-not-paged(it, if docx-styles { md-par(it) } else { it })
   show outline: it => if-not-paged(it, md-outline(it))
   show outline.entry: it => if-not-paged(it, md-outline-entry(level: it.level, it.element))
   show quote: it => if-not-paged(it, md-quote(it.body))
   show table: it => if-not-paged(it, md-table(it))
   show grid: it => if-not-paged(it, md-grid(columns: it.columns, ..it.children))
 
+  // The native equations are converted from the equation elements by typlite.
+  let native-math = sys.inputs.at("x-math-output", default: none) == "native"
+  // The inline equations are wrapped in spans to stay in their paragraphs.
   show math.equation.where(block: false): it => if-not-paged(
     it,
     html.elem(
-      "m1eqinline",
-      if sys.inputs.at("x-remove-html", default: none) != "true" { html.frame(box(inset: 0.5em, it)) } else {
-        process-math-eq(it.body).flatten().join()
-      },
+      "span",
+      html.elem(
+        "m1eqinline",
+        if sys.inputs.at("x-remove-html", default: none) != "true" {
+          if not native-math { html.frame(box(inset: 0.5em, it)) }
+        } else {
+          process-math-eq(it.body).flatten().join()
+        },
+      ),
     ),
   )
   show math.equation.where(block: true): it => if-not-paged(
@@ -215,7 +275,7 @@
     if sys.inputs.at("x-remove-html", default: none) != "true" {
       html.elem(
         "m1eqblock",
-        html.frame(block(inset: 0.5em, it)),
+        if not native-math { html.frame(block(inset: 0.5em, it)) },
       )
     } else {
       html.elem(
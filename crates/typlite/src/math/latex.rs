@@ -0,0 +1,573 @@
+//! Writes math expressions in LaTeX, which is also accepted by KaTeX and
+//! MathJax.
+
+use ecow::EcoString;
+
+use super::{BraceKind, MathExpr, MathSpace};
+
+/// Writes the math expression in LaTeX. The lines and alignment points of a
+/// block equation are wrapped in an `aligned` environment.
+pub fn to_latex(expr: &MathExpr, block: bool) -> EcoString {
+    let mut writer = LatexWriter::default();
+    if block && expr.is_multiline() {
+        writer.push("\\begin{aligned}");
+        writer.expr(expr);
+        writer.push("\\end{aligned}");
+    } else {
+        writer.expr(expr);
+    }
+    writer.out.trim().into()
+}
+
+#[derive(Default)]
+struct LatexWriter {
+    out: EcoString,
+}
+
+impl LatexWriter {
+    /// Pushes a piece, separating it from a preceding control word if needed.
+    fn push(&mut self, piece: &str) {
+        let starts_with_letter = piece.starts_with(|ch: char| ch.is_ascii_alphabetic());
+        if starts_with_letter && ends_with_control_word(&self.out) {
+            self.out.push(' ');
+        }
+        self.out.push_str(piece);
+    }
+
+    /// Pushes a separator of the rows or the cells, which absorbs the spaces
+    /// around it.
+    fn separator(&mut self, sep: &str) {
+        let len = self.out.trim_end_matches(' ').len();
+        self.out.truncate(len);
+        self.out.push_str(sep);
+    }
+
+    /// Pushes an expression as a group, i.e. `{...}`.
+    fn group(&mut self, expr: &MathExpr) {
+        self.out.push('{');
+        self.expr(expr);
+        self.out.push('}');
+    }
+
+    /// Pushes an expression as the base of an attachment, which is grouped if
+    /// it has more than one item.
+    fn base(&mut self, expr: &MathExpr) {
+        match expr {
+            MathExpr::Char(..) | MathExpr::Number(..) | MathExpr::Op { .. } => self.expr(expr),
+            MathExpr::Row(items) if items.len() == 1 => self.base(&items[0]),
+            _ => self.group(expr),
+        }
+    }
+
+    fn expr(&mut self, expr: &MathExpr) {
+        match expr {
+            MathExpr::Row(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            MathExpr::Char(ch) => match symbol(*ch) {
+                Some(cmd) => self.push(cmd),
+                None => self.push(ch.encode_utf8(&mut [0; 4])),
+            },
+            MathExpr::Number(num) => self.push(num),
+            MathExpr::Text(text) => {
+                self.push("\\text{");
+                self.out.push_str(&escape_text(text));
+                self.out.push('}');
+            }
+            MathExpr::Op { name, limits } => {
+                let name: String = name.chars().filter(|ch| !ch.is_whitespace()).collect();
+                if OPERATORS.contains(&name.as_str()) {
+                    self.push(&format!("\\{name}"));
+                } else {
+                    let star = if *limits { "*" } else { "" };
+                    self.push(&format!("\\operatorname{star}{{{}}}", escape_text(&name)));
+                }
+            }
+            MathExpr::Space(MathSpace::Normal) if self.out.ends_with(' ') => {}
+            MathExpr::Space(space) => self.push(match space {
+                MathSpace::Normal => " ",
+                MathSpace::Thin => "\\,",
+                MathSpace::Medium => "\\:",
+                MathSpace::Thick => "\\;",
+                MathSpace::Quad => "\\quad",
+                MathSpace::Wide => "\\qquad",
+            }),
+            MathExpr::Linebreak => self.separator(" \\\\ "),
+            MathExpr::AlignPoint => self.separator(" & "),
+            MathExpr::Frac(num, denom) => {
+                self.push("\\frac");
+                self.group(num);
+                self.group(denom);
+            }
+            MathExpr::Binom(upper, lower) => {
+                self.push("\\binom");
+                self.group(upper);
+                self.group(lower);
+            }
+            MathExpr::Attach {
+                base,
+                top,
+                bottom,
+                top_left,
+                bottom_left,
+            } => {
+                if top_left.is_some() || bottom_left.is_some() {
+                    self.push("{}");
+                    self.scripts(top_left.as_deref(), bottom_left.as_deref());
+                }
+                self.base(base);
+                self.scripts(top.as_deref(), bottom.as_deref());
+            }
+            MathExpr::Primes(count) => {
+                for _ in 0..*count {
+                    self.push("'");
+                }
+            }
+            MathExpr::Root { index, radicand } => {
+                self.push("\\sqrt");
+                if let Some(index) = index {
+                    self.out.push('[');
+                    self.expr(index);
+                    self.out.push(']');
+                }
+                self.group(radicand);
+            }
+            MathExpr::Matrix { delim, rows } => {
+                let env = match delim {
+                    None => "matrix",
+                    Some(('(', _)) => "pmatrix",
+                    Some(('[', _)) => "bmatrix",
+                    Some(('{', _)) => "Bmatrix",
+                    Some(('|', _)) => "vmatrix",
+                    Some(('‖', _)) => "Vmatrix",
+                    Some(..) => "matrix",
+                };
+                self.push(&format!("\\begin{{{env}}}"));
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 {
+                        self.separator(" \\\\ ");
+                    }
+                    for (j, cell) in row.iter().enumerate() {
+                        if j > 0 {
+                            self.separator(" & ");
+                        }
+                        self.expr(cell);
+                    }
+                }
+                self.push(&format!("\\end{{{env}}}"));
+            }
+            MathExpr::Cases { reverse, rows } => {
+                let env = if *reverse { "rcases" } else { "cases" };
+                self.push(&format!("\\begin{{{env}}}"));
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 {
+                        self.separator(" \\\\ ");
+                    }
+                    self.expr(row);
+                }
+                self.push(&format!("\\end{{{env}}}"));
+            }
+            MathExpr::Accent { base, accent } => {
+                match accent_command(*accent) {
+                    Some(cmd) => self.push(cmd),
+                    None => self.push("\\hat"),
+                }
+                self.group(base);
+            }
+            MathExpr::Fenced { open, close, body } => {
+                self.push("\\left");
+                self.delim(*open);
+                self.expr(body);
+                self.push("\\right");
+                self.delim(*close);
+            }
+            MathExpr::Brace {
+                kind,
+                body,
+                annotation,
+            } => {
+                self.push(match kind {
+                    BraceKind::Overline => "\\overline",
+                    BraceKind::Underline => "\\underline",
+                    BraceKind::Overbrace => "\\overbrace",
+                    BraceKind::Underbrace => "\\underbrace",
+                    BraceKind::Overbracket => "\\overbracket",
+                    BraceKind::Underbracket => "\\underbracket",
+                    BraceKind::Overparen => "\\overparen",
+                    BraceKind::Underparen => "\\underparen",
+                });
+                self.group(body);
+                if let Some(annotation) = annotation {
+                    self.out.push(if kind.is_over() { '^' } else { '_' });
+                    self.group(annotation);
+                }
+            }
+            MathExpr::Cancel(body) => {
+                self.push("\\cancel");
+                self.group(body);
+            }
+        }
+    }
+
+    /// Pushes the superscript and the subscript.
+    fn scripts(&mut self, top: Option<&MathExpr>, bottom: Option<&MathExpr>) {
+        if let Some(bottom) = bottom {
+            self.out.push('_');
+            self.group(bottom);
+        }
+        if let Some(top) = top {
+            // Primes are written without a superscript, e.g. `f'`.
+            if let MathExpr::Primes(..) = top {
+                self.expr(top);
+            } else {
+                self.out.push('^');
+                self.group(top);
+            }
+        }
+    }
+
+    /// Pushes a delimiter after `\left` or `\right`.
+    fn delim(&mut self, delim: Option<char>) {
+        match delim {
+            None => self.out.push('.'),
+            Some('{') => self.out.push_str("\\{"),
+            Some('}') => self.out.push_str("\\}"),
+            Some(ch) => match symbol(ch) {
+                Some(cmd) => self.push(cmd),
+                None => self.out.push(ch),
+            },
+        }
+    }
+}
+
+/// Whether the output ends with a control word, e.g. `\alpha`, which must be
+/// separated from a following letter.
+fn ends_with_control_word(out: &str) -> bool {
+    let word = out.trim_end_matches(|ch: char| ch.is_ascii_alphabetic());
+    word.len() < out.len() && word.ends_with('\\') && !word.ends_with("\\\\")
+}
+
+/// Escapes the text in `\text{...}`.
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '{' | '}' | '$' | '&' | '#' | '_' | '%' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            '^' => out.push_str("\\^{}"),
+            '~' => out.push_str("\\~{}"),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+/// The operators that have a LaTeX command.
+const OPERATORS: &[&str] = &[
+    "arccos", "arcsin", "arctan", "arg", "cos", "cosh", "cot", "coth", "csc", "deg", "det", "dim",
+    "exp", "gcd", "hom", "inf", "ker", "lg", "lim", "liminf", "limsup", "ln", "log", "max", "min",
+    "Pr", "sec", "sin", "sinh", "sup", "tan", "tanh",
+];
+
+/// Gets the LaTeX command of an accent, which is a combining character.
+fn accent_command(accent: char) -> Option<&'static str> {
+    Some(match accent {
+        '\u{0300}' | '`' => "\\grave",
+        '\u{0301}' | '´' => "\\acute",
+        '\u{0302}' | '^' => "\\hat",
+        '\u{0303}' | '~' => "\\tilde",
+        '\u{0304}' | '\u{0305}' | '¯' => "\\bar",
+        '\u{0306}' | '˘' => "\\breve",
+        '\u{0307}' | '.' => "\\dot",
+        '\u{0308}' | '¨' => "\\ddot",
+        '\u{030A}' | '∘' => "\\mathring",
+        '\u{030C}' | 'ˇ' => "\\check",
+        '\u{20D7}' | '→' => "\\vec",
+        '\u{20D6}' | '←' => "\\overleftarrow",
+        '\u{20E1}' | '↔' => "\\overleftrightarrow",
+        _ => return None,
+    })
+}
+
+/// Gets the LaTeX command of a symbol, or `None` if the symbol is written as
+/// is.
+fn symbol(ch: char) -> Option<&'static str> {
+    Some(match ch {
+        // Greek letters.
+        'α' => "\\alpha",
+        'β' => "\\beta",
+        'γ' => "\\gamma",
+        'δ' => "\\delta",
+        'ε' => "\\varepsilon",
+        'ϵ' => "\\epsilon",
+        'ζ' => "\\zeta",
+        'η' => "\\eta",
+        'θ' => "\\theta",
+        'ϑ' => "\\vartheta",
+        'ι' => "\\iota",
+        'κ' => "\\kappa",
+        'λ' => "\\lambda",
+        'μ' => "\\mu",
+        'ν' => "\\nu",
+        'ξ' => "\\xi",
+        'π' => "\\pi",
+        'ϖ' => "\\varpi",
+        'ρ' => "\\rho",
+        'ϱ' => "\\varrho",
+        'σ' => "\\sigma",
+        'ς' => "\\varsigma",
+        'τ' => "\\tau",
+        'υ' => "\\upsilon",
+        'φ' => "\\varphi",
+        'ϕ' => "\\phi",
+        'χ' => "\\chi",
+        'ψ' => "\\psi",
+        'ω' => "\\omega",
+        'Γ' => "\\Gamma",
+        'Δ' => "\\Delta",
+        'Θ' => "\\Theta",
+        'Λ' => "\\Lambda",
+        'Ξ' => "\\Xi",
+        'Π' => "\\Pi",
+        'Σ' => "\\Sigma",
+        'Υ' => "\\Upsilon",
+        'Φ' => "\\Phi",
+        'Ψ' => "\\Psi",
+        'Ω' => "\\Omega",
+        // Letter-like symbols.
+        'ℕ' => "\\mathbb{N}",
+        'ℤ' => "\\mathbb{Z}",
+        'ℚ' => "\\mathbb{Q}",
+        'ℝ' => "\\mathbb{R}",
+        'ℂ' => "\\mathbb{C}",
+        'ℓ' => "\\ell",
+        'ℏ' => "\\hbar",
+        'ℵ' => "\\aleph",
+        '∂' => "\\partial",
+        '∇' => "\\nabla",
+        '∞' => "\\infty",
+        '∅' => "\\emptyset",
+        '°' => "^{\\circ}",
+        // Big operators.
+        '∑' => "\\sum",
+        '∏' => "\\prod",
+        '∐' => "\\coprod",
+        '∫' => "\\int",
+        '∬' => "\\iint",
+        '∭' => "\\iiint",
+        '∮' => "\\oint",
+        '⋃' => "\\bigcup",
+        '⋂' => "\\bigcap",
+        '⨁' => "\\bigoplus",
+        '⨂' => "\\bigotimes",
+        // Binary operators.
+        '−' => "-",
+        '±' => "\\pm",
+        '∓' => "\\mp",
+        '×' => "\\times",
+        '÷' => "\\div",
+        '⋅' | '·' => "\\cdot",
+        '∘' => "\\circ",
+        '∗' => "\\ast",
+        '⊕' => "\\oplus",
+        '⊖' => "\\ominus",
+        '⊗' => "\\otimes",
+        '⊙' => "\\odot",
+        '∪' => "\\cup",
+        '∩' => "\\cap",
+        '∖' => "\\setminus",
+        '∧' => "\\wedge",
+        '∨' => "\\vee",
+        // Relations.
+        '≤' => "\\leq",
+        '≥' => "\\geq",
+        '≠' => "\\neq",
+        '≈' => "\\approx",
+        '≡' => "\\equiv",
+        '∼' => "\\sim",
+        '≃' => "\\simeq",
+        '≅' => "\\cong",
+        '∝' => "\\propto",
+        '≪' => "\\ll",
+        '≫' => "\\gg",
+        '≺' => "\\prec",
+        '≻' => "\\succ",
+        '≔' => "\\coloneqq",
+        '∈' => "\\in",
+        '∉' => "\\notin",
+        '∋' => "\\ni",
+        '⊂' => "\\subset",
+        '⊃' => "\\supset",
+        '⊆' => "\\subseteq",
+        '⊇' => "\\supseteq",
+        '⊊' => "\\subsetneq",
+        '⊥' => "\\perp",
+        '∥' => "\\parallel",
+        '∣' => "\\mid",
+        '⊢' => "\\vdash",
+        '⊨' => "\\models",
+        // Logic.
+        '∀' => "\\forall",
+        '∃' => "\\exists",
+        '∄' => "\\nexists",
+        '¬' => "\\neg",
+        // Arrows.
+        '→' => "\\to",
+        '←' => "\\leftarrow",
+        '↔' => "\\leftrightarrow",
+        '⇒' => "\\Rightarrow",
+        '⇐' => "\\Leftarrow",
+        '⇔' => "\\Leftrightarrow",
+        '⟶' => "\\longrightarrow",
+        '⟹' => "\\Longrightarrow",
+        '⟺' => "\\Longleftrightarrow",
+        '↦' => "\\mapsto",
+        '↑' => "\\uparrow",
+        '↓' => "\\downarrow",
+        '↪' => "\\hookrightarrow",
+        // Dots.
+        '…' => "\\ldots",
+        '⋯' => "\\cdots",
+        '⋮' => "\\vdots",
+        '⋱' => "\\ddots",
+        // Delimiters.
+        '⟨' => "\\langle",
+        '⟩' => "\\rangle",
+        '⌊' => "\\lfloor",
+        '⌋' => "\\rfloor",
+        '⌈' => "\\lceil",
+        '⌉' => "\\rceil",
+        '‖' => "\\|",
+        '{' => "\\{",
+        '}' => "\\}",
+        // Miscellaneous.
+        '′' => "'",
+        '″' => "''",
+        '∠' => "\\angle",
+        '△' => "\\triangle",
+        '□' => "\\square",
+        '◇' => "\\diamond",
+        '★' => "\\star",
+        '†' => "\\dagger",
+        '#' => "\\#",
+        '%' => "\\%",
+        '&' => "\\&",
+        '$' => "\\$",
+        '_' => "\\_",
+        '\\' => "\\backslash",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ch(ch: char) -> MathExpr {
+        MathExpr::Char(ch)
+    }
+
+    fn boxed(expr: MathExpr) -> Box<MathExpr> {
+        Box::new(expr)
+    }
+
+    #[test]
+    fn test_frac_and_attach() {
+        // sum_(i=1)^n 1/i
+        let expr = MathExpr::Row(vec![
+            MathExpr::Attach {
+                base: boxed(ch('∑')),
+                top: Some(boxed(ch('n'))),
+                bottom: Some(boxed(MathExpr::Row(vec![
+                    ch('i'),
+                    ch('='),
+                    MathExpr::Number("1".into()),
+                ]))),
+                top_left: None,
+                bottom_left: None,
+            },
+            MathExpr::Frac(boxed(MathExpr::Number("1".into())), boxed(ch('i'))),
+        ]);
+        assert_eq!(to_latex(&expr, false), "\\sum_{i=1}^{n}\\frac{1}{i}");
+    }
+
+    #[test]
+    fn test_control_word_spacing() {
+        let expr = MathExpr::Row(vec![ch('α'), ch('x'), ch('∈'), ch('ℝ')]);
+        assert_eq!(to_latex(&expr, false), "\\alpha x\\in\\mathbb{R}");
+    }
+
+    #[test]
+    fn test_matrix_and_cases() {
+        let mat = MathExpr::Matrix {
+            delim: Some(('[', ']')),
+            rows: vec![vec![ch('a'), ch('b')], vec![ch('c'), ch('d')]],
+        };
+        assert_eq!(
+            to_latex(&mat, true),
+            "\\begin{bmatrix}a & b \\\\ c & d\\end{bmatrix}"
+        );
+
+        let cases = MathExpr::Cases {
+            reverse: false,
+            rows: vec![
+                MathExpr::Row(vec![
+                    ch('x'),
+                    MathExpr::AlignPoint,
+                    MathExpr::Text("if".into()),
+                ]),
+                MathExpr::Row(vec![ch('y')]),
+            ],
+        };
+        assert_eq!(
+            to_latex(&cases, false),
+            "\\begin{cases}x & \\text{if} \\\\ y\\end{cases}"
+        );
+    }
+
+    #[test]
+    fn test_aligned_block() {
+        let expr = MathExpr::Row(vec![
+            ch('x'),
+            MathExpr::AlignPoint,
+            ch('='),
+            ch('y'),
+            MathExpr::Linebreak,
+            MathExpr::AlignPoint,
+            ch('='),
+            ch('z'),
+        ]);
+        assert_eq!(
+            to_latex(&expr, true),
+            "\\begin{aligned}x & =y \\\\ & =z\\end{aligned}"
+        );
+    }
+
+    #[test]
+    fn test_accent_and_op() {
+        let expr = MathExpr::Row(vec![
+            MathExpr::Accent {
+                base: boxed(ch('x')),
+                accent: '\u{0302}',
+            },
+            MathExpr::Op {
+                name: "sin".into(),
+                limits: false,
+            },
+            ch('x'),
+            MathExpr::Op {
+                name: "argmax".into(),
+                limits: true,
+            },
+        ]);
+        assert_eq!(
+            to_latex(&expr, false),
+            "\\hat{x}\\sin x\\operatorname*{argmax}"
+        );
+    }
+}
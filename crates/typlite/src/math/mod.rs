@@ -0,0 +1,450 @@
+//! Math equations in a format-independent form, which are written as native
+//! math markups instead of being rendered into frames.
+//!
+//! - LaTeX: for the Markdown (KaTeX/MathJax) and LaTeX writers.
+//! - OMML: Office Math Markup Language for the DOCX writer.
+
+mod latex;
+mod omml;
+
+pub use latex::to_latex;
+#[cfg(feature = "odt")]
+pub(crate) use omml::escape_xml;
+pub use omml::to_omml;
+
+use ecow::EcoString;
+use typst::foundations::{Content, Repr, Value};
+
+/// A math expression converted from the content of an equation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MathExpr {
+    /// A sequence of expressions.
+    Row(Vec<MathExpr>),
+    /// A letter or a symbol.
+    Char(char),
+    /// A number, e.g. `42` or `3.14`.
+    Number(EcoString),
+    /// An upright text, e.g. `"if"`.
+    Text(EcoString),
+    /// An operator, e.g. `lim` or `sin`, whose attachments are placed above
+    /// and below it if `limits` is true.
+    Op { name: EcoString, limits: bool },
+    /// A horizontal space.
+    Space(MathSpace),
+    /// A line break.
+    Linebreak,
+    /// An alignment point, i.e. `&`.
+    AlignPoint,
+    /// A fraction.
+    Frac(Box<MathExpr>, Box<MathExpr>),
+    /// A binomial coefficient.
+    Binom(Box<MathExpr>, Box<MathExpr>),
+    /// A base with attachments.
+    Attach {
+        base: Box<MathExpr>,
+        top: Option<Box<MathExpr>>,
+        bottom: Option<Box<MathExpr>>,
+        top_left: Option<Box<MathExpr>>,
+        bottom_left: Option<Box<MathExpr>>,
+    },
+    /// Primes, e.g. `'` or `''`.
+    Primes(usize),
+    /// A square root or a root with an index.
+    Root {
+        index: Option<Box<MathExpr>>,
+        radicand: Box<MathExpr>,
+    },
+    /// A matrix or a column vector, with optional delimiters.
+    Matrix {
+        delim: Option<(char, char)>,
+        rows: Vec<Vec<MathExpr>>,
+    },
+    /// Cases, whose brace is on the right if `reverse` is true.
+    Cases { reverse: bool, rows: Vec<MathExpr> },
+    /// An accent over a base, where the accent is a combining character.
+    Accent { base: Box<MathExpr>, accent: char },
+    /// A body between scaled delimiters.
+    Fenced {
+        open: Option<char>,
+        close: Option<char>,
+        body: Box<MathExpr>,
+    },
+    /// A line, brace, bracket or parenthesis over or under a body, with an
+    /// optional annotation.
+    Brace {
+        kind: BraceKind,
+        body: Box<MathExpr>,
+        annotation: Option<Box<MathExpr>>,
+    },
+    /// A body that is cancelled out.
+    Cancel(Box<MathExpr>),
+}
+
+/// The width of a horizontal space in math.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathSpace {
+    /// A space between words, which is ignored by LaTeX.
+    Normal,
+    /// `thin`, i.e. 1/6 em.
+    Thin,
+    /// `med`, i.e. 2/9 em.
+    Medium,
+    /// `thick`, i.e. 5/18 em.
+    Thick,
+    /// `quad`, i.e. 1 em.
+    Quad,
+    /// `wide`, i.e. 2 em.
+    Wide,
+}
+
+/// The kinds of lines and braces over or under a body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BraceKind {
+    Overline,
+    Underline,
+    Overbrace,
+    Underbrace,
+    Overbracket,
+    Underbracket,
+    Overparen,
+    Underparen,
+}
+
+impl BraceKind {
+    /// Whether the brace is over the body.
+    pub fn is_over(self) -> bool {
+        matches!(
+            self,
+            Self::Overline | Self::Overbrace | Self::Overbracket | Self::Overparen
+        )
+    }
+}
+
+impl MathExpr {
+    /// Converts an equation element.
+    pub fn from_equation(equation: &Content) -> Self {
+        match equation.get_by_name("body") {
+            Ok(body) => Self::from_value(&body),
+            Err(_) => empty(),
+        }
+    }
+
+    /// Converts the body of an equation.
+    pub fn from_content(content: &Content) -> Self {
+        let field = |name: &str| content.get_by_name(name).ok();
+        let child = |name: &str| Box::new(field(name).map_or_else(empty, |v| Self::from_value(&v)));
+        let opt_child = |name: &str| match field(name) {
+            None | Some(Value::None) => None,
+            Some(value) => Some(Box::new(Self::from_value(&value))),
+        };
+
+        match content.elem().name() {
+            "sequence" => match field("children") {
+                Some(Value::Array(children)) => {
+                    Self::Row(children.iter().map(Self::from_value).collect())
+                }
+                _ => empty(),
+            },
+            "text" | "symbol" => match field("text") {
+                Some(Value::Str(text)) => Self::from_text(&text),
+                _ => empty(),
+            },
+            "space" => Self::Space(MathSpace::Normal),
+            "h" => Self::Space(match field("amount") {
+                Some(Value::Length(len)) => MathSpace::from_em(len.em.get()),
+                _ => MathSpace::Thin,
+            }),
+            "linebreak" => Self::Linebreak,
+            "align-point" => Self::AlignPoint,
+            "frac" => Self::Frac(child("num"), child("denom")),
+            "binom" => {
+                let lower = match field("lower") {
+                    Some(Value::Array(lower)) => {
+                        separated(lower.iter().map(Self::from_value), Self::Char(','))
+                    }
+                    _ => empty(),
+                };
+                Self::Binom(child("upper"), Box::new(lower))
+            }
+            "attach" => Self::Attach {
+                base: child("base"),
+                top: opt_child("t").or_else(|| opt_child("tr")),
+                bottom: opt_child("b").or_else(|| opt_child("br")),
+                top_left: opt_child("tl"),
+                bottom_left: opt_child("bl"),
+            },
+            "primes" => match field("count") {
+                Some(Value::Int(count)) => Self::Primes(count.max(1) as usize),
+                _ => Self::Primes(1),
+            },
+            "root" => Self::Root {
+                index: opt_child("index"),
+                radicand: child("radicand"),
+            },
+            "mat" => {
+                let rows = match field("rows") {
+                    Some(Value::Array(rows)) => rows
+                        .iter()
+                        .map(|row| match row {
+                            Value::Array(cells) => cells.iter().map(Self::from_value).collect(),
+                            cell => vec![Self::from_value(cell)],
+                        })
+                        .collect(),
+                    _ => vec![],
+                };
+                Self::Matrix {
+                    delim: delim(content, Some(('(', ')'))),
+                    rows,
+                }
+            }
+            "vec" => {
+                let rows = match field("children") {
+                    Some(Value::Array(children)) => children
+                        .iter()
+                        .map(|child| vec![Self::from_value(child)])
+                        .collect(),
+                    _ => vec![],
+                };
+                Self::Matrix {
+                    delim: delim(content, Some(('(', ')'))),
+                    rows,
+                }
+            }
+            "cases" => Self::Cases {
+                reverse: matches!(field("reverse"), Some(Value::Bool(true))),
+                rows: match field("children") {
+                    Some(Value::Array(children)) => children.iter().map(Self::from_value).collect(),
+                    _ => vec![],
+                },
+            },
+            "accent" => {
+                let accent = match field("accent") {
+                    Some(Value::Str(accent)) => accent.chars().next(),
+                    Some(Value::Symbol(accent)) => Some(accent.get()),
+                    _ => None,
+                };
+                match accent {
+                    Some(accent) => Self::Accent {
+                        base: child("base"),
+                        accent,
+                    },
+                    None => *child("base"),
+                }
+            }
+            "lr" => Self::fenced(*child("body")),
+            "op" => Self::Op {
+                name: field("text")
+                    .map(|text| match text {
+                        Value::Content(text) => text.plain_text(),
+                        Value::Str(text) => text.into(),
+                        _ => EcoString::new(),
+                    })
+                    .unwrap_or_default(),
+                limits: matches!(field("limits"), Some(Value::Bool(true))),
+            },
+            "cancel" => Self::Cancel(child("body")),
+            name => match BraceKind::from_name(name) {
+                Some(kind) => Self::Brace {
+                    kind,
+                    body: child("body"),
+                    annotation: opt_child("annotation"),
+                },
+                // Elements that only change the styles or the layout, e.g.
+                // `styled`, `class`, `limits`, `scripts`, `stretch` and `mid`.
+                None => match field("body").or_else(|| field("child")) {
+                    Some(body) => Self::from_value(&body),
+                    None => Self::from_text(&content.plain_text()),
+                },
+            },
+        }
+    }
+
+    fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Content(content) => Self::from_content(content),
+            Value::Str(text) => Self::from_text(text),
+            Value::Symbol(symbol) => Self::Char(symbol.get()),
+            Value::Int(int) => Self::Number(int.to_string().into()),
+            Value::None => empty(),
+            value => Self::Text(value.repr()),
+        }
+    }
+
+    fn from_text(text: &str) -> Self {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => empty(),
+            (Some(ch), None) if !ch.is_ascii_digit() => Self::Char(ch),
+            _ if text.chars().all(|ch| ch.is_ascii_digit() || ch == '.') => {
+                Self::Number(text.into())
+            }
+            _ => Self::Text(text.into()),
+        }
+    }
+
+    /// Takes the delimiters from the start and the end of the body of `lr`.
+    fn fenced(body: Self) -> Self {
+        let mut items = match body {
+            Self::Row(items) => items,
+            item => vec![item],
+        };
+        let is_delim = |item: Option<&Self>| matches!(item, Some(Self::Char(ch)) if is_delim(*ch));
+
+        let open = is_delim(items.first()).then(|| items.remove(0));
+        let close = is_delim(items.last()).then(|| items.pop()).flatten();
+        let char_of = |item: Option<Self>| match item {
+            Some(Self::Char(ch)) => Some(ch),
+            _ => None,
+        };
+
+        Self::Fenced {
+            open: char_of(open),
+            close: char_of(close),
+            body: Box::new(Self::Row(items)),
+        }
+    }
+
+    /// Whether the expression renders nothing.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Row(items) => items.iter().all(Self::is_empty),
+            Self::Text(text) => text.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Splits the top-level rows of the expression at the line breaks, which
+    /// is a single row if there is no line break.
+    pub fn lines(&self) -> Vec<&[MathExpr]> {
+        match self {
+            Self::Row(items) => items.split(|item| *item == Self::Linebreak).collect(),
+            item => vec![std::slice::from_ref(item)],
+        }
+    }
+
+    /// Whether the expression has multiple lines or alignment points at the
+    /// top level.
+    pub fn is_multiline(&self) -> bool {
+        match self {
+            Self::Row(items) => items
+                .iter()
+                .any(|item| matches!(item, Self::Linebreak | Self::AlignPoint)),
+            _ => false,
+        }
+    }
+}
+
+impl MathSpace {
+    fn from_em(em: f64) -> Self {
+        match em {
+            em if em < 0.2 => Self::Thin,
+            em if em < 0.25 => Self::Medium,
+            em if em < 0.5 => Self::Thick,
+            em if em < 1.5 => Self::Quad,
+            _ => Self::Wide,
+        }
+    }
+}
+
+impl BraceKind {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "overline" => Self::Overline,
+            "underline" => Self::Underline,
+            "overbrace" => Self::Overbrace,
+            "underbrace" => Self::Underbrace,
+            "overbracket" => Self::Overbracket,
+            "underbracket" => Self::Underbracket,
+            "overparen" => Self::Overparen,
+            "underparen" => Self::Underparen,
+            _ => return None,
+        })
+    }
+}
+
+fn empty() -> MathExpr {
+    MathExpr::Row(vec![])
+}
+
+/// Joins the expressions with a separator.
+fn separated(items: impl Iterator<Item = MathExpr>, sep: MathExpr) -> MathExpr {
+    let mut row = vec![];
+    for (i, item) in items.enumerate() {
+        if i > 0 {
+            row.push(sep.clone());
+        }
+        row.push(item);
+    }
+    MathExpr::Row(row)
+}
+
+/// Whether the character is a delimiter scaled by `lr`.
+fn is_delim(ch: char) -> bool {
+    matches!(
+        ch,
+        '(' | ')' | '[' | ']' | '{' | '}' | '|' | '‖' | '⟨' | '⟩' | '⌊' | '⌋' | '⌈' | '⌉'
+    )
+}
+
+/// Gets the delimiters of a matrix or a vector.
+fn delim(content: &Content, default: Option<(char, char)>) -> Option<(char, char)> {
+    let single = |open: &str| {
+        let open = match open {
+            "||" => '‖',
+            open => open.chars().next()?,
+        };
+        let close = match open {
+            '(' => ')',
+            '[' => ']',
+            '{' => '}',
+            '⟨' => '⟩',
+            open => open,
+        };
+        Some((open, close))
+    };
+    let char_of = |value: &Value| match value {
+        Value::Str(s) => s.chars().next(),
+        Value::Symbol(symbol) => Some(symbol.get()),
+        _ => None,
+    };
+
+    match content.get_by_name("delim") {
+        Err(_) => default,
+        Ok(Value::Str(open)) => single(&open),
+        Ok(Value::Symbol(open)) => single(open.get().encode_utf8(&mut [0; 4])),
+        Ok(Value::Array(pair)) => match pair.as_slice() {
+            [open, close] => char_of(open).zip(char_of(close)),
+            _ => default,
+        },
+        Ok(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fenced() {
+        let body = MathExpr::Row(vec![
+            MathExpr::Char('('),
+            MathExpr::Char('x'),
+            MathExpr::Char(']'),
+        ]);
+        assert_eq!(
+            MathExpr::fenced(body),
+            MathExpr::Fenced {
+                open: Some('('),
+                close: Some(']'),
+                body: Box::new(MathExpr::Row(vec![MathExpr::Char('x')])),
+            }
+        );
+    }
+
+    #[test]
+    fn test_from_text() {
+        assert_eq!(MathExpr::from_text("x"), MathExpr::Char('x'));
+        assert_eq!(MathExpr::from_text("3.14"), MathExpr::Number("3.14".into()));
+        assert_eq!(MathExpr::from_text("if"), MathExpr::Text("if".into()));
+    }
+}
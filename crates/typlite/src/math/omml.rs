@@ -0,0 +1,394 @@
+//! Writes math expressions in Office Math Markup Language (OMML), which is the
+//! native equation format of Word.
+
+use super::{BraceKind, MathExpr, MathSpace};
+
+/// Writes the math expression as an `m:oMath` element. The lines of a block
+/// equation are written in an equation array, and a block equation is wrapped
+/// in an `m:oMathPara` element.
+pub fn to_omml(expr: &MathExpr, block: bool) -> String {
+    let mut writer = OmmlWriter::default();
+    writer.out.push_str("<m:oMath>");
+    let lines = expr.lines();
+    if block && lines.len() > 1 {
+        writer.out.push_str("<m:eqArr>");
+        for line in lines {
+            writer.out.push_str("<m:e>");
+            line.iter().for_each(|item| writer.expr(item));
+            writer.out.push_str("</m:e>");
+        }
+        writer.out.push_str("</m:eqArr>");
+    } else {
+        writer.expr(expr);
+    }
+    writer.out.push_str("</m:oMath>");
+
+    if block {
+        format!("<m:oMathPara>{}</m:oMathPara>", writer.out)
+    } else {
+        writer.out
+    }
+}
+
+#[derive(Default)]
+struct OmmlWriter {
+    out: String,
+}
+
+impl OmmlWriter {
+    /// Pushes a run of math text.
+    fn run(&mut self, text: &str, upright: bool) {
+        self.out.push_str("<m:r>");
+        if upright {
+            self.out.push_str("<m:rPr><m:sty m:val=\"p\"/></m:rPr>");
+        }
+        self.out.push_str("<m:t xml:space=\"preserve\">");
+        self.out.push_str(&escape_xml(text));
+        self.out.push_str("</m:t></m:r>");
+    }
+
+    /// Pushes an expression wrapped in an element, e.g. `<m:e>...</m:e>`.
+    fn arg(&mut self, tag: &str, expr: Option<&MathExpr>) {
+        self.out.push_str(&format!("<m:{tag}>"));
+        if let Some(expr) = expr {
+            self.expr(expr);
+        }
+        self.out.push_str(&format!("</m:{tag}>"));
+    }
+
+    /// Pushes a delimiter, i.e. `m:d`, whose content is written by `f`.
+    fn delimited(&mut self, open: Option<char>, close: Option<char>, f: impl FnOnce(&mut Self)) {
+        let chr = |ch: Option<char>| ch.map(String::from).unwrap_or_default();
+        self.out.push_str(&format!(
+            "<m:d><m:dPr><m:begChr m:val=\"{}\"/><m:endChr m:val=\"{}\"/></m:dPr><m:e>",
+            escape_xml(&chr(open)),
+            escape_xml(&chr(close)),
+        ));
+        f(self);
+        self.out.push_str("</m:e></m:d>");
+    }
+
+    fn expr(&mut self, expr: &MathExpr) {
+        match expr {
+            MathExpr::Row(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            MathExpr::Char(ch) => {
+                let ch = if *ch == '-' { '−' } else { *ch };
+                self.run(ch.encode_utf8(&mut [0; 4]), false);
+            }
+            MathExpr::Number(num) => self.run(num, false),
+            MathExpr::Text(text) => self.run(text, true),
+            MathExpr::Op { name, .. } => self.run(name, true),
+            MathExpr::Space(space) => match space {
+                MathSpace::Normal => {}
+                MathSpace::Thin => self.run("\u{2009}", true),
+                MathSpace::Medium => self.run("\u{205F}", true),
+                MathSpace::Thick => self.run("\u{2004}", true),
+                MathSpace::Quad => self.run("\u{2003}", true),
+                MathSpace::Wide => self.run("\u{2003}\u{2003}", true),
+            },
+            // Line breaks are only kept at the top level of block equations.
+            MathExpr::Linebreak => {}
+            MathExpr::AlignPoint => self.run(" ", true),
+            MathExpr::Frac(num, denom) => {
+                self.out.push_str("<m:f>");
+                self.arg("num", Some(num));
+                self.arg("den", Some(denom));
+                self.out.push_str("</m:f>");
+            }
+            MathExpr::Binom(upper, lower) => {
+                self.delimited(Some('('), Some(')'), |this| {
+                    this.out
+                        .push_str("<m:f><m:fPr><m:type m:val=\"noBar\"/></m:fPr>");
+                    this.arg("num", Some(upper));
+                    this.arg("den", Some(lower));
+                    this.out.push_str("</m:f>");
+                });
+            }
+            MathExpr::Attach {
+                base,
+                top,
+                bottom,
+                top_left,
+                bottom_left,
+            } => self.attach(
+                base,
+                top.as_deref(),
+                bottom.as_deref(),
+                top_left,
+                bottom_left,
+            ),
+            MathExpr::Primes(count) => self.run(&"′".repeat(*count), false),
+            MathExpr::Root { index, radicand } => {
+                self.out.push_str("<m:rad>");
+                if index.is_none() {
+                    self.out
+                        .push_str("<m:radPr><m:degHide m:val=\"1\"/></m:radPr>");
+                }
+                self.arg("deg", index.as_deref());
+                self.arg("e", Some(radicand));
+                self.out.push_str("</m:rad>");
+            }
+            MathExpr::Matrix { delim, rows } => {
+                let matrix = |this: &mut Self| {
+                    this.out.push_str("<m:m>");
+                    for row in rows {
+                        this.out.push_str("<m:mr>");
+                        for cell in row {
+                            this.arg("e", Some(cell));
+                        }
+                        this.out.push_str("</m:mr>");
+                    }
+                    this.out.push_str("</m:m>");
+                };
+                match delim {
+                    Some((open, close)) => self.delimited(Some(*open), Some(*close), matrix),
+                    None => matrix(self),
+                }
+            }
+            MathExpr::Cases { reverse, rows } => {
+                let (open, close) = if *reverse {
+                    (None, Some('}'))
+                } else {
+                    (Some('{'), None)
+                };
+                self.delimited(open, close, |this| {
+                    this.out.push_str("<m:eqArr>");
+                    for row in rows {
+                        this.arg("e", Some(row));
+                    }
+                    this.out.push_str("</m:eqArr>");
+                });
+            }
+            MathExpr::Accent { base, accent } => {
+                self.out.push_str(&format!(
+                    "<m:acc><m:accPr><m:chr m:val=\"{}\"/></m:accPr>",
+                    escape_xml(&combining(*accent).to_string())
+                ));
+                self.arg("e", Some(base));
+                self.out.push_str("</m:acc>");
+            }
+            MathExpr::Fenced { open, close, body } => {
+                self.delimited(*open, *close, |this| this.expr(body));
+            }
+            MathExpr::Brace {
+                kind,
+                body,
+                annotation,
+            } => self.brace(*kind, body, annotation.as_deref()),
+            MathExpr::Cancel(body) => {
+                self.out.push_str(
+                    "<m:borderBox><m:borderBoxPr><m:hideTop m:val=\"1\"/><m:hideBot m:val=\"1\"/>\
+                     <m:hideLeft m:val=\"1\"/><m:hideRight m:val=\"1\"/>\
+                     <m:strikeBLTR m:val=\"1\"/></m:borderBoxPr>",
+                );
+                self.arg("e", Some(body));
+                self.out.push_str("</m:borderBox>");
+            }
+        }
+    }
+
+    /// Pushes a base with attachments. The attachments of big operators and
+    /// operators with limits are placed above and below them.
+    fn attach(
+        &mut self,
+        base: &MathExpr,
+        top: Option<&MathExpr>,
+        bottom: Option<&MathExpr>,
+        top_left: &Option<Box<MathExpr>>,
+        bottom_left: &Option<Box<MathExpr>>,
+    ) {
+        if top_left.is_some() || bottom_left.is_some() {
+            self.out.push_str("<m:sPre>");
+            self.arg("sub", bottom_left.as_deref());
+            self.arg("sup", top_left.as_deref());
+            self.out.push_str("<m:e>");
+            self.attach(base, top, bottom, &None, &None);
+            self.out.push_str("</m:e></m:sPre>");
+            return;
+        }
+
+        match base {
+            MathExpr::Char(op) if is_nary(*op) => {
+                self.out.push_str(&format!(
+                    "<m:nary><m:naryPr><m:chr m:val=\"{op}\"/><m:limLoc m:val=\"undOvr\"/>\
+                     <m:subHide m:val=\"{}\"/><m:supHide m:val=\"{}\"/></m:naryPr>",
+                    u8::from(bottom.is_none()),
+                    u8::from(top.is_none()),
+                ));
+                self.arg("sub", bottom);
+                self.arg("sup", top);
+                // The operand follows the operator in the row.
+                self.out.push_str("<m:e/></m:nary>");
+            }
+            MathExpr::Op { limits: true, .. } => {
+                let inner = |this: &mut Self| match bottom {
+                    Some(bottom) => {
+                        this.out.push_str("<m:limLow>");
+                        this.arg("e", Some(base));
+                        this.arg("lim", Some(bottom));
+                        this.out.push_str("</m:limLow>");
+                    }
+                    None => this.expr(base),
+                };
+                match top {
+                    Some(top) => {
+                        self.out.push_str("<m:limUpp><m:e>");
+                        inner(self);
+                        self.out.push_str("</m:e>");
+                        self.arg("lim", Some(top));
+                        self.out.push_str("</m:limUpp>");
+                    }
+                    None => inner(self),
+                }
+            }
+            _ => match (top, bottom) {
+                (None, None) => self.expr(base),
+                (Some(top), None) => {
+                    self.out.push_str("<m:sSup>");
+                    self.arg("e", Some(base));
+                    self.arg("sup", Some(top));
+                    self.out.push_str("</m:sSup>");
+                }
+                (None, Some(bottom)) => {
+                    self.out.push_str("<m:sSub>");
+                    self.arg("e", Some(base));
+                    self.arg("sub", Some(bottom));
+                    self.out.push_str("</m:sSub>");
+                }
+                (Some(top), Some(bottom)) => {
+                    self.out.push_str("<m:sSubSup>");
+                    self.arg("e", Some(base));
+                    self.arg("sub", Some(bottom));
+                    self.arg("sup", Some(top));
+                    self.out.push_str("</m:sSubSup>");
+                }
+            },
+        }
+    }
+
+    /// Pushes a line or a brace over or under a body.
+    fn brace(&mut self, kind: BraceKind, body: &MathExpr, annotation: Option<&MathExpr>) {
+        let pos = if kind.is_over() { "top" } else { "bot" };
+        let chr = match kind {
+            BraceKind::Overline | BraceKind::Underline => {
+                self.out.push_str(&format!(
+                    "<m:bar><m:barPr><m:pos m:val=\"{pos}\"/></m:barPr>"
+                ));
+                self.arg("e", Some(body));
+                self.out.push_str("</m:bar>");
+                return;
+            }
+            BraceKind::Overbrace => '⏞',
+            BraceKind::Underbrace => '⏟',
+            BraceKind::Overbracket => '⎴',
+            BraceKind::Underbracket => '⎵',
+            BraceKind::Overparen => '⏜',
+            BraceKind::Underparen => '⏝',
+        };
+
+        let lim = if kind.is_over() { "limUpp" } else { "limLow" };
+        if annotation.is_some() {
+            self.out.push_str(&format!("<m:{lim}><m:e>"));
+        }
+        self.out.push_str(&format!(
+            "<m:groupChr><m:groupChrPr><m:chr m:val=\"{chr}\"/><m:pos m:val=\"{pos}\"/>\
+             </m:groupChrPr>"
+        ));
+        self.arg("e", Some(body));
+        self.out.push_str("</m:groupChr>");
+        if annotation.is_some() {
+            self.out.push_str("</m:e>");
+            self.arg("lim", annotation);
+            self.out.push_str(&format!("</m:{lim}>"));
+        }
+    }
+}
+
+/// Whether the character is a big operator written as an n-ary in OMML.
+fn is_nary(ch: char) -> bool {
+    matches!(
+        ch,
+        '∑' | '∏' | '∐' | '∫' | '∬' | '∭' | '∮' | '⋃' | '⋂' | '⨁' | '⨂'
+    )
+}
+
+/// Gets the combining character of an accent, which may be given in its
+/// spacing form.
+fn combining(accent: char) -> char {
+    match accent {
+        '`' => '\u{0300}',
+        '´' => '\u{0301}',
+        '^' => '\u{0302}',
+        '~' => '\u{0303}',
+        '¯' => '\u{0304}',
+        '˘' => '\u{0306}',
+        '.' => '\u{0307}',
+        '¨' => '\u{0308}',
+        '∘' => '\u{030A}',
+        'ˇ' => '\u{030C}',
+        '→' => '\u{20D7}',
+        '←' => '\u{20D6}',
+        '↔' => '\u{20E1}',
+        accent => accent,
+    }
+}
+
+/// Escapes the special characters of XML.
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frac_and_scripts() {
+        let expr = MathExpr::Row(vec![
+            MathExpr::Attach {
+                base: Box::new(MathExpr::Char('x')),
+                top: Some(Box::new(MathExpr::Number("2".into()))),
+                bottom: None,
+                top_left: None,
+                bottom_left: None,
+            },
+            MathExpr::Char('<'),
+            MathExpr::Frac(Box::new(MathExpr::Char('a')), Box::new(MathExpr::Char('b'))),
+        ]);
+        assert_eq!(
+            to_omml(&expr, false),
+            "<m:oMath><m:sSup>\
+             <m:e><m:r><m:t xml:space=\"preserve\">x</m:t></m:r></m:e>\
+             <m:sup><m:r><m:t xml:space=\"preserve\">2</m:t></m:r></m:sup></m:sSup>\
+             <m:r><m:t xml:space=\"preserve\">&lt;</m:t></m:r>\
+             <m:f><m:num><m:r><m:t xml:space=\"preserve\">a</m:t></m:r></m:num>\
+             <m:den><m:r><m:t xml:space=\"preserve\">b</m:t></m:r></m:den></m:f></m:oMath>"
+        );
+    }
+
+    #[test]
+    fn test_block_lines() {
+        let expr = MathExpr::Row(vec![
+            MathExpr::Char('a'),
+            MathExpr::Linebreak,
+            MathExpr::Char('b'),
+        ]);
+        let omml = to_omml(&expr, true);
+        assert!(omml.starts_with("<m:oMathPara><m:oMath><m:eqArr><m:e>"));
+        assert_eq!(omml.matches("<m:e>").count(), 2);
+    }
+}
@@ -6,10 +6,24 @@ use cmark_writer::ast::{CustomNode, HtmlAttribute, HtmlElement as CmarkHtmlEleme
 use cmark_writer::{CommonMarkWriter, WriteResult};
 use ecow::EcoString;
 use tinymist_project::LspWorld;
+use typst::foundations::{Content, Packed, StyleChain};
 use typst::html::{tag, HtmlElement, HtmlNode};
-
-use crate::attributes::{md_attr, AlertsAttr, HeadingAttr, RawAttr, TypliteAttrsParser};
-use crate::common::{AlertNode, CenterNode, VerbatimNode};
+use typst::introspection::{Location, Tag};
+use typst::math::EquationElem;
+use typst::model::{BibliographyElem, FigureElem, HeadingElem};
+use typst::syntax::Span;
+
+use crate::attributes::{
+    md_attr, AlertsAttr, CiteAttr, HeadingAttr, ParAttr, RawAttr, RefAttr, TypliteAttrsParser,
+};
+use crate::bib::{bib_title, BibSources};
+#[cfg(feature = "docx")]
+use crate::common::OutlineNode;
+use crate::common::{
+    AlertNode, AnchorNode, BibliographyNode, CenterNode, CitationNode, FootnoteDefinitionNode,
+    FootnoteNode, Format, LabelNode, MathNode, ParStyleNode, RefNode, VerbatimNode,
+};
+use crate::math::MathExpr;
 use crate::tags::md_tag;
 use crate::Result;
 use crate::TypliteFeat;
@@ -24,6 +38,26 @@ pub struct HtmlToAstParser {
     pub list_level: usize,
     pub blocks: Vec<Node>,
     pub inline_buffer: Vec<Node>,
+    /// The last started equation, which is converted to native math.
+    pub equation: Option<Content>,
+    /// The label of the last started heading or figure, which is kept as a
+    /// `\label` in LaTeX.
+    pub label: Option<EcoString>,
+    /// The location of the last started heading, figure or equation, which is
+    /// anchored when the references are resolved.
+    pub location: Option<Location>,
+    /// The contents of the footnotes, in the order of their numbers.
+    pub footnotes: Vec<Vec<Node>>,
+    /// The keys of the citations, in the order of their appearance.
+    pub citations: Vec<EcoString>,
+    /// The last started bibliography, whose references are rendered after
+    /// all citations are collected.
+    pub bibliography: Option<Packed<BibliographyElem>>,
+    /// The source spans of the converted top-level blocks, which are
+    /// collected only for source maps.
+    pub block_spans: Option<Vec<Vec<Span>>>,
+    /// The source spans of the contents in the inline buffer.
+    pub spans: Vec<Span>,
 }
 
 impl HtmlToAstParser {
@@ -35,6 +69,14 @@ impl HtmlToAstParser {
             list_level: 0,
             blocks: Vec::new(),
             inline_buffer: Vec::new(),
+            equation: None,
+            label: None,
+            location: None,
+            footnotes: Vec::new(),
+            citations: Vec::new(),
+            bibliography: None,
+            block_spans: None,
+            spans: Vec::new(),
         }
     }
 
@@ -42,6 +84,8 @@ impl HtmlToAstParser {
         match element.tag {
             tag::head => Ok(()),
 
+            md_tag::doc if self.block_spans.is_some() => self.convert_document(element),
+
             tag::html | tag::body | md_tag::doc => {
                 self.convert_children(element)?;
                 Ok(())
@@ -85,7 +129,15 @@ impl HtmlToAstParser {
             md_tag::heading => {
                 self.flush_inline_buffer();
                 let attrs = HeadingAttr::parse(&element.attrs)?;
+                let label = self.label.take();
+                if let Some(anchor) = self.take_anchor() {
+                    self.inline_buffer.push(anchor);
+                }
                 self.convert_children(element)?;
+                if let Some(name) = label.filter(|_| self.feat.target == Format::LaTeX) {
+                    self.inline_buffer
+                        .push(Node::Custom(Box::new(LabelNode { name })));
+                }
                 self.flush_inline_buffer_as_block(|content| {
                     Node::heading(attrs.level as u8 + 1, content)
                 });
@@ -149,8 +201,28 @@ impl HtmlToAstParser {
             }
 
             md_tag::math_equation_inline | md_tag::math_equation_block => {
-                if element.tag == md_tag::math_equation_block {
+                let block = element.tag == md_tag::math_equation_block;
+                let equation = self.equation.take();
+                let anchor = self.take_anchor().filter(|_| block);
+                if let Some(equation) = equation.filter(|_| self.feat.native_math(self.feat.target))
+                {
+                    let math = Node::Custom(Box::new(MathNode {
+                        expr: MathExpr::from_equation(&equation),
+                        block,
+                        label: equation
+                            .label()
+                            .map(|label| label.resolve().as_str().into()),
+                    }));
+                    if block {
+                        self.flush_inline_buffer();
+                        self.blocks
+                            .push(Node::Paragraph(anchor.into_iter().chain([math]).collect()));
+                    } else {
+                        self.inline_buffer.push(math);
+                    }
+                } else if block {
                     self.flush_inline_buffer();
+                    self.inline_buffer.extend(anchor);
                     self.convert_children(element)?;
                     let content = std::mem::take(&mut self.inline_buffer);
                     self.blocks
@@ -161,6 +233,83 @@ impl HtmlToAstParser {
                 Ok(())
             }
 
+            md_tag::footnote => {
+                let mut content = Vec::new();
+                self.convert_children_into(&mut content, element)?;
+                self.footnotes.push(content.clone());
+                self.inline_buffer.push(Node::Custom(Box::new(FootnoteNode {
+                    index: self.footnotes.len(),
+                    content,
+                })));
+                Ok(())
+            }
+
+            md_tag::cite => {
+                let attrs = CiteAttr::parse(&element.attrs)?;
+                let mut content = Vec::new();
+                self.convert_children_into(&mut content, element)?;
+                self.citations.push(attrs.key.clone());
+                self.inline_buffer.push(Node::Custom(Box::new(CitationNode {
+                    key: attrs.key,
+                    content,
+                })));
+                Ok(())
+            }
+
+            md_tag::reference if self.feat.link_refs => {
+                let attrs = RefAttr::parse(&element.attrs)?;
+                let mut content = Vec::new();
+                self.convert_children_into(&mut content, element)?;
+                self.inline_buffer.push(Node::Custom(Box::new(RefNode {
+                    target: attrs.target,
+                    content,
+                })));
+                Ok(())
+            }
+
+            md_tag::par => {
+                let attrs = ParAttr::parse(&element.attrs)?;
+                self.inline_buffer.push(Node::Custom(Box::new(ParStyleNode {
+                    font: attrs.font,
+                    size: attrs.size,
+                    justify: attrs.justify,
+                    leading: attrs.leading,
+                    indent: attrs.indent,
+                })));
+                self.convert_children(element)
+            }
+
+            // The outline is updated by Word from the headings.
+            #[cfg(feature = "docx")]
+            md_tag::outline if self.feat.target == Format::Docx => {
+                self.flush_inline_buffer();
+                let mut title = Vec::new();
+                let mut depth = 1;
+                for child in &element.children {
+                    let HtmlNode::Element(child) = child else {
+                        continue;
+                    };
+                    if child.tag == md_tag::heading {
+                        self.convert_children_into(&mut title, child)?;
+                    } else if child.tag == md_tag::outline_entry {
+                        depth = depth.max(HeadingAttr::parse(&child.attrs)?.level);
+                    }
+                }
+                self.blocks
+                    .push(Node::Custom(Box::new(OutlineNode { title, depth })));
+                Ok(())
+            }
+
+            md_tag::bibliography => {
+                self.flush_inline_buffer();
+                self.blocks.push(Node::Custom(Box::new(BibliographyNode {
+                    content: Vec::new(),
+                    bib_names: Vec::new(),
+                    bibtex_style: "plain".into(),
+                })));
+                Ok(())
+            }
+
             md_tag::alerts => {
                 self.flush_inline_buffer();
                 let attrs = AlertsAttr::parse(&element.attrs)?;
@@ -243,25 +392,91 @@ impl HtmlToAstParser {
         }
     }
 
+    /// Takes the anchor of the last started element, which is kept only when
+    /// the references are resolved.
+    pub fn take_anchor(&mut self) -> Option<Node> {
+        let location = self.location.take().filter(|_| self.feat.link_refs)?;
+        Some(Node::Custom(Box::new(AnchorNode { location })))
+    }
+
     pub fn convert_children(&mut self, element: &HtmlElement) -> Result<()> {
         for child in &element.children {
-            match child {
-                HtmlNode::Text(text, _) => {
-                    self.inline_buffer.push(Node::Text(text.clone()));
-                }
-                HtmlNode::Element(element) => {
-                    self.convert_element(element)?;
-                }
-                HtmlNode::Frame(frame) => {
-                    let res = self.convert_frame(frame);
-                    self.inline_buffer.push(res);
+            self.convert_node(child)?;
+        }
+        Ok(())
+    }
+
+    fn convert_node(&mut self, node: &HtmlNode) -> Result<()> {
+        match node {
+            HtmlNode::Text(text, span) => {
+                self.record_span(*span);
+                self.inline_buffer.push(Node::Text(text.clone()));
+            }
+            HtmlNode::Element(element) => {
+                self.convert_element(element)?;
+            }
+            HtmlNode::Frame(frame) => {
+                let res = self.convert_frame(frame);
+                self.inline_buffer.push(res);
+            }
+            HtmlNode::Tag(Tag::Start(elem)) => {
+                self.record_span(elem.span());
+                if elem.is::<EquationElem>() {
+                    self.equation = Some(elem.clone());
+                    self.location = elem.location();
+                } else if elem.is::<HeadingElem>() || elem.is::<FigureElem>() {
+                    self.label = elem.label().map(|label| label.resolve().as_str().into());
+                    self.location = elem.location();
+                } else if elem.is::<BibliographyElem>() {
+                    self.bibliography = elem.to_packed::<BibliographyElem>().cloned();
                 }
-                HtmlNode::Tag(..) => {}
             }
+            HtmlNode::Tag(..) => {}
         }
         Ok(())
     }
 
+    /// Converts the children of the document one by one, and assigns the
+    /// source spans collected meanwhile to the top-level blocks.
+    fn convert_document(&mut self, element: &HtmlElement) -> Result<()> {
+        let mut tag_spans = Vec::new();
+        for child in &element.children {
+            let len = self.blocks.len();
+            let has_inline = !self.inline_buffer.is_empty();
+            let prev_spans = std::mem::take(&mut self.spans);
+            self.convert_node(child)?;
+            let mut spans = std::mem::replace(&mut self.spans, prev_spans);
+            // The spans of the start tags belong to the elements after them.
+            if matches!(child, HtmlNode::Tag(..)) {
+                tag_spans.extend(spans);
+                continue;
+            }
+            spans.splice(0..0, tag_spans.drain(..));
+
+            let mut added = self.blocks.len() - len;
+            let block_spans = self.block_spans.get_or_insert_with(Vec::new);
+            // The inline buffer is flushed into a paragraph before the blocks
+            // of the child.
+            if has_inline && added > 0 {
+                block_spans.push(std::mem::take(&mut self.spans));
+                added -= 1;
+            }
+            if added > 0 {
+                block_spans.extend(std::iter::repeat_n(spans, added));
+            } else {
+                self.spans.extend(spans);
+            }
+        }
+        self.spans.extend(tag_spans);
+        Ok(())
+    }
+
+    fn record_span(&mut self, span: Span) {
+        if self.block_spans.is_some() && !span.is_detached() {
+            self.spans.push(span);
+        }
+    }
+
     pub fn convert_children_into(
         &mut self,
         target: &mut Vec<Node>,
@@ -343,6 +558,7 @@ impl HtmlToAstParser {
                 | md_tag::table
                 | md_tag::grid
                 | md_tag::figure
+                | md_tag::par
         )
     }
 
@@ -379,12 +595,71 @@ impl HtmlToAstParser {
     }
 
     pub fn parse(mut self, root: &HtmlElement) -> Result<Node> {
+        self.convert_root(root)
+    }
+
+    /// Parses the document with the source spans of its top-level blocks,
+    /// which are in the same order as the blocks.
+    pub fn parse_with_spans(mut self, root: &HtmlElement) -> Result<(Node, Vec<Vec<Span>>)> {
+        self.block_spans = Some(Vec::new());
+        let doc = self.convert_root(root)?;
+        Ok((doc, self.block_spans.unwrap_or_default()))
+    }
+
+    fn convert_root(&mut self, root: &HtmlElement) -> Result<Node> {
         self.blocks.clear();
         self.inline_buffer.clear();
 
         self.convert_element(root)?;
+        if let Some(block_spans) = &mut self.block_spans {
+            if !self.inline_buffer.is_empty() {
+                block_spans.push(std::mem::take(&mut self.spans));
+            }
+        }
         self.flush_inline_buffer();
+        self.render_bibliography();
+
+        let footnotes = std::mem::take(&mut self.footnotes);
+        for (idx, content) in footnotes.into_iter().enumerate() {
+            self.blocks
+                .push(Node::Custom(Box::new(FootnoteDefinitionNode {
+                    index: idx + 1,
+                    content,
+                })));
+        }
+
+        Ok(Node::Document(std::mem::take(&mut self.blocks)))
+    }
 
-        Ok(Node::Document(self.blocks))
+    /// Fills the bibliography with the references of the collected citations.
+    fn render_bibliography(&mut self) {
+        let Some(elem) = self.bibliography.take() else {
+            return;
+        };
+        let Some(node) = self.blocks.iter_mut().find_map(|node| match node {
+            Node::Custom(node) => node.as_any_mut().downcast_mut::<BibliographyNode>(),
+            _ => None,
+        }) else {
+            return;
+        };
+        let Some(sources) = BibSources::load(&self.world, &elem) else {
+            return;
+        };
+
+        if let Some(title) = bib_title(&elem) {
+            node.content.push(Node::heading(2, vec![Node::Text(title)]));
+        }
+        let references = sources.render(&self.citations, elem.full(StyleChain::default()));
+        node.content.extend(
+            references
+                .into_iter()
+                .map(|reference| Node::Paragraph(vec![Node::Text(reference.into())])),
+        );
+        node.bib_names = sources
+            .biblatex_files
+            .iter()
+            .map(|(name, _)| name.trim_end_matches(".bib").into())
+            .collect();
+        node.bibtex_style = sources.bibtex_style.into();
     }
 }
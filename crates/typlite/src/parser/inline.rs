@@ -74,14 +74,20 @@ impl HtmlToAstParser {
         // Parse figure attributes to extract caption
         let attrs = FigureAttr::parse(&element.attrs)?;
         let caption = attrs.caption.to_string();
+        let label = self.label.take();
 
         // Find image and body content
         let mut body_content = Vec::new();
+        body_content.extend(self.take_anchor());
         self.convert_children_into(&mut body_content, element)?;
         let body = Box::new(Node::Paragraph(body_content));
 
         // Create figure node with centering
-        let figure_node = Box::new(FigureNode { body, caption });
+        let figure_node = Box::new(FigureNode {
+            body,
+            caption,
+            label,
+        });
         let centered_node = CenterNode::new(vec![Node::Custom(figure_node)]);
 
         // Add the centered figure to blocks
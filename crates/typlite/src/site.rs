@@ -0,0 +1,541 @@
+//! Splits a document into the chapters of a documentation site
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use cmark_writer::ast::{ListItem, Node};
+use ecow::{eco_format, EcoString};
+use serde_json::{json, Value};
+use tinymist_project::LspWorld;
+use tinymist_std::error::prelude::*;
+use typst::foundations::Bytes;
+use typst::introspection::{Introspector, Location};
+use typst::model::HeadingElem;
+use typst::World;
+
+use crate::common::{
+    AlertNode, AnchorNode, BibliographyNode, CenterNode, ExternalFrameNode, FigureNode,
+    FootnoteDefinitionNode, FootnoteNode, Format, HighlightNode, InlineNode, RefNode,
+};
+use crate::writer::WriterFactory;
+
+/// The directory of the assets, relative to the chapters.
+pub const ASSETS_DIR: &str = "assets";
+
+/// The generators of documentation sites.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum SiteKind {
+    /// Generates the `SUMMARY.md` of mdBook.
+    #[default]
+    #[cfg_attr(feature = "clap", value(name = "mdbook"))]
+    MdBook,
+    /// Generates the `sidebars.json` of Docusaurus.
+    Docusaurus,
+}
+
+/// A documentation site split from a document.
+#[derive(Debug, Clone)]
+pub struct Site {
+    /// The chapters in the order of the document.
+    pub chapters: Vec<Chapter>,
+    /// The file name and the content of the table of contents, i.e.
+    /// `SUMMARY.md` or `sidebars.json`.
+    pub toc: (EcoString, EcoString),
+    /// The file names and the contents of the images, which are placed in
+    /// [`ASSETS_DIR`].
+    pub assets: Vec<(EcoString, Bytes)>,
+}
+
+/// A chapter of a documentation site.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    /// The file name of the chapter, e.g. `introduction.md`.
+    pub path: EcoString,
+    /// The title in the table of contents.
+    pub title: EcoString,
+    /// The nesting depth in the table of contents, starting from zero.
+    pub depth: usize,
+    /// The content in Markdown.
+    pub content: EcoString,
+}
+
+/// Maps the labels in the document to the locations of the headings of their
+/// sections in the introspector order, which are the targets of the links to
+/// the labels.
+pub(crate) fn label_headings(introspector: &Introspector) -> HashMap<EcoString, Location> {
+    let mut labels = HashMap::new();
+    let mut heading = None;
+    for elem in introspector.all() {
+        if elem.is::<HeadingElem>() {
+            heading = elem.location();
+        }
+        if let (Some(label), Some(heading)) = (elem.label(), heading) {
+            labels.insert(label.resolve().as_str().into(), heading);
+        }
+    }
+    labels
+}
+
+/// Maps the labels in the document to the locations of the labelled elements.
+#[cfg(feature = "docx")]
+pub(crate) fn label_locations(introspector: &Introspector) -> HashMap<EcoString, Location> {
+    introspector
+        .all()
+        .filter_map(|elem| Some((elem.label()?.resolve().as_str().into(), elem.location()?)))
+        .collect()
+}
+
+/// A part of the document before being written as a chapter.
+struct Part {
+    title: EcoString,
+    /// The level of the heading starting the part, or zero for the content
+    /// before the first heading.
+    level: usize,
+    path: EcoString,
+    blocks: Vec<Node>,
+}
+
+/// Splits the document at the headings whose levels are not greater than
+/// `split_level`, where `1` is the level of the top-level headings in typst.
+pub(crate) fn build_site(
+    doc: Node,
+    labels: &HashMap<EcoString, Location>,
+    world: &LspWorld,
+    title: Option<EcoString>,
+    kind: SiteKind,
+    split_level: usize,
+) -> tinymist_std::Result<Site> {
+    let Node::Document(blocks) = doc else {
+        bail!("expected a document node");
+    };
+    // The footnotes are defined in the chapters referring to them.
+    let (definitions, blocks): (Vec<_>, Vec<_>) = blocks
+        .into_iter()
+        .partition(|node| node.is_custom_type::<FootnoteDefinitionNode>());
+
+    // The typst headings are one level deeper than the Markdown headings.
+    let split_levels = 2..=split_level + 1;
+    let mut parts = vec![Part {
+        title: title.unwrap_or_else(|| "Introduction".into()),
+        level: 0,
+        path: "index.md".into(),
+        blocks: Vec::new(),
+    }];
+    for node in blocks {
+        if let Node::Heading { level, content, .. } = &node {
+            if split_levels.contains(&(*level as usize)) {
+                parts.push(Part {
+                    title: plain_text(content).trim().into(),
+                    level: *level as usize,
+                    path: EcoString::new(),
+                    blocks: Vec::new(),
+                });
+            }
+        }
+        parts.last_mut().unwrap().blocks.push(node);
+    }
+    if parts[0].blocks.is_empty() && parts.len() > 1 {
+        parts.remove(0);
+    }
+
+    let mut paths = HashMap::new();
+    for (idx, part) in parts.iter_mut().enumerate() {
+        if part.level == 0 {
+            continue;
+        }
+        let slug = match slugify(&part.title) {
+            slug if slug.is_empty() || slug == "index" || slug == "summary" => {
+                format!("chapter-{idx}")
+            }
+            slug => slug,
+        };
+        part.path = eco_format!("{}.md", unique(&mut paths, slug));
+    }
+
+    // The headings are found by the anchors of their elements.
+    let mut anchors = HashMap::new();
+    for part in &parts {
+        let mut ids = HashMap::new();
+        for node in &part.blocks {
+            if let Node::Heading { content, .. } = node {
+                let id = unique(&mut ids, slugify(plain_text(content).trim()));
+                let anchor = content
+                    .iter()
+                    .find_map(|node| node.as_custom_type::<AnchorNode>());
+                if let Some(anchor) = anchor {
+                    anchors.insert(anchor.location, (part.path.clone(), id));
+                }
+            }
+        }
+    }
+    let refs = labels
+        .iter()
+        .filter_map(|(label, heading)| {
+            let target = anchors.get(heading)?;
+            Some((label.clone(), target.clone()))
+        })
+        .collect();
+
+    let mut resolver = Resolver {
+        world,
+        refs,
+        path: EcoString::new(),
+        footnotes: BTreeSet::new(),
+        images: HashMap::new(),
+        asset_names: HashMap::new(),
+        assets: Vec::new(),
+    };
+    let mut chapters = Vec::new();
+    let mut depths: Vec<usize> = Vec::new();
+    for mut part in parts {
+        resolver.path = part.path.clone();
+        resolver.footnotes.clear();
+        resolver.rewrite(&mut part.blocks);
+
+        for definition in &definitions {
+            let definition = definition.as_custom_type::<FootnoteDefinitionNode>();
+            if let Some(definition) =
+                definition.filter(|definition| resolver.footnotes.contains(&definition.index))
+            {
+                let mut definition = definition.clone();
+                resolver.rewrite(&mut definition.content);
+                part.blocks.push(Node::Custom(Box::new(definition)));
+            }
+        }
+
+        // The heading starting a chapter is the title of the chapter.
+        let shift = part.level.saturating_sub(1) as u8;
+        for node in &mut part.blocks {
+            if let Node::Heading { level, .. } = node {
+                *level = level.saturating_sub(shift).max(1);
+            }
+        }
+
+        let mut content = EcoString::new();
+        let mut writer = WriterFactory::create(Format::Md);
+        writer
+            .write_eco(&Node::Document(part.blocks), &mut content)
+            .context_ut("failed to write")?;
+
+        while depths.last().is_some_and(|level| *level >= part.level) {
+            depths.pop();
+        }
+        chapters.push(Chapter {
+            path: part.path,
+            title: part.title,
+            depth: depths.len(),
+            content,
+        });
+        if part.level > 0 {
+            depths.push(part.level);
+        }
+    }
+
+    let toc = match kind {
+        SiteKind::MdBook => ("SUMMARY.md".into(), summary(&chapters)),
+        SiteKind::Docusaurus => {
+            let (items, _) = sidebar_items(&chapters, 0);
+            let sidebars = json!({ "docs": items });
+            let sidebars =
+                serde_json::to_string_pretty(&sidebars).context_ut("failed to write sidebars")?;
+            ("sidebars.json".into(), sidebars.into())
+        }
+    };
+
+    Ok(Site {
+        chapters,
+        toc,
+        assets: resolver.assets,
+    })
+}
+
+/// Rewrites the references and the images of a chapter.
+struct Resolver<'a> {
+    world: &'a LspWorld,
+    /// The chapters and the anchors of the labels.
+    refs: HashMap<EcoString, (EcoString, String)>,
+    /// The path of the current chapter.
+    path: EcoString,
+    /// The footnotes referred in the current chapter.
+    footnotes: BTreeSet<usize>,
+    /// The asset names of the copied images.
+    images: HashMap<EcoString, EcoString>,
+    asset_names: HashMap<String, usize>,
+    assets: Vec<(EcoString, Bytes)>,
+}
+
+impl Resolver<'_> {
+    fn rewrite(&mut self, nodes: &mut Vec<Node>) {
+        let mut idx = 0;
+        while idx < nodes.len() {
+            let Some(reference) = nodes[idx].as_custom_type::<RefNode>() else {
+                self.visit(&mut nodes[idx]);
+                idx += 1;
+                continue;
+            };
+
+            let mut content = reference.content.clone();
+            self.rewrite(&mut content);
+            // References to unknown labels, e.g. to the bibliography, are
+            // kept as text.
+            let replacement = match self.refs.get(&reference.target) {
+                Some((path, anchor)) if *path == self.path => vec![Node::Link {
+                    url: eco_format!("#{anchor}"),
+                    title: None,
+                    content,
+                }],
+                Some((path, anchor)) => vec![Node::Link {
+                    url: eco_format!("{path}#{anchor}"),
+                    title: None,
+                    content,
+                }],
+                None => content,
+            };
+            let len = replacement.len();
+            nodes.splice(idx..=idx, replacement);
+            idx += len;
+        }
+    }
+
+    fn visit(&mut self, node: &mut Node) {
+        match node {
+            Node::Document(content)
+            | Node::Paragraph(content)
+            | Node::BlockQuote(content)
+            | Node::Emphasis(content)
+            | Node::Strong(content)
+            | Node::Strikethrough(content)
+            | Node::Heading { content, .. }
+            | Node::Link { content, .. } => self.rewrite(content),
+            Node::OrderedList { items, .. } | Node::UnorderedList(items) => {
+                for item in items {
+                    if let ListItem::Ordered { content, .. } | ListItem::Unordered { content } =
+                        item
+                    {
+                        self.rewrite(content);
+                    }
+                }
+            }
+            Node::Table { headers, rows, .. } => {
+                self.rewrite(headers);
+                for row in rows {
+                    self.rewrite(row);
+                }
+            }
+            Node::HtmlElement(element) => self.rewrite(&mut element.children),
+            Node::Image { url, .. } => {
+                if let Some(asset) = self.copy_image(url) {
+                    *url = asset;
+                }
+            }
+            Node::Custom(node) => {
+                let node = node.as_any_mut();
+                if let Some(figure) = node.downcast_mut::<FigureNode>() {
+                    self.visit(&mut figure.body);
+                } else if let Some(center) = node.downcast_mut::<CenterNode>() {
+                    self.visit(&mut center.node);
+                } else if let Some(footnote) = node.downcast_mut::<FootnoteNode>() {
+                    self.footnotes.insert(footnote.index);
+                    self.rewrite(&mut footnote.content);
+                } else if let Some(alert) = node.downcast_mut::<AlertNode>() {
+                    self.rewrite(&mut alert.content);
+                } else if let Some(highlight) = node.downcast_mut::<HighlightNode>() {
+                    self.rewrite(&mut highlight.content);
+                } else if let Some(inline) = node.downcast_mut::<InlineNode>() {
+                    self.rewrite(&mut inline.content);
+                } else if let Some(bibliography) = node.downcast_mut::<BibliographyNode>() {
+                    self.rewrite(&mut bibliography.content);
+                } else if let Some(frame) = node.downcast_mut::<ExternalFrameNode>() {
+                    let name = frame
+                        .file_path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .unwrap_or("frame.svg");
+                    let name = self.add_asset(name, Bytes::from_string(frame.svg.clone()));
+                    frame.file_path = Path::new(ASSETS_DIR).join(name.as_str());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Copies a local image into the assets, and returns the new url.
+    fn copy_image(&mut self, url: &str) -> Option<EcoString> {
+        if url.is_empty() || url.contains("://") || url.starts_with("data:") {
+            return None;
+        }
+        if let Some(name) = self.images.get(url) {
+            return Some(eco_format!("{ASSETS_DIR}/{name}"));
+        }
+
+        // The paths of the images are resolved relative to the main file.
+        let fid = self.world.main().join(url);
+        let content = self.world.file(fid).ok()?;
+        let name = fid.vpath().as_rootless_path().file_name()?.to_str()?;
+        let name = self.add_asset(name, content);
+        self.images.insert(url.into(), name.clone());
+        Some(eco_format!("{ASSETS_DIR}/{name}"))
+    }
+
+    fn add_asset(&mut self, name: &str, content: Bytes) -> EcoString {
+        let name: EcoString = match self.asset_names.get_mut(name) {
+            Some(count) => {
+                *count += 1;
+                eco_format!("{count}-{name}")
+            }
+            None => {
+                self.asset_names.insert(name.to_owned(), 0);
+                name.into()
+            }
+        };
+        self.assets.push((name.clone(), content));
+        name
+    }
+}
+
+/// Generates the `SUMMARY.md` of mdBook. The content before the first
+/// heading is a prefix chapter.
+fn summary(chapters: &[Chapter]) -> EcoString {
+    let mut out = EcoString::from("# Summary\n\n");
+    let mut chapters = chapters.iter().peekable();
+    if let Some(prefix) = chapters.next_if(|chapter| chapter.path == "index.md") {
+        out.push_str(&eco_format!("[{}]({})\n\n", prefix.title, prefix.path));
+    }
+    for chapter in chapters {
+        let indent = "  ".repeat(chapter.depth);
+        out.push_str(&eco_format!(
+            "{indent}- [{}]({})\n",
+            chapter.title,
+            chapter.path
+        ));
+    }
+    out
+}
+
+/// Generates the items of the Docusaurus sidebar whose depth is not less
+/// than `depth`, and returns the number of the consumed chapters.
+fn sidebar_items(chapters: &[Chapter], depth: usize) -> (Vec<Value>, usize) {
+    let mut items = Vec::new();
+    let mut idx = 0;
+    while let Some(chapter) = chapters.get(idx).filter(|chapter| chapter.depth >= depth) {
+        let id = chapter.path.trim_end_matches(".md");
+        let (children, consumed) = sidebar_items(&chapters[idx + 1..], chapter.depth + 1);
+        idx += consumed + 1;
+
+        if children.is_empty() {
+            items.push(json!({ "type": "doc", "id": id, "label": chapter.title.as_str() }));
+        } else {
+            items.push(json!({
+                "type": "category",
+                "label": chapter.title.as_str(),
+                "link": { "type": "doc", "id": id },
+                "items": children,
+            }));
+        }
+    }
+    (items, idx)
+}
+
+/// Collects the text of the inline nodes.
+pub(crate) fn plain_text(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            Node::Text(text) | Node::InlineCode(text) => out.push_str(text),
+            Node::Emphasis(content)
+            | Node::Strong(content)
+            | Node::Strikethrough(content)
+            | Node::Link { content, .. } => out.push_str(&plain_text(content)),
+            Node::SoftBreak | Node::HardBreak => out.push(' '),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Generates the anchor of a heading in the same way as mdBook and
+/// Docusaurus.
+fn slugify(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                Some(c)
+            } else if c.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Makes the id unique by the suffix `-1`, `-2`, etc.
+fn unique(ids: &mut HashMap<String, usize>, id: String) -> String {
+    match ids.get_mut(&id) {
+        Some(count) => {
+            *count += 1;
+            format!("{id}-{count}")
+        }
+        None => {
+            ids.insert(id.clone(), 0);
+            id
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(path: &str, title: &str, depth: usize) -> Chapter {
+        Chapter {
+            path: path.into(),
+            title: title.into(),
+            depth,
+            content: EcoString::new(),
+        }
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("The `raw` Element"), "the-raw-element");
+
+        let mut ids = HashMap::new();
+        assert_eq!(unique(&mut ids, "intro".into()), "intro");
+        assert_eq!(unique(&mut ids, "intro".into()), "intro-1");
+    }
+
+    #[test]
+    fn test_toc() {
+        let chapters = [
+            chapter("index.md", "Handbook", 0),
+            chapter("setup.md", "Setup", 0),
+            chapter("install.md", "Install", 1),
+            chapter("usage.md", "Usage", 0),
+        ];
+
+        assert_eq!(
+            summary(&chapters),
+            "# Summary\n\n[Handbook](index.md)\n\n\
+             - [Setup](setup.md)\n  - [Install](install.md)\n- [Usage](usage.md)\n"
+        );
+
+        let (items, _) = sidebar_items(&chapters, 0);
+        assert_eq!(
+            Value::Array(items),
+            json!([
+                { "type": "doc", "id": "index", "label": "Handbook" },
+                {
+                    "type": "category",
+                    "label": "Setup",
+                    "link": { "type": "doc", "id": "setup" },
+                    "items": [{ "type": "doc", "id": "install", "label": "Install" }],
+                },
+                { "type": "doc", "id": "usage", "label": "Usage" },
+            ])
+        );
+    }
+}
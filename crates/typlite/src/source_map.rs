@@ -0,0 +1,226 @@
+//! Source maps from the blocks in the output back to the typst sources
+
+use std::ops::Range;
+use std::path::PathBuf;
+
+use ecow::{eco_format, EcoString};
+use serde_json::json;
+use tinymist_project::vfs::WorkspaceResolver;
+use tinymist_project::LspWorld;
+use typst::syntax::{FileId, Span};
+use typst::WorldExt;
+
+/// A top-level block in the output, which is written from a range of a typst
+/// source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMapEntry {
+    /// The first line of the block in the output, starting from 1.
+    pub start_line: usize,
+    /// The last line of the block in the output, inclusive.
+    pub end_line: usize,
+    /// The source file.
+    pub file: FileId,
+    /// The path of the source file in the file system, if any.
+    pub path: Option<PathBuf>,
+    /// The byte range in the source file.
+    pub range: Range<usize>,
+}
+
+/// The main file wrapped with the show rules converting the document, which
+/// is recorded when the world is prepared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WrappedMain {
+    /// The original main file.
+    pub main: FileId,
+    /// The entry of the compilation, which includes the wrapping file into
+    /// the `article` of the processor if any, or is the wrapping file itself.
+    pub entry: FileId,
+    /// The file wrapping the content of the main file.
+    pub wrapper: FileId,
+    /// The length of the content before the content of the main file in the
+    /// wrapping file.
+    pub prefix_len: usize,
+}
+
+/// Maps the top-level blocks in the output back to the typst sources, e.g. to
+/// turn a comment on a line of the generated Markdown into a comment on the
+/// typst source.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    /// The entries in the order of their lines.
+    pub entries: Vec<SourceMapEntry>,
+}
+
+impl SourceMap {
+    /// Creates a source map from the byte ranges of the blocks in the output
+    /// and the source spans of the blocks.
+    pub(crate) fn new(
+        world: &LspWorld,
+        wrapped: Option<WrappedMain>,
+        output: &str,
+        ranges: &[Range<usize>],
+        block_spans: &[Vec<Span>],
+    ) -> Self {
+        let entries = line_ranges(output, ranges)
+            .into_iter()
+            .zip(block_spans)
+            .filter_map(|(lines, spans)| {
+                let (start_line, end_line) = lines?;
+                let (file, range) = source_range(world, wrapped, spans)?;
+                Some(SourceMapEntry {
+                    start_line,
+                    end_line,
+                    file,
+                    path: world
+                        .path_for_id(file)
+                        .ok()
+                        .and_then(|path| path.to_err().ok()),
+                    range,
+                })
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Finds the entry containing a line of the output, starting from 1.
+    pub fn lookup(&self, line: usize) -> Option<&SourceMapEntry> {
+        let idx = self.entries.partition_point(|entry| entry.end_line < line);
+        self.entries
+            .get(idx)
+            .filter(|entry| entry.start_line <= line)
+    }
+
+    /// Serializes the source map into JSON, where the files are identified by
+    /// their paths in the workspace or the packages.
+    pub fn to_json(&self) -> String {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                json!({
+                    "startLine": entry.start_line,
+                    "endLine": entry.end_line,
+                    "file": file_name(entry.file).as_str(),
+                    "path": entry.path.as_ref().map(|path| path.display().to_string()),
+                    "range": [entry.range.start, entry.range.end],
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({ "version": 1, "entries": entries }).to_string()
+    }
+}
+
+/// Computes the lines of the blocks from their byte ranges in the output,
+/// ignoring the surrounding whitespace. The ranges are in ascending order.
+fn line_ranges(output: &str, ranges: &[Range<usize>]) -> Vec<Option<(usize, usize)>> {
+    let bytes = output.as_bytes();
+    let mut line = 1;
+    let mut pos = 0;
+    let mut line_at = |offset: usize| {
+        line += bytes[pos..offset].iter().filter(|&&b| b == b'\n').count();
+        pos = offset;
+        line
+    };
+
+    ranges
+        .iter()
+        .map(|range| {
+            let text = &output[range.clone()];
+            let trimmed = text.trim();
+            if trimmed.is_empty() {
+                return None;
+            }
+            let start = range.start + (text.len() - text.trim_start().len());
+            Some((line_at(start), line_at(start + trimmed.len())))
+        })
+        .collect()
+}
+
+/// Resolves the spans of a block into a range of a source file. The files
+/// not in packages are preferred over the templates in packages.
+fn source_range(
+    world: &LspWorld,
+    wrapped: Option<WrappedMain>,
+    spans: &[Span],
+) -> Option<(FileId, Range<usize>)> {
+    let ranges = spans
+        .iter()
+        .filter_map(|span| {
+            let id = span.id()?;
+            let range = world.range(*span)?;
+            let Some(wrapped) = wrapped else {
+                return Some((id, range));
+            };
+            if id == wrapped.wrapper {
+                let len = wrapped.prefix_len;
+                Some((
+                    wrapped.main,
+                    range.start.saturating_sub(len)..range.end.saturating_sub(len),
+                ))
+            } else if id == wrapped.entry {
+                // The entry generated for the processor is not in the sources.
+                None
+            } else {
+                Some((id, range))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let file = ranges
+        .iter()
+        .map(|(id, _)| *id)
+        .find(|id| !WorkspaceResolver::is_package_file(*id))
+        .or_else(|| ranges.first().map(|(id, _)| *id))?;
+    let range = ranges
+        .into_iter()
+        .filter(|(id, _)| *id == file)
+        .map(|(_, range)| range)
+        .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))?;
+    Some((file, range))
+}
+
+/// Names a file by its path in the workspace, or by the package and its path
+/// in the package.
+fn file_name(id: FileId) -> EcoString {
+    let path = id.vpath().as_rooted_path().display();
+    match id.package() {
+        Some(package) if WorkspaceResolver::is_package_file(id) => eco_format!("{package}{path}"),
+        _ => eco_format!("{path}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use typst::syntax::VirtualPath;
+
+    #[test]
+    fn test_line_ranges() {
+        let output = "# Title\n\nFirst line\nSecond line\n\n\n";
+        let ranges = [0..8, 9..31, 31..33];
+        assert_eq!(
+            line_ranges(output, &ranges),
+            vec![Some((1, 1)), Some((3, 4)), None]
+        );
+    }
+
+    #[test]
+    fn test_lookup() {
+        let file = FileId::new(None, VirtualPath::new("main.typ"));
+        let entry = |start_line, end_line| SourceMapEntry {
+            start_line,
+            end_line,
+            file,
+            path: None,
+            range: 0..1,
+        };
+        let map = SourceMap {
+            entries: vec![entry(1, 1), entry(3, 4)],
+        };
+        assert_eq!(map.lookup(1).map(|entry| entry.start_line), Some(1));
+        assert_eq!(map.lookup(2), None);
+        assert_eq!(map.lookup(4).map(|entry| entry.start_line), Some(3));
+        assert_eq!(map.lookup(5), None);
+    }
+}
@@ -42,5 +42,9 @@ pub mod md_tag {
         alerts -> m1alerts
         doc -> m1document
         link -> m1link
+        footnote -> m1footnote
+        cite -> m1cite
+        bibliography -> m1bib
+        par -> m1par
     }
 }
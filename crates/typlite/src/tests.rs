@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::sync::OnceLock;
 
 use regex::Regex;
@@ -26,6 +27,27 @@ fn convert_tex() {
     });
 }
 
+#[test]
+fn source_map_keeps_output() {
+    snapshot_testing("integration", &|world, path| {
+        let math_output = math_output(&world);
+        let world = Arc::new(world);
+        for format in [Format::Md, Format::LaTeX] {
+            let converter = Typlite::new(world.clone()).with_feature(TypliteFeat {
+                math_output,
+                ..Default::default()
+            });
+            let doc = converter.convert_doc(format).unwrap();
+            let (output, _) = doc.to_string_with_source_map(format).unwrap();
+            let expected = match format {
+                Format::LaTeX => doc.to_tex_string().unwrap(),
+                _ => doc.to_md_string().unwrap(),
+            };
+            assert_eq!(output, expected, "{format:?}: {}", path.display());
+        }
+    });
+}
+
 #[test]
 fn convert_docs() {
     snapshot_testing("docs", &|world, _path| {
@@ -69,6 +91,115 @@ fn test_docx_generation() {
     });
 }
 
+#[test]
+#[cfg(feature = "odt")]
+fn test_odt_generation() {
+    snapshot_testing("integration", &|world, _path| {
+        let converter = Typlite::new(Arc::new(world.clone())).with_format(Format::Odt);
+
+        let odt_data = match converter.to_odt() {
+            Ok(data) => data,
+            Err(err) => {
+                panic!("Failed to generate ODT: {}", err);
+            }
+        };
+
+        assert_eq!(
+            &odt_data[30..38],
+            b"mimetype",
+            "ODT data should start with the mimetype"
+        );
+    });
+}
+
+#[test]
+fn test_site_labels_by_location() {
+    let source = "#set heading(numbering: \"1.\")\n\
+                  = Setup\n== Notes\n\
+                  = Usage\n== Notes <notes>\n\
+                  = Conclusion\nSee @notes.";
+    tinymist_tests::run_with_sources(source, |verse, _path| {
+        let converter = Typlite::new(Arc::new(verse.snapshot()));
+        let doc = converter.convert_doc(Format::Md).unwrap();
+        let site = doc.to_site(SiteKind::MdBook, 1).unwrap();
+
+        let conclusion = site.chapters.last().unwrap();
+        assert_eq!(conclusion.path, "conclusion.md");
+        // The label is in the section of the second heading with the text.
+        assert!(
+            conclusion.content.contains("(usage.md#notes)"),
+            "{}",
+            conclusion.content
+        );
+    });
+}
+
+#[test]
+fn test_latex_bibliography_style() {
+    let source = "// path: refs.bib\n\
+                  @book{knuth, title = {The Art of Computer Programming}, \
+                  author = {Knuth, Donald}, year = {1968}, publisher = {Addison-Wesley}}\n\
+                  -----\n\
+                  // path: main.typ\n\
+                  #set bibliography(style: \"apa\")\n\
+                  See @knuth.\n\
+                  #bibliography(\"refs.bib\")";
+    tinymist_tests::run_with_sources(source, |verse, _path| {
+        let converter = Typlite::new(Arc::new(verse.snapshot()));
+        let doc = converter.convert_doc(Format::LaTeX).unwrap();
+        let tex = doc.to_tex_string().unwrap();
+
+        // The style is set by the rule rather than the default `ieee`.
+        assert!(
+            tex.contains("\\bibliographystyle{apalike}\n\\bibliography{refs}"),
+            "{tex}"
+        );
+    });
+}
+
+#[test]
+fn test_source_map_with_processor() {
+    let main = include_str!("fixtures/integration/base.typ");
+    let source = format!(
+        "// path: processor.typ\n\
+         #let article(body) = [Processed.\n\n#body]\n\
+         -----\n\
+         // path: main.typ\n\
+         {main}"
+    );
+    for processor in [None, Some("/processor.typ")] {
+        tinymist_tests::run_with_sources(&source, |verse, _path| {
+            let world = verse.snapshot();
+            let converter = Typlite::new(Arc::new(world.clone())).with_feature(TypliteFeat {
+                processor: processor.map(Into::into),
+                ..Default::default()
+            });
+            let doc = converter.convert_doc(Format::Md).unwrap();
+            let (output, map) = doc.to_string_with_source_map(Format::Md).unwrap();
+
+            // The blocks of the main file are mapped back without the prefix
+            // of the wrapping file.
+            for (block, text) in [("## Hello", "= Hello"), ("This is", "This is")] {
+                let line = output
+                    .lines()
+                    .position(|line| line.starts_with(block))
+                    .unwrap_or_else(|| panic!("{block:?} is not in {output}"))
+                    + 1;
+                let entry = map.lookup(line).unwrap();
+                assert_eq!(entry.file.vpath().as_rootless_path(), Path::new("main.typ"));
+                let source = world.source(entry.file).unwrap();
+                let mapped = source.text().get(entry.range.clone()).unwrap();
+                assert!(mapped.starts_with(text), "{processor:?}: {mapped:?}");
+            }
+            // The entry generated for the processor is not in the source map.
+            assert!(map
+                .entries
+                .iter()
+                .all(|entry| entry.file.vpath().as_rootless_path() != Path::new("__md_main.typ")));
+        });
+    }
+}
+
 enum ConvKind {
     Md { for_docs: bool },
     LaTeX,
@@ -90,10 +221,24 @@ impl ConvKind {
     }
 }
 
+/// Reads the output of math equations from the first line of the fixture,
+/// i.e. `// math_output: latex`.
+fn math_output(world: &LspWorld) -> MathOutput {
+    let main = world.source(world.main()).unwrap();
+    let first_line = main.text().lines().next().unwrap_or_default();
+    match first_line.strip_prefix("// math_output:").map(str::trim) {
+        Some("latex") => MathOutput::Latex,
+        Some("svg") | None => MathOutput::Svg,
+        Some(v) => panic!("invalid value for 'math_output': {v:?}"),
+    }
+}
+
 fn conv(world: LspWorld, kind: ConvKind) -> String {
+    let math_output = math_output(&world);
     let world = Arc::new(world);
     let converter = Typlite::new(world.clone()).with_feature(TypliteFeat {
         annotate_elem: kind.for_docs(),
+        math_output,
         ..Default::default()
     });
     let doc = match converter.convert_doc(kind.kind()) {
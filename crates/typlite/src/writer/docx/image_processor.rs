@@ -4,6 +4,7 @@ use base64::Engine;
 use docx_rs::*;
 use std::io::Cursor;
 
+use crate::writer::image::svg_to_png;
 use crate::Result;
 
 /// Image processor for DOCX documents
@@ -17,47 +18,7 @@ impl DocxImageProcessor {
 
     /// Convert SVG data to PNG format
     pub fn convert_svg_to_png(&self, svg_data: &[u8]) -> Result<Vec<u8>> {
-        // Check if data is valid SVG
-        let svg_str = match std::str::from_utf8(svg_data) {
-            Ok(s) => s,
-            Err(_) => return Err("Unable to parse input data as UTF-8 string".into()),
-        };
-
-        let dpi = 300.0;
-        let scale_factor = dpi / 96.0;
-
-        let opt = resvg::usvg::Options {
-            dpi,
-            ..resvg::usvg::Options::default()
-        };
-
-        // Parse SVG
-        let rtree = match resvg::usvg::Tree::from_str(svg_str, &opt) {
-            Ok(tree) => tree,
-            Err(e) => return Err(format!("SVG parsing error: {:?}", e).into()),
-        };
-
-        let size = rtree.size().to_int_size();
-        let width = (size.width() as f32 * scale_factor) as u32;
-        let height = (size.height() as f32 * scale_factor) as u32;
-
-        // Create pixel buffer
-        let mut pixmap = match resvg::tiny_skia::Pixmap::new(width, height) {
-            Some(pixmap) => pixmap,
-            None => return Err("Unable to create pixel buffer".into()),
-        };
-
-        // Render SVG to pixel buffer
-        resvg::render(
-            &rtree,
-            resvg::tiny_skia::Transform::from_scale(scale_factor, scale_factor),
-            &mut pixmap.as_mut(),
-        );
-
-        // Encode as PNG
-        pixmap
-            .encode_png()
-            .map_err(|e| format!("PNG encoding error: {:?}", e).into())
+        svg_to_png(svg_data)
     }
 
     /// Process image data and add to document
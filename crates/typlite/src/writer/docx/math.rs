@@ -0,0 +1,123 @@
+//! Office Math (OMML) in DOCX documents
+//!
+//! docx-rs cannot write math, so each equation is written as a placeholder text
+//! carrying its OMML, which is spliced into `word/document.xml` after the
+//! document is built.
+
+/// The namespace of Office Math.
+const MATH_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/math";
+
+/// Marks the start of a placeholder, in the Unicode private use area.
+const MATH_START: char = '\u{F8F0}';
+/// Marks the end of a placeholder.
+const MATH_END: char = '\u{F8F1}';
+
+/// Creates the placeholder text of an equation in OMML.
+pub(super) fn math_placeholder(omml: &str) -> String {
+    format!("{MATH_START}{omml}{MATH_END}")
+}
+
+/// Replaces the placeholders in the document XML with the equations. Each
+/// placeholder splits its text and run, and the run properties are kept for
+/// the text after the equation.
+pub(super) fn embed_math(document: &str) -> String {
+    if !document.contains(MATH_START) {
+        return document.to_owned();
+    }
+
+    let mut out = String::with_capacity(document.len());
+    let mut rest = document;
+    while let Some(start) = rest.find(MATH_START) {
+        let Some(len) = rest[start..].find(MATH_END) else {
+            break;
+        };
+        let end = start + len;
+
+        // The opening tag of the `<w:t>` element containing the placeholder.
+        let Some(text_tag) = rest[..start]
+            .rfind("<w:t")
+            .and_then(|idx| rest[idx..start].find('>').map(|len| &rest[idx..=idx + len]))
+        else {
+            break;
+        };
+        let run_props = rest[..start]
+            .rfind("<w:r>")
+            .and_then(|run_start| run_props(&rest[run_start..start]))
+            .unwrap_or_default();
+        let omml = unescape_xml(&rest[start + MATH_START.len_utf8()..end]);
+
+        out.push_str(&rest[..start]);
+        out.push_str("</w:t></w:r>");
+        out.push_str(&omml);
+        out.push_str("<w:r>");
+        out.push_str(run_props);
+        out.push_str(text_tag);
+        rest = &rest[end + MATH_END.len_utf8()..];
+    }
+    out.push_str(rest);
+
+    declare_math_ns(out)
+}
+
+/// Gets the properties at the start of a run.
+fn run_props(run: &str) -> Option<&str> {
+    let run = run.strip_prefix("<w:r>")?;
+    if run.starts_with("<w:rPr/>") || run.starts_with("<w:rPr />") {
+        return run.get(..run.find('>')? + 1);
+    }
+    if !run.starts_with("<w:rPr>") {
+        return None;
+    }
+    run.get(..run.find("</w:rPr>")? + "</w:rPr>".len())
+}
+
+/// Declares the namespace of Office Math in the root element.
+fn declare_math_ns(document: String) -> String {
+    if document.contains("xmlns:m=") {
+        return document;
+    }
+
+    document.replacen(
+        "<w:document ",
+        &format!("<w:document xmlns:m=\"{MATH_NS}\" "),
+        1,
+    )
+}
+
+/// Unescapes the text escaped by the XML writer.
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embed_math() {
+        let omml = "<m:oMath><m:r><m:t>x &amp; y</m:t></m:r></m:oMath>";
+        let escaped = math_placeholder(omml)
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        let document = format!(
+            "<w:document xmlns:w=\"w\"><w:body><w:p>\
+             <w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\">a {escaped} b</w:t></w:r>\
+             </w:p></w:body></w:document>"
+        );
+
+        assert_eq!(
+            embed_math(&document),
+            format!(
+                "<w:document xmlns:m=\"{MATH_NS}\" xmlns:w=\"w\"><w:body><w:p>\
+                 <w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\">a </w:t></w:r>{omml}\
+                 <w:r><w:rPr><w:b/></w:rPr><w:t xml:space=\"preserve\"> b</w:t></w:r>\
+                 </w:p></w:body></w:document>"
+            )
+        );
+    }
+}
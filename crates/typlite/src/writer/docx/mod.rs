@@ -4,9 +4,11 @@
 //! - Writer: Functionality for rendering intermediate DocxNode structure to DOCX format
 //! - Styles: Document style management
 //! - Numbering: List numbering management
+//! - Math: Office Math (OMML) of the equations
 //! - Node structures: DocxNode and DocxInline representing document structure
 
 mod image_processor;
+mod math;
 mod numbering;
 mod styles;
 mod writer;
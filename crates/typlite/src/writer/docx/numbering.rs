@@ -38,6 +38,15 @@ impl DocxNumbering {
         )
     }
 
+    /// Starts the ids after the numbering of the reference document.
+    pub fn reserve_ids(&mut self, docx: &Docx) {
+        let abstract_ids = docx.numberings.abstract_nums.iter().map(|num| num.id);
+        let ids = docx.numberings.numberings.iter().map(|num| num.id);
+        if let Some(max_id) = abstract_ids.chain(ids).max() {
+            self.next_id = self.next_id.max(max_id + 1);
+        }
+    }
+
     /// Initialize the numbering manager
     pub fn initialize_numbering(&mut self, docx: Docx) -> Docx {
         if self.initialized {
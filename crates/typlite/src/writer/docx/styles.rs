@@ -87,21 +87,35 @@ impl DocxStyles {
             .name("Table")
             .table_align(TableAlignmentType::Center);
 
-        docx.add_style(heading1)
-            .add_style(heading2)
-            .add_style(heading3)
-            .add_style(heading4)
-            .add_style(heading5)
-            .add_style(heading6)
-            .add_style(code_block)
-            .add_style(code_inline)
-            .add_style(math_block)
-            .add_style(emphasis)
-            .add_style(strong)
-            .add_style(highlight)
-            .add_style(hyperlink)
-            .add_style(blockquote)
-            .add_style(caption)
-            .add_style(table)
+        let toc_heading = Self::create_heading_style("TOCHeading", "TOC Heading", 32);
+
+        let styles = [
+            heading1,
+            heading2,
+            heading3,
+            heading4,
+            heading5,
+            heading6,
+            code_block,
+            code_inline,
+            math_block,
+            emphasis,
+            strong,
+            highlight,
+            hyperlink,
+            blockquote,
+            caption,
+            table,
+            toc_heading,
+        ];
+
+        // The styles of the reference document take precedence.
+        styles.into_iter().fold(docx, |docx, style| {
+            if docx.styles.find_style_by_id(&style.style_id).is_some() {
+                docx
+            } else {
+                docx.add_style(style)
+            }
+        })
     }
 }
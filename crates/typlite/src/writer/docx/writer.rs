@@ -4,15 +4,21 @@ use base64::Engine;
 use cmark_writer::ast::{ListItem, Node};
 use docx_rs::*;
 use ecow::EcoString;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Cursor;
+use typst::introspection::Location;
 
 use crate::common::{
-    CenterNode, FigureNode, FormatWriter, HighlightNode, InlineNode, VerbatimNode,
+    AnchorNode, BibliographyNode, CenterNode, CitationNode, FigureNode, FootnoteDefinitionNode,
+    FootnoteNode, FormatWriter, HighlightNode, InlineNode, MathNode, OutlineNode, ParStyleNode,
+    RefNode, VerbatimNode,
 };
+use crate::math::to_omml;
 use crate::Result;
 
 use super::image_processor::DocxImageProcessor;
+use super::math::{embed_math, math_placeholder};
 use super::numbering::DocxNumbering;
 use super::styles::DocxStyles;
 
@@ -24,6 +30,17 @@ pub struct DocxWriter {
     list_level: usize,
     list_numbering_count: usize,
     image_processor: DocxImageProcessor,
+    /// The reference document whose styles, page setup, headers, footers and
+    /// numbering are reused.
+    reference: Option<Vec<u8>>,
+    /// The labels and the locations of the labelled elements.
+    labels: HashMap<EcoString, Location>,
+    /// The bookmark names of the labels whose elements are anchored.
+    bookmarks: HashMap<EcoString, String>,
+    /// The bookmarks to place at the paragraphs of the anchored elements.
+    anchor_bookmarks: HashMap<Location, Vec<(usize, String)>>,
+    /// The paragraph styles converted from the settings of `text` and `par`.
+    par_styles: Vec<ParStyleNode>,
 }
 
 impl Default for DocxWriter {
@@ -40,9 +57,29 @@ impl DocxWriter {
             list_level: 0,
             list_numbering_count: 0,
             image_processor: DocxImageProcessor::new(),
+            reference: None,
+            labels: HashMap::new(),
+            bookmarks: HashMap::new(),
+            anchor_bookmarks: HashMap::new(),
+            par_styles: Vec::new(),
         }
     }
 
+    /// Reuses the styles, page setup, headers, footers and numbering of the
+    /// reference document.
+    pub fn with_reference(mut self, reference: Vec<u8>) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    /// Sets the labels and the locations of the labelled elements. The labels
+    /// of the headings, the figures and the block equations are converted to
+    /// bookmarks referred by the cross-reference fields.
+    pub fn with_labels(mut self, labels: HashMap<EcoString, Location>) -> Self {
+        self.labels = labels;
+        self
+    }
+
     /// Process image node
     fn process_image(&self, docx: Docx, url: &str, alt_nodes: &[Node]) -> Result<Docx> {
         // Build alt text
@@ -76,6 +113,9 @@ impl DocxWriter {
         match &*figure_node.body {
             Node::Paragraph(content) => {
                 for node in content {
+                    if node.is_custom_type::<AnchorNode>() {
+                        continue;
+                    }
                     if let Node::Image {
                         url,
                         title: _,
@@ -150,9 +190,35 @@ impl DocxWriter {
             }
         }
 
+        // The caption, or the body without a caption, is the target of the
+        // labels.
+        if let (Node::Paragraph(content), Some(DocumentChild::Paragraph(para))) =
+            (&*figure_node.body, docx.document.children.last_mut())
+        {
+            self.add_bookmarks(para, content);
+        }
+
         Ok(docx)
     }
 
+    /// Wraps the paragraph with the bookmarks of the anchors in its content.
+    fn add_bookmarks(&mut self, para: &mut Paragraph, content: &[Node]) {
+        for node in content {
+            let Some(anchor) = node.as_custom_type::<AnchorNode>() else {
+                continue;
+            };
+            let bookmarks = self.anchor_bookmarks.remove(&anchor.location);
+            for (id, name) in bookmarks.into_iter().flatten() {
+                para.children.insert(
+                    0,
+                    ParagraphChild::BookmarkStart(BookmarkStart::new(id, name)),
+                );
+                para.children
+                    .push(ParagraphChild::BookmarkEnd(BookmarkEnd::new(id)));
+            }
+        }
+    }
+
     /// Process inline element and add to Run
     fn process_inline_to_run(&self, mut run: Run, node: &Node) -> Result<Run> {
         match node {
@@ -254,6 +320,50 @@ impl DocxWriter {
                 let node = node.as_custom_type::<VerbatimNode>().unwrap();
                 eprintln!("Warning: `m1verbatim` is ignored {:?}.", node.content);
             }
+            node if node.is_custom_type::<MathNode>() => {
+                let math_node = node.as_custom_type::<MathNode>().unwrap();
+                let omml = to_omml(&math_node.expr, math_node.block);
+                run = run.add_text(math_placeholder(&omml));
+            }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                let footnote_node = node.as_custom_type::<FootnoteNode>().unwrap();
+                let mut para = Paragraph::new();
+                for child in &footnote_node.content {
+                    let child_run = self.process_inline_to_run(Run::new(), child)?;
+                    if !child_run.children.is_empty() {
+                        para = para.add_run(child_run);
+                    }
+                }
+                run = run.add_footnote_reference(Footnote::new().add_content(para));
+            }
+            node if node.is_custom_type::<CitationNode>() => {
+                let citation_node = node.as_custom_type::<CitationNode>().unwrap();
+                for child in &citation_node.content {
+                    run = self.process_inline_to_run(run, child)?;
+                }
+            }
+            node if node.is_custom_type::<RefNode>() => {
+                let ref_node = node.as_custom_type::<RefNode>().unwrap();
+                let bookmark = self.bookmarks.get(&ref_node.target);
+                // The rendered reference is the result of the field until Word
+                // updates it.
+                if let Some(bookmark) = bookmark {
+                    run = run
+                        .add_field_char(FieldCharType::Begin, false)
+                        .add_instr_text(InstrText::Unsupported(format!(" REF {bookmark} \\h ")))
+                        .add_field_char(FieldCharType::Separate, false);
+                }
+                for child in &ref_node.content {
+                    run = self.process_inline_to_run(run, child)?;
+                }
+                if bookmark.is_some() {
+                    run = run.add_field_char(FieldCharType::End, false);
+                }
+            }
+            // The paragraph styles are applied by the paragraphs, and the
+            // anchors are bookmarked by them
+            node if node.is_custom_type::<ParStyleNode>() => {}
+            node if node.is_custom_type::<AnchorNode>() => {}
             // Other inline element types
             _ => {
                 eprintln!("other inline element: {:?}", node);
@@ -265,7 +375,7 @@ impl DocxWriter {
 
     /// Process paragraph and add to document
     fn process_paragraph(
-        &self,
+        &mut self,
         mut docx: Docx,
         content: &[Node],
         style: Option<&str>,
@@ -275,6 +385,8 @@ impl DocxWriter {
         // Apply style
         if let Some(style_name) = style {
             para = para.style(style_name);
+        } else if let Some(style_name) = self.par_style(content) {
+            para = para.style(&style_name);
         }
 
         // Extract all link nodes
@@ -348,6 +460,7 @@ impl DocxWriter {
 
         // Only add when paragraph has content
         if !para.children.is_empty() {
+            self.add_bookmarks(&mut para, content);
             docx = docx.add_paragraph(para);
         }
 
@@ -504,6 +617,25 @@ impl DocxWriter {
                     docx = docx.add_paragraph(para);
                 }
             }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                let bibliography_node = node.as_custom_type::<BibliographyNode>().unwrap();
+                for block in &bibliography_node.content {
+                    docx = self.process_node(docx, block)?;
+                }
+            }
+            node if node.is_custom_type::<OutlineNode>() => {
+                let outline_node = node.as_custom_type::<OutlineNode>().unwrap();
+                if !outline_node.title.is_empty() {
+                    docx = self.process_paragraph(docx, &outline_node.title, Some("TOCHeading"))?;
+                }
+                // The typst headings are one level deeper than the Word headings.
+                let toc = TableOfContents::new()
+                    .heading_styles_range(1, outline_node.depth + 1)
+                    .alias("Table of contents");
+                docx = docx.add_table_of_contents(toc);
+            }
+            // Footnotes are placed by Word at the references
+            node if node.is_custom_type::<FootnoteDefinitionNode>() => {}
             Node::ThematicBreak => {
                 // Add horizontal line as specially formatted paragraph
                 let hr_para = Paragraph::new()
@@ -584,6 +716,9 @@ impl DocxWriter {
                 Node::Paragraph(inline) => {
                     let mut para = Paragraph::new()
                         .numbering(NumberingId::new(num_id), IndentLevel::new(level));
+                    if let Some(style_name) = self.par_style(inline) {
+                        para = para.style(&style_name);
+                    }
 
                     // Process paragraph content
                     for node in inline {
@@ -673,20 +808,115 @@ impl DocxWriter {
         Ok(docx)
     }
 
+    /// Gets the id of the paragraph style of the settings in the paragraph,
+    /// which is added to the document at the end.
+    fn par_style(&mut self, content: &[Node]) -> Option<String> {
+        let style = content
+            .iter()
+            .find_map(|node| node.as_custom_type::<ParStyleNode>())?;
+        let idx = match self.par_styles.iter().position(|s| s == style) {
+            Some(idx) => idx,
+            None => {
+                self.par_styles.push(style.clone());
+                self.par_styles.len() - 1
+            }
+        };
+        Some(format!("TypstParagraph{}", idx + 1))
+    }
+
+    /// Converts the settings of `text` and `par` to paragraph styles.
+    fn add_par_styles(&self, mut docx: Docx) -> Docx {
+        for (idx, style) in self.par_styles.iter().enumerate() {
+            let mut par_style =
+                Style::new(format!("TypstParagraph{}", idx + 1), StyleType::Paragraph)
+                    .name(format!("Typst Paragraph {}", idx + 1))
+                    .based_on("Normal");
+            if let Some(font) = &style.font {
+                let fonts = RunFonts::new()
+                    .ascii(font.as_str())
+                    .hi_ansi(font.as_str())
+                    .east_asia(font.as_str())
+                    .cs(font.as_str());
+                par_style = par_style.fonts(fonts);
+            }
+            // The sizes are in half-points and the lengths are in twips.
+            if let Some(size) = style.size {
+                par_style = par_style.size((size * 2.0).round() as usize);
+            }
+            if style.justify {
+                par_style = par_style.align(AlignmentType::Both);
+            }
+            if let Some(leading) = style.leading {
+                let line = (style.size.unwrap_or(11.0) + leading) * 20.0;
+                par_style.paragraph_property = par_style.paragraph_property.line_spacing(
+                    LineSpacing::new()
+                        .line_rule(LineSpacingType::AtLeast)
+                        .line(line.round() as i32),
+                );
+            }
+            if let Some(indent) = style.indent {
+                let indent = SpecialIndentType::FirstLine((indent * 20.0).round() as i32);
+                par_style = par_style.indent(None, Some(indent), None, None);
+            }
+            docx = docx.add_style(par_style);
+        }
+        docx
+    }
+
+    /// Assigns the bookmarks to the labels of the anchored elements in the
+    /// document. The references to other labels are kept as text.
+    fn prepare_bookmarks(&mut self, doc: &Node) {
+        let mut anchors = HashSet::new();
+        collect_anchors(doc, &mut anchors);
+
+        let mut labels: Vec<_> = self
+            .labels
+            .iter()
+            .filter(|(_, location)| anchors.contains(*location))
+            .collect();
+        labels.sort_by_key(|(label, _)| *label);
+
+        let mut names = HashSet::new();
+        for (id, (label, location)) in labels.into_iter().enumerate() {
+            let name = bookmark_name(label, &mut names);
+            self.bookmarks.insert(label.clone(), name.clone());
+            self.anchor_bookmarks
+                .entry(*location)
+                .or_default()
+                .push((id, name));
+        }
+    }
+
+    /// Reads the reference document, whose content is replaced.
+    fn from_reference(reference: &[u8]) -> Result<Docx> {
+        let mut docx = read_docx(reference)
+            .map_err(|e| format!("Failed to read the reference document: {}", e))?;
+        docx.document.children.clear();
+        Ok(docx)
+    }
+
     /// Generate DOCX document
     pub fn generate_docx(&mut self, doc: &Node) -> Result<Vec<u8>> {
         // Create DOCX document and initialize styles
-        let mut docx = Docx::new();
+        let mut docx = match &self.reference {
+            Some(reference) => Self::from_reference(reference)?,
+            None => Docx::new(),
+        };
         docx = self.styles.initialize_styles(docx);
+        self.numbering.reserve_ids(&docx);
+        self.prepare_bookmarks(doc);
 
         // Process document content
         docx = self.process_node(docx, doc)?;
+        docx = self.add_par_styles(docx);
 
         // Initialize numbering definitions
         docx = self.numbering.initialize_numbering(docx);
 
         // Build and pack document
-        let docx_built = docx.build();
+        let mut docx_built = docx.build();
+        let document = String::from_utf8_lossy(&docx_built.document);
+        docx_built.document = embed_math(&document).into_bytes();
         let mut buffer = Vec::new();
         docx_built
             .pack(&mut Cursor::new(&mut buffer))
@@ -696,10 +926,64 @@ impl DocxWriter {
     }
 }
 
+/// Collects the locations of the anchors in the headings, the figures and the
+/// paragraphs of the block equations.
+fn collect_anchors(node: &Node, anchors: &mut HashSet<Location>) {
+    match node {
+        Node::Document(content) | Node::BlockQuote(content) => {
+            for node in content {
+                collect_anchors(node, anchors);
+            }
+        }
+        Node::HtmlElement(element) => {
+            for node in &element.children {
+                collect_anchors(node, anchors);
+            }
+        }
+        Node::Paragraph(content) | Node::Heading { content, .. } => {
+            anchors.extend(
+                content
+                    .iter()
+                    .filter_map(|node| node.as_custom_type::<AnchorNode>())
+                    .map(|anchor| anchor.location),
+            );
+        }
+        node if node.is_custom_type::<CenterNode>() => {
+            let center_node = node.as_custom_type::<CenterNode>().unwrap();
+            collect_anchors(&center_node.node, anchors);
+        }
+        node if node.is_custom_type::<FigureNode>() => {
+            let figure_node = node.as_custom_type::<FigureNode>().unwrap();
+            collect_anchors(&figure_node.body, anchors);
+        }
+        _ => {}
+    }
+}
+
+/// Makes a valid and unique bookmark name of the label, which starts with a
+/// letter and has at most 40 characters.
+fn bookmark_name(label: &str, names: &mut HashSet<String>) -> String {
+    let base: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .take(32)
+        .collect();
+    let mut name = format!("ref_{base}");
+    let mut count = 1;
+    while !names.insert(name.clone()) {
+        name = format!("ref_{base}_{count}");
+        count += 1;
+    }
+    name
+}
+
 impl FormatWriter for DocxWriter {
     fn write_vec(&mut self, document: &Node) -> Result<Vec<u8>> {
         self.list_level = 0;
         self.list_numbering_count = 0;
+        self.bookmarks.clear();
+        self.anchor_bookmarks.clear();
+        self.par_styles.clear();
         self.generate_docx(document)
     }
 
@@ -707,3 +991,79 @@ impl FormatWriter for DocxWriter {
         Err("DOCX format does not support EcoString output".into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchor(hash: u128) -> Node {
+        Node::Custom(Box::new(AnchorNode {
+            location: Location::new(hash),
+        }))
+    }
+
+    #[test]
+    fn test_prepare_bookmarks() {
+        let figure = FigureNode {
+            body: Box::new(Node::Paragraph(vec![anchor(2), Node::Text("body".into())])),
+            caption: "A figure".into(),
+            label: None,
+        };
+        let doc = Node::Document(vec![
+            Node::heading(2, vec![anchor(1), Node::Text("Intro".into())]),
+            Node::Custom(Box::new(CenterNode::new(vec![Node::Custom(Box::new(
+                figure,
+            ))]))),
+            Node::Paragraph(vec![anchor(3), Node::Text("x".into())]),
+            Node::Paragraph(vec![Node::Text("text".into())]),
+        ]);
+
+        let labels = [("intro", 1), ("fig:a", 2), ("eq:b", 3), ("para", 4)]
+            .into_iter()
+            .map(|(label, hash)| (label.into(), Location::new(hash)))
+            .collect();
+        let mut writer = DocxWriter::new().with_labels(labels);
+        writer.prepare_bookmarks(&doc);
+
+        let mut bookmarks: Vec<_> = writer.bookmarks.keys().map(|k| k.as_str()).collect();
+        bookmarks.sort();
+        // The label of the paragraph is not anchored, so it is kept as text.
+        assert_eq!(bookmarks, ["eq:b", "fig:a", "intro"]);
+        assert_eq!(writer.bookmarks["fig:a"], "ref_fig_a");
+        assert_eq!(writer.anchor_bookmarks.len(), 3);
+    }
+
+    #[test]
+    fn test_reference_styles() {
+        let heading = Style::new("Heading1", StyleType::Paragraph)
+            .name("Heading 1")
+            .color("FF0000");
+        let mut reference = Vec::new();
+        Docx::new()
+            .add_style(heading)
+            .build()
+            .pack(&mut Cursor::new(&mut reference))
+            .unwrap();
+
+        let doc = Node::Document(vec![Node::heading(1, vec![Node::Text("Title".into())])]);
+        let output = DocxWriter::new()
+            .with_reference(reference)
+            .write_vec(&doc)
+            .unwrap();
+        let docx = read_docx(&output).unwrap();
+
+        // The style of the reference document wins over the built-in one.
+        let heading = docx.styles.find_style_by_id("Heading1").unwrap();
+        assert_eq!(heading.run_property.color, Some(Color::new("FF0000")));
+        let styles = serde_json::to_value(&docx.styles).unwrap();
+        let headings = styles["styles"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|style| style["styleId"] == "Heading1")
+            .count();
+        assert_eq!(headings, 1);
+        // The styles missing in the reference document are still added.
+        assert!(docx.styles.find_style_by_id("CodeBlock").is_some());
+    }
+}
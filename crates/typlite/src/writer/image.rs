@@ -0,0 +1,76 @@
+//! Image conversion shared by the writers of office documents
+
+use std::io::Cursor;
+
+use crate::Result;
+
+/// The resolution of the PNG images converted from SVG.
+pub const SVG_DPI: f32 = 300.0;
+
+/// Convert SVG data to PNG format
+pub fn svg_to_png(svg_data: &[u8]) -> Result<Vec<u8>> {
+    // Check if data is valid SVG
+    let svg_str = match std::str::from_utf8(svg_data) {
+        Ok(s) => s,
+        Err(_) => return Err("Unable to parse input data as UTF-8 string".into()),
+    };
+
+    let dpi = SVG_DPI;
+    let scale_factor = dpi / 96.0;
+
+    let opt = resvg::usvg::Options {
+        dpi,
+        ..resvg::usvg::Options::default()
+    };
+
+    // Parse SVG
+    let rtree = match resvg::usvg::Tree::from_str(svg_str, &opt) {
+        Ok(tree) => tree,
+        Err(e) => return Err(format!("SVG parsing error: {:?}", e).into()),
+    };
+
+    let size = rtree.size().to_int_size();
+    let width = (size.width() as f32 * scale_factor) as u32;
+    let height = (size.height() as f32 * scale_factor) as u32;
+
+    // Create pixel buffer
+    let mut pixmap = match resvg::tiny_skia::Pixmap::new(width, height) {
+        Some(pixmap) => pixmap,
+        None => return Err("Unable to create pixel buffer".into()),
+    };
+
+    // Render SVG to pixel buffer
+    resvg::render(
+        &rtree,
+        resvg::tiny_skia::Transform::from_scale(scale_factor, scale_factor),
+        &mut pixmap.as_mut(),
+    );
+
+    // Encode as PNG
+    pixmap
+        .encode_png()
+        .map_err(|e| format!("PNG encoding error: {:?}", e).into())
+}
+
+/// Read the dimensions of the image in pixels
+#[cfg(feature = "odt")]
+pub fn dimensions(data: &[u8]) -> Result<(u32, u32)> {
+    image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()?
+        .into_dimensions()
+        .map_err(|_| "Unable to load image".into())
+}
+
+/// Convert image data to PNG format, returning the PNG data and the
+/// dimensions in pixels
+#[cfg(feature = "odt")]
+pub fn to_png(data: &[u8]) -> Result<(Vec<u8>, (u32, u32))> {
+    image::guess_format(data).map_err(|_| "Unknown image format")?;
+    let img = image::load_from_memory(data).map_err(|_| "Unable to load image")?;
+    let dims = image::GenericImageView::dimensions(&img);
+
+    let mut buffer = Vec::new();
+    img.write_to(&mut Cursor::new(&mut buffer), image::ImageFormat::Png)
+        .map_err(|_| "Unable to convert to supported format")?;
+    Ok((buffer, dims))
+}
@@ -1,20 +1,42 @@
 //! LaTeX writer implementation
 
+use std::collections::HashSet;
+use std::ops::Range;
 use std::path::Path;
 
-use cmark_writer::ast::Node;
+use cmark_writer::ast::{ListItem, Node};
 use ecow::EcoString;
 use tinymist_std::path::unix_slash;
+use typst::model::DocumentInfo;
 
 use crate::common::{
-    CenterNode, ExternalFrameNode, FigureNode, FormatWriter, HighlightNode, InlineNode, ListState,
-    VerbatimNode,
+    BibliographyNode, CenterNode, CitationNode, ExternalFrameNode, FigureNode,
+    FootnoteDefinitionNode, FootnoteNode, FormatWriter, HighlightNode, InlineNode, LabelNode,
+    ListState, MathNode, RefNode, VerbatimNode,
 };
+use crate::math::to_latex;
+use crate::site::plain_text;
 use crate::Result;
 
 /// LaTeX writer implementation
 pub struct LaTeXWriter {
     list_state: Option<ListState>,
+    /// The labels written in the document, which can be referenced by
+    /// `\ref`.
+    labels: HashSet<EcoString>,
+    /// The labels of the equations, which are referenced by `\eqref`.
+    equations: HashSet<EcoString>,
+    /// The template wrapping the body, with the title and the authors of the
+    /// document.
+    template: Option<Template>,
+}
+
+/// A LaTeX template, whose `{{title}}`, `{{authors}}` and `{{body}}`
+/// placeholders are filled by the document.
+struct Template {
+    source: String,
+    title: String,
+    authors: String,
 }
 
 impl Default for LaTeXWriter {
@@ -25,7 +47,88 @@ impl Default for LaTeXWriter {
 
 impl LaTeXWriter {
     pub fn new() -> Self {
-        Self { list_state: None }
+        Self {
+            list_state: None,
+            labels: HashSet::new(),
+            equations: HashSet::new(),
+            template: None,
+        }
+    }
+
+    /// Wraps the body in a template, filled with the title and the authors
+    /// of the document.
+    pub fn with_template(mut self, source: String, info: &DocumentInfo) -> Self {
+        let title = info.title.as_deref().map(escape_latex).unwrap_or_default();
+        let authors = info
+            .author
+            .iter()
+            .map(|author| escape_latex(author))
+            .collect::<Vec<_>>()
+            .join(" \\and ");
+        self.template = Some(Template {
+            source,
+            title,
+            authors,
+        });
+        self
+    }
+
+    /// Collects the labels written in the document before writing it, since
+    /// the references may precede their targets.
+    fn collect_labels(&mut self, node: &Node) {
+        match node {
+            Node::Document(content)
+            | Node::Paragraph(content)
+            | Node::BlockQuote(content)
+            | Node::Emphasis(content)
+            | Node::Strong(content)
+            | Node::Heading { content, .. } => {
+                content.iter().for_each(|node| self.collect_labels(node))
+            }
+            Node::OrderedList { items, .. } | Node::UnorderedList(items) => {
+                for item in items {
+                    if let ListItem::Ordered { content, .. } | ListItem::Unordered { content } =
+                        item
+                    {
+                        content.iter().for_each(|node| self.collect_labels(node));
+                    }
+                }
+            }
+            Node::HtmlElement(element) => element
+                .children
+                .iter()
+                .for_each(|node| self.collect_labels(node)),
+            Node::Table { headers, rows, .. } => {
+                headers.iter().for_each(|node| self.collect_labels(node));
+                rows.iter()
+                    .flatten()
+                    .for_each(|node| self.collect_labels(node));
+            }
+            node if node.is_custom_type::<LabelNode>() => {
+                let label = node.as_custom_type::<LabelNode>().unwrap();
+                self.labels.insert(label.name.clone());
+            }
+            node if node.is_custom_type::<FigureNode>() => {
+                let figure = node.as_custom_type::<FigureNode>().unwrap();
+                // The label is written after the caption
+                if let Some(label) = figure.label.clone().filter(|_| !figure.caption.is_empty()) {
+                    self.labels.insert(label);
+                }
+                self.collect_labels(&figure.body);
+            }
+            node if node.is_custom_type::<CenterNode>() => {
+                let center = node.as_custom_type::<CenterNode>().unwrap();
+                self.collect_labels(&center.node);
+            }
+            node if node.is_custom_type::<MathNode>() => {
+                let math = node.as_custom_type::<MathNode>().unwrap();
+                if let Some(label) = math.label.clone().filter(|_| math.block) {
+                    self.labels.insert(label.clone());
+                    self.equations.insert(label);
+                }
+            }
+            _ => {}
+        }
     }
 
     fn write_inline_nodes(&mut self, nodes: &[Node], output: &mut EcoString) -> Result<()> {
@@ -108,8 +211,7 @@ impl LaTeXWriter {
                 output.push_str("\\begin{enumerate}\n");
                 for item in items {
                     match item {
-                        cmark_writer::ast::ListItem::Ordered { content, .. }
-                        | cmark_writer::ast::ListItem::Unordered { content } => {
+                        ListItem::Ordered { content, .. } | ListItem::Unordered { content } => {
                             output.push_str("\\item ");
                             for block in content {
                                 match block {
@@ -137,8 +239,7 @@ impl LaTeXWriter {
                 output.push_str("\\begin{itemize}\n");
                 for item in items {
                     match item {
-                        cmark_writer::ast::ListItem::Ordered { content, .. }
-                        | cmark_writer::ast::ListItem::Unordered { content } => {
+                        ListItem::Ordered { content, .. } | ListItem::Unordered { content } => {
                             output.push_str("\\item ");
                             for block in content {
                                 match block {
@@ -244,6 +345,11 @@ impl LaTeXWriter {
                     output.push_str("\\caption{");
                     output.push_str(&escape_latex(&figure_node.caption));
                     output.push_str("}\n");
+                    if let Some(label) = &figure_node.label {
+                        output.push_str("\\label{");
+                        output.push_str(label);
+                        output.push_str("}\n");
+                    }
                 }
 
                 // Close figure environment
@@ -293,6 +399,90 @@ impl LaTeXWriter {
                 let inline_node = node.as_custom_type::<VerbatimNode>().unwrap();
                 output.push_str(&inline_node.content);
             }
+            node if node.is_custom_type::<MathNode>() => {
+                let math_node = node.as_custom_type::<MathNode>().unwrap();
+                let latex = to_latex(&math_node.expr, math_node.block);
+                if let Some(label) = math_node.label.as_ref().filter(|_| math_node.block) {
+                    output.push_str("\\begin{equation}\n");
+                    output.push_str(&latex);
+                    output.push_str("\n\\label{");
+                    output.push_str(label);
+                    output.push_str("}\n\\end{equation}");
+                } else if math_node.block {
+                    output.push_str("\\[\n");
+                    output.push_str(&latex);
+                    output.push_str("\n\\]");
+                } else {
+                    output.push('$');
+                    output.push_str(&latex);
+                    output.push('$');
+                }
+            }
+            node if node.is_custom_type::<LabelNode>() => {
+                let label = node.as_custom_type::<LabelNode>().unwrap();
+                output.push_str("\\label{");
+                output.push_str(&label.name);
+                output.push('}');
+            }
+            node if node.is_custom_type::<RefNode>() => {
+                let reference = node.as_custom_type::<RefNode>().unwrap();
+                let target = &reference.target;
+                let text = plain_text(&reference.content);
+                if !self.labels.contains(target) {
+                    // The references to the citations and to the elements
+                    // without LaTeX labels are kept as rendered by typst
+                    self.write_inline_nodes(&reference.content, output)?;
+                } else if let Some(supplement) = ref_supplement(&text) {
+                    output.push_str(&escape_latex(supplement));
+                    if !supplement.is_empty() {
+                        output.push('~');
+                    }
+                    if self.equations.contains(target) {
+                        output.push_str("\\eqref{");
+                    } else {
+                        output.push_str("\\ref{");
+                    }
+                    output.push_str(target);
+                    output.push('}');
+                } else {
+                    // The references with custom contents are kept as links
+                    output.push_str("\\hyperref[");
+                    output.push_str(target);
+                    output.push_str("]{");
+                    self.write_inline_nodes(&reference.content, output)?;
+                    output.push('}');
+                }
+            }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                let footnote = node.as_custom_type::<FootnoteNode>().unwrap();
+                output.push_str("\\footnote{");
+                self.write_inline_nodes(&footnote.content, output)?;
+                output.push('}');
+            }
+            // Footnotes are placed by LaTeX
+            node if node.is_custom_type::<FootnoteDefinitionNode>() => {}
+            node if node.is_custom_type::<CitationNode>() => {
+                let citation = node.as_custom_type::<CitationNode>().unwrap();
+                output.push_str("\\cite{");
+                output.push_str(&citation.key);
+                output.push('}');
+            }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                let bibliography = node.as_custom_type::<BibliographyNode>().unwrap();
+                // The references from YAML sources are kept as rendered by typlite
+                if bibliography.bib_names.is_empty() {
+                    for block in &bibliography.content {
+                        self.write_node(block, output)?;
+                    }
+                } else {
+                    output.push_str("\\bibliographystyle{");
+                    output.push_str(&bibliography.bibtex_style);
+                    output.push_str("}\n");
+                    output.push_str("\\bibliography{");
+                    output.push_str(&bibliography.bib_names.join(","));
+                    output.push_str("}\n\n");
+                }
+            }
             Node::Text(text) => {
                 output.push_str(&escape_latex(text));
             }
@@ -375,22 +565,84 @@ impl LaTeXWriter {
 
 /// Escape LaTeX special characters in a string
 fn escape_latex(text: &str) -> String {
-    text.replace('&', "\\&")
-        .replace('%', "\\%")
-        .replace('$', "\\$")
-        .replace('#', "\\#")
-        .replace('_', "\\_")
-        .replace('{', "\\{")
-        .replace('}', "\\}")
-        .replace('~', "\\textasciitilde{}")
-        .replace('^', "\\textasciicircum{}")
-        .replace('\\', "\\textbackslash{}")
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '\\' => out.push_str("\\textbackslash{}"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Splits the supplement from a reference rendered by typst, e.g. `Section`
+/// from `Section 2`, which is followed by the number from `\ref` in LaTeX.
+/// Returns `None` if the reference doesn't end with a number.
+fn ref_supplement(text: &str) -> Option<&str> {
+    let text = text.trim();
+    let (supplement, number) = match text.rfind(char::is_whitespace) {
+        Some(idx) => (&text[..idx], &text[idx..]),
+        None => ("", text),
+    };
+    number
+        .contains(|c: char| c.is_ascii_digit())
+        .then(|| supplement.trim_end())
+}
+
+/// Fills the placeholders of a template in a single pass, so that the
+/// placeholders in the document are kept. The unknown placeholders are kept
+/// as well.
+fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = values.iter().find_map(|(key, value)| {
+            let tail = rest.strip_prefix("{{")?.strip_prefix(key)?;
+            Some((tail.strip_prefix("}}")?, value))
+        });
+        match value {
+            Some((tail, value)) => {
+                out.push_str(value);
+                rest = tail;
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
 }
 
 impl FormatWriter for LaTeXWriter {
     fn write_eco(&mut self, document: &Node, output: &mut EcoString) -> Result<()> {
-        // Write the document content
-        self.write_node(document, output)?;
+        self.collect_labels(document);
+
+        let Some(template) = self.template.take() else {
+            // Write the document content
+            return self.write_node(document, output);
+        };
+
+        let mut body = EcoString::new();
+        self.write_node(document, &mut body)?;
+        output.push_str(&fill_template(
+            &template.source,
+            &[
+                ("title", &template.title),
+                ("authors", &template.authors),
+                ("body", body.trim_end()),
+            ],
+        ));
+        self.template = Some(template);
         Ok(())
     }
 
@@ -399,4 +651,83 @@ impl FormatWriter for LaTeXWriter {
         self.write_eco(document, &mut output)?;
         Ok(output.as_str().as_bytes().to_vec())
     }
+
+    fn write_blocks(
+        &mut self,
+        document: &Node,
+        output: &mut EcoString,
+    ) -> Result<Vec<Range<usize>>> {
+        let Node::Document(blocks) = document else {
+            let start = output.len();
+            self.write_eco(document, output)?;
+            let end = output.len();
+            return Ok(vec![Range { start, end }]);
+        };
+        // The references may precede their targets in other blocks
+        self.collect_labels(document);
+
+        let mut body = EcoString::new();
+        let mut ranges = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let start = body.len();
+            self.write_node(block, &mut body)?;
+            ranges.push(start..body.len());
+        }
+
+        let Some(template) = &self.template else {
+            output.push_str(&body);
+            return Ok(ranges);
+        };
+        // The blocks are shifted by the part of the template before the body
+        let (before, after) = template
+            .source
+            .split_once("{{body}}")
+            .unwrap_or((&template.source, ""));
+        let values = [
+            ("title", template.title.as_str()),
+            ("authors", template.authors.as_str()),
+        ];
+        output.push_str(&fill_template(before, &values));
+        let offset = output.len();
+        let body = body.trim_end();
+        output.push_str(body);
+        output.push_str(&fill_template(after, &values));
+        Ok(ranges
+            .into_iter()
+            .map(|range| range.start.min(body.len()) + offset..range.end.min(body.len()) + offset)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_template() {
+        let template = "\\title{{{title}}}\n\\author{{{authors}}}\n{{body}}\n{{date}}";
+        let filled = fill_template(
+            template,
+            &[
+                ("title", "A \\& B"),
+                ("authors", "X"),
+                ("body", "{{title}}"),
+            ],
+        );
+        assert_eq!(filled, "\\title{A \\& B}\n\\author{X}\n{{title}}\n{{date}}");
+    }
+
+    #[test]
+    fn test_ref_supplement() {
+        assert_eq!(ref_supplement("Section 2"), Some("Section"));
+        assert_eq!(ref_supplement("Equation\u{a0}(1)"), Some("Equation"));
+        assert_eq!(ref_supplement("2.1"), Some(""));
+        assert_eq!(ref_supplement("the methods"), None);
+    }
+
+    #[test]
+    fn test_escape_latex() {
+        assert_eq!(escape_latex("a_b & 50%"), "a\\_b \\& 50\\%");
+        assert_eq!(escape_latex("\\{"), "\\textbackslash{}\\{");
+    }
 }
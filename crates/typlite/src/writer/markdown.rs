@@ -1,5 +1,7 @@
 //! Markdown writer implementation
 
+use std::ops::Range;
+
 use cmark_writer::ast::Node;
 use cmark_writer::writer::CommonMarkWriter;
 use cmark_writer::WriterOptions;
@@ -16,16 +18,20 @@ impl MarkdownWriter {
     pub fn new() -> Self {
         Self {}
     }
-}
 
-impl FormatWriter for MarkdownWriter {
-    fn write_eco(&mut self, document: &Node, output: &mut EcoString) -> Result<()> {
-        let mut writer = CommonMarkWriter::with_options(WriterOptions {
+    fn writer() -> CommonMarkWriter {
+        CommonMarkWriter::with_options(WriterOptions {
             strict: false,
             escape_special_chars: true,
             trim_paragraph_trailing_hard_breaks: true,
             ..Default::default()
-        });
+        })
+    }
+}
+
+impl FormatWriter for MarkdownWriter {
+    fn write_eco(&mut self, document: &Node, output: &mut EcoString) -> Result<()> {
+        let mut writer = Self::writer();
         writer
             .write(document)
             .map_err(|e| format!("failed to write document: {}", e))?;
@@ -36,4 +42,33 @@ impl FormatWriter for MarkdownWriter {
     fn write_vec(&mut self, _document: &Node) -> Result<Vec<u8>> {
         Err("Markdown writer does not support writing to Vec<u8>".into())
     }
+
+    fn write_blocks(
+        &mut self,
+        document: &Node,
+        output: &mut EcoString,
+    ) -> Result<Vec<Range<usize>>> {
+        let Node::Document(blocks) = document else {
+            let start = output.len();
+            self.write_eco(document, output)?;
+            let end = output.len();
+            return Ok(vec![Range { start, end }]);
+        };
+        let mut ranges = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            // The blocks are separated by blank lines
+            if !output.is_empty() {
+                output.push('\n');
+            }
+            let mut writer = Self::writer();
+            writer
+                .write(block)
+                .map_err(|e| format!("failed to write document: {}", e))?;
+            let start = output.len();
+            output.push_str(writer.into_string().trim_end_matches('\n'));
+            ranges.push(start..output.len());
+            output.push('\n');
+        }
+        Ok(ranges)
+    }
 }
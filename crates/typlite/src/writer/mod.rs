@@ -2,14 +2,22 @@
 
 #[cfg(feature = "docx")]
 pub mod docx;
+#[cfg(any(feature = "docx", feature = "odt"))]
+pub(crate) mod image;
 pub mod latex;
 pub mod markdown;
+#[cfg(feature = "odt")]
+pub mod odt;
+pub mod pandoc;
 pub mod text;
 
 #[cfg(feature = "docx")]
 pub use self::docx::DocxWriter;
 pub use latex::LaTeXWriter;
 pub use markdown::MarkdownWriter;
+#[cfg(feature = "odt")]
+pub use odt::OdtWriter;
+pub use pandoc::PandocWriter;
 pub use text::TextWriter;
 
 use crate::common::{Format, FormatWriter};
@@ -20,8 +28,11 @@ pub fn create_writer(format: Format) -> Box<dyn FormatWriter> {
         Format::Md => Box::new(markdown::MarkdownWriter::new()),
         Format::LaTeX => Box::new(latex::LaTeXWriter::new()),
         Format::Text => Box::new(text::TextWriter::new()),
+        Format::PandocJson => Box::new(pandoc::PandocWriter::new()),
         #[cfg(feature = "docx")]
         Format::Docx => Box::new(docx::DocxWriter::new()),
+        #[cfg(feature = "odt")]
+        Format::Odt => Box::new(odt::OdtWriter::new()),
     }
 }
 
@@ -0,0 +1,815 @@
+//! ODT writer implementation - produces OpenDocument text packages read by
+//! LibreOffice and other office suites
+
+use std::fs;
+use std::io::{Cursor, Write};
+
+use base64::Engine;
+use cmark_writer::ast::{HtmlElement, ListItem, Node};
+use ecow::EcoString;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::common::{
+    AlertNode, BibliographyNode, CenterNode, CitationNode, ExternalFrameNode, FigureNode,
+    FootnoteNode, FormatWriter, HighlightNode, InlineNode, MathNode, RefNode, VerbatimNode,
+};
+use crate::math::{escape_xml, to_latex};
+use crate::site::plain_text;
+use crate::writer::image::{dimensions, svg_to_png, to_png, SVG_DPI};
+use crate::Result;
+
+/// The media type of OpenDocument text, which is stored as the first file of
+/// the package.
+const MIMETYPE: &str = "application/vnd.oasis.opendocument.text";
+
+/// The namespaces used by `content.xml` and `styles.xml`.
+const NAMESPACES: &str = concat!(
+    r#"xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" "#,
+    r#"xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" "#,
+    r#"xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" "#,
+    r#"xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" "#,
+    r#"xmlns:draw="urn:oasis:names:tc:opendocument:xmlns:drawing:1.0" "#,
+    r#"xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" "#,
+    r#"xmlns:svg="urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0" "#,
+    r#"xmlns:xlink="http://www.w3.org/1999/xlink" "#,
+    r#"office:version="1.2""#,
+);
+
+/// The automatic styles of the content, i.e. the centered paragraphs, the
+/// struck-out and highlighted text, and the tables.
+const AUTOMATIC_STYLES: &str = concat!(
+    r#"<style:style style:name="P1" style:family="paragraph" style:parent-style-name="Text_20_body">"#,
+    r#"<style:paragraph-properties fo:text-align="center"/></style:style>"#,
+    r#"<style:style style:name="T1" style:family="text">"#,
+    r#"<style:text-properties style:text-line-through-style="solid"/></style:style>"#,
+    r#"<style:style style:name="T2" style:family="text">"#,
+    r##"<style:text-properties fo:background-color="#ffff00"/></style:style>"##,
+    r#"<style:style style:name="Table" style:family="table">"#,
+    r#"<style:table-properties style:width="17cm" table:align="margins"/></style:style>"#,
+    r#"<style:style style:name="TableCell" style:family="table-cell">"#,
+    r#"<style:table-cell-properties fo:padding="0.1cm" fo:border="0.5pt solid #000000"/>"#,
+    r#"</style:style>"#,
+);
+
+const CENTER_STYLE: &str = "P1";
+const STRIKE_STYLE: &str = "T1";
+const HIGHLIGHT_STYLE: &str = "T2";
+
+/// The widest width of the pictures in inches, i.e. about the width of the
+/// text area of an A4 page with 2cm margins.
+const MAX_PICTURE_WIDTH: f32 = 6.5;
+
+/// ODT writer that generates an OpenDocument text package directly from AST
+#[derive(Default)]
+pub struct OdtWriter {
+    /// The pictures embedded in the package, with their paths in the package.
+    pictures: Vec<(String, Vec<u8>)>,
+    /// The number of footnotes written.
+    notes: usize,
+    /// The number of captioned figures written.
+    figures: usize,
+    /// The number of tables written.
+    tables: usize,
+}
+
+impl OdtWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the nodes as blocks. Consecutive inline nodes are gathered into
+    /// a paragraph with the style.
+    fn blocks(&mut self, nodes: &[Node], style: &str, out: &mut String) -> Result<()> {
+        let mut start = 0;
+        for (idx, node) in nodes.iter().enumerate() {
+            if is_block(node) {
+                self.paragraph(&nodes[start..idx], style, out)?;
+                self.block(node, style, out)?;
+                start = idx + 1;
+            }
+        }
+        self.paragraph(&nodes[start..], style, out)
+    }
+
+    fn block(&mut self, node: &Node, style: &str, out: &mut String) -> Result<()> {
+        match node {
+            Node::Document(children) => self.blocks(children, style, out)?,
+            Node::Paragraph(content) => self.paragraph(content, style, out)?,
+            Node::Heading {
+                level,
+                content,
+                heading_type: _,
+            } => {
+                let level = (*level).clamp(1, 6);
+                out.push_str(&format!(
+                    r#"<text:h text:style-name="Heading_20_{level}" text:outline-level="{level}">"#
+                ));
+                self.inlines(content, out)?;
+                out.push_str("</text:h>");
+            }
+            Node::BlockQuote(content) => self.blocks(content, "Quotations", out)?,
+            Node::CodeBlock {
+                language: _,
+                content,
+                block_type: _,
+            } => {
+                out.push_str(r#"<text:p text:style-name="Preformatted_20_Text">"#);
+                for (idx, line) in content.trim_end_matches('\n').split('\n').enumerate() {
+                    if idx > 0 {
+                        out.push_str("<text:line-break/>");
+                    }
+                    preserve_space(line, out);
+                }
+                out.push_str("</text:p>");
+            }
+            Node::OrderedList { start, items } => {
+                self.list(items, "Numbering_20_123", Some(*start), out)?;
+            }
+            Node::UnorderedList(items) => self.list(items, "List_20_Bullet", None, out)?,
+            Node::Table {
+                headers,
+                rows,
+                alignments: _,
+            } => self.table(headers, rows, out)?,
+            Node::ThematicBreak => {
+                out.push_str(r#"<text:p text:style-name="Horizontal_20_Line"/>"#);
+            }
+            Node::HtmlElement(element) => self.blocks(&element.children, style, out)?,
+            node if node.is_custom_type::<FigureNode>() => {
+                let figure = node.as_custom_type::<FigureNode>().unwrap();
+                self.figure(figure, out)?;
+            }
+            node if node.is_custom_type::<ExternalFrameNode>() => {
+                let frame = node.as_custom_type::<ExternalFrameNode>().unwrap();
+                let data = base64::engine::general_purpose::STANDARD
+                    .decode(&frame.svg)
+                    .map_err(|e| format!("Failed to decode SVG data: {}", e))?;
+
+                out.push_str(&format!(r#"<text:p text:style-name="{style}">"#));
+                self.picture(&data, true, &frame.alt_text, out);
+                out.push_str("</text:p>");
+            }
+            node if node.is_custom_type::<CenterNode>() => {
+                let center = node.as_custom_type::<CenterNode>().unwrap();
+                self.block(&center.node, CENTER_STYLE, out)?;
+            }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                let bibliography = node.as_custom_type::<BibliographyNode>().unwrap();
+                self.blocks(&bibliography.content, style, out)?;
+            }
+            node if node.is_custom_type::<AlertNode>() => {
+                let alert = node.as_custom_type::<AlertNode>().unwrap();
+                self.blocks(&alert.content, "Quotations", out)?;
+            }
+            // Footnotes are placed at the references, and other blocks are
+            // not supported
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn paragraph(&mut self, content: &[Node], style: &str, out: &mut String) -> Result<()> {
+        if content.is_empty() {
+            return Ok(());
+        }
+
+        out.push_str(&format!(r#"<text:p text:style-name="{style}">"#));
+        self.inlines(content, out)?;
+        out.push_str("</text:p>");
+        Ok(())
+    }
+
+    fn list(
+        &mut self,
+        items: &[ListItem],
+        list_style: &str,
+        start: Option<u32>,
+        out: &mut String,
+    ) -> Result<()> {
+        out.push_str(&format!(r#"<text:list text:style-name="{list_style}">"#));
+        for (idx, item) in items.iter().enumerate() {
+            let content = match item {
+                ListItem::Ordered { content, .. } | ListItem::Unordered { content } => content,
+                _ => continue,
+            };
+
+            match start {
+                Some(start) if idx == 0 && start != 1 => {
+                    out.push_str(&format!(r#"<text:list-item text:start-value="{start}">"#));
+                }
+                _ => out.push_str("<text:list-item>"),
+            }
+            // A list item holds at least a paragraph
+            if content.is_empty() {
+                out.push_str(r#"<text:p text:style-name="List_20_Contents"/>"#);
+            }
+            self.blocks(content, "List_20_Contents", out)?;
+            out.push_str("</text:list-item>");
+        }
+        out.push_str("</text:list>");
+
+        Ok(())
+    }
+
+    fn table(&mut self, headers: &[Node], rows: &[Vec<Node>], out: &mut String) -> Result<()> {
+        let col_count = headers
+            .len()
+            .max(rows.iter().map(|row| row.len()).max().unwrap_or(0));
+        if col_count == 0 {
+            return Ok(());
+        }
+
+        self.tables += 1;
+        out.push_str(&format!(
+            r#"<table:table table:name="Table{}" table:style-name="Table">"#,
+            self.tables
+        ));
+        out.push_str(&format!(
+            r#"<table:table-column table:number-columns-repeated="{col_count}"/>"#
+        ));
+        if !headers.is_empty() {
+            out.push_str("<table:table-header-rows>");
+            self.table_row(headers, col_count, "Table_20_Heading", out)?;
+            out.push_str("</table:table-header-rows>");
+        }
+        for row in rows {
+            self.table_row(row, col_count, "Table_20_Contents", out)?;
+        }
+        out.push_str("</table:table>");
+
+        Ok(())
+    }
+
+    fn table_row(
+        &mut self,
+        cells: &[Node],
+        col_count: usize,
+        style: &str,
+        out: &mut String,
+    ) -> Result<()> {
+        out.push_str("<table:table-row>");
+        for idx in 0..col_count {
+            out.push_str(
+                r#"<table:table-cell table:style-name="TableCell" office:value-type="string">"#,
+            );
+            if let Some(cell) = cells.get(idx) {
+                self.blocks(std::slice::from_ref(cell), style, out)?;
+            }
+            out.push_str("</table:table-cell>");
+        }
+        out.push_str("</table:table-row>");
+
+        Ok(())
+    }
+
+    /// Writes the body of the figure, followed by the caption numbered by the
+    /// `Figure` sequence of the office suites.
+    fn figure(&mut self, figure: &FigureNode, out: &mut String) -> Result<()> {
+        self.blocks(std::slice::from_ref(&*figure.body), CENTER_STYLE, out)?;
+
+        if !figure.caption.is_empty() {
+            let idx = self.figures;
+            self.figures += 1;
+            out.push_str(&format!(
+                concat!(
+                    r#"<text:p text:style-name="Figure">Figure "#,
+                    r#"<text:sequence text:ref-name="refFigure{}" text:name="Figure" "#,
+                    r#"text:formula="ooow:Figure+1" style:num-format="1">{}</text:sequence>"#,
+                    r#": {}</text:p>"#,
+                ),
+                idx,
+                idx + 1,
+                escape_xml(&figure.caption)
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn inlines(&mut self, nodes: &[Node], out: &mut String) -> Result<()> {
+        for node in nodes {
+            self.inline(node, out)?;
+        }
+        Ok(())
+    }
+
+    fn inline(&mut self, node: &Node, out: &mut String) -> Result<()> {
+        match node {
+            Node::Text(content) => out.push_str(&escape_xml(content)),
+            Node::Emphasis(content) => self.span("Emphasis", content, out)?,
+            Node::Strong(content) => self.span("Strong_20_Emphasis", content, out)?,
+            Node::Strikethrough(content) => self.span(STRIKE_STYLE, content, out)?,
+            Node::Link {
+                url,
+                title: _,
+                content,
+            } => {
+                out.push_str(&format!(
+                    concat!(
+                        r#"<text:a xlink:type="simple" xlink:href="{}" "#,
+                        r#"text:style-name="Internet_20_link" "#,
+                        r#"text:visited-style-name="Visited_20_Internet_20_Link">"#,
+                    ),
+                    escape_xml(url)
+                ));
+                self.inlines(content, out)?;
+                out.push_str("</text:a>");
+            }
+            Node::Image { url, title: _, alt } => match fs::read(url.as_str()) {
+                Ok(data) => self.picture(&data, false, &plain_text(alt), out),
+                Err(_) => out.push_str(&escape_xml(&format!("[Image not found: {}]", url))),
+            },
+            Node::InlineCode(code) => {
+                out.push_str(r#"<text:span text:style-name="Source_20_Text">"#);
+                preserve_space(code, out);
+                out.push_str("</text:span>");
+            }
+            Node::HardBreak => out.push_str("<text:line-break/>"),
+            Node::SoftBreak => out.push(' '),
+            Node::HtmlElement(element) if element.tag == "img" => {
+                let src = html_attr_value(element, "src").unwrap_or_default();
+                let alt = html_attr_value(element, "alt").unwrap_or_default();
+                self.html_picture(src, alt, out);
+            }
+            Node::HtmlElement(element) => self.inlines(&element.children, out)?,
+            node if node.is_custom_type::<HighlightNode>() => {
+                let highlight = node.as_custom_type::<HighlightNode>().unwrap();
+                self.span(HIGHLIGHT_STYLE, &highlight.content, out)?;
+            }
+            node if node.is_custom_type::<InlineNode>() => {
+                let inline = node.as_custom_type::<InlineNode>().unwrap();
+                self.inlines(&inline.content, out)?;
+            }
+            node if node.is_custom_type::<VerbatimNode>() => {
+                let node = node.as_custom_type::<VerbatimNode>().unwrap();
+                log::warn!("`m1verbatim` is ignored {:?}", node.content);
+            }
+            // The equations are rendered into pictures unless the native math
+            // markups are requested, which are kept as LaTeX sources
+            node if node.is_custom_type::<MathNode>() => {
+                let math = node.as_custom_type::<MathNode>().unwrap();
+                out.push_str(r#"<text:span text:style-name="Source_20_Text">"#);
+                preserve_space(&to_latex(&math.expr, math.block), out);
+                out.push_str("</text:span>");
+            }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                let footnote = node.as_custom_type::<FootnoteNode>().unwrap();
+                self.notes += 1;
+                let n = self.notes;
+                out.push_str(&format!(
+                    concat!(
+                        r#"<text:note text:id="ftn{n}" text:note-class="footnote">"#,
+                        r#"<text:note-citation>{n}</text:note-citation><text:note-body>"#,
+                    ),
+                    n = n
+                ));
+                self.blocks(&footnote.content, "Footnote", out)?;
+                out.push_str("</text:note-body></text:note>");
+            }
+            node if node.is_custom_type::<CitationNode>() => {
+                let citation = node.as_custom_type::<CitationNode>().unwrap();
+                self.inlines(&citation.content, out)?;
+            }
+            node if node.is_custom_type::<RefNode>() => {
+                let reference = node.as_custom_type::<RefNode>().unwrap();
+                self.inlines(&reference.content, out)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn span(&mut self, style: &str, content: &[Node], out: &mut String) -> Result<()> {
+        out.push_str(&format!(r#"<text:span text:style-name="{style}">"#));
+        self.inlines(content, out)?;
+        out.push_str("</text:span>");
+        Ok(())
+    }
+
+    /// Writes the picture of an `img` element, whose source is either a data
+    /// URL or a path.
+    fn html_picture(&mut self, src: &str, alt: &str, out: &mut String) {
+        let Some(data_url) = src.strip_prefix("data:") else {
+            match fs::read(src) {
+                Ok(data) => self.picture(&data, false, alt, out),
+                Err(_) => out.push_str(&escape_xml(&format!("[Image not found: {}]", src))),
+            }
+            return;
+        };
+
+        let data = data_url
+            .split_once(";base64,")
+            .and_then(|(_, data)| base64::engine::general_purpose::STANDARD.decode(data).ok());
+        match data {
+            Some(data) => {
+                let is_svg = data_url.starts_with("image/svg+xml");
+                self.picture(&data, is_svg, alt, out);
+            }
+            None => out.push_str("[Invalid data URL]"),
+        }
+    }
+
+    /// Embeds the picture into the package as PNG and writes the frame
+    /// showing it. A placeholder is written if the picture cannot be
+    /// converted.
+    fn picture(&mut self, data: &[u8], is_svg: bool, alt: &str, out: &mut String) {
+        let converted = if is_svg {
+            svg_to_png(data).and_then(|png| Ok((dimensions(&png)?, png)))
+        } else {
+            to_png(data).map(|(png, dims)| (dims, png))
+        };
+        let ((width, height), png) = match converted {
+            Ok(converted) => converted,
+            Err(err) => {
+                let placeholder = format!("[Image processing error: {err}]");
+                out.push_str(&escape_xml(&placeholder));
+                return;
+            }
+        };
+
+        // The pictures converted from SVG have a higher resolution.
+        let dpi = if is_svg { SVG_DPI } else { 96.0 };
+        let mut width = width as f32 / dpi;
+        let mut height = height as f32 / dpi;
+        if width > MAX_PICTURE_WIDTH {
+            height *= MAX_PICTURE_WIDTH / width;
+            width = MAX_PICTURE_WIDTH;
+        }
+
+        let n = self.pictures.len() + 1;
+        let path = format!("Pictures/image{n}.png");
+        out.push_str(&format!(
+            concat!(
+                r#"<draw:frame draw:name="Image{}" text:anchor-type="as-char" "#,
+                r#"svg:width="{:.3}in" svg:height="{:.3}in" draw:z-index="0">"#,
+                r#"<draw:image xlink:href="{}" xlink:type="simple" xlink:show="embed" "#,
+                r#"xlink:actuate="onLoad"/>"#,
+            ),
+            n, width, height, path
+        ));
+        if !alt.is_empty() {
+            out.push_str(&format!("<svg:desc>{}</svg:desc>", escape_xml(alt)));
+        }
+        out.push_str("</draw:frame>");
+        self.pictures.push((path, png));
+    }
+
+    /// Converts the document to the content of `content.xml`.
+    fn content(&mut self, document: &Node) -> Result<String> {
+        let mut body = String::new();
+        self.blocks(std::slice::from_ref(document), "Text_20_body", &mut body)?;
+
+        Ok(format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<office:document-content {}>"#,
+                r#"<office:automatic-styles>{}</office:automatic-styles>"#,
+                r#"<office:body><office:text>"#,
+                r#"<text:sequence-decls>"#,
+                r#"<text:sequence-decl text:display-outline-level="0" text:name="Figure"/>"#,
+                r#"</text:sequence-decls>"#,
+                r#"{}</office:text></office:body></office:document-content>"#,
+            ),
+            NAMESPACES, AUTOMATIC_STYLES, body
+        ))
+    }
+
+    /// Lists the files of the package.
+    fn manifest(&self) -> String {
+        let mut manifest = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">"#,
+            r#"<manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.text"/>"#,
+            r#"<manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>"#,
+            r#"<manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/>"#,
+        ));
+        for (path, _) in &self.pictures {
+            manifest.push_str(&format!(
+                r#"<manifest:file-entry manifest:full-path="{path}" manifest:media-type="image/png"/>"#
+            ));
+        }
+        manifest.push_str("</manifest:manifest>");
+        manifest
+    }
+
+    /// Generate ODT document
+    pub fn generate_odt(&mut self, document: &Node) -> Result<Vec<u8>> {
+        let content = self.content(document)?;
+
+        let mut buffer = Vec::new();
+        let mut zip = ZipWriter::new(Cursor::new(&mut buffer));
+        let pack_err = |e: zip::result::ZipError| format!("Failed to pack ODT: {}", e);
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        // The uncompressed mimetype must be the first file of the package.
+        zip.start_file("mimetype", stored).map_err(pack_err)?;
+        zip.write_all(MIMETYPE.as_bytes())?;
+        let files = [
+            ("META-INF/manifest.xml", self.manifest()),
+            ("styles.xml", styles()),
+            ("content.xml", content),
+        ];
+        for (path, xml) in files {
+            zip.start_file(path, deflated).map_err(pack_err)?;
+            zip.write_all(xml.as_bytes())?;
+        }
+        // The pictures are already compressed.
+        for (path, data) in &self.pictures {
+            zip.start_file(path.as_str(), stored).map_err(pack_err)?;
+            zip.write_all(data)?;
+        }
+        zip.finish().map_err(pack_err)?;
+
+        Ok(buffer)
+    }
+}
+
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Document(..)
+        | Node::Paragraph(..)
+        | Node::Heading { .. }
+        | Node::BlockQuote(..)
+        | Node::CodeBlock { .. }
+        | Node::OrderedList { .. }
+        | Node::UnorderedList(..)
+        | Node::Table { .. }
+        | Node::ThematicBreak => true,
+        Node::HtmlElement(element) => element.tag == "p" || element.tag == "div",
+        Node::Custom(node) => node.is_block(),
+        _ => false,
+    }
+}
+
+fn html_attr_value<'a>(element: &'a HtmlElement, name: &str) -> Option<&'a str> {
+    element
+        .attributes
+        .iter()
+        .find(|attr| attr.name == name)
+        .map(|attr| attr.value.as_str())
+}
+
+/// Writes the text with its spaces and tabs kept, which are otherwise
+/// collapsed by the office suites.
+fn preserve_space(text: &str, out: &mut String) {
+    let mut spaces = 0;
+    let mut at_start = true;
+    let flush = |spaces: &mut usize, at_start: bool, out: &mut String| {
+        // The leading spaces of a line are all written as elements.
+        match (*spaces, at_start) {
+            (0, _) => {}
+            (1, false) => out.push(' '),
+            (n, true) => out.push_str(&format!(r#"<text:s text:c="{n}"/>"#)),
+            (n, false) => out.push_str(&format!(r#" <text:s text:c="{}"/>"#, n - 1)),
+        }
+        *spaces = 0;
+    };
+
+    for ch in text.chars() {
+        match ch {
+            ' ' => spaces += 1,
+            '\t' => {
+                flush(&mut spaces, at_start, out);
+                out.push_str("<text:tab/>");
+            }
+            ch => {
+                flush(&mut spaces, at_start, out);
+                match ch {
+                    '&' => out.push_str("&amp;"),
+                    '<' => out.push_str("&lt;"),
+                    '>' => out.push_str("&gt;"),
+                    ch => out.push(ch),
+                }
+            }
+        }
+        if ch != ' ' {
+            at_start = false;
+        }
+    }
+    flush(&mut spaces, at_start, out);
+}
+
+/// Creates the common styles of the document, named after the default styles
+/// of LibreOffice.
+fn styles() -> String {
+    let mut styles = String::from(concat!(
+        r#"<style:default-style style:family="paragraph">"#,
+        r#"<style:paragraph-properties fo:orphans="2" fo:widows="2"/>"#,
+        r#"<style:text-properties fo:font-family="'Liberation Serif'" "#,
+        r#"style:font-family-generic="roman" fo:font-size="12pt" fo:language="en"/>"#,
+        r#"</style:default-style>"#,
+        r#"<style:style style:name="Standard" style:family="paragraph" style:class="text"/>"#,
+        r#"<style:style style:name="Text_20_body" style:display-name="Text body" "#,
+        r#"style:family="paragraph" style:parent-style-name="Standard" style:class="text">"#,
+        r#"<style:paragraph-properties fo:margin-top="0cm" fo:margin-bottom="0.247cm" "#,
+        r#"fo:line-height="115%"/></style:style>"#,
+        r#"<style:style style:name="Heading" style:family="paragraph" "#,
+        r#"style:parent-style-name="Standard" style:next-style-name="Text_20_body" "#,
+        r#"style:class="text"><style:paragraph-properties fo:margin-top="0.423cm" "#,
+        r#"fo:margin-bottom="0.212cm" fo:keep-with-next="always"/>"#,
+        r#"<style:text-properties fo:font-family="'Liberation Sans'" "#,
+        r#"style:font-family-generic="swiss" fo:font-weight="bold"/></style:style>"#,
+        r#"<style:style style:name="Quotations" style:family="paragraph" "#,
+        r#"style:parent-style-name="Standard" style:class="html">"#,
+        r#"<style:paragraph-properties fo:margin-left="1cm" fo:margin-right="1cm" "#,
+        r#"fo:margin-top="0cm" fo:margin-bottom="0.283cm"/></style:style>"#,
+        r#"<style:style style:name="Preformatted_20_Text" style:display-name="Preformatted Text" "#,
+        r#"style:family="paragraph" style:parent-style-name="Standard" style:class="html">"#,
+        r#"<style:paragraph-properties fo:margin-top="0cm" fo:margin-bottom="0.247cm"/>"#,
+        r#"<style:text-properties fo:font-family="'Liberation Mono'" "#,
+        r#"style:font-family-generic="modern" style:font-pitch="fixed" fo:font-size="10pt"/>"#,
+        r#"</style:style>"#,
+        r#"<style:style style:name="List_20_Contents" style:display-name="List Contents" "#,
+        r#"style:family="paragraph" style:parent-style-name="Standard" style:class="html"/>"#,
+        r#"<style:style style:name="Table_20_Contents" style:display-name="Table Contents" "#,
+        r#"style:family="paragraph" style:parent-style-name="Standard" style:class="extra"/>"#,
+        r#"<style:style style:name="Table_20_Heading" style:display-name="Table Heading" "#,
+        r#"style:family="paragraph" style:parent-style-name="Table_20_Contents" "#,
+        r#"style:class="extra"><style:paragraph-properties fo:text-align="center"/>"#,
+        r#"<style:text-properties fo:font-weight="bold"/></style:style>"#,
+        r#"<style:style style:name="Caption" style:family="paragraph" "#,
+        r#"style:parent-style-name="Standard" style:class="extra">"#,
+        r#"<style:paragraph-properties fo:margin-top="0.212cm" fo:margin-bottom="0.212cm"/>"#,
+        r#"<style:text-properties fo:font-size="10pt" fo:font-style="italic"/></style:style>"#,
+        r#"<style:style style:name="Figure" style:family="paragraph" "#,
+        r#"style:parent-style-name="Caption" style:class="extra">"#,
+        r#"<style:paragraph-properties fo:text-align="center"/></style:style>"#,
+        r#"<style:style style:name="Footnote" style:family="paragraph" "#,
+        r#"style:parent-style-name="Standard" style:class="extra">"#,
+        r#"<style:paragraph-properties fo:margin-left="0.599cm" fo:text-indent="-0.599cm"/>"#,
+        r#"<style:text-properties fo:font-size="10pt"/></style:style>"#,
+        r#"<style:style style:name="Horizontal_20_Line" style:display-name="Horizontal Line" "#,
+        r#"style:family="paragraph" style:parent-style-name="Standard" style:class="html">"#,
+        r#"<style:paragraph-properties fo:margin-bottom="0.499cm" "#,
+        r#"fo:border-bottom="0.5pt solid #808080" fo:padding="0cm"/>"#,
+        r#"<style:text-properties fo:font-size="6pt"/></style:style>"#,
+        r#"<style:style style:name="Emphasis" style:family="text">"#,
+        r#"<style:text-properties fo:font-style="italic"/></style:style>"#,
+        r#"<style:style style:name="Strong_20_Emphasis" style:display-name="Strong Emphasis" "#,
+        r#"style:family="text"><style:text-properties fo:font-weight="bold"/></style:style>"#,
+        r#"<style:style style:name="Source_20_Text" style:display-name="Source Text" "#,
+        r#"style:family="text"><style:text-properties fo:font-family="'Liberation Mono'" "#,
+        r#"style:font-family-generic="modern" style:font-pitch="fixed"/></style:style>"#,
+        r#"<style:style style:name="Internet_20_link" style:display-name="Internet link" "#,
+        r##"style:family="text"><style:text-properties fo:color="#000080" "##,
+        r#"style:text-underline-style="solid" style:text-underline-width="auto" "#,
+        r#"style:text-underline-color="font-color"/></style:style>"#,
+        r#"<style:style style:name="Visited_20_Internet_20_Link" "#,
+        r#"style:display-name="Visited Internet Link" style:family="text">"#,
+        r##"<style:text-properties fo:color="#800000" style:text-underline-style="solid" "##,
+        r#"style:text-underline-width="auto" style:text-underline-color="font-color"/>"#,
+        r#"</style:style>"#,
+    ));
+
+    // The sizes of the headings follow the defaults of LibreOffice.
+    for (level, size) in [130, 115, 101, 95, 85, 85].into_iter().enumerate() {
+        let level = level + 1;
+        styles.push_str(&format!(
+            concat!(
+                r#"<style:style style:name="Heading_20_{level}" style:display-name="Heading {level}" "#,
+                r#"style:family="paragraph" style:parent-style-name="Heading" "#,
+                r#"style:next-style-name="Text_20_body" style:default-outline-level="{level}" "#,
+                r#"style:class="text"><style:text-properties fo:font-size="{size}%"/>"#,
+                r#"</style:style>"#,
+            ),
+            level = level,
+            size = size
+        ));
+    }
+
+    styles.push_str(r#"<text:outline-style style:name="Outline">"#);
+    for level in 1..=10 {
+        styles.push_str(&format!(
+            r#"<text:outline-level-style text:level="{level}" style:num-format=""/>"#
+        ));
+    }
+    styles.push_str("</text:outline-style>");
+    styles.push_str(&list_style("List_20_Bullet", "List Bullet", false));
+    styles.push_str(&list_style("Numbering_20_123", "Numbering 123", true));
+
+    format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<office:document-styles {}>"#,
+            r#"<office:styles>{}</office:styles>"#,
+            r#"<office:automatic-styles><style:page-layout style:name="pm1">"#,
+            r#"<style:page-layout-properties fo:page-width="21cm" fo:page-height="29.7cm" "#,
+            r#"style:print-orientation="portrait" fo:margin-top="2cm" fo:margin-bottom="2cm" "#,
+            r#"fo:margin-left="2cm" fo:margin-right="2cm"/></style:page-layout>"#,
+            r#"</office:automatic-styles><office:master-styles>"#,
+            r#"<style:master-page style:name="Standard" style:page-layout-name="pm1"/>"#,
+            r#"</office:master-styles></office:document-styles>"#,
+        ),
+        NAMESPACES, styles
+    )
+}
+
+/// Creates the style of the bulleted or numbered lists.
+fn list_style(name: &str, display_name: &str, numbered: bool) -> String {
+    let mut style =
+        format!(r#"<text:list-style style:name="{name}" style:display-name="{display_name}">"#);
+    for level in 1..=10 {
+        let indent = 0.635 * level as f32;
+        let properties = format!(
+            concat!(
+                r#"<style:list-level-properties "#,
+                r#"text:list-level-position-and-space-mode="label-alignment">"#,
+                r#"<style:list-level-label-alignment text:label-followed-by="listtab" "#,
+                r#"text:list-tab-stop-position="{indent:.3}cm" fo:text-indent="-0.635cm" "#,
+                r#"fo:margin-left="{indent:.3}cm"/></style:list-level-properties>"#,
+            ),
+            indent = indent
+        );
+        if numbered {
+            style.push_str(&format!(
+                concat!(
+                    r#"<text:list-level-style-number text:level="{}" style:num-suffix="." "#,
+                    r#"style:num-format="1">{}</text:list-level-style-number>"#,
+                ),
+                level, properties
+            ));
+        } else {
+            let bullet = ["•", "◦", "▪"][(level - 1) % 3];
+            style.push_str(&format!(
+                concat!(
+                    r#"<text:list-level-style-bullet text:level="{}" text:bullet-char="{}">"#,
+                    r#"{}</text:list-level-style-bullet>"#,
+                ),
+                level, bullet, properties
+            ));
+        }
+    }
+    style.push_str("</text:list-style>");
+    style
+}
+
+impl FormatWriter for OdtWriter {
+    fn write_vec(&mut self, document: &Node) -> Result<Vec<u8>> {
+        *self = Self::default();
+        self.generate_odt(document)
+    }
+
+    fn write_eco(&mut self, _document: &Node, _output: &mut EcoString) -> Result<()> {
+        Err("ODT format does not support EcoString output".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(document: Node) -> String {
+        let content = OdtWriter::new().content(&document).unwrap();
+        let start = content.find("</text:sequence-decls>").unwrap();
+        let end = content.find("</office:text>").unwrap();
+        content[start + "</text:sequence-decls>".len()..end].to_owned()
+    }
+
+    #[test]
+    fn test_heading_and_list() {
+        let document = Node::Document(vec![
+            Node::heading(1, vec![Node::Text("A & B".into())]),
+            Node::UnorderedList(vec![ListItem::Unordered {
+                content: vec![Node::Paragraph(vec![Node::Strong(vec![Node::Text(
+                    "item".into(),
+                )])])],
+            }]),
+        ]);
+
+        assert_eq!(
+            body(document),
+            concat!(
+                r#"<text:h text:style-name="Heading_20_1" text:outline-level="1">A &amp; B</text:h>"#,
+                r#"<text:list text:style-name="List_20_Bullet"><text:list-item>"#,
+                r#"<text:p text:style-name="List_20_Contents">"#,
+                r#"<text:span text:style-name="Strong_20_Emphasis">item</text:span>"#,
+                r#"</text:p></text:list-item></text:list>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_preserve_space() {
+        let mut out = String::new();
+        preserve_space("  a  b\tc", &mut out);
+        assert_eq!(
+            out,
+            r#"<text:s text:c="2"/>a <text:s text:c="1"/>b<text:tab/>c"#
+        );
+    }
+
+    #[test]
+    fn test_mimetype_first() {
+        let document = Node::Document(vec![Node::Paragraph(vec![Node::Text("text".into())])]);
+        let odt = OdtWriter::new().write_vec(&document).unwrap();
+
+        // The name and the content follow the 30-byte local file header.
+        assert_eq!(&odt[0..4], b"PK\x03\x04");
+        assert_eq!(&odt[30..38], b"mimetype");
+        assert_eq!(&odt[38..38 + MIMETYPE.len()], MIMETYPE.as_bytes());
+    }
+}
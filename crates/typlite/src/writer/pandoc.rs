@@ -0,0 +1,475 @@
+//! Pandoc writer implementation - produces the JSON AST read by `pandoc -f json`
+
+use cmark_writer::ast::{HtmlElement, ListItem, Node};
+use ecow::EcoString;
+use serde_json::{json, Value};
+
+use crate::common::{
+    AlertNode, BibliographyNode, CenterNode, CitationNode, ExternalFrameNode, FigureNode,
+    FootnoteNode, FormatWriter, HighlightNode, InlineNode, MathNode, VerbatimNode,
+};
+use crate::math::to_latex;
+use crate::Result;
+
+/// The version of pandoc-types that the AST conforms to.
+const PANDOC_API_VERSION: [u32; 3] = [1, 23, 1];
+
+/// Pandoc JSON writer implementation
+#[derive(Default)]
+pub struct PandocWriter {}
+
+impl PandocWriter {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Converts the nodes into blocks. Consecutive inline nodes are gathered
+    /// into a paragraph, or a plain block in tight contexts like list items
+    /// and table cells.
+    fn blocks(nodes: &[Node], tight: bool) -> Vec<Value> {
+        let mut blocks = Vec::new();
+        let mut inlines = Vec::new();
+        let flush = |inlines: &mut Vec<Value>, blocks: &mut Vec<Value>| {
+            if !inlines.is_empty() {
+                let t = if tight { "Plain" } else { "Para" };
+                blocks.push(json!({ "t": t, "c": std::mem::take(inlines) }));
+            }
+        };
+
+        for node in nodes {
+            if Self::is_block(node) {
+                flush(&mut inlines, &mut blocks);
+                Self::block(node, tight, &mut blocks);
+            } else {
+                Self::inline(node, &mut inlines);
+            }
+        }
+        flush(&mut inlines, &mut blocks);
+
+        blocks
+    }
+
+    fn is_block(node: &Node) -> bool {
+        match node {
+            Node::Document(..)
+            | Node::Paragraph(..)
+            | Node::Heading { .. }
+            | Node::BlockQuote(..)
+            | Node::CodeBlock { .. }
+            | Node::OrderedList { .. }
+            | Node::UnorderedList(..)
+            | Node::Table { .. }
+            | Node::ThematicBreak => true,
+            Node::HtmlElement(element) => element.tag == "p" || element.tag == "div",
+            Node::Custom(node) => node.is_block(),
+            _ => false,
+        }
+    }
+
+    fn block(node: &Node, tight: bool, blocks: &mut Vec<Value>) {
+        match node {
+            Node::Document(children) => blocks.extend(Self::blocks(children, false)),
+            Node::Paragraph(content) => {
+                let t = if tight { "Plain" } else { "Para" };
+                blocks.push(json!({ "t": t, "c": Self::inlines(content) }));
+            }
+            Node::Heading {
+                level,
+                content,
+                heading_type: _,
+            } => {
+                blocks.push(json!({
+                    "t": "Header",
+                    "c": [level, attr("", &[], &[]), Self::inlines(content)],
+                }));
+            }
+            Node::BlockQuote(content) => {
+                blocks.push(json!({ "t": "BlockQuote", "c": Self::blocks(content, false) }));
+            }
+            Node::CodeBlock {
+                language,
+                content,
+                block_type: _,
+            } => {
+                let classes: Vec<&str> = language
+                    .iter()
+                    .map(EcoString::as_str)
+                    .filter(|lang| !lang.is_empty())
+                    .collect();
+                blocks.push(json!({
+                    "t": "CodeBlock",
+                    "c": [attr("", &classes, &[]), content.as_str()],
+                }));
+            }
+            Node::OrderedList { start, items } => {
+                let attrs = json!([start, { "t": "Decimal" }, { "t": "Period" }]);
+                blocks.push(json!({
+                    "t": "OrderedList",
+                    "c": [attrs, Self::list_items(items)],
+                }));
+            }
+            Node::UnorderedList(items) => {
+                blocks.push(json!({ "t": "BulletList", "c": Self::list_items(items) }));
+            }
+            Node::Table {
+                headers,
+                rows,
+                alignments: _,
+            } => blocks.push(Self::table(headers, rows)),
+            Node::ThematicBreak => blocks.push(json!({ "t": "HorizontalRule" })),
+            Node::HtmlElement(element) => {
+                blocks.push(json!({
+                    "t": "Div",
+                    "c": [html_attr(element), Self::blocks(&element.children, tight)],
+                }));
+            }
+            node if node.is_custom_type::<FigureNode>() => {
+                let figure = node.as_custom_type::<FigureNode>().unwrap();
+                let caption = if figure.caption.is_empty() {
+                    vec![]
+                } else {
+                    vec![json!({ "t": "Plain", "c": text(&figure.caption) })]
+                };
+                blocks.push(json!({
+                    "t": "Figure",
+                    "c": [
+                        attr("", &[], &[]),
+                        [null, caption],
+                        Self::blocks(std::slice::from_ref(&*figure.body), false),
+                    ],
+                }));
+            }
+            node if node.is_custom_type::<ExternalFrameNode>() => {
+                let frame = node.as_custom_type::<ExternalFrameNode>().unwrap();
+                let url = frame.file_path.display().to_string();
+                blocks.push(json!({
+                    "t": "Para",
+                    "c": [image(&url, "", text(&frame.alt_text))],
+                }));
+            }
+            node if node.is_custom_type::<CenterNode>() => {
+                let center = node.as_custom_type::<CenterNode>().unwrap();
+                Self::block(&center.node, tight, blocks);
+            }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                let bibliography = node.as_custom_type::<BibliographyNode>().unwrap();
+                // Pandoc places the references in the div with the `refs` id
+                blocks.push(json!({
+                    "t": "Div",
+                    "c": [
+                        attr("refs", &[], &[]),
+                        Self::blocks(&bibliography.content, false),
+                    ],
+                }));
+            }
+            node if node.is_custom_type::<AlertNode>() => {
+                let alert = node.as_custom_type::<AlertNode>().unwrap();
+                blocks.push(json!({
+                    "t": "Div",
+                    "c": [
+                        attr("", &[alert.class.as_str()], &[]),
+                        Self::blocks(&alert.content, false),
+                    ],
+                }));
+            }
+            // Footnotes are placed by pandoc at the references, and other
+            // blocks are not supported
+            _ => {}
+        }
+    }
+
+    fn list_items(items: &[ListItem]) -> Vec<Value> {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                ListItem::Ordered { content, .. } | ListItem::Unordered { content } => {
+                    Some(Value::Array(Self::blocks(content, true)))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn table(headers: &[Node], rows: &[Vec<Node>]) -> Value {
+        let col_count = headers
+            .len()
+            .max(rows.iter().map(|row| row.len()).max().unwrap_or(0));
+        let col_specs: Vec<Value> = (0..col_count)
+            .map(|_| json!([{ "t": "AlignDefault" }, { "t": "ColWidthDefault" }]))
+            .collect();
+
+        let row = |cells: &[Node]| {
+            let cells: Vec<Value> = cells
+                .iter()
+                .map(|cell| {
+                    json!([
+                        attr("", &[], &[]),
+                        { "t": "AlignDefault" },
+                        1,
+                        1,
+                        Self::blocks(std::slice::from_ref(cell), true),
+                    ])
+                })
+                .collect();
+            json!([attr("", &[], &[]), cells])
+        };
+
+        let head: Vec<Value> = if headers.is_empty() {
+            vec![]
+        } else {
+            vec![row(headers)]
+        };
+        let body: Vec<Value> = rows.iter().map(|cells| row(cells)).collect();
+
+        json!({
+            "t": "Table",
+            "c": [
+                attr("", &[], &[]),
+                [null, []],
+                col_specs,
+                [attr("", &[], &[]), head],
+                [[attr("", &[], &[]), 0, [], body]],
+                [attr("", &[], &[]), []],
+            ],
+        })
+    }
+
+    fn inlines(nodes: &[Node]) -> Vec<Value> {
+        let mut inlines = Vec::new();
+        for node in nodes {
+            Self::inline(node, &mut inlines);
+        }
+        inlines
+    }
+
+    fn inline(node: &Node, inlines: &mut Vec<Value>) {
+        match node {
+            Node::Text(content) => inlines.extend(text(content)),
+            Node::Emphasis(content) => {
+                inlines.push(json!({ "t": "Emph", "c": Self::inlines(content) }));
+            }
+            Node::Strong(content) => {
+                inlines.push(json!({ "t": "Strong", "c": Self::inlines(content) }));
+            }
+            Node::Strikethrough(content) => {
+                inlines.push(json!({ "t": "Strikeout", "c": Self::inlines(content) }));
+            }
+            Node::Link {
+                url,
+                title,
+                content,
+            } => {
+                let title = title.as_deref().unwrap_or_default();
+                inlines.push(json!({
+                    "t": "Link",
+                    "c": [attr("", &[], &[]), Self::inlines(content), [url.as_str(), title]],
+                }));
+            }
+            Node::Image { url, title, alt } => {
+                let title = title.as_deref().unwrap_or_default();
+                inlines.push(image(url, title, Self::inlines(alt)));
+            }
+            Node::InlineCode(code) => {
+                inlines.push(json!({ "t": "Code", "c": [attr("", &[], &[]), code.as_str()] }));
+            }
+            Node::HardBreak => inlines.push(json!({ "t": "LineBreak" })),
+            Node::SoftBreak => inlines.push(json!({ "t": "SoftBreak" })),
+            Node::HtmlElement(element) if element.tag == "img" => {
+                let src = html_attr_value(element, "src").unwrap_or_default();
+                let alt = html_attr_value(element, "alt").unwrap_or_default();
+                inlines.push(image(src, "", text(alt)));
+            }
+            Node::HtmlElement(element) => {
+                inlines.push(json!({
+                    "t": "Span",
+                    "c": [html_attr(element), Self::inlines(&element.children)],
+                }));
+            }
+            node if node.is_custom_type::<HighlightNode>() => {
+                let highlight = node.as_custom_type::<HighlightNode>().unwrap();
+                inlines.push(json!({
+                    "t": "Span",
+                    "c": [attr("", &["mark"], &[]), Self::inlines(&highlight.content)],
+                }));
+            }
+            node if node.is_custom_type::<InlineNode>() => {
+                let inline = node.as_custom_type::<InlineNode>().unwrap();
+                inlines.extend(Self::inlines(&inline.content));
+            }
+            node if node.is_custom_type::<VerbatimNode>() => {
+                let verbatim = node.as_custom_type::<VerbatimNode>().unwrap();
+                inlines.push(
+                    json!({ "t": "RawInline", "c": ["markdown", verbatim.content.as_str()] }),
+                );
+            }
+            node if node.is_custom_type::<MathNode>() => {
+                let math = node.as_custom_type::<MathNode>().unwrap();
+                let kind = if math.block {
+                    "DisplayMath"
+                } else {
+                    "InlineMath"
+                };
+                inlines.push(json!({
+                    "t": "Math",
+                    "c": [{ "t": kind }, to_latex(&math.expr, math.block).as_str()],
+                }));
+            }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                let footnote = node.as_custom_type::<FootnoteNode>().unwrap();
+                inlines.push(json!({
+                    "t": "Note",
+                    "c": Self::blocks(&footnote.content, false),
+                }));
+            }
+            node if node.is_custom_type::<CitationNode>() => {
+                let citation = node.as_custom_type::<CitationNode>().unwrap();
+                let item = json!({
+                    "citationId": citation.key.as_str(),
+                    "citationPrefix": [],
+                    "citationSuffix": [],
+                    "citationMode": { "t": "NormalCitation" },
+                    "citationNoteNum": 0,
+                    "citationHash": 0,
+                });
+                inlines.push(json!({
+                    "t": "Cite",
+                    "c": [[item], Self::inlines(&citation.content)],
+                }));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Splits the text into words, spaces and soft breaks.
+fn text(content: &str) -> Vec<Value> {
+    let is_space = |c: char| matches!(c, ' ' | '\t' | '\n' | '\r');
+
+    let mut inlines = Vec::new();
+    let mut rest = content;
+    while !rest.is_empty() {
+        let space_len = rest.find(|c| !is_space(c)).unwrap_or(rest.len());
+        if space_len > 0 {
+            if rest[..space_len].contains('\n') {
+                inlines.push(json!({ "t": "SoftBreak" }));
+            } else {
+                inlines.push(json!({ "t": "Space" }));
+            }
+            rest = &rest[space_len..];
+            continue;
+        }
+
+        let word_len = rest.find(is_space).unwrap_or(rest.len());
+        inlines.push(json!({ "t": "Str", "c": &rest[..word_len] }));
+        rest = &rest[word_len..];
+    }
+    inlines
+}
+
+fn image(url: &str, title: &str, alt: Vec<Value>) -> Value {
+    json!({ "t": "Image", "c": [attr("", &[], &[]), alt, [url, title]] })
+}
+
+/// Creates the attributes of an element, i.e. the identifier, the classes and
+/// the key-value pairs.
+fn attr(id: &str, classes: &[&str], pairs: &[(&str, &str)]) -> Value {
+    json!([id, classes, pairs])
+}
+
+fn html_attr(element: &HtmlElement) -> Value {
+    let id = html_attr_value(element, "id").unwrap_or_default();
+    let classes: Vec<&str> = html_attr_value(element, "class")
+        .map(|class| class.split_whitespace().collect())
+        .unwrap_or_default();
+    let pairs: Vec<(&str, &str)> = element
+        .attributes
+        .iter()
+        .filter(|attr| attr.name != "id" && attr.name != "class")
+        .map(|attr| (attr.name.as_str(), attr.value.as_str()))
+        .collect();
+    attr(id, &classes, &pairs)
+}
+
+fn html_attr_value<'a>(element: &'a HtmlElement, name: &str) -> Option<&'a str> {
+    element
+        .attributes
+        .iter()
+        .find(|attr| attr.name == name)
+        .map(|attr| attr.value.as_str())
+}
+
+impl FormatWriter for PandocWriter {
+    fn write_eco(&mut self, document: &Node, output: &mut EcoString) -> Result<()> {
+        let document = json!({
+            "pandoc-api-version": PANDOC_API_VERSION,
+            "meta": {},
+            "blocks": Self::blocks(std::slice::from_ref(document), false),
+        });
+        let json = serde_json::to_string(&document)
+            .map_err(|err| format!("failed to serialize pandoc AST: {err}"))?;
+        output.push_str(&json);
+        Ok(())
+    }
+
+    fn write_vec(&mut self, document: &Node) -> Result<Vec<u8>> {
+        let mut output = EcoString::new();
+        self.write_eco(document, &mut output)?;
+        Ok(output.as_str().as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(document: Node) -> Value {
+        let mut output = EcoString::new();
+        PandocWriter::new()
+            .write_eco(&document, &mut output)
+            .unwrap();
+        serde_json::from_str(&output).unwrap()
+    }
+
+    #[test]
+    fn test_paragraph() {
+        let document = Node::Document(vec![Node::Paragraph(vec![
+            Node::Text("Hello, ".into()),
+            Node::Strong(vec![Node::Text("typst world".into())]),
+        ])]);
+
+        assert_eq!(
+            convert(document),
+            json!({
+                "pandoc-api-version": [1, 23, 1],
+                "meta": {},
+                "blocks": [{
+                    "t": "Para",
+                    "c": [
+                        { "t": "Str", "c": "Hello," },
+                        { "t": "Space" },
+                        { "t": "Strong", "c": [
+                            { "t": "Str", "c": "typst" },
+                            { "t": "Space" },
+                            { "t": "Str", "c": "world" },
+                        ] },
+                    ],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_list_items_are_tight() {
+        let document = Node::UnorderedList(vec![ListItem::Unordered {
+            content: vec![Node::Paragraph(vec![Node::Text("item".into())])],
+        }]);
+
+        assert_eq!(
+            convert(document)["blocks"],
+            json!([{
+                "t": "BulletList",
+                "c": [[{ "t": "Plain", "c": [{ "t": "Str", "c": "item" }] }]],
+            }])
+        );
+    }
+}
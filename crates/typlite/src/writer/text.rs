@@ -3,7 +3,11 @@
 use cmark_writer::ast::Node;
 use ecow::EcoString;
 
-use crate::common::{ExternalFrameNode, FigureNode, FormatWriter};
+use crate::common::{
+    BibliographyNode, CitationNode, ExternalFrameNode, FigureNode, FootnoteDefinitionNode,
+    FootnoteNode, FormatWriter, MathNode,
+};
+use crate::math::to_latex;
 use crate::Result;
 
 /// Text writer implementation
@@ -168,6 +172,39 @@ impl TextWriter {
                     }
                 }
             }
+            node if node.is_custom_type::<FootnoteNode>() => {
+                if let Some(footnote) = node.as_custom_type::<FootnoteNode>() {
+                    output.push_str(&format!("[{}]", footnote.index));
+                }
+            }
+            node if node.is_custom_type::<FootnoteDefinitionNode>() => {
+                if let Some(footnote) = node.as_custom_type::<FootnoteDefinitionNode>() {
+                    output.push_str(&format!("[{}] ", footnote.index));
+                    for inline in &footnote.content {
+                        Self::write_node(inline, output)?;
+                    }
+                    output.push_str("\n");
+                }
+            }
+            node if node.is_custom_type::<CitationNode>() => {
+                if let Some(citation) = node.as_custom_type::<CitationNode>() {
+                    for inline in &citation.content {
+                        Self::write_node(inline, output)?;
+                    }
+                }
+            }
+            node if node.is_custom_type::<BibliographyNode>() => {
+                if let Some(bibliography) = node.as_custom_type::<BibliographyNode>() {
+                    for block in &bibliography.content {
+                        Self::write_node(block, output)?;
+                    }
+                }
+            }
+            node if node.is_custom_type::<MathNode>() => {
+                if let Some(math) = node.as_custom_type::<MathNode>() {
+                    output.push_str(&to_latex(&math.expr, math.block));
+                }
+            }
             _ => {}
         }
         Ok(())
@@ -11,7 +11,7 @@ use crate::debug_loc::{InternQuery, SpanInterner};
 use crate::outline::Outline;
 use crate::{
     ChangeCursorPositionRequest, DocToSrcJumpInfo, EditorServer, MemoryFiles, MemoryFilesShort,
-    ResolveSourceLocRequest,
+    ParticipantCursorRequest, ParticipantLeaveRequest, ResolveSourceLocRequest, ReviewCommentInfo,
 };
 
 use super::webview::WebviewActorRequest;
@@ -26,6 +26,12 @@ pub struct PanelScrollByPositionRequest {
     position: DocumentPosition,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct PanelSlideToRequest {
+    /// The 1-based page number of the slide.
+    page: usize,
+}
+
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 #[serde(tag = "kind", content = "data")]
 pub enum CompileStatus {
@@ -39,6 +45,7 @@ pub enum EditorActorRequest {
     Shutdown,
     DocToSrcJumpResolve(DocToSrcJumpResolveRequest),
     DocToSrcJump(DocToSrcJumpInfo),
+    ReviewComment(ReviewCommentInfo),
     Outline(Outline),
     CompileStatus(CompileStatus),
 }
@@ -125,6 +132,8 @@ pub enum ControlPlaneMessage {
     ResolveSourceLoc(ResolveSourceLocRequest),
     #[serde(rename = "panelScrollByPosition")]
     PanelScrollByPosition(PanelScrollByPositionRequest),
+    #[serde(rename = "panelSlideTo")]
+    PanelSlideTo(PanelSlideToRequest),
     #[serde(rename = "sourceScrollBySpan")]
     DocToSrcJumpResolve(DocToSrcJumpResolveRequest),
     #[serde(rename = "syncMemoryFiles")]
@@ -133,6 +142,10 @@ pub enum ControlPlaneMessage {
     UpdateMemoryFiles(MemoryFiles),
     #[serde(rename = "removeMemoryFiles")]
     RemoveMemoryFiles(MemoryFilesShort),
+    #[serde(rename = "participantCursor")]
+    ParticipantCursor(ParticipantCursorRequest),
+    #[serde(rename = "participantLeave")]
+    ParticipantLeave(ParticipantLeaveRequest),
 }
 
 #[derive(Debug, Serialize)]
@@ -146,6 +159,8 @@ pub enum ControlPlaneResponse {
     CompileStatus(CompileStatus),
     #[serde(rename = "outline")]
     Outline(Outline),
+    #[serde(rename = "reviewComment")]
+    ReviewComment(ReviewCommentInfo),
 }
 
 impl<T: EditorServer> EditorActor<T> {
@@ -191,6 +206,9 @@ impl<T: EditorServer> EditorActor<T> {
 
                             false
                         },
+                        EditorActorRequest::ReviewComment(info) => {
+                            self.editor_conn.resp_ctl_plane("ReviewComment", ControlPlaneResponse::ReviewComment(info)).await
+                        },
                         EditorActorRequest::CompileStatus(status) => {
                             self.editor_conn.resp_ctl_plane("CompileStatus", ControlPlaneResponse::CompileStatus(status)).await
                         },
@@ -217,6 +235,18 @@ impl<T: EditorServer> EditorActor<T> {
                             log::debug!("EditorActor: received message from editor: {:?}", jump_info);
                             self.webview_sender.send(WebviewActorRequest::ViewportPosition(jump_info.position)).log_error("EditorActor");
                         }
+                        ControlPlaneMessage::PanelSlideTo(req) => {
+                            log::debug!("EditorActor: received message from editor: {:?}", req);
+                            self.webview_sender.send(WebviewActorRequest::SlidePage(req.page)).log_error("EditorActor");
+                        }
+                        ControlPlaneMessage::ParticipantCursor(req) => {
+                            log::debug!("EditorActor: received message from editor: {:?}", req);
+                            self.renderer_sender.send(RenderActorRequest::ParticipantCursor(req)).log_error("EditorActor");
+                        }
+                        ControlPlaneMessage::ParticipantLeave(req) => {
+                            log::debug!("EditorActor: received message from editor: {:?}", req);
+                            self.webview_sender.send(WebviewActorRequest::ParticipantLeave(req.id)).log_error("EditorActor");
+                        }
                         ControlPlaneMessage::DocToSrcJumpResolve(jump_info) => {
                             log::debug!("EditorActor: received message from editor: {:?}", jump_info);
 
@@ -0,0 +1,186 @@
+use std::sync::Arc;
+
+use reflexo_typst::debug_loc::{LspPosition, SourceLocation};
+use tinymist_std::typst::TypstHtmlDocument;
+use tokio::sync::{broadcast, mpsc};
+use typst::syntax::Span;
+
+use super::render::RenderActorRequest;
+use super::{editor::EditorActorRequest, webview::WebviewActorRequest};
+use crate::html::{diff, find_paths_by_span, HtmlTree};
+use crate::{CompileView, Location};
+
+/// The render actor of the HTML preview, which sends DOM patches instead of
+/// incremental SVG to the webview.
+pub struct HtmlRenderActor {
+    mailbox: broadcast::Receiver<RenderActorRequest>,
+    view: Arc<parking_lot::RwLock<Option<Arc<dyn CompileView>>>>,
+    editor_conn_sender: mpsc::UnboundedSender<EditorActorRequest>,
+    html_sender: mpsc::UnboundedSender<Vec<u8>>,
+    webview_sender: broadcast::Sender<WebviewActorRequest>,
+
+    /// The last rendered document.
+    doc: Option<Arc<TypstHtmlDocument>>,
+    /// The trees last sent to the webview.
+    trees: Option<Vec<HtmlTree>>,
+}
+
+impl HtmlRenderActor {
+    pub fn new(
+        mailbox: broadcast::Receiver<RenderActorRequest>,
+        view: Arc<parking_lot::RwLock<Option<Arc<dyn CompileView>>>>,
+        editor_conn_sender: mpsc::UnboundedSender<EditorActorRequest>,
+        html_sender: mpsc::UnboundedSender<Vec<u8>>,
+        webview_sender: broadcast::Sender<WebviewActorRequest>,
+    ) -> Self {
+        Self {
+            mailbox,
+            view,
+            editor_conn_sender,
+            html_sender,
+            webview_sender,
+            doc: None,
+            trees: None,
+        }
+    }
+
+    fn process_message(&mut self, msg: RenderActorRequest) -> bool {
+        log::trace!("HtmlRenderActor: received message: {msg:?}");
+
+        let res = msg.is_full_render();
+        match msg {
+            RenderActorRequest::WebviewResolveHtmlSpan(span) => {
+                log::debug!("HtmlRenderActor: resolving WebviewResolveHtmlSpan: {span:?}");
+                self.resolve_span(span);
+            }
+            RenderActorRequest::ChangeCursorPosition(req) => {
+                let paths =
+                    self.resolve_paths(req.filepath.to_string_lossy(), req.line, req.character);
+                if let Some(paths) = paths {
+                    let _ = self
+                        .webview_sender
+                        .send(WebviewActorRequest::HtmlCursorPaths(paths));
+                }
+            }
+            RenderActorRequest::ResolveSourceLoc(req) => {
+                let paths =
+                    self.resolve_paths(req.filepath.to_string_lossy(), req.line, req.character);
+                if let Some(paths) = paths {
+                    let _ = self
+                        .webview_sender
+                        .send(WebviewActorRequest::HtmlJumpPaths(paths));
+                }
+            }
+            RenderActorRequest::EditorResolveSpanRange(range) => {
+                self.resolve_span(range.end.span);
+            }
+            RenderActorRequest::WebviewResolveSpan(..)
+            | RenderActorRequest::WebviewResolveFrameLoc(..)
+            | RenderActorRequest::WebviewReviewComment(..)
+            | RenderActorRequest::ParticipantCursor(..) => {
+                log::debug!("HtmlRenderActor: ignored paged request: {msg:?}");
+            }
+            RenderActorRequest::RenderFullLatest | RenderActorRequest::RenderIncremental => {}
+        }
+
+        res
+    }
+
+    pub async fn run(mut self) {
+        loop {
+            let mut has_full_render = false;
+            log::debug!("HtmlRenderActor: waiting for message");
+            match self.mailbox.recv().await {
+                Ok(msg) => {
+                    has_full_render |= self.process_message(msg);
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    log::info!("HtmlRenderActor: no more messages");
+                    break;
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    log::info!("HtmlRenderActor: lagged message. Some events are dropped");
+                }
+            }
+            // read the queue to empty
+            while let Ok(msg) = self.mailbox.try_recv() {
+                has_full_render |= self.process_message(msg);
+            }
+
+            let Some(doc) = self.view().and_then(|view| view.html_doc()) else {
+                log::info!("HtmlRenderActor: document is not ready");
+                continue;
+            };
+            let unchanged = self
+                .doc
+                .as_ref()
+                .is_some_and(|last| Arc::ptr_eq(last, &doc));
+            if unchanged && !has_full_render {
+                continue;
+            }
+
+            self.doc = Some(doc.clone());
+            let Some(data) = self.render(has_full_render, &doc) else {
+                continue;
+            };
+            let Ok(_) = self.html_sender.send(data) else {
+                log::info!("HtmlRenderActor: html_sender is dropped");
+                break;
+            };
+        }
+        log::info!("HtmlRenderActor: exiting")
+    }
+
+    /// Renders the document to a full tree or patches against the last sent
+    /// trees.
+    #[typst_macros::time]
+    fn render(&mut self, has_full_render: bool, doc: &TypstHtmlDocument) -> Option<Vec<u8>> {
+        let trees = HtmlTree::from_doc(doc);
+
+        let msg = match self.trees.as_ref() {
+            Some(last) if !has_full_render => {
+                let patches = diff(last, &trees);
+                if patches.is_empty() {
+                    return None;
+                }
+                log::debug!("HtmlRenderActor: sending {} patches", patches.len());
+                format!("html-patch,{}", serde_json::to_string(&patches).ok()?)
+            }
+            _ => format!("html,{}", serde_json::to_string(&trees).ok()?),
+        };
+
+        self.trees = Some(trees);
+        Some(msg.into_bytes())
+    }
+
+    fn view(&self) -> Option<Arc<dyn CompileView>> {
+        self.view.read().clone()
+    }
+
+    fn resolve_span(&self, span: Span) -> Option<()> {
+        let info = self.view()?.resolve_span(span, None)?;
+        let _ = self
+            .editor_conn_sender
+            .send(EditorActorRequest::DocToSrcJump(info));
+
+        Some(())
+    }
+
+    fn resolve_paths(
+        &self,
+        filepath: impl Into<String>,
+        line: u32,
+        character: u32,
+    ) -> Option<Vec<Vec<usize>>> {
+        let view = self.view()?;
+        let span = view.resolve_source_span(Location::Src(SourceLocation {
+            filepath: filepath.into(),
+            pos: LspPosition { line, character },
+        }))?;
+        let doc = view.html_doc()?;
+
+        let paths = find_paths_by_span(&doc, span.span);
+        log::debug!("HtmlRenderActor: resolved element paths: {paths:?}");
+        (!paths.is_empty()).then_some(paths)
+    }
+}
@@ -1,3 +1,4 @@
 pub mod editor;
+pub mod html;
 pub mod render;
 pub mod webview;
@@ -5,17 +5,35 @@ use reflexo_typst::debug_loc::{
     DocumentPosition, ElementPoint, LspPosition, SourceLocation, SourceSpanOffset,
 };
 use reflexo_vec2svg::IncrSvgDocServer;
+use serde::Deserialize;
 use tinymist_std::typst::TypstDocument;
 use tokio::sync::{broadcast, mpsc};
+use typst::syntax::Span;
 
 use super::{editor::EditorActorRequest, webview::WebviewActorRequest};
 use crate::debug_loc::SpanInterner;
 use crate::outline::Outline;
-use crate::{ChangeCursorPositionRequest, CompileView, DocToSrcJumpInfo, ResolveSourceLocRequest};
+use crate::presenter::{speaker_notes, SpeakerNote};
+use crate::{
+    ChangeCursorPositionRequest, CompileView, DocToSrcJumpInfo, ParticipantCursorRequest,
+    ParticipantPresence, ResolveSourceLocRequest, ReviewCommentInfo,
+};
 
 #[derive(Debug, Clone)]
 pub struct ResolveSpanRequest(pub Vec<ElementPoint>);
 
+/// A review comment left at a point of the webview.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReviewCommentRequest {
+    /// The commented point in the document.
+    pub position: DocumentPosition,
+    /// The name of the reviewer.
+    #[serde(default)]
+    pub author: Option<String>,
+    /// The content of the comment.
+    pub text: String,
+}
+
 #[derive(Debug, Clone)]
 pub enum RenderActorRequest {
     RenderFullLatest,
@@ -25,6 +43,9 @@ pub enum RenderActorRequest {
     WebviewResolveFrameLoc(DocumentPosition),
     ResolveSourceLoc(ResolveSourceLocRequest),
     ChangeCursorPosition(ChangeCursorPositionRequest),
+    ParticipantCursor(ParticipantCursorRequest),
+    WebviewResolveHtmlSpan(Span),
+    WebviewReviewComment(ReviewCommentRequest),
 }
 
 impl RenderActorRequest {
@@ -37,6 +58,9 @@ impl RenderActorRequest {
             Self::ResolveSourceLoc(_) => false,
             Self::WebviewResolveFrameLoc(_) => false,
             Self::ChangeCursorPosition(_) => false,
+            Self::ParticipantCursor(_) => false,
+            Self::WebviewResolveHtmlSpan(_) => false,
+            Self::WebviewReviewComment(_) => false,
         }
     }
 }
@@ -48,6 +72,11 @@ pub struct RenderActor {
     editor_conn_sender: mpsc::UnboundedSender<EditorActorRequest>,
     svg_sender: mpsc::UnboundedSender<Vec<u8>>,
     webview_sender: broadcast::Sender<WebviewActorRequest>,
+
+    /// The label of speaker notes, which is only set in the slide mode.
+    notes_label: Option<String>,
+    /// The speaker notes last sent to the webview.
+    notes: Option<Vec<SpeakerNote>>,
 }
 
 impl RenderActor {
@@ -57,6 +86,7 @@ impl RenderActor {
         editor_conn_sender: mpsc::UnboundedSender<EditorActorRequest>,
         svg_sender: mpsc::UnboundedSender<Vec<u8>>,
         webview_sender: broadcast::Sender<WebviewActorRequest>,
+        notes_label: Option<String>,
     ) -> Self {
         let mut res = Self {
             mailbox,
@@ -65,6 +95,8 @@ impl RenderActor {
             editor_conn_sender,
             svg_sender,
             webview_sender,
+            notes_label,
+            notes: None,
         };
         res.renderer.set_should_attach_debug_info(true);
         res
@@ -116,6 +148,19 @@ impl RenderActor {
 
                 self.change_cursor_position(req);
             }
+            RenderActorRequest::ParticipantCursor(req) => {
+                log::debug!("RenderActor: processing ParticipantCursor: {req:?}");
+
+                self.participant_cursor(req);
+            }
+            RenderActorRequest::WebviewReviewComment(req) => {
+                log::debug!("RenderActor: resolving WebviewReviewComment: {req:?}");
+
+                self.review_comment(req);
+            }
+            RenderActorRequest::WebviewResolveHtmlSpan(..) => {
+                log::debug!("RenderActor: ignored html request: {msg:?}");
+            }
             RenderActorRequest::RenderFullLatest | RenderActorRequest::RenderIncremental => {}
         }
 
@@ -156,6 +201,13 @@ impl RenderActor {
                 log::info!("RenderActor: svg_sender is dropped");
                 break;
             };
+
+            if let Some(data) = self.render_notes(has_full_render, &document) {
+                let Ok(_) = self.svg_sender.send(data) else {
+                    log::info!("RenderActor: svg_sender is dropped");
+                    break;
+                };
+            }
         }
         log::info!("RenderActor: exiting")
     }
@@ -182,6 +234,23 @@ impl RenderActor {
         self.renderer.pack_delta(document)
     }
 
+    /// Renders the speaker notes if they are changed since the last render.
+    fn render_notes(&mut self, has_full_render: bool, document: &TypstDocument) -> Option<Vec<u8>> {
+        let label = self.notes_label.as_ref()?;
+        let TypstDocument::Paged(doc) = document else {
+            return None;
+        };
+
+        let notes = speaker_notes(doc, label);
+        if !has_full_render && self.notes.as_ref() == Some(&notes) {
+            return None;
+        }
+
+        let msg = format!("notes,{}", serde_json::to_string(&notes).ok()?);
+        self.notes = Some(notes);
+        Some(msg.into_bytes())
+    }
+
     fn view(&self) -> Option<Arc<dyn CompileView>> {
         self.view.read().clone()
     }
@@ -193,6 +262,22 @@ impl RenderActor {
         Some(())
     }
 
+    /// Pins the review comment to the source location of the commented point.
+    fn review_comment(&mut self, req: ReviewCommentRequest) -> Option<()> {
+        let spans = self.resolve_span_by_frame_loc(&req.position)?;
+        let location = self.resolve_span_range(spans.0..spans.1)?;
+        let info = ReviewCommentInfo {
+            location,
+            author: req.author,
+            text: req.text,
+        };
+        let _ = self
+            .editor_conn_sender
+            .send(EditorActorRequest::ReviewComment(info));
+
+        Some(())
+    }
+
     fn resolve_span_range(&self, range: Range<SourceSpanOffset>) -> Option<DocToSrcJumpInfo> {
         let view = self.view()?;
         // Resolves FileLoC of start, end, and the element wide
@@ -281,6 +366,35 @@ impl RenderActor {
         Some(())
     }
 
+    /// Shows the cursor of a participant in the webview.
+    fn participant_cursor(&self, req: ParticipantCursorRequest) -> Option<()> {
+        let cursor = req.cursor;
+        let info = self
+            .view()?
+            .resolve_document_position(crate::Location::Src(SourceLocation {
+                filepath: cursor.filepath.to_string_lossy().to_string(),
+                pos: LspPosition {
+                    line: cursor.line,
+                    character: cursor.character,
+                },
+            }));
+        let info = info.into_iter().next()?;
+
+        let presence = ParticipantPresence {
+            id: req.id,
+            name: req.name,
+            leader: req.leader,
+            page: info.page.into(),
+            x: info.point.x.to_pt() as f32,
+            y: info.point.y.to_pt() as f32,
+        };
+        let _ = self
+            .webview_sender
+            .send(WebviewActorRequest::Presence(presence));
+
+        Some(())
+    }
+
     fn resolve_source_loc(&self, req: ResolveSourceLocRequest) -> Option<()> {
         // todo: change name to resolve resolve src position
         let info = self
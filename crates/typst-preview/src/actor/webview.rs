@@ -6,7 +6,8 @@ use tokio::sync::{broadcast, mpsc};
 use super::{editor::EditorActorRequest, render::RenderActorRequest};
 use crate::{
     actor::{editor::DocToSrcJumpResolveRequest, render::ResolveSpanRequest},
-    WsMessage,
+    html::span_from_hex,
+    ParticipantPresence, WsMessage,
 };
 
 // pub type CursorPosition = DocumentPosition;
@@ -18,6 +19,14 @@ pub enum WebviewActorRequest {
     SrcToDocJump(Vec<SrcToDocJumpInfo>),
     // CursorPosition(CursorPosition),
     CursorPaths(Vec<Vec<ElementPoint>>),
+    HtmlCursorPaths(Vec<Vec<usize>>),
+    HtmlJumpPaths(Vec<Vec<usize>>),
+    /// Turns all slide previews to the page.
+    SlidePage(usize),
+    /// Shows the cursor of a participant of a shared preview.
+    Presence(ParticipantPresence),
+    /// Removes the cursor of a participant that left a shared preview.
+    ParticipantLeave(u64),
 }
 
 fn position_req(
@@ -107,6 +116,34 @@ where
                             self.webview_websocket_conn.send(WsMessage::Binary(msg.into_bytes()))
                               .await.log_error("WebViewActor");
                         }
+                        WebviewActorRequest::HtmlCursorPaths(paths) => {
+                            let json = serde_json::to_string(&paths).unwrap();
+                            let msg = format!("html-cursor,{json}");
+                            self.webview_websocket_conn.send(WsMessage::Binary(msg.into_bytes()))
+                              .await.log_error("WebViewActor");
+                        }
+                        WebviewActorRequest::HtmlJumpPaths(paths) => {
+                            let json = serde_json::to_string(&paths).unwrap();
+                            let msg = format!("html-jump,{json}");
+                            self.webview_websocket_conn.send(WsMessage::Binary(msg.into_bytes()))
+                              .await.log_error("WebViewActor");
+                        }
+                        WebviewActorRequest::SlidePage(page) => {
+                            let msg = format!("slide-sync,{page}");
+                            self.webview_websocket_conn.send(WsMessage::Binary(msg.into_bytes()))
+                              .await.log_error("WebViewActor");
+                        }
+                        WebviewActorRequest::Presence(presence) => {
+                            let json = serde_json::to_string(&presence).unwrap();
+                            let msg = format!("presence,{json}");
+                            self.webview_websocket_conn.send(WsMessage::Binary(msg.into_bytes()))
+                              .await.log_error("WebViewActor");
+                        }
+                        WebviewActorRequest::ParticipantLeave(id) => {
+                            let msg = format!("presence-leave,{id}");
+                            self.webview_websocket_conn.send(WsMessage::Binary(msg.into_bytes()))
+                              .await.log_error("WebViewActor");
+                        }
                     }
                 }
                 Some(svg) = self.svg_receiver.recv() => {
@@ -153,6 +190,23 @@ where
                             let path = path.into_iter().map(ElementPoint::from).collect::<Vec<_>>();
                             self.render_sender.send(RenderActorRequest::WebviewResolveSpan(ResolveSpanRequest(path))).log_error("WebViewActor");
                         };
+                    } else if msg.starts_with("html-srcspan") {
+                        let span = msg.split(' ').nth(1).and_then(span_from_hex);
+                        if let Some(span) = span {
+                            self.render_sender.send(RenderActorRequest::WebviewResolveHtmlSpan(span)).log_error("WebViewActor");
+                        }
+                    } else if msg.starts_with("slide-sync") {
+                        let page = msg.split(' ').nth(1).and_then(|page| page.trim().parse().ok());
+                        if let Some(page) = page {
+                            self.broadcast_sender.send(WebviewActorRequest::SlidePage(page)).log_error("WebViewActor");
+                        }
+                    } else if let Some(req) = msg.strip_prefix("review-comment ") {
+                        match serde_json::from_str(req) {
+                            Ok(req) => {
+                                self.render_sender.send(RenderActorRequest::WebviewReviewComment(req)).log_error("WebViewActor");
+                            }
+                            Err(err) => log::warn!("WebviewActor: invalid review comment: {err}"),
+                        }
                     } else if msg.starts_with("src-point") {
                         let path = msg.split(' ').nth(1).unwrap();
                         let path = serde_json::from_str(path);
@@ -0,0 +1,353 @@
+//! The live HTML preview, which mirrors the HTML export of a document into the
+//! webview and keeps it up to date by DOM patches.
+
+use std::num::NonZeroU64;
+
+use serde::Serialize;
+use tinymist_std::typst::TypstHtmlDocument;
+use typst::html::{tag, HtmlElement, HtmlNode};
+use typst::syntax::Span;
+
+/// The attribute carrying the span of an element, in hex format.
+pub const SPAN_ATTR: &str = "data-tinymist-span";
+
+/// A node of the HTML tree sent to the webview.
+///
+/// The tree maps one-to-one to the DOM nodes created by the webview, so a
+/// node can be addressed by the child indices from the mounted root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum HtmlTree {
+    /// An element.
+    Element {
+        /// The tag name.
+        tag: String,
+        /// The attributes.
+        attrs: Vec<(String, String)>,
+        /// The child nodes.
+        children: Vec<HtmlTree>,
+    },
+    /// A text node.
+    Text {
+        /// The text content.
+        text: String,
+    },
+    /// A frame rendered as an inline SVG.
+    Frame {
+        /// The SVG markup.
+        svg: String,
+    },
+}
+
+/// A DOM patch. The paths are child indices from the mounted root, resolved
+/// against the DOM after applying the previous patches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum HtmlPatch {
+    /// Replaces the node at the path.
+    Replace {
+        /// The path to the node.
+        path: Vec<usize>,
+        /// The new node.
+        node: HtmlTree,
+    },
+    /// Replaces the attributes of the element at the path.
+    Attrs {
+        /// The path to the element.
+        path: Vec<usize>,
+        /// The new attributes.
+        attrs: Vec<(String, String)>,
+    },
+    /// Inserts a node into the element at the path.
+    Insert {
+        /// The path to the parent element.
+        path: Vec<usize>,
+        /// The index to insert at.
+        index: usize,
+        /// The inserted node.
+        node: HtmlTree,
+    },
+    /// Removes a child from the element at the path.
+    Remove {
+        /// The path to the parent element.
+        path: Vec<usize>,
+        /// The index of the removed child.
+        index: usize,
+    },
+}
+
+impl HtmlTree {
+    /// Converts the mounted part of the document to trees.
+    pub fn from_doc(doc: &TypstHtmlDocument) -> Vec<HtmlTree> {
+        mount_root(doc).map(Self::from_node).collect()
+    }
+
+    fn from_node(node: &HtmlNode) -> HtmlTree {
+        match node {
+            HtmlNode::Element(elem) => {
+                let mut attrs = elem
+                    .attrs
+                    .0
+                    .iter()
+                    .map(|(name, value)| (name.resolve().to_string(), value.to_string()))
+                    .collect::<Vec<_>>();
+                if !elem.span.is_detached() {
+                    attrs.push((SPAN_ATTR.to_owned(), span_to_hex(elem.span)));
+                }
+
+                HtmlTree::Element {
+                    tag: elem.tag.resolve().to_string(),
+                    attrs,
+                    children: children(elem).map(Self::from_node).collect(),
+                }
+            }
+            HtmlNode::Text(text, _) => HtmlTree::Text {
+                text: text.to_string(),
+            },
+            HtmlNode::Frame(frame) => HtmlTree::Frame {
+                svg: typst_svg::svg_frame(frame),
+            },
+            HtmlNode::Tag(..) => unreachable!("tags are filtered out"),
+        }
+    }
+}
+
+/// Computes the patches turning the `old` trees into the `new` trees.
+pub fn diff(old: &[HtmlTree], new: &[HtmlTree]) -> Vec<HtmlPatch> {
+    let mut patches = vec![];
+    diff_children(&mut vec![], old, new, &mut patches);
+    patches
+}
+
+fn diff_children(
+    path: &mut Vec<usize>,
+    old: &[HtmlTree],
+    new: &[HtmlTree],
+    patches: &mut Vec<HtmlPatch>,
+) {
+    // Skips the common prefix and suffix, so that inserting or removing a
+    // paragraph only patches the paragraph.
+    let prefix = old.iter().zip(new).take_while(|(x, y)| x == y).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+
+    let common = old.len().min(new.len());
+    for (idx, (old, new)) in old.iter().zip(new).enumerate() {
+        path.push(prefix + idx);
+        diff_node(path, old, new, patches);
+        path.pop();
+    }
+    for (idx, node) in new.iter().enumerate().skip(common) {
+        patches.push(HtmlPatch::Insert {
+            path: path.clone(),
+            index: prefix + idx,
+            node: node.clone(),
+        });
+    }
+    for _ in common..old.len() {
+        patches.push(HtmlPatch::Remove {
+            path: path.clone(),
+            index: prefix + common,
+        });
+    }
+}
+
+fn diff_node(path: &mut Vec<usize>, old: &HtmlTree, new: &HtmlTree, patches: &mut Vec<HtmlPatch>) {
+    match (old, new) {
+        (
+            HtmlTree::Element {
+                tag: old_tag,
+                attrs: old_attrs,
+                children: old_children,
+            },
+            HtmlTree::Element {
+                tag: new_tag,
+                attrs: new_attrs,
+                children: new_children,
+            },
+        ) if old_tag == new_tag => {
+            if old_attrs != new_attrs {
+                patches.push(HtmlPatch::Attrs {
+                    path: path.clone(),
+                    attrs: new_attrs.clone(),
+                });
+            }
+            diff_children(path, old_children, new_children, patches);
+        }
+        _ if old == new => {}
+        _ => patches.push(HtmlPatch::Replace {
+            path: path.clone(),
+            node: new.clone(),
+        }),
+    }
+}
+
+/// Finds the paths to the elements generated by the span, or containing a text
+/// generated by the span.
+pub fn find_paths_by_span(doc: &TypstHtmlDocument, span: Span) -> Vec<Vec<usize>> {
+    fn walk<'a>(
+        nodes: impl Iterator<Item = &'a HtmlNode>,
+        span: Span,
+        path: &mut Vec<usize>,
+        res: &mut Vec<Vec<usize>>,
+    ) {
+        for (idx, node) in nodes.enumerate() {
+            match node {
+                HtmlNode::Element(elem) => {
+                    path.push(idx);
+                    if elem.span == span {
+                        res.push(path.clone());
+                    } else {
+                        walk(children(elem), span, path, res);
+                    }
+                    path.pop();
+                }
+                HtmlNode::Text(_, text_span) if *text_span == span => {
+                    if !path.is_empty() && res.last().map(Vec::as_slice) != Some(path.as_slice()) {
+                        res.push(path.clone());
+                    }
+                }
+                HtmlNode::Text(..) | HtmlNode::Frame(..) | HtmlNode::Tag(..) => {}
+            }
+        }
+    }
+
+    let mut res = vec![];
+    if !span.is_detached() {
+        walk(mount_root(doc), span, &mut vec![], &mut res);
+    }
+    res
+}
+
+/// Encodes a span to be carried by an element.
+pub fn span_to_hex(span: Span) -> String {
+    format!("{:x}", span.into_raw().get())
+}
+
+/// Decodes a span carried by an element.
+pub fn span_from_hex(hex: &str) -> Option<Span> {
+    let raw = u64::from_str_radix(hex.trim(), 16).ok()?;
+    Some(Span::from_raw(NonZeroU64::new(raw)?))
+}
+
+/// Gets the nodes mounted into the webview, which are the children of the body
+/// element.
+fn mount_root(doc: &TypstHtmlDocument) -> impl Iterator<Item = &HtmlNode> {
+    let root = &doc.root;
+    let body = (root.tag == tag::html)
+        .then(|| {
+            root.children.iter().find_map(|node| match node {
+                HtmlNode::Element(elem) if elem.tag == tag::body => Some(elem),
+                _ => None,
+            })
+        })
+        .flatten();
+
+    children(body.unwrap_or(root))
+}
+
+/// Gets the child nodes that are rendered to the DOM.
+fn children(elem: &HtmlElement) -> impl Iterator<Item = &HtmlNode> {
+    elem.children
+        .iter()
+        .filter(|node| !matches!(node, HtmlNode::Tag(..)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> HtmlTree {
+        HtmlTree::Text { text: text.into() }
+    }
+
+    fn elem(tag: &str, children: Vec<HtmlTree>) -> HtmlTree {
+        HtmlTree::Element {
+            tag: tag.into(),
+            attrs: vec![],
+            children,
+        }
+    }
+
+    fn apply(trees: &mut Vec<HtmlTree>, patches: Vec<HtmlPatch>) {
+        fn children_at<'a>(trees: &'a mut Vec<HtmlTree>, path: &[usize]) -> &'a mut Vec<HtmlTree> {
+            let Some((first, rest)) = path.split_first() else {
+                return trees;
+            };
+            match &mut trees[*first] {
+                HtmlTree::Element { children, .. } => children_at(children, rest),
+                _ => panic!("not an element"),
+            }
+        }
+
+        for patch in patches {
+            match patch {
+                HtmlPatch::Replace { path, node } => {
+                    let (last, parent) = path.split_last().unwrap();
+                    children_at(trees, parent)[*last] = node;
+                }
+                HtmlPatch::Attrs { path, attrs: new } => {
+                    let (last, parent) = path.split_last().unwrap();
+                    match &mut children_at(trees, parent)[*last] {
+                        HtmlTree::Element { attrs, .. } => *attrs = new,
+                        _ => panic!("not an element"),
+                    }
+                }
+                HtmlPatch::Insert { path, index, node } => {
+                    children_at(trees, &path).insert(index, node);
+                }
+                HtmlPatch::Remove { path, index } => {
+                    children_at(trees, &path).remove(index);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_diff_insert_paragraph() {
+        let old = vec![elem("p", vec![text("a")]), elem("p", vec![text("c")])];
+        let new = vec![
+            elem("p", vec![text("a")]),
+            elem("p", vec![text("b")]),
+            elem("p", vec![text("c")]),
+        ];
+
+        let patches = diff(&old, &new);
+        assert_eq!(
+            patches,
+            vec![HtmlPatch::Insert {
+                path: vec![],
+                index: 1,
+                node: elem("p", vec![text("b")]),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_roundtrip() {
+        let old = vec![
+            elem("h2", vec![text("Title")]),
+            elem("p", vec![text("a"), elem("strong", vec![text("b")])]),
+            elem(
+                "ul",
+                vec![elem("li", vec![text("1")]), elem("li", vec![text("2")])],
+            ),
+        ];
+        let new = vec![
+            elem("h3", vec![text("Title")]),
+            elem("p", vec![text("a"), elem("em", vec![text("b")]), text("c")]),
+            elem("ul", vec![elem("li", vec![text("2")])]),
+        ];
+
+        let mut patched = old.clone();
+        apply(&mut patched, diff(&old, &new));
+        assert_eq!(patched, new);
+        assert!(diff(&new, &new).is_empty());
+    }
+}
@@ -1,12 +1,16 @@
 mod actor;
 mod debug_loc;
+mod html;
 mod outline;
+mod presenter;
 
 pub use crate::actor::editor::{
     CompileStatus, ControlPlaneMessage, ControlPlaneResponse, ControlPlaneRx, ControlPlaneTx,
-    PanelScrollByPositionRequest,
+    PanelScrollByPositionRequest, PanelSlideToRequest,
 };
+pub use crate::html::{HtmlPatch, HtmlTree};
 pub use crate::outline::Outline;
+pub use crate::presenter::{speaker_notes, SpeakerNote, DEFAULT_NOTES_LABEL};
 
 use std::sync::{Arc, OnceLock};
 use std::{collections::HashMap, future::Future, path::PathBuf, pin::Pin};
@@ -17,7 +21,7 @@ use reflexo_typst::debug_loc::{DocumentPosition, SourceSpanOffset};
 use reflexo_typst::Error;
 use serde::{Deserialize, Serialize};
 use tinymist_std::error::IgnoreLogging;
-use tinymist_std::typst::TypstDocument;
+use tinymist_std::typst::{TypstDocument, TypstHtmlDocument};
 use tokio::sync::{broadcast, mpsc};
 use typst::{layout::Position, syntax::Span};
 
@@ -29,27 +33,37 @@ use crate::debug_loc::SpanInterner;
 type StopFuture = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
 
 // enum Preview Mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 pub enum PreviewMode {
     /// Preview mode for regular document
     #[cfg_attr(feature = "clap", clap(name = "document"))]
+    #[default]
     Document,
 
     /// Preview mode for slide
     #[cfg_attr(feature = "clap", clap(name = "slide"))]
     Slide,
+
+    /// Preview mode for the HTML export of the document
+    #[cfg_attr(feature = "clap", clap(name = "html"))]
+    Html,
 }
 
 /// The configurations about the preview.
 #[derive(Debug, Clone, Default)]
 pub struct PreviewConfig {
+    /// The preview mode.
+    pub mode: PreviewMode,
     /// Whether to enable partial rendering.
     pub enable_partial_rendering: bool,
     /// The refresh style of the preview.
     pub refresh_style: TaskWhen,
     /// The invert colors setting for the preview.
     pub invert_colors: String,
+    /// The label of the metadata carrying speaker notes in the slide mode,
+    /// defaults to [`DEFAULT_NOTES_LABEL`].
+    pub notes_label: Option<String>,
 }
 
 /// Get the HTML for the frontend by a given preview mode and server to connect
@@ -57,6 +71,7 @@ pub fn frontend_html(html: &str, mode: PreviewMode, to: &str) -> String {
     let mode = match mode {
         PreviewMode::Document => "Doc",
         PreviewMode::Slide => "Slide",
+        PreviewMode::Html => "Html",
     };
 
     html.replace("ws://127.0.0.1:23625", to).replace(
@@ -142,14 +157,27 @@ impl Previewer {
                     h.editor_tx.clone(),
                     h.renderer_tx.clone(),
                 );
-                let render_actor = actor::render::RenderActor::new(
-                    h.renderer_tx.subscribe(),
-                    h.doc_sender.clone(),
-                    h.editor_tx.clone(),
-                    svg.0,
-                    h.webview_tx,
-                );
-                tokio::spawn(render_actor.run());
+                if matches!(h.mode, PreviewMode::Html) {
+                    let render_actor = actor::html::HtmlRenderActor::new(
+                        h.renderer_tx.subscribe(),
+                        h.doc_sender.clone(),
+                        h.editor_tx.clone(),
+                        svg.0,
+                        h.webview_tx,
+                    );
+                    tokio::spawn(render_actor.run());
+                } else {
+                    let notes_label = matches!(h.mode, PreviewMode::Slide).then_some(h.notes_label);
+                    let render_actor = actor::render::RenderActor::new(
+                        h.renderer_tx.subscribe(),
+                        h.doc_sender.clone(),
+                        h.editor_tx.clone(),
+                        svg.0,
+                        h.webview_tx,
+                        notes_label,
+                    );
+                    tokio::spawn(render_actor.run());
+                }
                 let outline_render_actor = actor::render::OutlineRenderActor::new(
                     h.renderer_tx.subscribe(),
                     h.doc_sender.clone(),
@@ -238,6 +266,12 @@ impl PreviewBuilder {
         self
     }
 
+    /// Gets a reader of the last compiled view, which is updated by the
+    /// [`CompileWatcher`] of the preview.
+    pub fn view_reader(&self) -> CompileViewReader {
+        CompileViewReader(self.doc_sender.clone())
+    }
+
     pub fn compile_watcher(&self, task_id: String) -> &Arc<CompileWatcher> {
         self.compile_watcher.get_or_init(|| {
             Arc::new(CompileWatcher {
@@ -283,6 +317,10 @@ impl PreviewBuilder {
             webview_tx: webview_tx.clone(),
             editor_tx: editor_tx.clone(),
             invert_colors: config.invert_colors,
+            mode: config.mode,
+            notes_label: config
+                .notes_label
+                .unwrap_or_else(|| DEFAULT_NOTES_LABEL.to_owned()),
             renderer_tx: renderer_mailbox.0.clone(),
             enable_partial_rendering: config.enable_partial_rendering,
             doc_sender,
@@ -340,7 +378,20 @@ pub struct DocToSrcJumpInfo {
     pub end: Option<(usize, usize)>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// A review comment left in the preview, which is pinned to the source location
+/// of the commented point.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReviewCommentInfo {
+    /// The source location of the commented point.
+    #[serde(flatten)]
+    pub location: DocToSrcJumpInfo,
+    /// The name of the reviewer.
+    pub author: Option<String>,
+    /// The content of the comment.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChangeCursorPositionRequest {
     filepath: PathBuf,
     line: u32,
@@ -349,6 +400,44 @@ pub struct ChangeCursorPositionRequest {
     character: u32,
 }
 
+/// The cursor of a participant in a preview shared by several editors.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParticipantCursorRequest {
+    /// The id of the participant.
+    pub id: u64,
+    /// The name of the participant.
+    pub name: String,
+    /// Whether the participant is the leader driving the preview.
+    pub leader: bool,
+    /// The cursor of the participant in the source.
+    pub cursor: ChangeCursorPositionRequest,
+}
+
+/// A participant that left a shared preview.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParticipantLeaveRequest {
+    /// The id of the participant.
+    pub id: u64,
+}
+
+/// The position of a participant's cursor in the document, which is shown in
+/// the preview.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParticipantPresence {
+    /// The id of the participant.
+    pub id: u64,
+    /// The name of the participant.
+    pub name: String,
+    /// Whether the participant is the leader driving the preview.
+    pub leader: bool,
+    /// The 1-based page of the cursor.
+    pub page: usize,
+    /// The horizontal position of the cursor in the page.
+    pub x: f32,
+    /// The vertical position of the cursor in the page.
+    pub y: f32,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ResolveSourceLocRequest {
     pub filepath: PathBuf,
@@ -375,9 +464,33 @@ pub struct MemoryFilesShort {
     // mtime: Option<u64>,
 }
 
+/// Reads the last compiled view of a preview.
+#[derive(Clone)]
+pub struct CompileViewReader(Arc<parking_lot::RwLock<Option<Arc<dyn CompileView>>>>);
+
+impl CompileViewReader {
+    /// Gets the last compiled document, if any.
+    pub fn doc(&self) -> Option<TypstDocument> {
+        self.0.read().as_ref()?.doc()
+    }
+}
+
+/// Gets the outline of the document. The span ids in the outline are interned
+/// freshly, so they cannot be resolved by the previewer.
+pub fn document_outline(document: &TypstDocument) -> Outline {
+    outline::outline(&mut debug_loc::SpanInternerImpl::new(), document)
+}
+
 pub trait CompileView: Send + Sync {
     /// Get the compiled document.
     fn doc(&self) -> Option<TypstDocument>;
+    /// Get the compiled HTML document, which is used by the HTML preview.
+    fn html_doc(&self) -> Option<Arc<TypstHtmlDocument>> {
+        match self.doc()? {
+            TypstDocument::Html(doc) => Some(doc),
+            TypstDocument::Paged(..) => None,
+        }
+    }
     /// Get the compile status.
     fn status(&self) -> CompileStatus;
 
@@ -471,6 +584,8 @@ struct DataPlane {
     editor_tx: mpsc::UnboundedSender<EditorActorRequest>,
     enable_partial_rendering: bool,
     invert_colors: String,
+    mode: PreviewMode,
+    notes_label: String,
     renderer_tx: broadcast::Sender<RenderActorRequest>,
     doc_sender: Arc<parking_lot::RwLock<Option<Arc<dyn CompileView>>>>,
 }
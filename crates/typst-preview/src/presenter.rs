@@ -0,0 +1,104 @@
+//! The presenter console of slide previews.
+
+use std::num::NonZeroUsize;
+
+use serde::Serialize;
+use tinymist_std::typst::TypstPagedDocument;
+use typst::foundations::{Label, Selector, Value};
+use typst::introspection::MetadataElem;
+use typst::utils::PicoStr;
+
+/// The default label of the metadata carrying speaker notes, which is used by
+/// the pdfpc support of Touying and Polylux.
+pub const DEFAULT_NOTES_LABEL: &str = "pdfpc";
+
+/// The speaker note of a slide.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpeakerNote {
+    /// The 1-based page number of the slide.
+    pub page: NonZeroUsize,
+    /// The note in plain text.
+    pub note: String,
+}
+
+/// Extracts speaker notes from the metadata labelled by `label`.
+///
+/// The metadata can either be a pdfpc entry, i.e. `(t: "Note", v: ..)`, or a
+/// string or content, which is taken as the note of the slide containing it.
+/// Other pdfpc entries are ignored. Notes on the same slide are joined by
+/// lines.
+pub fn speaker_notes(doc: &TypstPagedDocument, label: &str) -> Vec<SpeakerNote> {
+    let label = Label::new(PicoStr::intern(label));
+    let elems = doc.introspector.query(&Selector::Label(label));
+
+    let mut notes: Vec<SpeakerNote> = vec![];
+    for elem in elems.iter() {
+        let Some(metadata) = elem.to_packed::<MetadataElem>() else {
+            continue;
+        };
+        let Some(note) = note_text(&metadata.value) else {
+            continue;
+        };
+        let Some(loc) = elem.location() else {
+            continue;
+        };
+        let page = doc.introspector.page(loc);
+
+        match notes.iter_mut().find(|n| n.page == page) {
+            Some(existing) => {
+                existing.note.push('\n');
+                existing.note.push_str(&note);
+            }
+            None => notes.push(SpeakerNote { page, note }),
+        }
+    }
+
+    notes.sort_by_key(|n| n.page);
+    notes
+}
+
+fn note_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Str(s) => Some(s.to_string()),
+        Value::Content(c) => Some(c.plain_text().to_string()),
+        Value::Dict(dict) => {
+            let kind = dict.get("t").ok()?;
+            if !matches!(kind, Value::Str(s) if s.as_str() == "Note") {
+                return None;
+            }
+            note_text(dict.get("v").ok()?)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::foundations::{Dict, Str};
+
+    use super::*;
+
+    fn pdfpc(kind: &str, value: &str) -> Value {
+        let dict: Dict = [
+            (Str::from("t"), Value::Str(kind.into())),
+            (Str::from("v"), Value::Str(value.into())),
+        ]
+        .into_iter()
+        .collect();
+        Value::Dict(dict)
+    }
+
+    #[test]
+    fn test_note_text() {
+        assert_eq!(
+            note_text(&pdfpc("Note", "Say hello")),
+            Some("Say hello".to_owned())
+        );
+        assert_eq!(note_text(&pdfpc("Duration", "20")), None);
+        assert_eq!(
+            note_text(&Value::Str("Plain".into())),
+            Some("Plain".to_owned())
+        );
+        assert_eq!(note_text(&Value::None), None);
+    }
+}
@@ -47,6 +47,13 @@ Supported format:
 - `output.md`: Markdown
 - `output.tex`: LaTeX
 - `output.docx`: Word
+- `output.odt`: OpenDocument Text, e.g. for LibreOffice
+
+The format can also be specified by `--format`, e.g. `--format pandoc-json` writes the JSON AST of #link("https://pandoc.org/")[pandoc], which can be piped into pandoc to convert the document to other formats supported by pandoc, such as reStructuredText and AsciiDoc:
+
+```shell
+typlite main.typ --format pandoc-json - | pandoc -f json -o main.rst
+```
 
 Todo: We may support custom format by typst scripting in future, like:
 
@@ -58,6 +65,37 @@ typlite main.typ --post-process @preview/typlite-mdx output.mdx
 = Feature
 
 - *Contexual Content Rendering*: Contents begin with `context` keyword will be rendered as svg output. The svg output will be embedded inline in the output file as *base64* by default, if the `--assets-path` parameter is not specified. Otherwise, the svg output will be saved in the specified folder and the path will be embedded in the output file.
+- *Native Math*: Equations are rendered as svg output by default. Pass `--math-output=latex` to write them in LaTeX instead, e.g. `$x^2$` and `$$ ... $$` in Markdown, which can be rendered by KaTeX or MathJax. Fractions, attachments, roots, matrices, cases, accents and common symbols are converted. Equations are always written as Word equations (Office Math) in DOCX, and embedded as pictures in ODT.
+- *Footnotes and Citations*: Footnotes are written as GFM footnotes, e.g. `[^1]`, in Markdown, `\footnote{}` in LaTeX and footnotes in DOCX and ODT. Citations keep the text rendered by typst, and the bibliography is rendered as a list of references in the order of citations. In LaTeX, citations are written as `\cite{}` and the BibLaTeX sources of the bibliography are copied next to the output file. The CSL style is mapped to the equivalent BibTeX style, e.g. `ieee` to `IEEEtran` and `apa` to `apalike`, or `plain` if there is none.
+- *Word Templates*: Pass `--reference-doc reference.docx` to reuse the styles, page setup, headers, footers and numbering of a Word template in DOCX. The `outline()` is written as a table of contents field, which is filled when Word updates the fields. The settings of `set text` and `set par` differing from the defaults are converted to paragraph styles, and references to the headings, figures and equations are written as cross-reference fields to the bookmarks at them.
+- *LaTeX Templates*: Pass `--template template.tex` to wrap the LaTeX body in a template, whose `{{title}}`, `{{authors}}` and `{{body}}` placeholders are filled from the document. Labels of headings, figures and equations are written as `\label{}`, and references to them as `\ref{}` or `\eqref{}` following the supplements rendered by typst, e.g. `Section~\ref{sec:methods}`.
+- *Source Maps*: Pass `--source-map` to write a source map next to the Markdown, LaTeX or text output, e.g. `main.md.map`. It is a JSON file with one entry per top-level block in the output, mapping its lines (`startLine` and `endLine`, starting from 1) to the typst file (`file` in the workspace and `path` in the file system) and the byte `range` in the file.
+
+= Documentation Sites
+
+`--site` splits the document into one Markdown file per chapter, which can be published by #link("https://rust-lang.github.io/mdBook/")[mdBook] or #link("https://docusaurus.io/")[Docusaurus]:
+
+```shell
+# writes book/src/*.md and book/src/SUMMARY.md
+typlite handbook.typ book/src --site mdbook
+# writes docs/*.md and docs/sidebars.json, split at `=` and `==` headings
+typlite handbook.typ docs --site docusaurus --split-level 2
+```
+
+The document is split at the headings whose levels are not greater than `--split-level`, and the content before the first heading goes to `index.md`. References and links to labels are rewritten to links to the anchors of the headings in the chapters. Images and rendered frames are written into the `assets` directory. For Docusaurus, the generated sidebar can be loaded by `sidebars.js`, e.g. `module.exports = require("./docs/sidebars.json");`.
+
+= Importing Markdown
+
+`typlite import` converts a Markdown document into typst markup:
+
+```shell
+# default output is README.typ
+typlite import README.md
+# read from stdin and write to stdout
+cat README.md | typlite import - -
+```
+
+CommonMark and GFM elements are converted, including tables, task lists, strikethrough, footnotes and alerts. The common subset of math in LaTeX is translated into typst math, and the rest is kept as raw LaTeX, or written by #link("https://typst.app/universe/package/mitex")[mitex] with `--mitex`. The YAML front matter is converted into a `#set document(..)` rule and a metadata labelled `<front-matter>`. In VS Code, the command "Paste Markdown as Typst" pastes the Markdown in the clipboard as typst markup.
 
 = Typlite-Specific `sys.inputs`
 
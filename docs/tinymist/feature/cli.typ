@@ -60,8 +60,62 @@ To save the compilation command to the lock file at the path `some/tinymist.lock
 tinymist compile --lockfile some/tinymist.lock path/to/main.typ
 ```
 
+To write the files that the output depends on, as a Makefile depfile (also understood by Ninja) or as a JSON file:
+
+```bash
+tinymist compile --make-deps main.d --deps-json main.deps.json path/to/main.typ
+```
+
+The dependencies include the source files, assets, package files, and the font files used by the compilation.
+
 The lock file feature is in development. It is to help the language server to understand the structure of your projects. See #github-link("/editors/vscode/Configuration.md#tinymistprojectresolution")[Configuration: tinymist.projectResolution].
 
+== Reproducible Builds
+
+To compile a document reproducibly, set the `SOURCE_DATE_EPOCH` environment variable and pass `--reproducible`:
+
+```bash
+SOURCE_DATE_EPOCH=$(git log -1 --format=%ct) tinymist compile --reproducible --save-lock path/to/main.typ
+```
+
+In the reproducible mode, the creation timestamp is taken from `SOURCE_DATE_EPOCH`, system fonts are ignored unless included via `--font-path`, and the contents of the used packages are checked against the ones pinned in the lock file. With `--save-lock`, the used packages are pinned to the lock file by the SHA-256 digest of their used files, listed in the format of `sha256sum` and sorted by path.
+
+To verify that an artifact is produced from the sources, rebuild it reproducibly and compare it byte by byte:
+
+```bash
+SOURCE_DATE_EPOCH=$(git log -1 --format=%ct) tinymist verify path/to/main.typ path/to/main.pdf
+```
+
+== Comparing against a Git Revision
+
+To review the changes of a document since a git revision, compare the document in the working tree against the one at the revision:
+
+```bash
+tinymist diff path/to/main.typ --rev HEAD~1 -o main.diff.pdf --open
+```
+
+The files under the project root are read from the revision by the local `git` command, so the working tree is never checked out. Pages are aligned by their text, so that an inserted or removed page does not shift the comparison of the following pages. The report shows the changed pages side by side, with the changed pixels highlighted, followed by the changed lines of text. Pass an output path ending with `.png` to get the report as an image, and `--ppi` to adjust the resolution of the rendered pages.
+
+To review the changes in the browser instead of writing a report, pass `--serve`, which serves the report with the preview server. The served report is a snapshot of the working tree when the command runs, and is not updated when the files change, so run the command again after editing the document:
+
+```bash
+tinymist diff path/to/main.typ --rev HEAD~1 --serve
+```
+
+== Managing the Lock File
+
+To migrate a lock file written by an older version of tinymist to the current schema in place:
+
+```bash
+tinymist project migrate --lockfile some/tinymist.lock
+```
+
+To generate the JSON schema of the lock file, which can be used by editors to validate `tinymist.lock`:
+
+```bash
+tinymist project schema --output tinymist.lock.schema.json
+```
+
 == Running Tests
 
 To run tests, you can use the `test` command, which is also compatible with `typst compile`:
@@ -185,6 +185,43 @@ After configuring the tasks, you can run them using the command palette.
 + Type `Run Task` and select the task you want to run.
 + Select the task you want to run.
 
+= Running Hooks after Exporting
+
+You can run hooks after the language server exports a document, for example, to copy the exported PDF to a synced folder or to refresh a local web server. A hook is either a local command or a webhook receiving a JSON payload by `POST`:
+
+```json
+{
+  "tinymist.exportPdf": "onSave",
+  "tinymist.exportHooks": {
+    "onSuccess": [
+      { "type": "command", "command": "sh", "args": ["-c", "cp \"$TINYMIST_ARTIFACT\" ~/Sync/"] },
+      { "type": "webhook", "url": "http://localhost:8080/refresh", "timeout": 5 }
+    ],
+    "onFailure": [
+      { "type": "command", "command": "notify-send", "args": ["Typst export failed"] }
+    ]
+  }
+}
+```
+
+A command hook is run with the following environment variables:
+- `TINYMIST_TASK_ID`: the ID of the exported project.
+- `TINYMIST_ARTIFACT`: the path to the exported artifact, which is empty if nothing is exported.
+- `TINYMIST_DIAGNOSTICS`: the number of diagnostics of the compilation.
+- `TINYMIST_STATUS`: either `success` or `failure`.
+
+A webhook receives the same information in a JSON object with the fields `taskId`, `success`, `artifact`, `diagnostics`, and `error`. Hooks run in order and time out after 30 seconds by default, which can be changed by the `timeout` field in seconds. The output of the hooks is written to the log of the language server. The failure hooks only run when a document is saved, since a document fails to compile frequently while it is being typed.
+
+The hooks can also be attached to an export task in the lock file by the `on-success` and `on-failure` fields. `tinymist compile` runs the hooks of the task having the same ID in the lock file, while the language server doesn't run the hooks in a lock file.
+
+*Security:* a hook runs arbitrary commands on your machine, so you should only configure hooks you trust. The language server only runs the hooks in its own configuration, and VS Code only reads `tinymist.exportHooks` from the user settings, which means that the settings of an opened workspace, e.g. `.vscode/settings.json`, cannot run commands on your machine. If you configure hooks in another editor, make sure they are not loaded from the project-local configuration of untrusted projects. Likewise, check the lock file of an untrusted project before running `tinymist compile` in it.
+
+= Copying a Selection
+
+The `Typst: Copy as Markdown`, `Typst: Copy as LaTeX` and `Typst: Copy as Plain Text` commands convert the selected paragraph, table or equation through typlite and put the result on the clipboard. The selection is converted with the imports of the file, its definitions, and the set and show rules before the selection, while the show rules applying templates, e.g. `#show: template`, are skipped. Equations are written in LaTeX.
+
+Other editors can call the `tinymist.copyAs` command with the path to the file and an object containing the `range` of the selection and the `format`, which is one of `markdown`, `latex` and `text`. The command returns the converted text.
+
 = Neovim: Export Commands
 
 You can call the following export commands.
@@ -194,6 +231,7 @@ You can call the following export commands.
 - `tinymist.exportHtml`
 - `tinymist.exportMarkdown`
 - `tinymist.exportTeX`
+- `tinymist.exportOdt`
 - `tinymist.exportText`
 - `tinymist.exportQuery`
 
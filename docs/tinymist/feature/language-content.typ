@@ -30,6 +30,7 @@ Language service (LSP) features:
 - #link("https://code.visualstudio.com/api/language-extensions/programmatic-language-features#show-hovers")[Hover tips]
   - Also known as "hovering tooltip".
   - Render docs according to #link("https://github.com/Mc-Zen/tidy")[tidy] style.
+  - With `hoverPeriscope` configured, show the thumbnail of the referenced figure, equation or table on hovering a label or a reference, and of the content bound by a `#let` binding on hovering the variable. The thumbnails are also shown in the documentation of `@label` completions. Set `thumbnailFormat` in `hoverPeriscope` to `"png"` for the editors not rendering SVG images in markdown.
 - #link("https://www.jetbrains.com/help/idea/inlay-hints.html")[Inlay hints]
   - Inlay hints are special markers that appear in the editor and provide you with additional information about your code, like the names of the parameters that a called method expects.
 - #link("https://code.visualstudio.com/api/language-extensions/programmatic-language-features#show-color-decorators")[Color Provider]
@@ -100,6 +100,122 @@ is equivalent to
 tinymist preview /abs-path/to/main.typ --partial-rendering
 ```
 
+== HTML Preview
+
+To preview the HTML export of a document instead of its pages, pass `--preview-mode=html`:
+
+```bash
+tinymist preview /abs-path/to/main.typ --preview-mode=html
+```
+
+The preview is updated by patching only the changed elements of the page. Clicking on an element jumps to its source code, and moving the cursor in the editor highlights the corresponding elements in the preview.
+
+In editors, you can add `--preview-mode=html` to the configuration `tinymist.preview.browsing.args` to start the HTML preview by the `tinymist.startDefaultPreview` command.
+
+== Presenter Mode
+
+When previewing slides with `--preview-mode=slide`, the preview server also serves a presenter console at the `/presenter` path, e.g. `http://127.0.0.1:23625/presenter`. The console shows the current slide, the next slide, a timer and the speaker notes of the current slide. Open the usual preview URL in another window for the audience. All slide previews turn pages together, so you can drive the talk from either window.
+
+The timer counts the elapsed time and is reset by clicking on it. Pass the planned duration in minutes to also show the remaining time, e.g. `/presenter?duration=20`.
+
+Speaker notes are read from the metadata labelled with `<pdfpc>`, which is emitted by `#speaker-note` of #link("https://touying-typ.github.io/")[Touying] and `#pdfpc.speaker-note` of #link("https://polylux.dev/")[Polylux]. A plain string or content in the metadata is also taken as the note of the slide containing it. To use another label, pass `--notes-label`:
+
+```typ
+#let note(body) = [#metadata(body) <my-notes>]
+```
+
+```bash
+tinymist preview /abs-path/to/slides.typ --preview-mode=slide --notes-label=my-notes
+```
+
+Editors can turn the slides by sending a `panelSlideTo` message with the 1-based `page` number through the control plane.
+
+== Snippet Preview
+
+To iterate on a component without a scratch document, preview a snippet of a file in isolation. Code lenses are shown above the example blocks in doc comments, i.e. `/// ```example`. Clicking on them previews the example in a panel, which is shared by all snippets:
+
+```typ
+/// ```example
+/// #badge[New]
+/// ```
+#let badge(fill: blue, body) = box(fill: fill, inset: 2pt, body)
+
+#let banner(title: "Hello") = heading(title)
+```
+
+The snippet is wrapped in a temporary entry, which has the top-level imports of the file, and also imports all definitions of the file. The same preview is reused by the following snippets.
+
+Editors start the snippet preview with the `tinymist.startSnippetPreview` command, which takes the URI of the file, a range, and optionally the arguments of the preview. A selected range is previewed as is, and an empty range previews the example block, or the call of the function at the position if all its parameters have default values, e.g. `banner` above. Pass `--open` to open the preview in the browser:
+
+```json
+["file:///path/to/lib.typ", { "start": { "line": 5, "character": 0 }, "end": { "line": 5, "character": 0 } }, ["--open"]]
+```
+
+== Review Comments
+
+Reviewers can leave comments on the preview by clicking on a point of a page with the `Alt` key pressed. The comment is pinned to the source code of the clicked point, and stored in a sidecar file next to the source, e.g. `chapter1.review.json` for `chapter1.typ`, which can be committed with the project. The name of the reviewer is asked on the first comment and remembered by the browser.
+
+The comments are shown as code lenses above the commented lines in the editor. Each comment remembers the source text at its position, so it follows the text when lines are inserted or removed before it. Clicking on "Resolve" removes the comment from the sidecar file.
+
+Editors connected to the control plane also receive a `reviewComment` event with the source location and the content of each new comment.
+
+== Remote Preview <remote-preview>
+
+By default, the preview servers have no access control beyond checking the `Origin` header, so they should only listen on localhost. To open a preview running on a remote machine from the browser of another one, bind the server to a public address and protect it:
+
+```bash
+tinymist preview /abs-path/to/main.typ --data-plane-host=0.0.0.0:23625 \
+  --auth --tls-cert=cert.pem --tls-key=key.pem --allowed-origin=https://devbox:23625
+```
+
+- `--auth` generates a random token, which is printed in the preview URL, e.g. `https://0.0.0.0:23625/?token=...`. Replace the host in the URL with the address of the machine to open it. The token is also accepted from a bearer `Authorization` header, and is kept in a cookie after the first visit. Pass `--token` to use a fixed token instead. The token is not required by the control plane bound to a loopback address, e.g. the default `127.0.0.1:23626`, so that the editors keep syncing with the preview. If the control plane is bound to another address by `--control-plane-host`, the token is required as well, e.g. `ws://devbox:23626/?token=...`.
+- `--tls-cert` and `--tls-key` serve the preview over HTTPS with the certificate chain and private key in PEM files.
+- `--allowed-origin` allows the websocket connections from the origin, which is the URL of the preview without the path. It can be passed multiple times.
+
+== Sharing a Preview between Editors
+
+By default, a preview is driven by the only editor connected to its control plane. Pass `--multi-client` to let several editors, e.g. the Neovim and VS Code of two people pairing on a document, connect to the same control plane:
+
+```bash
+tinymist preview /abs-path/to/main.typ --multi-client
+```
+
+The first connected editor is the leader, whose cursor, scrolling and unsaved changes drive the preview. The other editors are followers. They receive a `followCursor` event with the `filepath`, `line` and `character` of the leader's cursor on every move, and their requests to scroll the preview are ignored. A follower becomes the leader by sending a `takeLead` message, and the earliest remaining editor becomes the leader when the leader disconnects. The new leader is asked to sync its unsaved changes by a `syncEditorChanges` event.
+
+```json
+{ "event": "join", "name": "Alice" }
+{ "event": "takeLead" }
+```
+
+The `join` message sets the name shown to the others. Whenever an editor joins or leaves, or the leader changes, each editor receives a `participants` event with its own id, the id of the leader, and the ids and names of all editors. The preview shows a panel of the pages where the cursors of the editors are. Clicking on an editor in the panel scrolls the preview to its cursor.
+
+== Exporting from the Preview Server
+
+The preview server also serves the artifacts of the document that is currently previewed, which lets scripts and other tools fetch the compiled document without compiling it again:
+
+- `/export.pdf`: the document in PDF.
+- `/export.svg`: all pages merged into a single SVG image.
+- `/export/page/<n>.png`: the `n`-th page (1-based) in PNG. The resolution is 144 pixels per inch by default, and can be adjusted by the `ppi` query parameter, e.g. `/export/page/1.png?ppi=300`.
+- `/outline.json`: the outline of the document, i.e. the headings with their titles and positions.
+
+```bash
+curl -o main.pdf http://127.0.0.1:23625/export.pdf
+```
+
+The artifacts are exported from the last successful compilation. If the document has not been compiled yet, the server responds with `503 Service Unavailable`. In the HTML preview mode, only `/outline.json` is available. When the server is protected by a token, pass it by the `token` query parameter or a bearer `Authorization` header, as described in the #link(<remote-preview>)[Remote Preview] section.
+
+== Terminal Preview
+
+To preview a document without a browser, e.g. over SSH, pass `--terminal` to draw the pages in the terminal:
+
+```bash
+tinymist preview /abs-path/to/main.typ --terminal
+```
+
+The current page is redrawn on every compilation. Press `n` (or Enter, Space, →) and `p` (or ←) to turn to the next and the previous page, `g` and `G` to go to the first and the last page, a page number followed by Enter to go to the page, `r` to recompile, and `q` to quit.
+
+The pages are drawn by the #link("https://sw.kovidgoyal.net/kitty/graphics-protocol/")[kitty graphics protocol], the #link("https://iterm2.com/documentation-images.html")[inline images protocol] of iTerm2, or sixel graphics, which is detected from the environment variables of the terminal. Pass `--graphics=kitty`, `--graphics=iterm` or `--graphics=sixel` to choose one explicitly, and `--terminal-ppi` to adjust the size of the pages. Inside tmux, the kitty and iTerm2 images are passed through to the outer terminal, which requires `set -g allow-passthrough on` in the tmux configuration.
+
 == Editor Integration
 
 #pro-tip[
@@ -126,6 +126,16 @@ function determineVscodeTheme(): any {
 //                 "never"
 //             ],
 //             "default": "auto"
+//         },
+//         "thumbnailFormat": {
+//             "title": "Thumbnail format",
+//             "description": "The image format of the thumbnails of figures, equations and other content shown on hovering references.",
+//             "type": "string",
+//             "enum": [
+//                 "svg",
+//                 "png"
+//             ],
+//             "default": "svg"
 //         }
 //     }
 // },
@@ -214,6 +214,10 @@ async function languageActivate(context: IContext) {
     commands.registerCommand("tinymist.clearCache", commandClearCache),
     commands.registerCommand("tinymist.runCodeLens", commandRunCodeLens),
     commands.registerCommand("tinymist.copyAnsiHighlight", commandCopyAnsiHighlight),
+    commands.registerCommand("tinymist.copyAsMarkdown", () => commandCopyAs("markdown")),
+    commands.registerCommand("tinymist.copyAsLaTeX", () => commandCopyAs("latex")),
+    commands.registerCommand("tinymist.copyAsText", () => commandCopyAs("text")),
+    commands.registerCommand("tinymist.pasteMarkdownAsTypst", commandPasteMarkdownAsTypst),
     commands.registerCommand("tinymist.viewAst", commandViewAst(context)),
 
     commands.registerCommand("tinymist.pinMainToCurrent", () => commandPinMain(true)),
@@ -291,6 +295,48 @@ async function commandCopyAnsiHighlight(): Promise<void> {
   await vscode.env.clipboard.writeText(res);
 }
 
+async function commandCopyAs(format: "markdown" | "latex" | "text"): Promise<void> {
+  const editor = activeTypstEditor();
+  if (editor === undefined) {
+    return;
+  }
+
+  const range = await getNonEmptySelection(editor);
+  if (range === undefined) {
+    return;
+  }
+
+  const res = await tinymist.copyAs(editor.document.uri.fsPath, { range, format });
+  if (res === null) {
+    return;
+  }
+
+  await vscode.env.clipboard.writeText(res);
+}
+
+async function commandPasteMarkdownAsTypst(): Promise<void> {
+  const editor = activeTypstEditor();
+  if (editor === undefined) {
+    return;
+  }
+
+  const markdown = await vscode.env.clipboard.readText();
+  if (!markdown) {
+    return;
+  }
+
+  const res = await tinymist.importMarkdown(markdown);
+  if (res === null) {
+    return;
+  }
+
+  await editor.edit((builder) => {
+    for (const selection of editor.selections) {
+      builder.replace(selection, res);
+    }
+  });
+}
+
 function commandViewAst(ctx: IContext) {
   const scheme = "tinymist-ast";
   const uri = `${scheme}://viewAst/ast.typ`;
@@ -556,6 +602,10 @@ async function commandRunCodeLens(...args: string[]): Promise<void> {
       void vscode.commands.executeCommand(`typst-preview.preview`);
       return;
     }
+    case "preview-snippet": {
+      void vscode.commands.executeCommand(`tinymist.previewSnippet`, args[1], args[2]);
+      return;
+    }
     case "export-html": {
       await commandShow("Html");
       break;
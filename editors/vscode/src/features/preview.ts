@@ -106,6 +106,7 @@ export function previewActivate(context: vscode.ExtensionContext, isCompat: bool
     vscode.commands.registerCommand("typst-preview.browser-slide", launch("browser", "slide")),
     vscode.commands.registerCommand("tinymist.previewDev", launchDevPreview("doc")),
     vscode.commands.registerCommand("tinymist.previewDevSlide", launchDevPreview("slide")),
+    vscode.commands.registerCommand("tinymist.previewSnippet", launchSnippetPreview),
     ...(isCompat
       ? [
           vscode.commands.registerCommand("typst-preview.eject", ejectPreviewPanelCompat),
@@ -218,6 +219,62 @@ export function previewActivate(context: vscode.ExtensionContext, isCompat: bool
     });
   }
 
+  /**
+   * Previews a snippet of a file in the snippet panel, which is shared by all snippets.
+   *
+   * @param uri The URI of the file containing the snippet.
+   * @param range The range of the snippet, or an empty range at an example block or a function.
+   */
+  async function launchSnippetPreview(uri: string, range: unknown) {
+    const bindDocument =
+      vscode.workspace.textDocuments.find((doc) => doc.uri.toString() === uri) ||
+      (await vscode.workspace.openTextDocument(vscode.Uri.parse(uri)));
+    const editor =
+      vscode.window.visibleTextEditors.find((editor) => editor.document === bindDocument) ||
+      (await vscode.window.showTextDocument(bindDocument));
+
+    const res = await tinymist
+      .startSnippetPreview(uri, range, ["--data-plane-host", "127.0.0.1:0"])
+      .catch((e) => {
+        vscode.window.showErrorMessage(`failed to launch snippet preview: ${e}`);
+      });
+    if (!res) {
+      return;
+    }
+
+    // The running snippet preview has switched to the snippet.
+    if (snippetPanel) {
+      snippetPanel.reveal(undefined, true);
+      return;
+    }
+    if (!res.dataPlanePort) {
+      vscode.window.showErrorMessage("failed to launch snippet preview");
+      return;
+    }
+
+    const panel = await openPreviewInWebView({
+      context,
+      task: {
+        kind: "webview",
+        context,
+        editor,
+        bindDocument,
+        mode: "doc",
+        isNotPrimary: true,
+      },
+      activeEditor: editor,
+      dataPlanePort: res.dataPlanePort,
+      dataPlaneUrl: res.dataPlaneUrl,
+      async panelDispose() {
+        if (snippetPanel === panel) {
+          snippetPanel = undefined;
+        }
+        await tinymist.killPreview(SNIPPET_TASK_ID);
+      },
+    });
+    snippetPanel = panel;
+  }
+
   /**
    * Ejects the preview panel to the external browser.
    */
@@ -278,6 +335,10 @@ interface OpenPreviewInWebViewArgs {
    * The server is already opened by the {@link launchImpl} function.
    */
   dataPlanePort: string | number;
+  /**
+   * The URL to connect to the data plane, which carries the token if it is required.
+   */
+  dataPlaneUrl?: string;
   /**
    * The existing webview panel to reuse.
    */
@@ -299,6 +360,7 @@ export async function openPreviewInWebView({
   task,
   activeEditor,
   dataPlanePort,
+  dataPlaneUrl,
   webviewPanel,
   panelDispose,
 }: OpenPreviewInWebViewArgs) {
@@ -363,7 +425,7 @@ export async function openPreviewInWebView({
   html = html.replace("preview-arg:state:", `preview-arg:state:${previewStateEncoded}`);
   html = html.replace(
     "ws://127.0.0.1:23625",
-    translateExternalURL(`ws://127.0.0.1:${dataPlanePort}`),
+    translateExternalURL(dataPlaneUrl ?? `ws://127.0.0.1:${dataPlanePort}`),
   );
 
   // Sets the HTML content to the webview panel.
@@ -390,6 +452,15 @@ interface TaskControlBlock {
 }
 const activeTask = new Map<vscode.TextDocument, TaskControlBlock>();
 
+/**
+ * The task id of the snippet preview, which is fixed by the language server.
+ */
+const SNIPPET_TASK_ID = "snippet";
+/**
+ * The panel of the snippet preview, which is shared by all snippets.
+ */
+let snippetPanel: vscode.WebviewPanel | undefined = undefined;
+
 async function launchPreviewLsp(task: LaunchInBrowserTask | LaunchInWebViewTask) {
   const { kind, context, editor, bindDocument, webviewPanel, isBrowsing, isDev, isNotPrimary } =
     task;
@@ -411,7 +482,8 @@ async function launchPreviewLsp(task: LaunchInBrowserTask | LaunchInWebViewTask)
   const disposes = new DisposeList();
   registerPreviewTaskDispose(taskId, disposes);
 
-  const { dataPlanePort, staticServerPort, isPrimary } = await invokeLspCommand();
+  const { dataPlanePort, dataPlaneUrl, staticServerPort, staticServerUrl, isPrimary } =
+    await invokeLspCommand();
   if (!dataPlanePort || !staticServerPort) {
     disposes.dispose();
     throw new Error(`Failed to launch preview ${filePath}`);
@@ -421,7 +493,7 @@ async function launchPreviewLsp(task: LaunchInBrowserTask | LaunchInWebViewTask)
   task.isNotPrimary = !isPrimary;
 
   if (isPrimary) {
-    const connectUrl = translateExternalURL(`ws://127.0.0.1:${dataPlanePort}`);
+    const connectUrl = translateExternalURL(dataPlaneUrl ?? `ws://127.0.0.1:${dataPlanePort}`);
     contentPreviewProvider.then((p) => p.postActivate(connectUrl));
     disposes.add(() => {
       contentPreviewProvider.then((p) => p.postDeactivate(connectUrl));
@@ -436,6 +508,7 @@ async function launchPreviewLsp(task: LaunchInBrowserTask | LaunchInWebViewTask)
         task,
         activeEditor: editor,
         dataPlanePort,
+        dataPlaneUrl,
         webviewPanel,
         async panelDispose() {
           disposes.dispose();
@@ -445,7 +518,9 @@ async function launchPreviewLsp(task: LaunchInBrowserTask | LaunchInWebViewTask)
       break;
     }
     case "browser": {
-      vscode.env.openExternal(vscode.Uri.parse(`http://127.0.0.1:${staticServerPort}`));
+      vscode.env.openExternal(
+        vscode.Uri.parse(staticServerUrl ?? `http://127.0.0.1:${staticServerPort}`),
+      );
       break;
     }
   }
@@ -482,9 +557,10 @@ async function launchPreviewLsp(task: LaunchInBrowserTask | LaunchInWebViewTask)
       filePath,
     ];
 
-    const { dataPlanePort, staticServerPort, isPrimary } = await (isBrowsing
-      ? tinymist.startBrowsingPreview(previewArgs)
-      : tinymist.startPreview(previewArgs));
+    const { dataPlanePort, dataPlaneUrl, staticServerPort, staticServerUrl, isPrimary } =
+      await (isBrowsing
+        ? tinymist.startBrowsingPreview(previewArgs)
+        : tinymist.startPreview(previewArgs));
     console.log(
       `Launched preview, browsing:${isBrowsing}, data plane port:${dataPlanePort}, static server port:${staticServerPort}`,
     );
@@ -517,7 +593,7 @@ async function launchPreviewLsp(task: LaunchInBrowserTask | LaunchInWebViewTask)
       disposes.add(vscode.window.onDidChangeTextEditorSelection(src2docHandler, 500));
     }
 
-    return { staticServerPort, dataPlanePort, isPrimary };
+    return { staticServerPort, staticServerUrl, dataPlanePort, dataPlaneUrl, isPrimary };
 
     /**
      * Reports the position of the editor when necessary.
@@ -3,7 +3,17 @@ import { tinymist } from "../lsp";
 import { VirtualConsole } from "../util";
 import { extensionState } from "../state";
 
-type ExportFormat = "pdf" | "png" | "svg" | "html" | "markdown" | "text" | "query" | "pdfpc";
+type ExportFormat =
+  | "pdf"
+  | "png"
+  | "svg"
+  | "html"
+  | "markdown"
+  | "tex"
+  | "odt"
+  | "text"
+  | "query"
+  | "pdfpc";
 
 interface ExportArgs {
   format: ExportFormat | ExportFormat[];
@@ -38,6 +48,7 @@ interface ExportArgs {
   assetsPath?: string;
   "markdown.assetsPath"?: string;
   "tex.assetsPath"?: string;
+  "odt.processor"?: string;
 }
 
 export const runExport = (def: vscode.TaskDefinition) => {
@@ -168,6 +179,14 @@ const provideFormats = (exportArgs: ExportArgs, ops = exportOps(exportArgs)) =>
     },
     export: tinymist.exportTeX,
   },
+  odt: {
+    opts() {
+      return {
+        processor: exportArgs["odt.processor"] || exportArgs["processor"],
+      };
+    },
+    export: tinymist.exportOdt,
+  },
   text: {
     opts() {
       return {};
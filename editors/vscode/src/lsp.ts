@@ -53,10 +53,18 @@ export interface PreviewResult {
    * The frontend port
    */
   staticServerPort?: number;
+  /**
+   * The URL to open the frontend, carrying the token if it is required
+   */
+  staticServerUrl?: string;
   /**
    * The data plane address
    */
   dataPlanePort?: number;
+  /**
+   * The URL to connect to the data plane, carrying the token if it is required
+   */
+  dataPlaneUrl?: string;
   /**
    * Whether the preview content is provided by the primary compiler instance. This must be indicate by the CLI argument `--not-primary`
    * when starts a preview task by *LSP Command*.
@@ -349,10 +357,16 @@ export class LanguageState {
   exportHtml = exportCommand("tinymist.exportHtml");
   exportMarkdown = exportCommand("tinymist.exportMarkdown");
   exportTeX = exportCommand("tinymist.exportTeX");
+  exportOdt = exportCommand("tinymist.exportOdt");
   exportText = exportCommand("tinymist.exportText");
   exportQuery = exportCommand("tinymist.exportQuery");
   exportAnsiHighlight = exportCommand("tinymist.exportAnsiHighlight");
   exportAst = exportCommand("tinymist.exportAst");
+  copyAs = exportCommand("tinymist.copyAs");
+
+  importMarkdown(markdown: string) {
+    return tinymist.executeCommand<string>("tinymist.importMarkdown", [markdown]);
+  }
 
   getResource<T extends keyof ResourceRoutes>(path: T, ...args: any[]) {
     return tinymist.executeCommand<ResourceRoutes[T]>("tinymist.getResources", [path, ...args]);
@@ -420,6 +434,29 @@ export class LanguageState {
     return res || {};
   }
 
+  /**
+   * Starts a preview task of a snippet, or shows the snippet in the running snippet preview, in
+   * which case no port is returned. See {@link _GroupDocumentPreviewFeatureCommands} for more
+   * information.
+   *
+   * @param uri - The URI of the file containing the snippet.
+   * @param range - The range of the snippet, or an empty range at an example block or a function.
+   * @param previewArgs - The *CLI arguments* to pass to the preview task.
+   * @returns The result of the preview task.
+   */
+  async startSnippetPreview(
+    uri: string,
+    range: unknown,
+    previewArgs: string[],
+  ): Promise<PreviewResult> {
+    const res = await tinymist.executeCommand<PreviewResult>(`tinymist.startSnippetPreview`, [
+      uri,
+      range,
+      previewArgs,
+    ]);
+    return res || {};
+  }
+
   /**
    * Kills a preview task. See {@link _GroupDocumentPreviewFeatureCommands} for more information.
    *
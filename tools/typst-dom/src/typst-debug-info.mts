@@ -185,6 +185,12 @@ export function installEditorJumpToHandler(docRoot: HTMLElement) {
       return;
     }
     console.log("frameLoc", frameLoc);
+    if (event.altKey) {
+      // Alt+click leaves a review comment at the point.
+      event.preventDefault();
+      sendReviewComment(frameLoc);
+      return;
+    }
     window.typstWebsocket.send(`src-point ${JSON.stringify(frameLoc)}`);
 
     const triggerWindow = document.body || document.firstElementChild;
@@ -208,6 +214,28 @@ export function installEditorJumpToHandler(docRoot: HTMLElement) {
   docRoot.addEventListener("click", sourceMappingHandler);
 }
 
+/// The key of the reviewer name remembered in the local storage.
+const REVIEWER_KEY = "typst-preview-reviewer";
+
+/// Prompts for a review comment and sends it with the commented point, which is
+/// pinned to the source by the server.
+function sendReviewComment(position: { page_no: number; x: number; y: number }) {
+  const text = window.prompt("Review comment:")?.trim();
+  if (!text) {
+    return;
+  }
+
+  // The name is asked only once, even if it is left empty.
+  let author = localStorage.getItem(REVIEWER_KEY);
+  if (author === null) {
+    author = window.prompt("Your name (optional):")?.trim() || "";
+    localStorage.setItem(REVIEWER_KEY, author);
+  }
+
+  const comment = { position, author: author || undefined, text };
+  window.typstWebsocket.send(`review-comment ${JSON.stringify(comment)}`);
+}
+
 export interface TypstDebugJumpDocument {}
 
 export function provideDebugJumpDoc<TBase extends GConstructor<TypstDocumentContext>>(
@@ -0,0 +1,197 @@
+/// The HTML preview, which mounts the HTML export of the document and keeps it
+/// up to date by DOM patches sent from the preview server.
+
+const enc = new TextEncoder();
+const dec = new TextDecoder();
+const COMMA = enc.encode(",")[0];
+/// The attribute carrying the span of an element, in hex format.
+const SPAN_ATTR = "data-tinymist-span";
+
+type HtmlTree =
+  | { kind: "element"; tag: string; attrs: [string, string][]; children: HtmlTree[] }
+  | { kind: "text"; text: string }
+  | { kind: "frame"; svg: string };
+
+type HtmlPatch =
+  | { op: "replace"; path: number[]; node: HtmlTree }
+  | { op: "attrs"; path: number[]; attrs: [string, string][] }
+  | { op: "insert"; path: number[]; index: number; node: HtmlTree }
+  | { op: "remove"; path: number[]; index: number };
+
+export interface HtmlWsArgs {
+  url: string;
+}
+
+export async function wsHtmlMain({ url }: HtmlWsArgs): Promise<() => void> {
+  const hookedElem = document.getElementById("typst-app")!;
+  hookedElem.innerHTML = "";
+  const root = document.createElement("div");
+  root.classList.add("typst-html-root");
+  hookedElem.appendChild(root);
+
+  let disposed = false;
+  let ws: WebSocket | undefined = undefined;
+  let cursorElems: Element[] = [];
+
+  const onClick = (event: MouseEvent) => {
+    const target = event.target as Element | null;
+    const elem = target?.closest?.(`[${SPAN_ATTR}]`);
+    const span = elem?.getAttribute(SPAN_ATTR);
+    if (span && ws?.readyState === WebSocket.OPEN) {
+      ws.send(`html-srcspan ${span}`);
+    }
+  };
+  root.addEventListener("click", onClick);
+
+  function setupSocket() {
+    ws = new WebSocket(url);
+    ws.binaryType = "arraybuffer";
+    ws.onopen = () => {
+      console.log("WebSocket connection opened", ws);
+      window.typstWebsocket = ws as any;
+      ws!.send("current");
+    };
+    ws.onclose = (e) => {
+      console.log("WebSocket connection closed", e);
+      if (!disposed) {
+        setTimeout(setupSocket, 1000);
+      }
+    };
+    ws.onmessage = (event) => {
+      if (!(event.data instanceof ArrayBuffer)) {
+        return;
+      }
+      const data = new Uint8Array(event.data);
+      const idx = data.indexOf(COMMA);
+      const kind = dec.decode(data.slice(0, idx));
+      const payload = dec.decode(data.slice(idx + 1));
+      processMessage(kind, payload);
+    };
+  }
+
+  function processMessage(kind: string, payload: string) {
+    switch (kind) {
+      case "html": {
+        const trees: HtmlTree[] = JSON.parse(payload);
+        root.replaceChildren(...trees.map(createNode));
+        return;
+      }
+      case "html-patch": {
+        const patches: HtmlPatch[] = JSON.parse(payload);
+        for (const patch of patches) {
+          if (!applyPatch(root, patch)) {
+            // The DOM is out of sync, so we request the full tree.
+            console.warn("failed to apply html patch, requesting full tree", patch);
+            ws?.send("current");
+            return;
+          }
+        }
+        return;
+      }
+      case "html-cursor": {
+        const paths: number[][] = JSON.parse(payload);
+        cursorElems.forEach((elem) => elem.classList.remove("typst-html-cursor"));
+        cursorElems = resolveElements(root, paths);
+        cursorElems.forEach((elem) => elem.classList.add("typst-html-cursor"));
+        return;
+      }
+      case "html-jump": {
+        const paths: number[][] = JSON.parse(payload);
+        resolveElements(root, paths)[0]?.scrollIntoView({ behavior: "smooth", block: "center" });
+        return;
+      }
+      default:
+        console.log("html preview ignores message", kind);
+    }
+  }
+
+  setupSocket();
+
+  return () => {
+    disposed = true;
+    root.removeEventListener("click", onClick);
+    ws?.close();
+  };
+}
+
+function createNode(tree: HtmlTree): Node {
+  switch (tree.kind) {
+    case "text":
+      return document.createTextNode(tree.text);
+    case "frame": {
+      const frame = document.createElement("span");
+      frame.classList.add("typst-html-frame");
+      frame.innerHTML = tree.svg;
+      return frame;
+    }
+    case "element": {
+      const elem = document.createElement(tree.tag);
+      setAttrs(elem, tree.attrs);
+      elem.append(...tree.children.map(createNode));
+      return elem;
+    }
+  }
+}
+
+function setAttrs(elem: Element, attrs: [string, string][]) {
+  const names = new Set(attrs.map(([name]) => name));
+  for (const name of elem.getAttributeNames()) {
+    if (!names.has(name)) {
+      elem.removeAttribute(name);
+    }
+  }
+  for (const [name, value] of attrs) {
+    elem.setAttribute(name, value);
+  }
+}
+
+function resolvePath(root: Node, path: number[]): Node | undefined {
+  let node: Node | undefined = root;
+  for (const idx of path) {
+    node = node?.childNodes[idx];
+  }
+  return node;
+}
+
+function resolveElements(root: Node, paths: number[][]): Element[] {
+  return paths
+    .map((path) => resolvePath(root, path))
+    .filter((node): node is Element => node instanceof Element);
+}
+
+function applyPatch(root: Node, patch: HtmlPatch): boolean {
+  const node = resolvePath(root, patch.path);
+  if (!node) {
+    return false;
+  }
+
+  switch (patch.op) {
+    case "replace":
+      if (!node.parentNode) {
+        return false;
+      }
+      node.parentNode.replaceChild(createNode(patch.node), node);
+      return true;
+    case "attrs":
+      if (!(node instanceof Element)) {
+        return false;
+      }
+      setAttrs(node, patch.attrs);
+      return true;
+    case "insert": {
+      if (patch.index > node.childNodes.length) {
+        return false;
+      }
+      node.insertBefore(createNode(patch.node), node.childNodes[patch.index] || null);
+      return true;
+    }
+    case "remove": {
+      const child = node.childNodes[patch.index];
+      if (!child) {
+        return false;
+      }
+      node.removeChild(child);
+      return true;
+    }
+  }
+}
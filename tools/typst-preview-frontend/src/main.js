@@ -5,6 +5,7 @@ import "./styles/toolbar.css";
 import "./styles/layout.css";
 import "./styles/help-panel.css";
 import "./styles/outline.css";
+import "./styles/presence.css";
 
 import { wsMain, PreviewMode } from "./ws";
 import { setupDrag } from "./drag";
@@ -85,10 +86,13 @@ function buildWs() {
     /// Set the root css selector to the preview mode.
     app.classList.remove("mode-slide");
     app.classList.remove("mode-doc");
+    app.classList.remove("mode-html");
     if (mode === PreviewMode.Slide) {
       app.classList.add("mode-slide");
     } else if (mode === PreviewMode.Doc) {
       app.classList.add("mode-doc");
+    } else if (mode === PreviewMode.Html) {
+      app.classList.add("mode-html");
     } else {
       throw new Error(`Unknown preview mode: ${mode}`);
     }
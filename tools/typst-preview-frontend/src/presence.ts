@@ -0,0 +1,53 @@
+/// The cursor of a participant of a preview shared by several editors.
+export interface Presence {
+  id: number;
+  name: string;
+  leader: boolean;
+  /// The 1-based page of the cursor.
+  page: number;
+  x: number;
+  y: number;
+}
+
+const presences = new Map<number, Presence>();
+
+/// Shows the cursor of a participant in the presence panel.
+export function updatePresence(presence: Presence) {
+  presences.set(presence.id, presence);
+  renderPresence();
+}
+
+/// Removes a participant that left from the presence panel.
+export function removePresence(id: number) {
+  presences.delete(id);
+  renderPresence();
+}
+
+/// Renders the pages of the cursors of all participants. Clicking on a
+/// participant scrolls the preview to its cursor.
+function renderPresence() {
+  let panel = document.getElementById("typst-presence");
+  if (!panel) {
+    panel = document.createElement("div");
+    panel.id = "typst-presence";
+    document.body.appendChild(panel);
+  }
+
+  panel.replaceChildren();
+  panel.style.display = presences.size ? "" : "none";
+
+  const sorted = [...presences.values()].sort((a, b) => a.id - b.id);
+  for (const presence of sorted) {
+    const item = document.createElement("div");
+    item.className = "typst-presence-item" + (presence.leader ? " leader" : "");
+    item.textContent = `${presence.name} · p. ${presence.page}`;
+    item.title = presence.leader ? "Leading the preview" : "Following the leader";
+    item.addEventListener("click", () => {
+      const rootElem = document.getElementById("typst-app")?.firstElementChild;
+      if (rootElem) {
+        window.handleTypstLocation(rootElem, presence.page, presence.x, presence.y);
+      }
+    });
+    panel.appendChild(item);
+  }
+}
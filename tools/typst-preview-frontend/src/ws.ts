@@ -10,6 +10,8 @@ import renderModule from "@myriaddreamin/typst-ts-renderer/pkg/typst_ts_renderer
 import { RenderSession } from "@myriaddreamin/typst.ts/dist/esm/renderer.mjs";
 import { WebSocketSubject, webSocket } from "rxjs/webSocket";
 import { Subject, Subscription, buffer, debounceTime, fromEvent, tap } from "rxjs";
+import { wsHtmlMain } from "./html";
+import { removePresence, updatePresence } from "./presence";
 export { PreviewMode } from "typst-dom/typst-doc.mjs";
 
 // for debug propose
@@ -36,10 +38,41 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
     return () => {};
   }
 
+  if (previewMode === PreviewMode.Html) {
+    return wsHtmlMain({ url });
+  }
+
   let disposed = false;
   let $ws: WebSocketSubject<ArrayBuffer> | undefined = undefined;
   const subsribes: Subscription[] = [];
 
+  // The role of the slide preview in the presenter console, see `/presenter`.
+  const role = new URLSearchParams(location.search).get("role");
+  // The next-slide view is always one slide ahead of the current slide.
+  const pageOffset = role === "presenter-next" ? 1 : 0;
+
+  /// Turns the slide preview to the page, and syncs the page to other slide
+  /// previews if `sync` is set.
+  function slideTo(svgDoc: TypstDocument, page: number, sync: boolean): boolean {
+    if (page <= 0 || !svgDoc.setPartialPageNumber(page)) {
+      return false;
+    }
+
+    const pageSelector = document.getElementById("typst-page-selector") as
+      | HTMLSelectElement
+      | undefined;
+    if (pageSelector) {
+      pageSelector.value = page.toString();
+    }
+    if (role === "presenter-current") {
+      window.parent.postMessage({ type: "presenter-page", page }, location.origin);
+    }
+    if (sync && role !== "presenter-next") {
+      window.typstWebsocket?.send(`slide-sync ${page}`);
+    }
+    return true;
+  }
+
   function createSvgDocument(kModule: RenderSession) {
     const hookedElem = document.getElementById("typst-app")!;
     if (hookedElem.firstElementChild?.tagName !== "svg") {
@@ -100,7 +133,7 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
               return;
             }
             const page = Number.parseInt(inpPageSelector.value);
-            svgDoc.setPartialPageNumber(page);
+            slideTo(svgDoc, page, true);
           });
         }
       }
@@ -135,11 +168,7 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
             return;
           }
           const page = Number.parseInt(v) + diff;
-          if (page <= 0) {
-            return;
-          }
-          if (svgDoc.setPartialPageNumber(page)) {
-            pageSelector.value = page.toString();
+          if (slideTo(svgDoc, page, true)) {
             blurInput();
           }
         }
@@ -175,9 +204,9 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
       const helpButton = document.getElementById("typst-top-help-button");
       helpButton?.addEventListener("click", toggleHelp);
 
-      window.addEventListener("keydown", (e) => {
+      const handleKey = (key: string) => {
         let handled = true;
-        switch (e.key) {
+        switch (key) {
           case "ArrowLeft":
           case "ArrowUp":
             blurInput();
@@ -207,11 +236,23 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
           default:
             handled = false;
         }
+        return handled;
+      };
 
-        if (handled) {
+      window.addEventListener("keydown", (e) => {
+        if (handleKey(e.key)) {
           e.preventDefault();
         }
       });
+
+      // The presenter console forwards key presses to the current slide.
+      subsribes.push(
+        fromEvent<MessageEvent>(window, "message").subscribe((event) => {
+          if (event.origin === location.origin && event.data?.type === "presenter-key") {
+            handleKey(event.data.key);
+          }
+        }),
+      );
     }
 
     return svgDoc;
@@ -359,19 +400,9 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
         }
 
         if (previewMode === PreviewMode.Slide) {
-          const pageSelector = document.getElementById("typst-page-selector") as
-            | HTMLSelectElement
-            | undefined;
-          if (svgDoc.setPartialPageNumber(page)) {
-            if (pageSelector) {
-              pageSelector.value = page.toString();
-            }
-            // pageToJump = 1;
-            // todo: hint location
-            return;
-          } else {
-            return;
-          }
+          // todo: hint location
+          slideTo(svgDoc, page + pageOffset, false);
+          return;
         }
 
         if (rootElem) {
@@ -408,6 +439,24 @@ export async function wsMain({ url, previewMode, isContentPreview }: WsArgs) {
         console.log("Experimental feature: invert colors strategy taken:", strategy);
         ensureInvertColors(document.getElementById("typst-app"), strategy);
         return;
+      } else if (message[0] === "slide-sync") {
+        const page = Number.parseInt(dec.decode((message[1] as any).buffer));
+        if (previewMode === PreviewMode.Slide && !Number.isNaN(page)) {
+          slideTo(svgDoc, page + pageOffset, false);
+        }
+        return;
+      } else if (message[0] === "notes") {
+        if (role === "presenter-current") {
+          const notes = JSON.parse(dec.decode((message[1] as any).buffer));
+          window.parent.postMessage({ type: "presenter-notes", notes }, location.origin);
+        }
+        return;
+      } else if (message[0] === "presence") {
+        updatePresence(JSON.parse(dec.decode((message[1] as any).buffer)));
+        return;
+      } else if (message[0] === "presence-leave") {
+        removePresence(Number.parseInt(dec.decode((message[1] as any).buffer)));
+        return;
       } else if (message[0] === "outline") {
         console.log("Experimental feature: outline rendering");
         return;